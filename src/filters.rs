@@ -8,11 +8,17 @@ use video_resize::algorithms::{
 };
 use video_resize::{CropDimensions, ResizeDimensions, crop, resize};
 
+/// A chain of crop and resize filters applied to source frames before diffing.
 pub struct FilterChain {
     filters: Vec<Filter>,
 }
 
 impl FilterChain {
+    /// Parses a semicolon-separated list of filters,
+    /// e.g. `"crop:top=42,left=64;resize:width=1920,height=1080"`.
+    ///
+    /// # Errors
+    /// Returns an error if a filter or one of its options is unrecognized or invalid.
     pub fn new(filters: &str) -> Result<Self> {
         if filters.is_empty() {
             return Ok(Self {
@@ -109,6 +115,8 @@ impl FilterChain {
         Ok(Self { filters: parsed })
     }
 
+    /// Applies every filter in the chain to `frame`, in order.
+    #[must_use]
     pub fn apply<T: Pixel>(&self, frame: Frame<T>, source_bd: NonZeroU8) -> Frame<T> {
         self.filters
             .iter()
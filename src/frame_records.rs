@@ -0,0 +1,374 @@
+//! Per-frame records of parsed frame headers, and their CSV and JSON export.
+
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::parser::{
+    frame::{Dimensions, FrameHeader, FrameType},
+    grain::FilmGrainHeader,
+};
+
+/// The columns of [`write_frame_records_csv`], which are also the keys of
+/// [`write_frame_records_json`].
+const COLUMNS: [&str; 20] = [
+    "decode_index",
+    "timestamp",
+    "obu_size",
+    "temporal_id",
+    "spatial_id",
+    "frame_type",
+    "show_existing_frame",
+    "show_frame",
+    "showable_frame",
+    "base_q_idx",
+    "tile_cols",
+    "tile_rows",
+    "frame_width",
+    "frame_height",
+    "upscaled_width",
+    "render_width",
+    "render_height",
+    "grain_action",
+    "grain_seed",
+    "grain_ref_idx",
+];
+
+/// The columns with text values, which are quoted in JSON.
+const TEXT_COLUMNS: [&str; 2] = ["frame_type", "grain_action"];
+
+/// What a frame header does with film grain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrainAction {
+    /// No grain is applied, including to frames that are never shown.
+    Disable,
+    /// `update_grain = 0`: the parameters are loaded from a reference slot with a new seed.
+    Copy,
+    /// New parameters are coded in the frame header.
+    Update,
+}
+
+impl GrainAction {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Disable => "disable",
+            Self::Copy => "copy",
+            Self::Update => "update",
+        }
+    }
+}
+
+/// The main properties of one frame header, in decoding order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameRecord {
+    /// Position of the frame header among those parsed, counting from 0.
+    pub decode_index: usize,
+    /// Presentation timestamp of the packet that carried the frame, in
+    /// 1/10,000,000ths of a second, like grain headers.
+    pub timestamp: u64,
+    /// Payload size in bytes of the frame or frame header OBU, plus any tile group
+    /// OBUs that follow it.
+    pub obu_size: usize,
+    pub temporal_id: u8,
+    pub spatial_id: u8,
+    pub frame_type: FrameType,
+    pub show_existing_frame: bool,
+    pub show_frame: bool,
+    pub showable_frame: bool,
+    pub base_q_idx: u8,
+    pub tile_cols: u32,
+    pub tile_rows: u32,
+    /// The coded size, after super-resolution downscaling.
+    pub frame_size: Dimensions,
+    pub upscaled_width: u32,
+    pub render_size: Dimensions,
+    /// For `show_existing_frame`, nothing is coded and the grain is that stored
+    /// with the shown frame.
+    pub grain_action: GrainAction,
+    pub grain_seed: Option<u16>,
+    /// The reference slot that copied grain parameters are loaded from.
+    pub grain_ref_idx: Option<u8>,
+}
+
+impl FrameRecord {
+    /// Summarizes `header`, the `decode_index`th frame header of a stream.
+    #[must_use]
+    pub fn new(decode_index: usize, timestamp: u64, obu_size: usize, header: &FrameHeader) -> Self {
+        let film_grain_params = if header.show_existing_frame {
+            &header.film_grain_params
+        } else {
+            &header.coded_film_grain_params
+        };
+        let (grain_action, grain_seed, grain_ref_idx) = match film_grain_params {
+            FilmGrainHeader::Disable => (GrainAction::Disable, None, None),
+            FilmGrainHeader::CopyRefFrame {
+                ref_idx,
+                grain_seed,
+            } => (GrainAction::Copy, Some(*grain_seed), Some(*ref_idx)),
+            FilmGrainHeader::UpdateGrain(params) => {
+                (GrainAction::Update, Some(params.grain_seed), None)
+            }
+        };
+
+        Self {
+            decode_index,
+            timestamp,
+            obu_size,
+            temporal_id: header.temporal_id,
+            spatial_id: header.spatial_id,
+            frame_type: header.frame_type,
+            show_existing_frame: header.show_existing_frame,
+            show_frame: header.show_frame,
+            showable_frame: header.showable_frame,
+            base_q_idx: header.quantization_params.base_q_idx,
+            tile_cols: header.tile_info.tile_cols,
+            tile_rows: header.tile_info.tile_rows,
+            frame_size: header.frame_size.frame_size,
+            upscaled_width: header.frame_size.upscaled_size.width,
+            render_size: header.frame_size.render_size,
+            grain_action,
+            grain_seed,
+            grain_ref_idx,
+        }
+    }
+
+    /// The value of each of [`COLUMNS`], or `None` where there is none.
+    fn values(&self) -> [Option<String>; COLUMNS.len()] {
+        let frame_type = match self.frame_type {
+            FrameType::Key => "key",
+            FrameType::Inter => "inter",
+            FrameType::IntraOnly => "intra_only",
+            FrameType::Switch => "switch",
+        };
+        [
+            Some(self.decode_index.to_string()),
+            Some(self.timestamp.to_string()),
+            Some(self.obu_size.to_string()),
+            Some(self.temporal_id.to_string()),
+            Some(self.spatial_id.to_string()),
+            Some(frame_type.to_owned()),
+            Some(self.show_existing_frame.to_string()),
+            Some(self.show_frame.to_string()),
+            Some(self.showable_frame.to_string()),
+            Some(self.base_q_idx.to_string()),
+            Some(self.tile_cols.to_string()),
+            Some(self.tile_rows.to_string()),
+            Some(self.frame_size.width.to_string()),
+            Some(self.frame_size.height.to_string()),
+            Some(self.upscaled_width.to_string()),
+            Some(self.render_size.width.to_string()),
+            Some(self.render_size.height.to_string()),
+            Some(self.grain_action.name().to_owned()),
+            self.grain_seed.map(|seed| seed.to_string()),
+            self.grain_ref_idx.map(|ref_idx| ref_idx.to_string()),
+        ]
+    }
+}
+
+/// Writes frame records as CSV, with a header row and one row per frame.
+/// Missing values are left empty.
+///
+/// # Errors
+/// Returns an error if writing to `output` fails.
+pub fn write_frame_records_csv<W: Write>(records: &[FrameRecord], output: &mut W) -> Result<()> {
+    writeln!(output, "{}", COLUMNS.join(","))?;
+    for record in records {
+        let row: Vec<String> = record
+            .values()
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect();
+        writeln!(output, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+/// Writes frame records as a JSON array, with one object per frame on each line.
+/// Missing values are `null`.
+///
+/// # Errors
+/// Returns an error if writing to `output` fails.
+pub fn write_frame_records_json<W: Write>(records: &[FrameRecord], output: &mut W) -> Result<()> {
+    write!(output, "[")?;
+    for (i, record) in records.iter().enumerate() {
+        let fields: Vec<String> = COLUMNS
+            .iter()
+            .zip(record.values())
+            .map(|(column, value)| match value {
+                Some(value) if TEXT_COLUMNS.contains(column) => format!("\"{column}\":\"{value}\""),
+                Some(value) => format!("\"{column}\":{value}"),
+                None => format!("\"{column}\":null"),
+            })
+            .collect();
+        let separator = if i == 0 { "" } else { "," };
+        write!(output, "{separator}\n  {{{}}}", fields.join(","))?;
+    }
+    if !records.is_empty() {
+        writeln!(output)?;
+    }
+    writeln!(output, "]")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{frame::QuantizationParams, grain::FilmGrainParams};
+
+    fn grain_params(grain_seed: u16) -> FilmGrainParams {
+        FilmGrainParams {
+            grain_seed,
+            scaling_points_y: Default::default(),
+            scaling_points_cb: Default::default(),
+            scaling_points_cr: Default::default(),
+            scaling_shift: 8,
+            ar_coeff_lag: 0,
+            ar_coeffs_y: Default::default(),
+            ar_coeffs_cb: Default::default(),
+            ar_coeffs_cr: Default::default(),
+            ar_coeff_shift: 6,
+            cb_mult: 0,
+            cb_luma_mult: 0,
+            cb_offset: 0,
+            cr_mult: 0,
+            cr_luma_mult: 0,
+            cr_offset: 0,
+            chroma_scaling_from_luma: false,
+            grain_scale_shift: 0,
+            overlap_flag: false,
+            clip_to_restricted_range: false,
+        }
+    }
+
+    fn record(decode_index: usize, grain_action: GrainAction) -> FrameRecord {
+        FrameRecord {
+            decode_index,
+            timestamp: 417_083 * decode_index as u64,
+            obu_size: 1200,
+            temporal_id: 0,
+            spatial_id: 0,
+            frame_type: FrameType::Inter,
+            show_existing_frame: false,
+            show_frame: true,
+            showable_frame: false,
+            base_q_idx: 96,
+            tile_cols: 2,
+            tile_rows: 1,
+            frame_size: Dimensions {
+                width: 1440,
+                height: 1080,
+            },
+            upscaled_width: 1920,
+            render_size: Dimensions {
+                width: 1920,
+                height: 1080,
+            },
+            grain_action,
+            grain_seed: (grain_action != GrainAction::Disable).then_some(4660),
+            grain_ref_idx: (grain_action == GrainAction::Copy).then_some(3),
+        }
+    }
+
+    #[test]
+    fn new_reports_coded_grain_of_decoded_frames() {
+        let header = FrameHeader {
+            frame_type: FrameType::Inter,
+            showable_frame: true,
+            quantization_params: QuantizationParams {
+                base_q_idx: 120,
+                ..Default::default()
+            },
+            // The resolved parameters must not be reported as an update.
+            film_grain_params: FilmGrainHeader::UpdateGrain(grain_params(76)),
+            coded_film_grain_params: FilmGrainHeader::CopyRefFrame {
+                ref_idx: 5,
+                grain_seed: 77,
+            },
+            ..Default::default()
+        };
+
+        let record = FrameRecord::new(4, 100, 2000, &header);
+
+        assert_eq!(record.decode_index, 4);
+        assert_eq!(record.base_q_idx, 120);
+        assert!(record.showable_frame);
+        assert_eq!(record.grain_action, GrainAction::Copy);
+        assert_eq!(record.grain_seed, Some(77));
+        assert_eq!(record.grain_ref_idx, Some(5));
+    }
+
+    #[test]
+    fn new_reports_stored_grain_of_shown_existing_frames() {
+        let header = FrameHeader {
+            show_frame: true,
+            show_existing_frame: true,
+            film_grain_params: FilmGrainHeader::UpdateGrain(grain_params(9)),
+            ..Default::default()
+        };
+
+        let record = FrameRecord::new(0, 0, 1, &header);
+
+        assert_eq!(record.grain_action, GrainAction::Update);
+        assert_eq!(record.grain_seed, Some(9));
+    }
+
+    #[test]
+    fn write_csv_leaves_missing_values_empty() {
+        let mut output = Vec::new();
+        write_frame_records_csv(
+            &[
+                record(0, GrainAction::Disable),
+                record(1, GrainAction::Copy),
+            ],
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "decode_index,timestamp,obu_size,temporal_id,spatial_id,frame_type,\
+             show_existing_frame,show_frame,showable_frame,base_q_idx,tile_cols,tile_rows,\
+             frame_width,frame_height,upscaled_width,render_width,render_height,grain_action,\
+             grain_seed,grain_ref_idx\n\
+             0,0,1200,0,0,inter,false,true,false,96,2,1,1440,1080,1920,1920,1080,disable,,\n\
+             1,417083,1200,0,0,inter,false,true,false,96,2,1,1440,1080,1920,1920,1080,copy,4660,3\n"
+        );
+    }
+
+    #[test]
+    fn write_json_writes_one_object_per_line() {
+        let mut output = Vec::new();
+        write_frame_records_json(
+            &[
+                record(0, GrainAction::Update),
+                record(1, GrainAction::Disable),
+            ],
+            &mut output,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "[");
+        assert_eq!(
+            lines[1],
+            "  {\"decode_index\":0,\"timestamp\":0,\"obu_size\":1200,\"temporal_id\":0,\
+             \"spatial_id\":0,\"frame_type\":\"inter\",\"show_existing_frame\":false,\
+             \"show_frame\":true,\"showable_frame\":false,\"base_q_idx\":96,\"tile_cols\":2,\
+             \"tile_rows\":1,\"frame_width\":1440,\"frame_height\":1080,\"upscaled_width\":1920,\
+             \"render_width\":1920,\"render_height\":1080,\"grain_action\":\"update\",\
+             \"grain_seed\":4660,\"grain_ref_idx\":null},"
+        );
+        assert!(lines[2].ends_with("\"grain_seed\":null,\"grain_ref_idx\":null}"));
+        assert_eq!(lines[3], "]");
+    }
+
+    #[test]
+    fn write_json_without_records_is_an_empty_array() {
+        let mut output = Vec::new();
+        write_frame_records_json(&[], &mut output).unwrap();
+
+        assert_eq!(output, b"[]\n");
+    }
+}
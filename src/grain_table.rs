@@ -0,0 +1,346 @@
+//! Reading and writing of aomenc-style film grain tables.
+
+use std::{collections::BTreeMap, io::Write};
+
+use anyhow::Result;
+use num_rational::Rational32;
+
+use crate::parser::grain::{FilmGrainHeader, FilmGrainParams, FrameGrainHeader};
+
+/// Parses a film grain table, as written by aomenc or by [`write_grain_table`].
+///
+/// # Errors
+/// Returns an error if `input` is not a valid film grain table.
+pub fn read_grain_table(input: &str) -> Result<Vec<GrainTableSegment>> {
+    Ok(av1_grain::parse_grain_table(input)?
+        .into_iter()
+        .map(GrainTableSegment::from)
+        .collect())
+}
+
+/// Writes a complete film grain table, including the `filmgrn1` header.
+///
+/// # Errors
+/// Returns an error if writing to `output` fails.
+pub fn write_grain_table<W: Write>(segments: &[GrainTableSegment], output: &mut W) -> Result<()> {
+    writeln!(output, "filmgrn1")?;
+    for segment in segments {
+        write_film_grain_segment(segment, output)?;
+    }
+    Ok(())
+}
+
+/// Writes a single segment of a grain table, without the `filmgrn1` header.
+///
+/// # Errors
+/// Returns an error if writing to `output` fails.
+pub fn write_film_grain_segment<W: Write>(
+    segment: &GrainTableSegment,
+    output: &mut W,
+) -> Result<()> {
+    let params = &segment.grain_params;
+
+    writeln!(
+        output,
+        "E {} {} 1 {} 1",
+        segment.start_time, segment.end_time, params.grain_seed,
+    )?;
+    writeln!(
+        output,
+        "\tp {} {} {} {} {} {} {} {} {} {} {} {}",
+        params.ar_coeff_lag,
+        params.ar_coeff_shift,
+        params.grain_scale_shift,
+        params.scaling_shift,
+        u8::from(params.chroma_scaling_from_luma),
+        u8::from(params.overlap_flag),
+        params.cb_mult,
+        params.cb_luma_mult,
+        params.cb_offset,
+        params.cr_mult,
+        params.cr_luma_mult,
+        params.cr_offset
+    )?;
+
+    write!(output, "\tsY {} ", params.scaling_points_y.len())?;
+    for point in &params.scaling_points_y {
+        write!(output, " {} {}", point[0], point[1])?;
+    }
+    writeln!(output)?;
+
+    write!(output, "\tsCb {}", params.scaling_points_cb.len())?;
+    for point in &params.scaling_points_cb {
+        write!(output, " {} {}", point[0], point[1])?;
+    }
+    writeln!(output)?;
+
+    write!(output, "\tsCr {}", params.scaling_points_cr.len())?;
+    for point in &params.scaling_points_cr {
+        write!(output, " {} {}", point[0], point[1])?;
+    }
+    writeln!(output)?;
+
+    write!(output, "\tcY")?;
+    for coeff in &params.ar_coeffs_y {
+        write!(output, " {}", *coeff)?;
+    }
+    writeln!(output)?;
+
+    write!(output, "\tcCb")?;
+    for coeff in &params.ar_coeffs_cb {
+        write!(output, " {}", *coeff)?;
+    }
+    writeln!(output)?;
+
+    write!(output, "\tcCr")?;
+    for coeff in &params.ar_coeffs_cr {
+        write!(output, " {}", *coeff)?;
+    }
+    writeln!(output)?;
+
+    Ok(())
+}
+
+/// The film grain parameters applied to the frames from `start_time` up to,
+/// but not including, `end_time`.
+///
+/// Times are in 1/10,000,000ths of a second, as in aomenc's grain tables.
+#[derive(Debug, Clone)]
+pub struct GrainTableSegment {
+    pub start_time: u64,
+    pub end_time: u64,
+    pub grain_params: FilmGrainParams,
+}
+
+impl From<av1_grain::GrainTableSegment> for GrainTableSegment {
+    fn from(data: av1_grain::GrainTableSegment) -> Self {
+        GrainTableSegment {
+            start_time: data.start_time,
+            end_time: data.end_time,
+            grain_params: data.into(),
+        }
+    }
+}
+
+// I don't know why this is the base unit for a timestamp but it is. 1/10000000
+// of a second.
+const TIMESTAMP_BASE_UNIT: f64 = 10_000_000f64;
+
+/// Builds grain table segments from per-frame grain headers.
+///
+/// Each frame is displayed from its own timestamp until the next frame's, so
+/// segments follow the real packet timestamps even for VFR sources. `frame_rate`
+/// is only used to estimate how long the final frame is displayed.
+#[must_use]
+pub fn aggregate_grain_headers(
+    grain_headers: &[FrameGrainHeader],
+    frame_rate: Rational32,
+) -> Vec<GrainTableSegment> {
+    let time_per_packet = (*frame_rate.denom() as f64 / *frame_rate.numer() as f64
+        * TIMESTAMP_BASE_UNIT)
+        .ceil() as u64;
+
+    grain_headers
+        .iter()
+        .enumerate()
+        .fold(Vec::new(), |mut acc, (i, frame)| {
+            let cur_packet_start = frame.timestamp;
+            let cur_packet_end = grain_headers
+                .get(i + 1)
+                .map_or(cur_packet_start.saturating_add(time_per_packet), |next| {
+                    next.timestamp
+                });
+            if cur_packet_end <= cur_packet_start {
+                // This frame is never on screen, e.g. it shares a timestamp with the next one.
+                return acc;
+            }
+
+            let elem = &frame.film_grain_params;
+            let prev_packet_has_grain = acc
+                .last()
+                .is_some_and(|last: &GrainTableSegment| last.end_time == cur_packet_start);
+            if prev_packet_has_grain {
+                match *elem {
+                    FilmGrainHeader::Disable => {
+                        // Do nothing. This will disable film grain for this
+                        // and future frames.
+                    }
+                    FilmGrainHeader::CopyRefFrame { .. } => {
+                        // The parser already resolves these against the reference slot, so an
+                        // unresolved copy is treated as unchanged params.
+                        // Increment the end time of the current table segment.
+                        let cur_segment = acc.last_mut().expect("prev_packet_has_grain is true");
+                        cur_segment.end_time = cur_packet_end;
+                    }
+                    FilmGrainHeader::UpdateGrain(ref grain_params) => {
+                        let cur_segment = acc.last_mut().expect("prev_packet_has_grain is true");
+                        if grain_params == &cur_segment.grain_params {
+                            // Increment the end time of the current table segment.
+                            cur_segment.end_time = cur_packet_end;
+                        } else {
+                            // The grain params changed, so we have to make a new segment.
+                            acc.push(GrainTableSegment {
+                                start_time: cur_packet_start,
+                                end_time: cur_packet_end,
+                                grain_params: grain_params.clone(),
+                            });
+                        }
+                    }
+                }
+            } else if let FilmGrainHeader::UpdateGrain(ref grain_params) = *elem {
+                acc.push(GrainTableSegment {
+                    start_time: cur_packet_start,
+                    end_time: cur_packet_end,
+                    grain_params: grain_params.clone(),
+                });
+            }
+
+            acc
+        })
+}
+
+/// Groups per-frame grain headers by the `(temporal_id, spatial_id)` of their frame.
+///
+/// Scalable streams carry a frame header per layer in each temporal unit, so a flat
+/// list interleaves the layers. Each group keeps its frames in order.
+#[must_use]
+pub fn group_grain_headers_by_layer(
+    grain_headers: &[FrameGrainHeader],
+) -> BTreeMap<(u8, u8), Vec<FrameGrainHeader>> {
+    let mut layers: BTreeMap<(u8, u8), Vec<FrameGrainHeader>> = BTreeMap::new();
+    for frame in grain_headers {
+        layers
+            .entry((frame.temporal_id, frame.spatial_id))
+            .or_default()
+            .push(frame.clone());
+    }
+    layers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_grain_params(grain_seed: u16) -> FilmGrainParams {
+        FilmGrainParams {
+            grain_seed,
+            scaling_points_y: Default::default(),
+            scaling_points_cb: Default::default(),
+            scaling_points_cr: Default::default(),
+            scaling_shift: 8,
+            ar_coeff_lag: 0,
+            ar_coeffs_y: Default::default(),
+            ar_coeffs_cb: Default::default(),
+            ar_coeffs_cr: Default::default(),
+            ar_coeff_shift: 6,
+            cb_mult: 0,
+            cb_luma_mult: 0,
+            cb_offset: 0,
+            cr_mult: 0,
+            cr_luma_mult: 0,
+            cr_offset: 0,
+            chroma_scaling_from_luma: false,
+            grain_scale_shift: 0,
+            overlap_flag: false,
+            clip_to_restricted_range: false,
+        }
+    }
+
+    fn frame(timestamp: u64, film_grain_params: FilmGrainHeader) -> FrameGrainHeader {
+        FrameGrainHeader {
+            timestamp,
+            film_grain_params,
+            temporal_id: 0,
+            spatial_id: 0,
+        }
+    }
+
+    #[test]
+    fn aggregate_merges_frames_with_same_params() {
+        let params = sample_grain_params(1);
+        let headers = [
+            frame(0, FilmGrainHeader::UpdateGrain(params.clone())),
+            frame(400_000, FilmGrainHeader::UpdateGrain(params.clone())),
+            frame(800_000, FilmGrainHeader::UpdateGrain(params)),
+        ];
+
+        let segments = aggregate_grain_headers(&headers, Rational32::new(25, 1));
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].start_time, 0);
+        assert_eq!(segments[0].end_time, 1_200_000);
+    }
+
+    #[test]
+    fn aggregate_uses_packet_timestamps_for_vfr() {
+        let params = sample_grain_params(1);
+        let headers = [
+            frame(0, FilmGrainHeader::UpdateGrain(params.clone())),
+            // A frame that stays on screen for much longer than the nominal frame rate.
+            frame(400_000, FilmGrainHeader::UpdateGrain(params)),
+            frame(
+                5_000_000,
+                FilmGrainHeader::UpdateGrain(FilmGrainParams {
+                    scaling_shift: 9,
+                    ..sample_grain_params(2)
+                }),
+            ),
+        ];
+
+        let segments = aggregate_grain_headers(&headers, Rational32::new(25, 1));
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].end_time, 5_000_000);
+        assert_eq!(segments[1].start_time, 5_000_000);
+        assert_eq!(segments[1].end_time, 5_400_000);
+    }
+
+    #[test]
+    fn aggregate_skips_disabled_frames() {
+        let params = sample_grain_params(1);
+        let headers = [
+            frame(0, FilmGrainHeader::UpdateGrain(params.clone())),
+            frame(400_000, FilmGrainHeader::Disable),
+            frame(800_000, FilmGrainHeader::UpdateGrain(params)),
+        ];
+
+        let segments = aggregate_grain_headers(&headers, Rational32::new(25, 1));
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].end_time, 400_000);
+        assert_eq!(segments[1].start_time, 800_000);
+    }
+
+    #[test]
+    fn group_by_layer_separates_interleaved_layers() {
+        let params = sample_grain_params(1);
+        let base = frame(0, FilmGrainHeader::UpdateGrain(params.clone()));
+        let mut enhancement = frame(0, FilmGrainHeader::Disable);
+        enhancement.spatial_id = 1;
+        let mut next_base = frame(400_000, FilmGrainHeader::UpdateGrain(params));
+        next_base.temporal_id = 1;
+        let headers = [base.clone(), enhancement.clone(), next_base.clone()];
+
+        let layers = group_grain_headers_by_layer(&headers);
+
+        assert_eq!(layers.len(), 3);
+        assert_eq!(layers[&(0, 0)], [base]);
+        assert_eq!(layers[&(0, 1)], [enhancement]);
+        assert_eq!(layers[&(1, 0)], [next_base]);
+    }
+
+    #[test]
+    fn write_grain_table_starts_with_header() {
+        let segments = [GrainTableSegment {
+            start_time: 0,
+            end_time: 400_000,
+            grain_params: sample_grain_params(7),
+        }];
+        let mut output = Vec::new();
+
+        write_grain_table(&segments, &mut output).expect("writing to a Vec cannot fail");
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("filmgrn1\nE 0 400000 1 7 1\n"));
+    }
+}
@@ -0,0 +1,489 @@
+//! Native reading and writing of IVF files, without going through FFmpeg.
+//!
+//! IVF is a minimal container: a 32-byte file header followed by frames,
+//! each prefixed with a 12-byte header holding its size and PTS.
+
+use std::io::{self, Read, Write};
+
+use anyhow::{Result, bail, ensure};
+use log::debug;
+use num_rational::Rational32;
+
+use crate::parser::{BitstreamParser, grain::FrameGrainHeader};
+
+const IVF_SIGNATURE: &[u8; 4] = b"DKIF";
+const IVF_HEADER_SIZE: usize = 32;
+const IVF_FRAME_HEADER_SIZE: usize = 12;
+
+/// The file header of an IVF file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IvfHeader {
+    /// The codec FourCC, `AV01` for AV1.
+    pub fourcc: [u8; 4],
+    pub width: u16,
+    pub height: u16,
+    /// The unit of frame PTS values, in seconds.
+    ///
+    /// Stored as the IVF frame rate (`denom`) and scale (`numer`).
+    pub time_base: Rational32,
+    /// The number of frames in the file, as stated by the header.
+    pub frame_count: u32,
+}
+
+impl IvfHeader {
+    /// The frame rate stated by the header, which is the inverse of the time base.
+    #[must_use]
+    pub fn frame_rate(&self) -> Rational32 {
+        Rational32::new_raw(*self.time_base.denom(), *self.time_base.numer())
+    }
+
+    /// Checks that the file holds AV1, before any of it is parsed.
+    fn ensure_av1(&self) -> Result<()> {
+        ensure!(
+            &self.fourcc == b"AV01",
+            "stream 0 is {}, not AV1",
+            String::from_utf8_lossy(&self.fourcc).trim_end()
+        );
+        Ok(())
+    }
+
+    fn parse(data: &[u8; IVF_HEADER_SIZE]) -> Result<(Self, usize)> {
+        ensure!(&data[0..4] == IVF_SIGNATURE, "not an IVF file");
+        let header_size = usize::from(u16::from_le_bytes([data[6], data[7]]));
+        ensure!(
+            header_size >= IVF_HEADER_SIZE,
+            "IVF header size of {header_size} bytes is too small"
+        );
+
+        let rate = u32::from_le_bytes([data[16], data[17], data[18], data[19]]);
+        let scale = u32::from_le_bytes([data[20], data[21], data[22], data[23]]);
+        let header = Self {
+            fourcc: [data[8], data[9], data[10], data[11]],
+            width: u16::from_le_bytes([data[12], data[13]]),
+            height: u16::from_le_bytes([data[14], data[15]]),
+            time_base: Rational32::new_raw(scale as i32, rate as i32),
+            frame_count: u32::from_le_bytes([data[24], data[25], data[26], data[27]]),
+        };
+        Ok((header, header_size))
+    }
+
+    fn to_bytes(self) -> [u8; IVF_HEADER_SIZE] {
+        let mut data = [0u8; IVF_HEADER_SIZE];
+        data[0..4].copy_from_slice(IVF_SIGNATURE);
+        // Bytes 4..6 are the version, which is always 0.
+        data[6..8].copy_from_slice(&(IVF_HEADER_SIZE as u16).to_le_bytes());
+        data[8..12].copy_from_slice(&self.fourcc);
+        data[12..14].copy_from_slice(&self.width.to_le_bytes());
+        data[14..16].copy_from_slice(&self.height.to_le_bytes());
+        data[16..20].copy_from_slice(&(*self.time_base.denom() as u32).to_le_bytes());
+        data[20..24].copy_from_slice(&(*self.time_base.numer() as u32).to_le_bytes());
+        data[24..28].copy_from_slice(&self.frame_count.to_le_bytes());
+        data
+    }
+}
+
+/// A single frame (temporal unit) of an IVF file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IvfFrame {
+    /// Presentation timestamp, in units of the file's time base.
+    pub pts: i64,
+    pub data: Vec<u8>,
+}
+
+/// Reads the header and frames of an IVF file.
+pub struct IvfReader<R: Read> {
+    inner: R,
+    header: IvfHeader,
+}
+
+impl<R: Read> IvfReader<R> {
+    /// Reads the IVF file header from `inner`.
+    ///
+    /// # Errors
+    /// Returns an error if `inner` does not start with a valid IVF header.
+    pub fn new(mut inner: R) -> Result<Self> {
+        let mut data = [0u8; IVF_HEADER_SIZE];
+        inner.read_exact(&mut data)?;
+        let (header, header_size) = IvfHeader::parse(&data)?;
+        // Skip any header extension we don't know about.
+        io::copy(
+            &mut (&mut inner).take((header_size - IVF_HEADER_SIZE) as u64),
+            &mut io::sink(),
+        )?;
+
+        Ok(Self { inner, header })
+    }
+
+    #[must_use]
+    pub const fn header(&self) -> &IvfHeader {
+        &self.header
+    }
+
+    /// Reads the next frame, or returns `None` at the end of the file.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails or the file ends in the middle of a frame.
+    pub fn read_frame(&mut self) -> Result<Option<IvfFrame>> {
+        let mut frame_header = [0u8; IVF_FRAME_HEADER_SIZE];
+        let mut filled = 0;
+        while filled < IVF_FRAME_HEADER_SIZE {
+            match self.inner.read(&mut frame_header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => bail!("IVF file ends in the middle of a frame header"),
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let size = u32::from_le_bytes([
+            frame_header[0],
+            frame_header[1],
+            frame_header[2],
+            frame_header[3],
+        ]);
+        let mut pts = [0u8; 8];
+        pts.copy_from_slice(&frame_header[4..12]);
+        // The size is untrusted, so only allocate as much as the file actually holds.
+        let mut data = Vec::new();
+        (&mut self.inner)
+            .take(u64::from(size))
+            .read_to_end(&mut data)?;
+        ensure!(
+            data.len() == size as usize,
+            "IVF file ends in the middle of a frame"
+        );
+
+        Ok(Some(IvfFrame {
+            pts: i64::from_le_bytes(pts),
+            data,
+        }))
+    }
+}
+
+/// Writes an IVF file header followed by frames.
+pub struct IvfWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> IvfWriter<W> {
+    /// Writes `header` to `inner`.
+    ///
+    /// The header is written as-is, so `frame_count` should already be final.
+    ///
+    /// # Errors
+    /// Returns an error if writing to `inner` fails.
+    pub fn new(mut inner: W, header: &IvfHeader) -> Result<Self> {
+        inner.write_all(&header.to_bytes())?;
+        Ok(Self { inner })
+    }
+
+    /// Writes one frame with its IVF frame header.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the underlying writer fails.
+    pub fn write_frame(&mut self, pts: i64, data: &[u8]) -> Result<()> {
+        let Ok(size) = u32::try_from(data.len()) else {
+            bail!("frame of {} bytes is too large for IVF", data.len());
+        };
+        self.inner.write_all(&size.to_le_bytes())?;
+        self.inner.write_all(&pts.to_le_bytes())?;
+        self.inner.write_all(data)?;
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer.
+    ///
+    /// # Errors
+    /// Returns an error if flushing fails.
+    pub fn finish(mut self) -> Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl BitstreamParser<false> {
+    /// Parses every frame of an IVF file and returns the film grain applied to
+    /// each displayed frame, like `get_grain_headers` does for FFmpeg inputs.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or contains invalid OBUs.
+    pub fn parse_ivf<R: Read>(&mut self, mut reader: IvfReader<R>) -> Result<&[FrameGrainHeader]> {
+        reader.header().ensure_av1()?;
+        let time_base = reader.header().time_base;
+        let mut first = true;
+        while let Some(frame) = reader.read_frame()? {
+            if first {
+                self.set_start_pts(frame.pts);
+                first = false;
+            }
+
+            debug!(
+                target: "trace_headers",
+                "Packet: {} bytes, pts {}.",
+                frame.data.len(),
+                frame.pts,
+            );
+
+            let packet_ts = self.packet_ts(Some(frame.pts), time_base);
+            self.parse_packet(&frame.data, packet_ts)?;
+        }
+
+        Ok(self.grain_headers())
+    }
+}
+
+impl BitstreamParser<true> {
+    /// Rewrites the film grain of every frame of an IVF file into `writer`.
+    ///
+    /// Without a container frame rate, the IVF header and frame timestamps are kept
+    /// unchanged. With one, the header's time base becomes its inverse, and as every
+    /// IVF frame is one temporal unit, each frame's timestamp becomes its index in
+    /// the file.
+    ///
+    /// # Errors
+    /// Returns an error if the input cannot be read, contains invalid OBUs,
+    /// or the output cannot be written.
+    pub fn rewrite_ivf<R: Read, W: Write>(
+        &mut self,
+        mut reader: IvfReader<R>,
+        writer: W,
+    ) -> Result<W> {
+        reader.header().ensure_av1()?;
+        let time_base = reader.header().time_base;
+        let mut header = *reader.header();
+        let conform_frame_rate = self.container_frame_rate();
+        if let Some(frame_rate) = conform_frame_rate {
+            header.time_base = frame_rate.recip();
+        }
+        let mut writer = IvfWriter::new(writer, &header)?;
+        let mut index = 0;
+        while let Some(frame) = reader.read_frame()? {
+            if index == 0 {
+                self.set_start_pts(frame.pts);
+            }
+
+            debug!(
+                target: "trace_headers",
+                "Packet: {} bytes, pts {}.",
+                frame.data.len(),
+                frame.pts,
+            );
+
+            let packet_ts = self.packet_ts(Some(frame.pts), time_base);
+            let packet_out = self.rewrite_packet(&frame.data, packet_ts)?;
+            if packet_out.len() != frame.data.len() {
+                debug!(
+                    "Resized packet from {} to {}",
+                    frame.data.len(),
+                    packet_out.len()
+                );
+            }
+            let pts = if conform_frame_rate.is_some() {
+                index
+            } else {
+                frame.pts
+            };
+            writer.write_frame(pts, &packet_out)?;
+            index += 1;
+        }
+
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_rational::Rational64;
+
+    use super::*;
+
+    fn sample_header() -> IvfHeader {
+        IvfHeader {
+            fourcc: *b"AV01",
+            width: 352,
+            height: 288,
+            time_base: Rational32::new_raw(1001, 24000),
+            frame_count: 2,
+        }
+    }
+
+    fn sample_file() -> Vec<u8> {
+        let mut writer = IvfWriter::new(Vec::new(), &sample_header()).unwrap();
+        // Each frame is a temporal delimiter followed by a padding OBU.
+        writer
+            .write_frame(0, &[0x12, 0x00, 0x7A, 0x01, 0xFF])
+            .unwrap();
+        writer.write_frame(1, &[0x12, 0x00]).unwrap();
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn header_round_trips() {
+        let file = sample_file();
+
+        let reader = IvfReader::new(file.as_slice()).expect("header should parse");
+
+        assert_eq!(*reader.header(), sample_header());
+        assert_eq!(
+            reader.header().frame_rate(),
+            Rational32::new_raw(24000, 1001)
+        );
+    }
+
+    #[test]
+    fn header_has_expected_layout() {
+        let file = sample_file();
+
+        assert_eq!(&file[0..4], b"DKIF");
+        assert_eq!(&file[6..8], &[32, 0]);
+        assert_eq!(&file[8..12], b"AV01");
+        assert_eq!(&file[16..20], &24000u32.to_le_bytes());
+        assert_eq!(&file[20..24], &1001u32.to_le_bytes());
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let file = sample_file();
+        let mut reader = IvfReader::new(file.as_slice()).unwrap();
+
+        let first = reader.read_frame().unwrap().expect("first frame");
+        let second = reader.read_frame().unwrap().expect("second frame");
+
+        assert_eq!(first.pts, 0);
+        assert_eq!(first.data, vec![0x12, 0x00, 0x7A, 0x01, 0xFF]);
+        assert_eq!(second.pts, 1);
+        assert_eq!(second.data, vec![0x12, 0x00]);
+        assert!(reader.read_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_bad_signature() {
+        let mut file = sample_file();
+        file[0] = b'X';
+
+        assert!(IvfReader::new(file.as_slice()).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_frame() {
+        let mut file = sample_file();
+        file.pop();
+        let mut reader = IvfReader::new(file.as_slice()).unwrap();
+
+        reader.read_frame().unwrap();
+        assert!(reader.read_frame().is_err());
+    }
+
+    #[test]
+    fn rejects_frame_size_beyond_end_of_file() {
+        let mut file = sample_file();
+        file[IVF_HEADER_SIZE..IVF_HEADER_SIZE + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut reader = IvfReader::new(file.as_slice()).unwrap();
+
+        assert!(reader.read_frame().is_err());
+    }
+
+    #[test]
+    fn skips_header_extension() {
+        let mut file = sample_file();
+        file[6] = 36;
+        file.splice(32..32, [0u8; 4]);
+
+        let mut reader = IvfReader::new(file.as_slice()).unwrap();
+
+        assert_eq!(reader.read_frame().unwrap().expect("first frame").pts, 0);
+    }
+
+    #[test]
+    fn parse_ivf_reads_all_frames() {
+        let file = sample_file();
+        let mut parser = BitstreamParser::<false>::without_container(None);
+
+        let headers = parser
+            .parse_ivf(IvfReader::new(file.as_slice()).unwrap())
+            .expect("file should parse");
+
+        // Neither frame contains a frame header.
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn rewrite_ivf_passes_through_frames_without_headers() {
+        let file = sample_file();
+        let mut parser = BitstreamParser::<true>::without_container(None);
+
+        let output = parser
+            .rewrite_ivf(IvfReader::new(file.as_slice()).unwrap(), Vec::new())
+            .expect("file should rewrite");
+
+        assert_eq!(output, file);
+    }
+
+    #[test]
+    fn rewrite_ivf_conforms_header_frame_rate() {
+        let file = sample_file();
+        let mut parser = BitstreamParser::<true>::without_container(None)
+            .with_container_frame_rate(Rational32::new(25, 1));
+
+        let output = parser
+            .rewrite_ivf(IvfReader::new(file.as_slice()).unwrap(), Vec::new())
+            .expect("file should rewrite");
+
+        let reader = IvfReader::new(output.as_slice()).unwrap();
+        assert_eq!(reader.header().frame_rate(), Rational32::new(25, 1));
+        // Only the header's time base changes.
+        assert_eq!(output[IVF_HEADER_SIZE..], file[IVF_HEADER_SIZE..]);
+
+        // Rounded millisecond timestamps become frame counts, without drifting.
+        for input_rate in [
+            Rational64::new(24, 1),
+            Rational64::new(24000, 1001),
+            Rational64::new(30000, 1001),
+        ] {
+            let frame_count = 500;
+            let mut writer = IvfWriter::new(
+                Vec::new(),
+                &IvfHeader {
+                    time_base: Rational32::new_raw(1, 1000),
+                    frame_count,
+                    ..sample_header()
+                },
+            )
+            .unwrap();
+            for index in 0..i64::from(frame_count) {
+                let pts = (Rational64::new(index * 1000, 1) / input_rate)
+                    .round()
+                    .to_integer();
+                writer.write_frame(pts, &[0x12, 0x00]).unwrap();
+            }
+            let file = writer.finish().unwrap();
+            let mut parser = BitstreamParser::<true>::without_container(None)
+                .with_container_frame_rate(Rational32::new(25, 1));
+
+            let output = parser
+                .rewrite_ivf(IvfReader::new(file.as_slice()).unwrap(), Vec::new())
+                .expect("file should rewrite");
+
+            let mut reader = IvfReader::new(output.as_slice()).unwrap();
+            assert_eq!(reader.header().frame_rate(), Rational32::new(25, 1));
+            for pts in 0..i64::from(frame_count) {
+                assert_eq!(reader.read_frame().unwrap().expect("frame").pts, pts);
+            }
+            assert!(reader.read_frame().unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn parse_ivf_rejects_other_codecs() {
+        let mut file = sample_file();
+        file[8..12].copy_from_slice(b"VP90");
+        let mut parser = BitstreamParser::<false>::without_container(None);
+
+        let err = parser
+            .parse_ivf(IvfReader::new(file.as_slice()).unwrap())
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "stream 0 is VP90, not AV1");
+    }
+}
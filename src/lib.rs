@@ -0,0 +1,35 @@
+//! Grain synth analyzer and editor for AV1 files.
+//!
+//! [`BitstreamParser`] reads the film grain parameters out of an AV1 stream,
+//! or rewrites them while remuxing, and [`grain_table`] converts between
+//! those parameters and aomenc-style film grain tables. [`frame_records`]
+//! exports the main properties of each parsed frame header for analysis.
+//! The `grav1synth` binary is a thin command line wrapper around this crate.
+//!
+//! Reading from and remuxing to media containers, as well as decoding frames,
+//! goes through FFmpeg and requires the `ffmpeg` feature. Without it,
+//! [`BitstreamParser`] is fed one packet at a time with
+//! [`BitstreamParser::parse_packet`] and [`BitstreamParser::rewrite_packet`].
+
+pub mod filters;
+pub mod frame_records;
+pub mod grain_table;
+pub mod ivf;
+pub mod misc;
+pub mod obu_stream;
+pub mod parser;
+#[cfg(feature = "ffmpeg")]
+pub mod reader;
+
+pub use filters::FilterChain;
+pub use frame_records::{FrameRecord, write_frame_records_csv, write_frame_records_json};
+pub use grain_table::{
+    GrainTableSegment, aggregate_grain_headers, group_grain_headers_by_layer, read_grain_table,
+    write_film_grain_segment, write_grain_table,
+};
+pub use parser::{
+    BitstreamParser,
+    grain::{FilmGrainHeader, FilmGrainParams, FrameGrainHeader},
+};
+#[cfg(feature = "ffmpeg")]
+pub use reader::{BitstreamReader, NotAv1Error, StreamSelection, VideoDetails};
@@ -1,14 +1,10 @@
-mod filters;
-mod misc;
-pub mod parser;
-pub mod reader;
-
 use std::{
+    collections::{BTreeMap, BTreeSet},
     env,
     fs::{File, read_to_string},
-    io::{BufWriter, Write, stderr},
+    io::{BufRead, BufReader, BufWriter, Write, stderr},
     num::NonZeroU8,
-    path::PathBuf,
+    path::{Path, PathBuf},
     time::Duration,
 };
 
@@ -16,7 +12,7 @@ use anyhow::{Result, anyhow, bail};
 #[cfg(feature = "unstable")]
 use av1_grain::estimate_plane_noise;
 use av1_grain::{
-    DiffGenerator, TransferFunction, generate_photon_noise_params, parse_grain_table,
+    DiffGenerator, TransferFunction, generate_photon_noise_params,
     v_frame::{frame::Frame, pixel::Pixel},
 };
 use clap::{Parser, Subcommand};
@@ -26,14 +22,32 @@ use ffmpeg::{
     ffi::{AVColorRange, AVColorTransferCharacteristic},
     format,
 };
+use grav1synth::{
+    BitstreamParser, BitstreamReader, FilmGrainHeader, FilterChain, FrameGrainHeader, FrameRecord,
+    GrainTableSegment, StreamSelection, aggregate_grain_headers,
+    ivf::IvfReader,
+    misc::get_frame_count,
+    obu_stream::{ObuFraming, ObuStreamReader},
+    parser::{
+        frame::Dimensions,
+        metadata::{
+            ContentLightLevel, ItutT35Kind, MasteringDisplay, Metadata, MetadataType, TimedMetadata,
+        },
+        sequence::{
+            ColorDescription, ColorPrimaries, ColorRange, MatrixCoefficients, SequenceHeader,
+            TimingInfo, TransferCharacteristics,
+        },
+    },
+    read_grain_table, write_frame_records_csv, write_frame_records_json, write_grain_table,
+};
 use indicatif::{HumanDuration, ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
 use log::{debug, error, info, warn};
 use num_rational::Rational32;
-use parser::grain::{FilmGrainHeader, FilmGrainParams};
 
-use crate::{
-    filters::FilterChain, misc::get_frame_count, parser::BitstreamParser, reader::BitstreamReader,
-};
+/// The peak luminance in cd/m² that BT.1886 assumes for SDR displays.
+const SDR_PEAK_LUMINANCE: f64 = 100.0;
+/// How many packets of the video stream `generate` searches for HDR metadata.
+const METADATA_PROBE_PACKETS: usize = 16;
 
 const PROGRESS_CHARS: &str = "█▉▊▋▌▍▎▏  ";
 const INDICATIF_PROGRESS_TEMPLATE: &str = if cfg!(windows) {
@@ -148,6 +162,11 @@ pub fn main() -> Result<()> {
             input,
             output,
             overwrite,
+            absolute_timestamps,
+            frame_rate,
+            stream,
+            operating_point,
+            per_layer,
         } => {
             if input == output {
                 error!(
@@ -157,50 +176,140 @@ pub fn main() -> Result<()> {
                 return Ok(());
             }
 
-            if output.exists()
-                && !overwrite
-                && !Confirm::new()
-                    .with_prompt(format!(
-                        "File {} exists. Overwrite?",
-                        output.to_string_lossy()
-                    ))
-                    .interact()?
-            {
+            // With every stream or layer split out, each table gets its own path, which is
+            // checked once the streams are known.
+            let all_streams =
+                stream == StreamSelection::AllAv1 && !is_ivf(&input) && !is_obu_stream(&input);
+            let split_outputs = all_streams || per_layer;
+            if !split_outputs && !confirm_overwrite(&output, overwrite)? {
                 warn!("Not overwriting existing file. Exiting.");
                 return Ok(());
             }
 
-            let reader = BitstreamReader::open(&input)?;
-            let frame_rate = reader.get_video_details().frame_rate;
-            let mut parser: BitstreamParser<false> = BitstreamParser::new(reader);
-            let grain_headers = parser.get_grain_headers()?;
+            let mut parser: BitstreamParser<false>;
+            let streams: Vec<(usize, &[FrameGrainHeader], Rational32)> = if is_ivf(&input) {
+                warn_stream_ignored(stream);
+                let reader = IvfReader::new(BufReader::new(File::open(&input)?))?;
+                let frame_rate = reader.header().frame_rate();
+                parser = BitstreamParser::without_container(None)
+                    .with_absolute_timestamps(absolute_timestamps)
+                    .with_operating_point(operating_point);
+                vec![(0, parser.parse_ivf(reader)?, frame_rate)]
+            } else if is_obu_stream(&input) {
+                warn_stream_ignored(stream);
+                parser =
+                    BitstreamParser::without_container(None).with_operating_point(operating_point);
+                parser.parse_obu_stream(open_obu_stream(&input)?, frame_rate)?;
+                let frame_rate = frame_rate
+                    .or_else(|| parser.signalled_frame_rate())
+                    .ok_or_else(|| anyhow!("No frame rate given for the OBU stream"))?;
+                vec![(0, parser.grain_headers(), frame_rate)]
+            } else {
+                let reader = BitstreamReader::open_streams(&input, stream)?;
+                let frame_rates: Vec<Rational32> = reader
+                    .stream_indices()
+                    .iter()
+                    .filter_map(|&index| reader.frame_rate(index))
+                    .collect();
+                parser = BitstreamParser::new(reader)
+                    .with_absolute_timestamps(absolute_timestamps)
+                    .with_operating_point(operating_point);
+                parser
+                    .get_stream_grain_headers()?
+                    .into_iter()
+                    .zip(frame_rates)
+                    .map(|((index, grain_headers), frame_rate)| (index, grain_headers, frame_rate))
+                    .collect()
+            };
 
-            if !grain_headers
-                .iter()
-                .any(|h| matches!(h, &FilmGrainHeader::UpdateGrain(_)))
-            {
-                info!("No film grain headers found--this video does not use grain synthesis");
-                return Ok(());
-            }
+            for (index, grain_headers, frame_rate) in streams {
+                let output = if all_streams {
+                    suffixed_output_path(&output, &format!("stream{index}"))
+                } else {
+                    output.clone()
+                };
+                let spatial_ids: BTreeSet<u8> =
+                    grain_headers.iter().map(|h| h.spatial_id).collect();
+                let tables: Vec<(PathBuf, Vec<FrameGrainHeader>, String)> = if per_layer {
+                    spatial_ids
+                        .into_iter()
+                        .map(|spatial_id| {
+                            (
+                                suffixed_output_path(&output, &format!("layer{spatial_id}")),
+                                grain_headers
+                                    .iter()
+                                    .filter(|h| h.spatial_id == spatial_id)
+                                    .cloned()
+                                    .collect(),
+                                format!("stream {index}, spatial layer {spatial_id}"),
+                            )
+                        })
+                        .collect()
+                } else {
+                    if spatial_ids.len() > 1 {
+                        warn!(
+                            "Stream {index} has {} spatial layers, which share one table. Use \
+                             --per-layer to write a table for each.",
+                            spatial_ids.len()
+                        );
+                    }
+                    vec![(output, grain_headers.to_vec(), format!("stream {index}"))]
+                };
 
-            // As you can expect, this may lead to odd behaviors with VFR.
-            // VFR is cursed.
-            let grain_tables = aggregate_grain_headers(grain_headers, frame_rate);
+                for (output, grain_headers, description) in tables {
+                    if !grain_headers
+                        .iter()
+                        .any(|h| matches!(h.film_grain_params, FilmGrainHeader::UpdateGrain(_)))
+                    {
+                        if split_outputs {
+                            info!("No film grain headers found in {description}");
+                        } else {
+                            info!(
+                                "No film grain headers found--this video does not use grain \
+                                 synthesis"
+                            );
+                        }
+                        continue;
+                    }
 
-            let mut output_file = BufWriter::new(File::create(&output)?);
-            writeln!(&mut output_file, "filmgrn1")?;
-            for segment in grain_tables {
-                write_film_grain_segment(&segment, &mut output_file)?;
+                    if split_outputs && !confirm_overwrite(&output, overwrite)? {
+                        warn!(
+                            "Not overwriting existing file {}.",
+                            output.to_string_lossy()
+                        );
+                        continue;
+                    }
+
+                    let grain_tables = aggregate_grain_headers(&grain_headers, frame_rate);
+
+                    let mut output_file = BufWriter::new(File::create(&output)?);
+                    write_grain_table(&grain_tables, &mut output_file)?;
+                    output_file.flush()?;
+
+                    info!("Done, wrote grain table to {}", output.to_string_lossy());
+                }
             }
-            output_file.flush()?;
 
-            info!("Done, wrote grain table to {}", output.to_string_lossy());
+            for (index, parser) in parser.stream_parsers() {
+                if all_streams {
+                    info!("Stream {index}:");
+                }
+                if let Some(sequence_header) = parser.sequence_header() {
+                    report_operating_points(sequence_header, operating_point);
+                }
+                report_metadata(parser.metadata());
+            }
         }
         Commands::Apply {
             input,
             output,
             overwrite,
             grain,
+            absolute_timestamps,
+            frame_rate,
+            stream,
+            operating_point,
+            layer_grain,
         } => {
             if input == output {
                 error!(
@@ -210,35 +319,80 @@ pub fn main() -> Result<()> {
                 return Ok(());
             }
 
-            if output.exists()
-                && !overwrite
-                && !Confirm::new()
-                    .with_prompt(format!(
-                        "File {} exists. Overwrite?",
-                        output.to_string_lossy()
-                    ))
-                    .interact()?
-            {
+            if !confirm_overwrite(&output, overwrite)? {
                 warn!("Not overwriting existing file. Exiting.");
                 return Ok(());
             }
 
-            let reader = BitstreamReader::open(&input)?;
-            let writer = format::output(&output)?;
-            let grain_data = read_to_string(grain)?;
-            let new_headers = parse_grain_table(&grain_data)?;
-            let mut parser: BitstreamParser<true> = BitstreamParser::with_writer(
-                reader,
-                writer,
-                Some(
-                    new_headers
-                        .into_iter()
-                        .map(|h| h.into())
-                        .collect::<Vec<_>>(),
-                ),
-            );
+            let new_headers = grain
+                .map(|grain| read_grain_table(&read_to_string(grain)?))
+                .transpose()?;
+            let layer_tables = layer_grain
+                .iter()
+                .map(|(spatial_id, path)| -> Result<_> {
+                    Ok((*spatial_id, read_grain_table(&read_to_string(path)?)?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            // Hidden frames take the grain of the time they are shown at, which a first
+            // pass over the input finds.
+            if is_ivf(&input) && is_ivf(&output) {
+                warn_stream_ignored(stream);
+                let mut scan = BitstreamParser::<false>::without_container(None)
+                    .with_absolute_timestamps(absolute_timestamps)
+                    .with_operating_point(operating_point);
+                scan.parse_ivf(IvfReader::new(BufReader::new(File::open(&input)?))?)?;
+                let reader = IvfReader::new(BufReader::new(File::open(&input)?))?;
+                let mut parser: BitstreamParser<true> = with_layer_grain_tables(
+                    BitstreamParser::without_container(new_headers)
+                        .with_absolute_timestamps(absolute_timestamps)
+                        .with_operating_point(operating_point)
+                        .with_display_times(scan.display_times().clone()),
+                    layer_tables,
+                );
+                parser.rewrite_ivf(reader, BufWriter::new(File::create(&output)?))?;
+                warn_unsignalled_operating_point(parser.sequence_header(), operating_point);
+            } else if is_obu_stream(&input) && is_obu_stream(&output) {
+                warn_stream_ignored(stream);
+                let mut scan = BitstreamParser::<false>::without_container(None)
+                    .with_operating_point(operating_point);
+                scan.parse_obu_stream(open_obu_stream(&input)?, frame_rate)?;
+                let mut parser: BitstreamParser<true> = with_layer_grain_tables(
+                    BitstreamParser::without_container(new_headers)
+                        .with_operating_point(operating_point)
+                        .with_display_times(scan.display_times().clone()),
+                    layer_tables,
+                );
+                parser.rewrite_obu_stream(
+                    open_obu_stream(&input)?,
+                    BufWriter::new(File::create(&output)?),
+                    frame_rate,
+                )?;
+                warn_unsignalled_operating_point(parser.sequence_header(), operating_point);
+            } else {
+                let reader = BitstreamReader::open_streams(&input, stream)?;
+                // Check before the output file is created.
+                reader.ensure_av1()?;
+                let mut scan =
+                    BitstreamParser::<false>::new(BitstreamReader::open_streams(&input, stream)?)
+                        .with_absolute_timestamps(absolute_timestamps)
+                        .with_operating_point(operating_point);
+                let display_times: Vec<(usize, BTreeMap<usize, u64>)> = scan
+                    .get_stream_display_times()?
+                    .into_iter()
+                    .map(|(index, display_times)| (index, display_times.clone()))
+                    .collect();
+                let writer = format::output(&output)?;
+                let mut parser: BitstreamParser<true> = with_layer_grain_tables(
+                    BitstreamParser::with_writer(reader, writer, new_headers)
+                        .with_absolute_timestamps(absolute_timestamps)
+                        .with_operating_point(operating_point)
+                        .with_stream_display_times(display_times),
+                    layer_tables,
+                );
 
-            parser.modify_grain_headers()?;
+                parser.modify_grain_headers()?;
+                warn_unsignalled_operating_point(parser.sequence_header(), operating_point);
+            }
 
             info!("Done, wrote output file to {}", output.to_string_lossy());
         }
@@ -248,6 +402,8 @@ pub fn main() -> Result<()> {
             overwrite,
             iso,
             chroma,
+            stream,
+            operating_point,
         } => {
             if input == output {
                 error!(
@@ -257,20 +413,13 @@ pub fn main() -> Result<()> {
                 return Ok(());
             }
 
-            if output.exists()
-                && !overwrite
-                && !Confirm::new()
-                    .with_prompt(format!(
-                        "File {} exists. Overwrite?",
-                        output.to_string_lossy()
-                    ))
-                    .interact()?
-            {
+            if !confirm_overwrite(&output, overwrite)? {
                 warn!("Not overwriting existing file. Exiting.");
                 return Ok(());
             }
 
-            let reader = BitstreamReader::open(&input)?;
+            let reader = BitstreamReader::open_streams(&input, stream)?;
+            reader.ensure_av1()?;
             let writer = format::output(&output)?;
             // SAFETY: We extract the items we need from the struct within the unsafe block,
             // so there's no possibility of use-after-free later.
@@ -285,6 +434,21 @@ pub fn main() -> Result<()> {
                 )
             };
 
+            // Files without a PQ transfer in the container can still carry HDR mastering
+            // metadata in band.
+            let transfer_function = if trc == AVColorTransferCharacteristic::SMPTE2084 {
+                TransferFunction::SMPTE2084
+            } else if let Some(mastering_display) = probe_mastering_display(&input, stream)?
+                && mastering_display.max_luminance() > SDR_PEAK_LUMINANCE
+            {
+                info!(
+                    "Mastering display peaks at {:.0} cd/m², generating grain for PQ",
+                    mastering_display.max_luminance()
+                );
+                TransferFunction::SMPTE2084
+            } else {
+                TransferFunction::BT1886
+            };
             let grain_data = generate_photon_noise_params(
                 0,
                 u64::MAX,
@@ -292,20 +456,18 @@ pub fn main() -> Result<()> {
                     iso_setting: iso,
                     width,
                     height,
-                    transfer_function: if trc == AVColorTransferCharacteristic::SMPTE2084 {
-                        TransferFunction::SMPTE2084
-                    } else {
-                        TransferFunction::BT1886
-                    },
+                    transfer_function,
                     chroma_grain: chroma,
                     full_range: range == AVColorRange::JPEG,
                     random_seed: None,
                 },
             );
             let mut parser: BitstreamParser<true> =
-                BitstreamParser::with_writer(reader, writer, Some(vec![grain_data.into()]));
+                BitstreamParser::with_writer(reader, writer, Some(vec![grain_data.into()]))
+                    .with_operating_point(operating_point);
 
             parser.modify_grain_headers()?;
+            warn_unsignalled_operating_point(parser.sequence_header(), operating_point);
 
             info!("Done, wrote output file to {}", output.to_string_lossy());
         }
@@ -313,6 +475,9 @@ pub fn main() -> Result<()> {
             input,
             output,
             overwrite,
+            frame_rate,
+            stream,
+            operating_point,
         } => {
             if input == output {
                 error!(
@@ -322,28 +487,214 @@ pub fn main() -> Result<()> {
                 return Ok(());
             }
 
-            if output.exists()
-                && !overwrite
-                && !Confirm::new()
-                    .with_prompt(format!(
-                        "File {} exists. Overwrite?",
-                        output.to_string_lossy()
-                    ))
-                    .interact()?
-            {
+            if !confirm_overwrite(&output, overwrite)? {
                 warn!("Not overwriting existing file. Exiting.");
                 return Ok(());
             }
 
-            let reader = BitstreamReader::open(&input)?;
-            let writer = format::output(&output)?;
-            let mut parser: BitstreamParser<true> =
-                BitstreamParser::with_writer(reader, writer, None);
+            if is_ivf(&input) && is_ivf(&output) {
+                warn_stream_ignored(stream);
+                let reader = IvfReader::new(BufReader::new(File::open(&input)?))?;
+                let mut parser: BitstreamParser<true> =
+                    BitstreamParser::without_container(None).with_operating_point(operating_point);
+                parser.rewrite_ivf(reader, BufWriter::new(File::create(&output)?))?;
+                warn_unsignalled_operating_point(parser.sequence_header(), operating_point);
+            } else if is_obu_stream(&input) && is_obu_stream(&output) {
+                warn_stream_ignored(stream);
+                let mut parser: BitstreamParser<true> =
+                    BitstreamParser::without_container(None).with_operating_point(operating_point);
+                parser.rewrite_obu_stream(
+                    open_obu_stream(&input)?,
+                    BufWriter::new(File::create(&output)?),
+                    frame_rate,
+                )?;
+                warn_unsignalled_operating_point(parser.sequence_header(), operating_point);
+            } else {
+                let reader = BitstreamReader::open_streams(&input, stream)?;
+                // Check before the output file is created.
+                reader.ensure_av1()?;
+                let writer = format::output(&output)?;
+                let mut parser: BitstreamParser<true> =
+                    BitstreamParser::with_writer(reader, writer, None)
+                        .with_operating_point(operating_point);
 
-            parser.modify_grain_headers()?;
+                parser.modify_grain_headers()?;
+                warn_unsignalled_operating_point(parser.sequence_header(), operating_point);
+            }
 
             info!("Done, wrote output file to {}", output.to_string_lossy());
         }
+        Commands::Metadata {
+            input,
+            output,
+            overwrite,
+            strip,
+            mdcv,
+            cll,
+            frame_rate,
+            stream,
+        } => {
+            let insert = mdcv
+                .map(Metadata::HdrMdcv)
+                .into_iter()
+                .chain(cll.map(Metadata::HdrCll))
+                .collect::<Vec<_>>();
+            if strip.is_empty() && insert.is_empty() {
+                bail!("Nothing to do, give --strip, --mdcv or --cll");
+            }
+
+            rewrite_stream(&input, &output, overwrite, stream, frame_rate, |parser| {
+                with_metadata_edits(parser, &strip, &insert)
+            })?;
+        }
+        Commands::Color {
+            input,
+            output,
+            overwrite,
+            primaries,
+            transfer,
+            matrix,
+            range,
+            frame_rate,
+            stream,
+        } => {
+            let color_description = ColorDescription {
+                color_primaries: primaries,
+                transfer_characteristics: transfer,
+                matrix_coefficients: matrix,
+                color_range: range,
+            };
+            if color_description == ColorDescription::default() {
+                bail!("Nothing to do, give --primaries, --transfer, --matrix or --range");
+            }
+
+            rewrite_stream(&input, &output, overwrite, stream, frame_rate, |parser| {
+                Ok(parser
+                    .with_original_grain()
+                    .with_color_description(color_description))
+            })?;
+        }
+        Commands::Timing {
+            input,
+            output,
+            overwrite,
+            frame_rate,
+            container,
+            stream,
+        } => {
+            let timing_info = TimingInfo::from_frame_rate(frame_rate)
+                .ok_or_else(|| anyhow!("Frame rate {frame_rate} does not fit in timing_info"))?;
+            if container && is_obu_stream(&input) && is_obu_stream(&output) {
+                warn!("Raw OBU streams have no container frame rate to change");
+            }
+
+            let with_timing = |parser: BitstreamParser<true>| {
+                let parser = parser.with_original_grain().with_timing_info(timing_info);
+                Ok(if container {
+                    parser.with_container_frame_rate(frame_rate)
+                } else {
+                    parser
+                })
+            };
+            rewrite_stream(
+                &input,
+                &output,
+                overwrite,
+                stream,
+                Some(frame_rate),
+                with_timing,
+            )?;
+        }
+        Commands::RenderSize {
+            input,
+            output,
+            overwrite,
+            size,
+            frame_rate,
+            stream,
+        } => {
+            rewrite_stream(&input, &output, overwrite, stream, frame_rate, |parser| {
+                parser.with_original_grain().with_render_size(size)
+            })?;
+        }
+        Commands::Frames {
+            input,
+            output,
+            overwrite,
+            absolute_timestamps,
+            frame_rate,
+            stream,
+            operating_point,
+        } => {
+            if input == output {
+                error!(
+                    "Input and output paths are the same. This is probably a typo, because this \
+                     would overwrite your input. Exiting."
+                );
+                return Ok(());
+            }
+
+            // With every stream split out, each file gets its own path, which is checked
+            // once the streams are known.
+            let all_streams =
+                stream == StreamSelection::AllAv1 && !is_ivf(&input) && !is_obu_stream(&input);
+            if !all_streams && !confirm_overwrite(&output, overwrite)? {
+                warn!("Not overwriting existing file. Exiting.");
+                return Ok(());
+            }
+
+            let mut parser: BitstreamParser<false>;
+            let streams: Vec<(usize, &[FrameRecord])> = if is_ivf(&input) {
+                warn_stream_ignored(stream);
+                let reader = IvfReader::new(BufReader::new(File::open(&input)?))?;
+                parser = BitstreamParser::without_container(None)
+                    .with_absolute_timestamps(absolute_timestamps)
+                    .with_operating_point(operating_point);
+                parser.parse_ivf(reader)?;
+                vec![(0, parser.frame_records())]
+            } else if is_obu_stream(&input) {
+                warn_stream_ignored(stream);
+                parser =
+                    BitstreamParser::without_container(None).with_operating_point(operating_point);
+                parser.parse_obu_stream(open_obu_stream(&input)?, frame_rate)?;
+                vec![(0, parser.frame_records())]
+            } else {
+                let reader = BitstreamReader::open_streams(&input, stream)?;
+                parser = BitstreamParser::new(reader)
+                    .with_absolute_timestamps(absolute_timestamps)
+                    .with_operating_point(operating_point);
+                parser.get_stream_frame_records()?
+            };
+
+            for (index, records) in streams {
+                let output = if all_streams {
+                    suffixed_output_path(&output, &format!("stream{index}"))
+                } else {
+                    output.clone()
+                };
+                if all_streams && !confirm_overwrite(&output, overwrite)? {
+                    warn!(
+                        "Not overwriting existing file {}.",
+                        output.to_string_lossy()
+                    );
+                    continue;
+                }
+
+                let mut output_file = BufWriter::new(File::create(&output)?);
+                if is_json(&output) {
+                    write_frame_records_json(records, &mut output_file)?;
+                } else {
+                    write_frame_records_csv(records, &mut output_file)?;
+                }
+                output_file.flush()?;
+
+                info!(
+                    "Done, wrote {} frames to {}",
+                    records.len(),
+                    output.to_string_lossy()
+                );
+            }
+        }
         Commands::Diff {
             source,
             denoised,
@@ -379,15 +730,7 @@ pub fn main() -> Result<()> {
                 None => None,
             };
 
-            if output.exists()
-                && !overwrite
-                && !Confirm::new()
-                    .with_prompt(format!(
-                        "File {} exists. Overwrite?",
-                        output.to_string_lossy()
-                    ))
-                    .interact()?
-            {
+            if !confirm_overwrite(&output, overwrite)? {
                 warn!("Not overwriting existing file. Exiting.");
                 return Ok(());
             }
@@ -521,12 +864,13 @@ pub fn main() -> Result<()> {
             }
             progress.finish();
 
-            let grain_tables = differ.finish();
+            let grain_tables = differ
+                .finish()
+                .into_iter()
+                .map(GrainTableSegment::from)
+                .collect::<Vec<_>>();
             let mut output_file = BufWriter::new(File::create(&output)?);
-            writeln!(&mut output_file, "filmgrn1")?;
-            for segment in grain_tables {
-                write_film_grain_segment(&segment.into(), &mut output_file)?;
-            }
+            write_grain_table(&grain_tables, &mut output_file)?;
             output_file.flush()?;
             info!("Computed diff for {frames} frames");
             info!("Done, wrote output file to {}", output.to_string_lossy());
@@ -546,15 +890,7 @@ pub fn main() -> Result<()> {
                 return Ok(());
             }
 
-            if output.exists()
-                && !overwrite
-                && !Confirm::new()
-                    .with_prompt(format!(
-                        "File {} exists. Overwrite?",
-                        output.to_string_lossy()
-                    ))
-                    .interact()?
-            {
+            if !confirm_overwrite(&output, overwrite)? {
                 warn!("Not overwriting existing file. Exiting.");
                 return Ok(());
             }
@@ -611,164 +947,502 @@ pub fn main() -> Result<()> {
     Ok(())
 }
 
-#[allow(clippy::type_complexity)]
-fn get_filtered_frame_pair<T: Pixel, U: Pixel>(
-    source_reader: &mut BitstreamReader,
-    denoised_reader: &mut BitstreamReader,
-    source_bd: NonZeroU8,
-    filters: Option<&FilterChain>,
-) -> Result<(Option<Frame<T>>, Option<Frame<U>>)> {
-    let mut frame = source_reader.get_frame::<T>();
-    if let Some(f) = filters.as_ref() {
-        frame = frame.map(|opt| opt.map(|source_frame| f.apply(source_frame, source_bd)));
-    }
-    let source_frame = frame;
-    let denoised_frame = denoised_reader.get_frame::<U>();
+/// IVF files are read and written natively instead of through FFmpeg.
+fn is_ivf(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ivf"))
+}
 
-    Ok((source_frame?, denoised_frame?))
+/// Raw `.obu` elementary streams are likewise handled natively, in either framing.
+fn is_obu_stream(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("obu"))
 }
 
-fn write_film_grain_segment(
-    segment: &GrainTableSegment,
-    output: &mut BufWriter<File>,
-) -> anyhow::Result<()> {
-    let params = &segment.grain_params;
-
-    writeln!(
-        output,
-        "E {} {} 1 {} 1",
-        segment.start_time, segment.end_time, params.grain_seed,
-    )?;
-    writeln!(
-        output,
-        "\tp {} {} {} {} {} {} {} {} {} {} {} {}",
-        params.ar_coeff_lag,
-        params.ar_coeff_shift,
-        params.grain_scale_shift,
-        params.scaling_shift,
-        u8::from(params.chroma_scaling_from_luma),
-        u8::from(params.overlap_flag),
-        params.cb_mult,
-        params.cb_luma_mult,
-        params.cb_offset,
-        params.cr_mult,
-        params.cr_luma_mult,
-        params.cr_offset
-    )?;
-
-    write!(output, "\tsY {} ", params.scaling_points_y.len())?;
-    for point in &params.scaling_points_y {
-        write!(output, " {} {}", point[0], point[1])?;
-    }
-    writeln!(output)?;
+/// `frames` writes JSON to `.json` files and CSV to anything else.
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
 
-    write!(output, "\tsCb {}", params.scaling_points_cb.len())?;
-    for point in &params.scaling_points_cb {
-        write!(output, " {} {}", point[0], point[1])?;
-    }
-    writeln!(output)?;
+/// Whether `output` may be written: it does not exist yet, `overwrite` is set, or the
+/// user agrees to overwrite it.
+fn confirm_overwrite(output: &Path, overwrite: bool) -> Result<bool> {
+    Ok(!output.exists()
+        || overwrite
+        || Confirm::new()
+            .with_prompt(format!(
+                "File {} exists. Overwrite?",
+                output.to_string_lossy()
+            ))
+            .interact()?)
+}
 
-    write!(output, "\tsCr {}", params.scaling_points_cr.len())?;
-    for point in &params.scaling_points_cr {
-        write!(output, " {} {}", point[0], point[1])?;
+/// Elementary streams and IVF files hold a single stream, so there is nothing to select.
+fn warn_stream_ignored(stream: StreamSelection) {
+    if stream != StreamSelection::Best {
+        warn!("--stream only applies to media containers, ignoring it");
     }
-    writeln!(output)?;
+}
 
-    write!(output, "\tcY")?;
-    for coeff in &params.ar_coeffs_y {
-        write!(output, " {}", *coeff)?;
+/// Rewrites `input` into `output` with the parser that `configure` sets up, natively
+/// for IVF files and OBU streams and through FFmpeg for anything else.
+///
+/// Nothing is written if the paths are the same or the user declines to overwrite
+/// `output`.
+fn rewrite_stream(
+    input: &Path,
+    output: &Path,
+    overwrite: bool,
+    stream: StreamSelection,
+    frame_rate: Option<Rational32>,
+    configure: impl FnOnce(BitstreamParser<true>) -> Result<BitstreamParser<true>>,
+) -> Result<()> {
+    if input == output {
+        error!(
+            "Input and output paths are the same. This is probably a typo, because this would \
+             overwrite your input. Exiting."
+        );
+        return Ok(());
     }
-    writeln!(output)?;
 
-    write!(output, "\tcCb")?;
-    for coeff in &params.ar_coeffs_cb {
-        write!(output, " {}", *coeff)?;
+    if !confirm_overwrite(output, overwrite)? {
+        warn!("Not overwriting existing file. Exiting.");
+        return Ok(());
     }
-    writeln!(output)?;
 
-    write!(output, "\tcCr")?;
-    for coeff in &params.ar_coeffs_cr {
-        write!(output, " {}", *coeff)?;
+    if is_ivf(input) && is_ivf(output) {
+        warn_stream_ignored(stream);
+        let reader = IvfReader::new(BufReader::new(File::open(input)?))?;
+        let mut parser = configure(BitstreamParser::without_container(None))?;
+        parser.rewrite_ivf(reader, BufWriter::new(File::create(output)?))?;
+    } else if is_obu_stream(input) && is_obu_stream(output) {
+        warn_stream_ignored(stream);
+        let mut parser = configure(BitstreamParser::without_container(None))?;
+        parser.rewrite_obu_stream(
+            open_obu_stream(input)?,
+            BufWriter::new(File::create(output)?),
+            frame_rate,
+        )?;
+    } else {
+        let reader = BitstreamReader::open_streams(input, stream)?;
+        // Check before the output file is created.
+        reader.ensure_av1()?;
+        let writer = format::output(output)?;
+        let mut parser = configure(BitstreamParser::with_writer(reader, writer, None))?;
+
+        parser.modify_grain_headers()?;
     }
-    writeln!(output)?;
 
+    info!("Done, wrote output file to {}", output.to_string_lossy());
     Ok(())
 }
 
-#[derive(Debug, Clone)]
-pub struct GrainTableSegment {
-    pub start_time: u64,
-    pub end_time: u64,
-    pub grain_params: FilmGrainParams,
+/// Gives each spatial layer its own grain table, from `--layer-grain`.
+fn with_layer_grain_tables(
+    parser: BitstreamParser<true>,
+    layer_tables: Vec<(u8, Vec<GrainTableSegment>)>,
+) -> BitstreamParser<true> {
+    layer_tables
+        .into_iter()
+        .fold(parser, |parser, (spatial_id, table)| {
+            parser.with_layer_grain(spatial_id, table)
+        })
+}
+
+/// Parses a `--layer-grain` value of the form `<spatial_id>=<path>`.
+fn parse_layer_grain(input: &str) -> Result<(u8, PathBuf), String> {
+    let (spatial_id, path) = input
+        .split_once('=')
+        .ok_or_else(|| "expected <spatial_id>=<path>".to_string())?;
+    let spatial_id = spatial_id.trim().parse::<u8>().map_err(|e| e.to_string())?;
+    if spatial_id > 3 {
+        return Err("spatial_id must be between 0 and 3".to_string());
+    }
+    Ok((spatial_id, PathBuf::from(path)))
+}
+
+/// Sets up a parser that only edits metadata, keeping the film grain of every frame.
+///
+/// Metadata of an inserted type is stripped as well, so it replaces what was in the stream.
+fn with_metadata_edits(
+    parser: BitstreamParser<true>,
+    strip: &[u64],
+    insert: &[Metadata],
+) -> Result<BitstreamParser<true>> {
+    let parser = strip
+        .iter()
+        .copied()
+        .chain(insert.iter().map(Metadata::metadata_type))
+        .fold(
+            parser.with_original_grain(),
+            BitstreamParser::with_stripped_metadata,
+        );
+    insert
+        .iter()
+        .try_fold(parser, BitstreamParser::with_inserted_metadata)
+}
+
+/// Parses a `--strip` value: the name of a registered metadata type, or any
+/// `metadata_type` number.
+fn parse_metadata_type(input: &str) -> Result<u64, String> {
+    let metadata_type = match input.trim().to_ascii_lowercase().as_str() {
+        "cll" => MetadataType::HdrCll,
+        "mdcv" => MetadataType::HdrMdcv,
+        "scalability" => MetadataType::Scalability,
+        "t35" => MetadataType::ItutT35,
+        "timecode" => MetadataType::Timecode,
+        other => {
+            return other.parse::<u64>().map_err(|_| {
+                format!(
+                    "unknown metadata type {other}, expected cll, mdcv, scalability, t35, \
+                     timecode or a number"
+                )
+            });
+        }
+    };
+    Ok(metadata_type as u64)
+}
+
+/// Parses a `--cll` value of the form `<max_cll>,<max_fall>`.
+fn parse_content_light_level(input: &str) -> Result<ContentLightLevel, String> {
+    let (max_cll, max_fall) = input
+        .split_once(',')
+        .ok_or_else(|| "expected <max_cll>,<max_fall>".to_string())?;
+    Ok(ContentLightLevel {
+        max_cll: max_cll.trim().parse::<u16>().map_err(|e| e.to_string())?,
+        max_fall: max_fall.trim().parse::<u16>().map_err(|e| e.to_string())?,
+    })
 }
 
-impl From<av1_grain::GrainTableSegment> for GrainTableSegment {
-    fn from(data: av1_grain::GrainTableSegment) -> Self {
-        GrainTableSegment {
-            start_time: data.start_time,
-            end_time: data.end_time,
-            grain_params: data.into(),
+/// Parses a `--mdcv` value of the form `G(x,y)B(x,y)R(x,y)WP(x,y)L(max,min)`, as taken
+/// by SVT-AV1's `--mastering-display`. Chromaticities are CIE 1931 coordinates and
+/// luminance is in cd/m².
+fn parse_mastering_display(input: &str) -> Result<MasteringDisplay, String> {
+    let pair = |label: &str| -> Result<(f64, f64), String> {
+        let start = input
+            .find(&format!("{label}("))
+            .ok_or_else(|| format!("missing {label}(...)"))?
+            + label.len()
+            + 1;
+        let (x, y) = input[start..]
+            .split_once(')')
+            .and_then(|(values, _)| values.split_once(','))
+            .ok_or_else(|| format!("expected two values in {label}(...)"))?;
+        let x = x
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("{label}: {e}"))?;
+        let y = y
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("{label}: {e}"))?;
+        Ok((x, y))
+    };
+    let chromaticity = |label: &str| -> Result<(u16, u16), String> {
+        let (x, y) = pair(label)?;
+        if !(0.0..1.0).contains(&x) || !(0.0..1.0).contains(&y) {
+            return Err(format!("{label} coordinates must be between 0 and 1"));
         }
+        // 0.16 fixed point
+        let scale = f64::from(1 << 16);
+        Ok(((x * scale).round() as u16, (y * scale).round() as u16))
+    };
+
+    let mut primary_chromaticity_x = [0; 3];
+    let mut primary_chromaticity_y = [0; 3];
+    for (i, label) in ["R", "G", "B"].into_iter().enumerate() {
+        (primary_chromaticity_x[i], primary_chromaticity_y[i]) = chromaticity(label)?;
+    }
+    let (white_point_chromaticity_x, white_point_chromaticity_y) = chromaticity("WP")?;
+    let (max_luminance, min_luminance) = pair("L")?;
+    if !(0.0..=max_luminance).contains(&min_luminance) {
+        return Err("luminance must be given as max,min with min <= max".to_string());
     }
+    // The largest values that 24.8 and 18.14 fixed point can hold
+    if max_luminance > f64::from(u32::MAX >> 8) || min_luminance > f64::from(u32::MAX >> 14) {
+        return Err(format!(
+            "luminance must be at most {} cd/m² for max and {} cd/m² for min",
+            u32::MAX >> 8,
+            u32::MAX >> 14
+        ));
+    }
+    Ok(MasteringDisplay {
+        primary_chromaticity_x,
+        primary_chromaticity_y,
+        white_point_chromaticity_x,
+        white_point_chromaticity_y,
+        // 24.8 and 18.14 fixed point
+        luminance_max: (max_luminance * f64::from(1 << 8)).round() as u32,
+        luminance_min: (min_luminance * f64::from(1 << 14)).round() as u32,
+    })
 }
 
-// I don't know why this is the base unit for a timestamp but it is. 1/10000000
-// of a second.
-const TIMESTAMP_BASE_UNIT: f64 = 10_000_000f64;
-
-fn aggregate_grain_headers(
-    grain_headers: &[FilmGrainHeader],
-    frame_rate: Rational32,
-) -> Vec<GrainTableSegment> {
-    let time_per_packet: f64 =
-        *frame_rate.denom() as f64 / *frame_rate.numer() as f64 * TIMESTAMP_BASE_UNIT;
-    let mut cur_packet_start: u64 = 0;
-    let mut cur_packet_end_f: f64 = time_per_packet;
-    let mut cur_packet_end: u64 = cur_packet_end_f.ceil() as u64;
-
-    grain_headers.iter().fold(Vec::new(), |mut acc, elem| {
-        let prev_packet_has_grain = acc
-            .last()
-            .is_some_and(|last: &GrainTableSegment| last.end_time == cur_packet_start);
-        if prev_packet_has_grain {
-            match *elem {
-                FilmGrainHeader::Disable => {
-                    // Do nothing. This will disable film grain for this
-                    // and future frames.
-                }
-                FilmGrainHeader::CopyRefFrame => {
-                    // Increment the end time of the current table segment.
-                    let cur_segment = acc.last_mut().expect("prev_packet_has_grain is true");
-                    cur_segment.end_time = cur_packet_end;
-                }
-                FilmGrainHeader::UpdateGrain(ref grain_params) => {
-                    let cur_segment = acc.last_mut().expect("prev_packet_has_grain is true");
-                    if grain_params == &cur_segment.grain_params {
-                        // Increment the end time of the current table segment.
-                        cur_segment.end_time = cur_packet_end;
-                    } else {
-                        // The grain params changed, so we have to make a new segment.
-                        acc.push(GrainTableSegment {
-                            start_time: cur_packet_start,
-                            end_time: cur_packet_end,
-                            grain_params: grain_params.clone(),
-                        });
+/// Parses a color code given by name, as in FFmpeg, or by its number in ITU-T H.273.
+fn parse_color_code<T: TryFrom<u8>>(input: &str, names: &[(&str, u8)]) -> Result<T, String> {
+    let input = input.trim().to_ascii_lowercase();
+    let code = names
+        .iter()
+        .find(|(name, _)| *name == input)
+        .map(|&(_, code)| code)
+        .or_else(|| input.parse().ok())
+        .ok_or_else(|| {
+            let names = names.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+            format!("expected a number or one of {}", names.join(", "))
+        })?;
+    T::try_from(code).map_err(|_| format!("{code} is not a valid value"))
+}
+
+fn parse_color_primaries(input: &str) -> Result<ColorPrimaries, String> {
+    parse_color_code(
+        input,
+        &[
+            ("bt709", 1),
+            ("unspecified", 2),
+            ("bt470m", 4),
+            ("bt470bg", 5),
+            ("smpte170m", 6),
+            ("smpte240m", 7),
+            ("film", 8),
+            ("bt2020", 9),
+            ("smpte428", 10),
+            ("smpte431", 11),
+            ("smpte432", 12),
+            ("ebu3213", 22),
+        ],
+    )
+}
+
+fn parse_transfer_characteristics(input: &str) -> Result<TransferCharacteristics, String> {
+    parse_color_code(
+        input,
+        &[
+            ("bt709", 1),
+            ("unspecified", 2),
+            ("bt470m", 4),
+            ("bt470bg", 5),
+            ("smpte170m", 6),
+            ("smpte240m", 7),
+            ("linear", 8),
+            ("log100", 9),
+            ("log316", 10),
+            ("iec61966-2-4", 11),
+            ("bt1361e", 12),
+            ("iec61966-2-1", 13),
+            ("bt2020-10", 14),
+            ("bt2020-12", 15),
+            ("smpte2084", 16),
+            ("smpte428", 17),
+            ("arib-std-b67", 18),
+        ],
+    )
+}
+
+fn parse_matrix_coefficients(input: &str) -> Result<MatrixCoefficients, String> {
+    parse_color_code(
+        input,
+        &[
+            ("gbr", 0),
+            ("bt709", 1),
+            ("unspecified", 2),
+            ("fcc", 4),
+            ("bt470bg", 5),
+            ("smpte170m", 6),
+            ("smpte240m", 7),
+            ("ycgco", 8),
+            ("bt2020nc", 9),
+            ("bt2020c", 10),
+            ("smpte2085", 11),
+            ("chroma-derived-nc", 12),
+            ("chroma-derived-c", 13),
+            ("ictcp", 14),
+        ],
+    )
+}
+
+fn parse_color_range(input: &str) -> Result<ColorRange, String> {
+    parse_color_code(input, &[("tv", 0), ("limited", 0), ("pc", 1), ("full", 1)])
+}
+
+/// Summarizes the metadata OBUs of the stream. Static HDR metadata is shown as first
+/// signalled; per-frame metadata is counted.
+fn report_metadata(metadata: &[TimedMetadata]) {
+    let mut reported = BTreeSet::new();
+    let mut t35_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut other_count = 0;
+    for TimedMetadata { metadata, .. } in metadata {
+        match metadata {
+            Metadata::HdrCll(cll) if reported.insert("cll") => {
+                info!(
+                    "Content light level: MaxCLL {} cd/m², MaxFALL {} cd/m²",
+                    cll.max_cll, cll.max_fall
+                );
+            }
+            Metadata::HdrMdcv(mdcv) if reported.insert("mdcv") => {
+                info!(
+                    "Mastering display luminance: {:.4}-{:.0} cd/m²",
+                    mdcv.min_luminance(),
+                    mdcv.max_luminance()
+                );
+            }
+            Metadata::ItutT35(t35) => {
+                let kind = match t35.kind() {
+                    ItutT35Kind::Hdr10Plus => "HDR10+ dynamic metadata",
+                    ItutT35Kind::DolbyVisionRpu => "Dolby Vision RPUs",
+                    ItutT35Kind::Other => "other ITU-T T.35 metadata",
+                };
+                *t35_counts.entry(kind).or_default() += 1;
+            }
+            Metadata::Scalability(scalability) if reported.insert("scalability") => {
+                info!("Scalability mode {}", scalability.scalability_mode_idc);
+                if let Some(structure) = &scalability.structure {
+                    for (i, (width, height)) in
+                        structure.spatial_layer_dimensions.iter().enumerate()
+                    {
+                        info!("Spatial layer {i}: up to {width}x{height}");
                     }
                 }
             }
-        } else if let FilmGrainHeader::UpdateGrain(ref grain_params) = *elem {
-            acc.push(GrainTableSegment {
-                start_time: cur_packet_start,
-                end_time: cur_packet_end,
-                grain_params: grain_params.clone(),
-            });
+            Metadata::Timecode(timecode) if reported.insert("timecode") => {
+                info!("Starting timecode: {timecode}");
+            }
+            Metadata::Other { .. } => other_count += 1,
+            _ => (),
         }
+    }
+    for (kind, count) in t35_counts {
+        info!("Found {kind} in {count} metadata OBUs");
+    }
+    if other_count > 0 {
+        info!("Found {other_count} metadata OBUs of unregistered types");
+    }
+}
 
-        cur_packet_start = cur_packet_end;
-        cur_packet_end_f += time_per_packet;
-        cur_packet_end = cur_packet_end_f.ceil() as u64;
-        acc
-    })
+/// Looks for HDR mastering display metadata in the first packets of the video stream.
+fn probe_mastering_display(
+    input: &Path,
+    stream: StreamSelection,
+) -> Result<Option<MasteringDisplay>> {
+    let mut reader = BitstreamReader::open_streams(input, stream)?;
+    let stream_index = reader.stream_indices()[0];
+    let mut parser: BitstreamParser<false> = BitstreamParser::without_container(None);
+    for (_, packet) in reader
+        .input()
+        .packets()
+        .filter_map(Result::ok)
+        .filter(|(stream, _)| stream.index() == stream_index)
+        .take(METADATA_PROBE_PACKETS)
+    {
+        let Some(data) = packet.data() else {
+            break;
+        };
+        parser.parse_packet(data, 0)?;
+    }
+    Ok(parser
+        .metadata()
+        .iter()
+        .find_map(|timed| match timed.metadata {
+            Metadata::HdrMdcv(mastering_display) => Some(mastering_display),
+            _ => None,
+        }))
+}
+
+/// Lists every operating point of the sequence with its level and tier,
+/// marking the one whose layers were parsed.
+fn report_operating_points(sequence_header: &SequenceHeader, requested: usize) {
+    warn_unsignalled_operating_point(Some(sequence_header), requested);
+    for (index, op) in sequence_header.operating_points().enumerate() {
+        let level = op.level().map_or_else(
+            || "unrestricted".to_string(),
+            |(major, minor)| format!("{major}.{minor}"),
+        );
+        let tier = if op.seq_tier { "High" } else { "Main" };
+        let selected = if index == sequence_header.operating_point {
+            " (selected)"
+        } else {
+            ""
+        };
+        info!(
+            "Operating point {index}: idc {:#05x}, level {level}, {tier} tier{selected}",
+            op.idc
+        );
+    }
+}
+
+/// The parser falls back to operating point 0 when the requested one is not signalled.
+fn warn_unsignalled_operating_point(sequence_header: Option<&SequenceHeader>, requested: usize) {
+    if let Some(sequence_header) = sequence_header
+        && sequence_header.operating_point != requested
+    {
+        warn!(
+            "Operating point {requested} is not signalled by the sequence header, used operating \
+             point {} instead",
+            sequence_header.operating_point
+        );
+    }
+}
+
+/// The path of the output for one stream or layer, when they are inspected
+/// separately, e.g. `grain.stream1.txt`.
+fn suffixed_output_path(output: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = output.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(suffix);
+    if let Some(extension) = output.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+    output.with_file_name(file_name)
+}
+
+fn open_obu_stream(path: &Path) -> Result<ObuStreamReader<BufReader<File>>> {
+    let mut input = BufReader::new(File::open(path)?);
+    let framing = ObuFraming::detect(input.fill_buf()?).ok_or_else(|| {
+        anyhow!(
+            "{} is neither a Section 5 nor an Annex B OBU stream",
+            path.to_string_lossy()
+        )
+    })?;
+    Ok(ObuStreamReader::new(input, framing))
+}
+
+fn parse_frame_rate(input: &str) -> Result<Rational32, String> {
+    let (numer, denom) = input.split_once('/').unwrap_or((input, "1"));
+    let numer = numer.trim().parse::<i32>().map_err(|e| e.to_string())?;
+    let denom = denom.trim().parse::<i32>().map_err(|e| e.to_string())?;
+    if numer <= 0 || denom <= 0 {
+        return Err("frame rate must be positive".to_string());
+    }
+    Ok(Rational32::new(numer, denom))
+}
+
+fn parse_dimensions(input: &str) -> Result<Dimensions, String> {
+    let (width, height) = input
+        .split_once('x')
+        .ok_or_else(|| "expected <width>x<height>".to_string())?;
+    let width = width.trim().parse::<u32>().map_err(|e| e.to_string())?;
+    let height = height.trim().parse::<u32>().map_err(|e| e.to_string())?;
+    if width == 0 || height == 0 {
+        return Err("dimensions must be positive".to_string());
+    }
+    Ok(Dimensions { width, height })
+}
+
+#[allow(clippy::type_complexity)]
+fn get_filtered_frame_pair<T: Pixel, U: Pixel>(
+    source_reader: &mut BitstreamReader,
+    denoised_reader: &mut BitstreamReader,
+    source_bd: NonZeroU8,
+    filters: Option<&FilterChain>,
+) -> Result<(Option<Frame<T>>, Option<Frame<U>>)> {
+    let mut frame = source_reader.get_frame::<T>();
+    if let Some(f) = filters.as_ref() {
+        frame = frame.map(|opt| opt.map(|source_frame| f.apply(source_frame, source_bd)));
+    }
+    let source_frame = frame;
+    let denoised_frame = denoised_reader.get_frame::<U>();
+
+    Ok((source_frame?, denoised_frame?))
 }
 
 #[derive(Parser, Debug)]
@@ -791,6 +1465,26 @@ pub enum Commands {
         /// Overwrite the output file without prompting.
         #[clap(long, short = 'y')]
         overwrite: bool,
+        /// Use the container's timestamps as-is, instead of starting the table
+        /// at the first frame of the video stream.
+        #[clap(long)]
+        absolute_timestamps: bool,
+        /// The frame rate of a raw `.obu` stream, such as `24000/1001`.
+        /// Only needed if the stream does not signal `timing_info`.
+        #[clap(long, value_parser = parse_frame_rate)]
+        frame_rate: Option<Rational32>,
+        /// The video stream to work on: its index in the container, `best` for the
+        /// stream FFmpeg picks, or `all` for every AV1 stream.
+        #[clap(long, default_value = "best")]
+        stream: StreamSelection,
+        /// The operating point to work on, by its index in the sequence header.
+        /// OBUs outside of its temporal and spatial layers are left untouched.
+        #[clap(long, default_value_t = 0)]
+        operating_point: usize,
+        /// Write a separate table for each spatial layer of a scalable stream,
+        /// e.g. `grain.layer1.txt`, for use with `apply --layer-grain`.
+        #[clap(long)]
+        per_layer: bool,
     },
     /// Applies film grain from a table file to a given AV1 video,
     /// and outputs it at a given `output` path.
@@ -804,9 +1498,31 @@ pub enum Commands {
         /// Overwrite the output file without prompting.
         #[clap(long, short = 'y')]
         overwrite: bool,
-        /// The path to the input film grain table.
-        #[clap(long, short, value_parser)]
-        grain: PathBuf,
+        /// The path to the input film grain table. Without it, grain is removed from
+        /// every spatial layer that has no `--layer-grain` table.
+        #[clap(long, short, value_parser, required_unless_present = "layer_grain")]
+        grain: Option<PathBuf>,
+        /// Use the container's timestamps as-is, instead of starting the table
+        /// at the first frame of the video stream.
+        #[clap(long)]
+        absolute_timestamps: bool,
+        /// The frame rate of a raw `.obu` stream, such as `24000/1001`.
+        /// Only needed if the stream does not signal `timing_info`.
+        #[clap(long, value_parser = parse_frame_rate)]
+        frame_rate: Option<Rational32>,
+        /// The video stream to work on: its index in the container, `best` for the
+        /// stream FFmpeg picks, or `all` for every AV1 stream.
+        #[clap(long, default_value = "best")]
+        stream: StreamSelection,
+        /// The operating point to work on, by its index in the sequence header.
+        /// OBUs outside of its temporal and spatial layers are left untouched.
+        #[clap(long, default_value_t = 0)]
+        operating_point: usize,
+        /// A grain table for one spatial layer of a scalable stream, as
+        /// `<spatial_id>=<path>`. Can be given once per layer, and takes precedence
+        /// over `--grain` for that layer.
+        #[clap(long, value_parser = parse_layer_grain)]
+        layer_grain: Vec<(u8, PathBuf)>,
     },
     /// Generates photon-noise-based film grain based on a given ISO value,
     /// adds it to a given AV1 video, and outputs it at a given `output` path.
@@ -826,6 +1542,14 @@ pub enum Commands {
         /// Whether to apply grain to the chroma planes as well.
         #[clap(long)]
         chroma: bool,
+        /// The video stream to work on: its index in the container, `best` for the
+        /// stream FFmpeg picks, or `all` for every AV1 stream.
+        #[clap(long, default_value = "best")]
+        stream: StreamSelection,
+        /// The operating point to work on, by its index in the sequence header.
+        /// OBUs outside of its temporal and spatial layers are left untouched.
+        #[clap(long, default_value_t = 0)]
+        operating_point: usize,
     },
     /// Removes all film grain from a given AV1 video,
     /// and outputs it at a given `output` path.
@@ -839,6 +1563,165 @@ pub enum Commands {
         /// Overwrite the output file without prompting.
         #[clap(long, short = 'y')]
         overwrite: bool,
+        /// The frame rate of a raw `.obu` stream, such as `24000/1001`.
+        /// Only needed if the stream does not signal `timing_info`.
+        #[clap(long, value_parser = parse_frame_rate)]
+        frame_rate: Option<Rational32>,
+        /// The video stream to work on: its index in the container, `best` for the
+        /// stream FFmpeg picks, or `all` for every AV1 stream.
+        #[clap(long, default_value = "best")]
+        stream: StreamSelection,
+        /// The operating point to work on, by its index in the sequence header.
+        /// OBUs outside of its temporal and spatial layers are left untouched.
+        #[clap(long, default_value_t = 0)]
+        operating_point: usize,
+    },
+    /// Removes or adds metadata OBUs, such as HDR10 static metadata, without touching
+    /// film grain, and outputs the video at a given `output` path.
+    Metadata {
+        /// The AV1 file to edit.
+        #[clap(value_parser)]
+        input: PathBuf,
+        /// The path to write the edited AV1 file to.
+        #[clap(long, short, value_parser)]
+        output: PathBuf,
+        /// Overwrite the output file without prompting.
+        #[clap(long, short = 'y')]
+        overwrite: bool,
+        /// A metadata type to remove: `cll`, `mdcv`, `scalability`, `t35`, `timecode`,
+        /// or a `metadata_type` number. Can be given multiple times.
+        #[clap(long, value_parser = parse_metadata_type)]
+        strip: Vec<u64>,
+        /// Mastering display metadata to write after every sequence header, replacing
+        /// any in the stream, as `G(x,y)B(x,y)R(x,y)WP(x,y)L(max,min)` with
+        /// luminance in cd/m².
+        #[clap(long, value_parser = parse_mastering_display)]
+        mdcv: Option<MasteringDisplay>,
+        /// Content light level to write after every sequence header, replacing any
+        /// in the stream, as `<max_cll>,<max_fall>` in cd/m².
+        #[clap(long, value_parser = parse_content_light_level)]
+        cll: Option<ContentLightLevel>,
+        /// The frame rate of a raw `.obu` stream, such as `24000/1001`.
+        /// Only needed if the stream does not signal `timing_info`.
+        #[clap(long, value_parser = parse_frame_rate)]
+        frame_rate: Option<Rational32>,
+        /// The video stream to work on: its index in the container, `best` for the
+        /// stream FFmpeg picks, or `all` for every AV1 stream.
+        #[clap(long, default_value = "best")]
+        stream: StreamSelection,
+    },
+    /// Rewrites the color description of a given AV1 video's sequence headers, and of
+    /// its container, without re-encoding. Outputs it at a given `output` path.
+    Color {
+        /// The AV1 file to retag.
+        #[clap(value_parser)]
+        input: PathBuf,
+        /// The path to write the retagged AV1 file to.
+        #[clap(long, short, value_parser)]
+        output: PathBuf,
+        /// Overwrite the output file without prompting.
+        #[clap(long, short = 'y')]
+        overwrite: bool,
+        /// The color primaries, by FFmpeg name (e.g. `bt709`, `bt2020`) or number.
+        #[clap(long, value_parser = parse_color_primaries)]
+        primaries: Option<ColorPrimaries>,
+        /// The transfer characteristics, by FFmpeg name (e.g. `bt709`, `smpte2084`,
+        /// `arib-std-b67`) or number.
+        #[clap(long, value_parser = parse_transfer_characteristics)]
+        transfer: Option<TransferCharacteristics>,
+        /// The matrix coefficients, by FFmpeg name (e.g. `bt709`, `bt2020nc`) or number.
+        #[clap(long, value_parser = parse_matrix_coefficients)]
+        matrix: Option<MatrixCoefficients>,
+        /// The color range: `limited` (`tv`) or `full` (`pc`).
+        #[clap(long, value_parser = parse_color_range)]
+        range: Option<ColorRange>,
+        /// The frame rate of a raw `.obu` stream, such as `24000/1001`.
+        /// Only needed if the stream does not signal `timing_info`.
+        #[clap(long, value_parser = parse_frame_rate)]
+        frame_rate: Option<Rational32>,
+        /// The video stream to work on: its index in the container, `best` for the
+        /// stream FFmpeg picks, or `all` for every AV1 stream.
+        #[clap(long, default_value = "best")]
+        stream: StreamSelection,
+    },
+    /// Rewrites the frame rate signalled by a given AV1 video's sequence headers, and
+    /// optionally its container frame rate, without re-encoding. Outputs it at a given
+    /// `output` path.
+    Timing {
+        /// The AV1 file to retime.
+        #[clap(value_parser)]
+        input: PathBuf,
+        /// The path to write the retimed AV1 file to.
+        #[clap(long, short, value_parser)]
+        output: PathBuf,
+        /// Overwrite the output file without prompting.
+        #[clap(long, short = 'y')]
+        overwrite: bool,
+        /// The frame rate to signal in `timing_info`, such as `24000/1001`.
+        #[clap(long, value_parser = parse_frame_rate)]
+        frame_rate: Rational32,
+        /// Also conform the container's frame rate, scaling the AV1 stream's timestamps.
+        /// Other streams, such as audio, are copied as they are.
+        #[clap(long)]
+        container: bool,
+        /// The video stream to work on: its index in the container, `best` for the
+        /// stream FFmpeg picks, or `all` for every AV1 stream.
+        #[clap(long, default_value = "best")]
+        stream: StreamSelection,
+    },
+    /// Rewrites the render size of every frame header of a given AV1 video, which sets
+    /// its display dimensions without re-encoding. Outputs it at a given `output` path.
+    RenderSize {
+        /// The AV1 file to change.
+        #[clap(value_parser)]
+        input: PathBuf,
+        /// The path to write the changed AV1 file to.
+        #[clap(long, short, value_parser)]
+        output: PathBuf,
+        /// Overwrite the output file without prompting.
+        #[clap(long, short = 'y')]
+        overwrite: bool,
+        /// The render size, as `<width>x<height>`.
+        #[clap(long, value_parser = parse_dimensions)]
+        size: Dimensions,
+        /// The frame rate of a raw `.obu` stream, such as `24000/1001`.
+        /// Only needed if the stream does not signal `timing_info`.
+        #[clap(long, value_parser = parse_frame_rate)]
+        frame_rate: Option<Rational32>,
+        /// The video stream to work on: its index in the container, `best` for the
+        /// stream FFmpeg picks, or `all` for every AV1 stream.
+        #[clap(long, default_value = "best")]
+        stream: StreamSelection,
+    },
+    /// Writes a record of every frame header of a given AV1 video, with its frame
+    /// type, quantizer, tile layout, sizes and film grain.
+    Frames {
+        /// The AV1 file to inspect.
+        #[clap(value_parser)]
+        input: PathBuf,
+        /// The path to the output file, written as JSON if it ends in `.json`
+        /// and as CSV otherwise.
+        #[clap(long, short, value_parser)]
+        output: PathBuf,
+        /// Overwrite the output file without prompting.
+        #[clap(long, short = 'y')]
+        overwrite: bool,
+        /// Use the container's timestamps as-is, instead of starting at the first
+        /// frame of the video stream.
+        #[clap(long)]
+        absolute_timestamps: bool,
+        /// The frame rate of a raw `.obu` stream, such as `24000/1001`.
+        /// Only needed if the stream does not signal `timing_info`.
+        #[clap(long, value_parser = parse_frame_rate)]
+        frame_rate: Option<Rational32>,
+        /// The video stream to work on: its index in the container, `best` for the
+        /// stream FFmpeg picks, or `all` for every AV1 stream.
+        #[clap(long, default_value = "best")]
+        stream: StreamSelection,
+        /// The operating point to work on, by its index in the sequence header.
+        /// Frames outside of its temporal and spatial layers are left out.
+        #[clap(long, default_value_t = 0)]
+        operating_point: usize,
     },
     /// Compares a source video and a denoised video and generates a film grain
     /// table based on the difference between them. This will provide the most
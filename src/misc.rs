@@ -2,6 +2,7 @@ use std::{path::Path, process::Command};
 
 use anyhow::Result;
 
+/// Counts the video packets in the first video stream of `video` using `ffprobe`.
 pub fn get_frame_count(video: &Path) -> Result<usize> {
     // Would it be better to use the ffmpeg API for this? Yes.
     // But it would also be an outrageous pain in the rear,
@@ -24,6 +25,7 @@ pub fn get_frame_count(video: &Path) -> Result<usize> {
 }
 
 /// Convert a byte slice to a binary string, primarily for debugging
+#[must_use]
 pub fn to_binary_string(bytes: &[u8]) -> String {
     bytes
         .iter()
@@ -0,0 +1,562 @@
+//! Reading and writing of raw AV1 elementary streams, without a container.
+//!
+//! Two framings are supported: the low-overhead bitstream format from Section 5
+//! of the AV1 spec, where every OBU carries `obu_size` and temporal units are
+//! split on temporal delimiters, and the length-delimited format from Annex B,
+//! where temporal units, frame units and OBUs are each prefixed with their size.
+
+use std::io::{self, Read, Write};
+
+use anyhow::{Result, anyhow, bail, ensure};
+use log::debug;
+use nom::Finish;
+use num_rational::Rational32;
+
+use crate::parser::{
+    BitstreamParser,
+    grain::FrameGrainHeader,
+    obu::ObuType,
+    util::{leb128, leb128_write},
+};
+
+/// How the OBUs of an elementary stream are framed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObuFraming {
+    /// Low-overhead bitstream format (Section 5), as written by most encoders.
+    Section5,
+    /// Length-delimited bitstream format (Annex B).
+    AnnexB,
+}
+
+impl ObuFraming {
+    /// Guesses the framing from the first bytes of a stream.
+    ///
+    /// Both framings must start with a temporal delimiter, so this checks
+    /// which of the two layouts puts one at the start of the stream.
+    #[must_use]
+    pub fn detect(prefix: &[u8]) -> Option<Self> {
+        if let Some(&header) = prefix.first()
+            && obu_type(header) == Some(ObuType::TemporalDelimiter)
+            && header & 0x02 != 0
+        {
+            return Some(Self::Section5);
+        }
+
+        // temporal_unit_size, frame_unit_size and obu_length, then the OBU header.
+        let mut input = prefix;
+        for _ in 0..3 {
+            let (rest, _) = leb128(input).finish().ok()?;
+            input = rest;
+        }
+        let &header = input.first()?;
+        (obu_type(header) == Some(ObuType::TemporalDelimiter)).then_some(Self::AnnexB)
+    }
+}
+
+fn obu_type(header: u8) -> Option<ObuType> {
+    if header & 0x80 != 0 {
+        // obu_forbidden_bit
+        return None;
+    }
+    ObuType::try_from((header >> 3) & 0x0F).ok()
+}
+
+/// Reads a LEB128 value from `inner`, or returns `None` at a clean end of stream.
+fn read_leb128<R: Read>(inner: &mut R, bytes: &mut Vec<u8>) -> Result<Option<u64>> {
+    let mut value = 0u64;
+    for i in 0..8 {
+        let mut byte = [0u8; 1];
+        match inner.read_exact(&mut byte) {
+            Ok(()) => (),
+            Err(e) if i == 0 && e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        bytes.push(byte[0]);
+        value |= u64::from(byte[0] & 0x7F) << (i * 7);
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(value));
+        }
+    }
+    bail!("LEB128 value is longer than 8 bytes")
+}
+
+/// Appends exactly `size` bytes from `inner` to `bytes`. The size is untrusted, so
+/// only as much is allocated as the stream actually holds.
+fn read_exactly<R: Read>(inner: &mut R, size: u64, bytes: &mut Vec<u8>) -> Result<()> {
+    let read = inner.take(size).read_to_end(bytes)?;
+    ensure!(
+        read as u64 == size,
+        "stream ends in the middle of an OBU or temporal unit"
+    );
+    Ok(())
+}
+
+/// Splits an elementary stream into temporal units.
+pub struct ObuStreamReader<R: Read> {
+    inner: R,
+    framing: ObuFraming,
+    /// A temporal delimiter that was read while looking for the end of the previous unit.
+    pending: Option<Vec<u8>>,
+}
+
+impl<R: Read> ObuStreamReader<R> {
+    #[must_use]
+    pub const fn new(inner: R, framing: ObuFraming) -> Self {
+        Self {
+            inner,
+            framing,
+            pending: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn framing(&self) -> ObuFraming {
+        self.framing
+    }
+
+    /// Reads the next temporal unit, or returns `None` at the end of the stream.
+    ///
+    /// For Section 5 streams this is the OBUs of the unit, starting with its
+    /// temporal delimiter. For Annex B streams this is the contents of
+    /// `temporal_unit()`, without the leading `temporal_unit_size`.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails or the stream ends in the middle of a unit.
+    pub fn read_temporal_unit(&mut self) -> Result<Option<Vec<u8>>> {
+        match self.framing {
+            ObuFraming::Section5 => self.read_section5_temporal_unit(),
+            ObuFraming::AnnexB => {
+                let Some(size) = read_leb128(&mut self.inner, &mut Vec::new())? else {
+                    return Ok(None);
+                };
+                let mut data = Vec::new();
+                read_exactly(&mut self.inner, size, &mut data)?;
+                Ok(Some(data))
+            }
+        }
+    }
+
+    fn read_section5_temporal_unit(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut unit = self.pending.take().unwrap_or_default();
+        loop {
+            let mut obu = Vec::new();
+            let mut header = [0u8; 1];
+            match self.inner.read_exact(&mut header) {
+                Ok(()) => (),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok((!unit.is_empty()).then_some(unit));
+                }
+                Err(e) => return Err(e.into()),
+            }
+            obu.push(header[0]);
+            ensure!(
+                header[0] & 0x02 != 0,
+                "OBU without obu_size in a Section 5 stream; is this an Annex B stream?"
+            );
+            if header[0] & 0x04 != 0 {
+                let mut extension = [0u8; 1];
+                self.inner.read_exact(&mut extension)?;
+                obu.push(extension[0]);
+            }
+            let size = read_leb128(&mut self.inner, &mut obu)?
+                .ok_or_else(|| anyhow!("stream ends in the middle of an OBU header"))?;
+            read_exactly(&mut self.inner, size, &mut obu)?;
+
+            if obu_type(header[0]) == Some(ObuType::TemporalDelimiter) && !unit.is_empty() {
+                self.pending = Some(obu);
+                return Ok(Some(unit));
+            }
+            unit.extend_from_slice(&obu);
+        }
+    }
+}
+
+/// Writes temporal units back out in the framing they were read with.
+pub struct ObuStreamWriter<W: Write> {
+    inner: W,
+    framing: ObuFraming,
+}
+
+impl<W: Write> ObuStreamWriter<W> {
+    #[must_use]
+    pub const fn new(inner: W, framing: ObuFraming) -> Self {
+        Self { inner, framing }
+    }
+
+    /// Writes one temporal unit, in the same layout that
+    /// [`ObuStreamReader::read_temporal_unit`] returns.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn write_temporal_unit(&mut self, data: &[u8]) -> Result<()> {
+        if self.framing == ObuFraming::AnnexB {
+            self.inner
+                .write_all(&leb128_write(u32::try_from(data.len())?))?;
+        }
+        self.inner.write_all(data)?;
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer.
+    ///
+    /// # Errors
+    /// Returns an error if flushing fails.
+    pub fn finish(mut self) -> Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<const WRITE: bool> BitstreamParser<WRITE> {
+    /// The frame rate signalled by the `timing_info` of the current sequence header, if any.
+    #[must_use]
+    pub fn signalled_frame_rate(&self) -> Option<Rational32> {
+        self.sequence_header()?.timing_info?.frame_rate()
+    }
+
+    /// Parses one temporal unit as returned by [`ObuStreamReader::read_temporal_unit`].
+    ///
+    /// When `WRITE` is `true`, returns the rewritten unit in the same framing.
+    fn parse_temporal_unit(
+        &mut self,
+        framing: ObuFraming,
+        unit: &[u8],
+        packet_ts: u64,
+    ) -> Result<Vec<u8>> {
+        if framing == ObuFraming::Section5 {
+            self.parse_packet(unit, packet_ts)?;
+            return Ok(self.take_packet_out());
+        }
+
+        let mut unit_out = Vec::new();
+        let mut input = unit;
+        while !input.is_empty() {
+            let (rest, frame_unit_size) = leb128(input).finish().map_err(|e| anyhow!("{e:?}"))?;
+            let frame_unit_size = usize::try_from(frame_unit_size.value)?;
+            ensure!(
+                rest.len() >= frame_unit_size,
+                "frame unit exceeds its temporal unit"
+            );
+            let (mut frame_unit, rest) = rest.split_at(frame_unit_size);
+            input = rest;
+
+            let mut frame_unit_out = Vec::new();
+            while !frame_unit.is_empty() {
+                let (rest, obu_length) =
+                    leb128(frame_unit).finish().map_err(|e| anyhow!("{e:?}"))?;
+                let obu_length = usize::try_from(obu_length.value)?;
+                ensure!(rest.len() >= obu_length, "OBU exceeds its frame unit");
+                let (obu, rest) = rest.split_at(obu_length);
+                frame_unit = rest;
+
+                self.parse_sized_obu(obu, packet_ts)?;
+                if WRITE {
+                    let obu_out = self.take_packet_out();
+                    // Stripped metadata OBUs leave nothing to write.
+                    if !obu_out.is_empty() {
+                        frame_unit_out
+                            .extend_from_slice(&leb128_write(u32::try_from(obu_out.len())?));
+                        frame_unit_out.extend_from_slice(&obu_out);
+                    }
+                    if obu.first().copied().and_then(obu_type) == Some(ObuType::SequenceHeader) {
+                        for metadata in self.inserted_metadata() {
+                            frame_unit_out
+                                .extend_from_slice(&leb128_write(u32::try_from(metadata.len())?));
+                            frame_unit_out.extend_from_slice(metadata);
+                        }
+                    }
+                }
+            }
+
+            if WRITE {
+                unit_out.extend_from_slice(&leb128_write(u32::try_from(frame_unit_out.len())?));
+                unit_out.extend_from_slice(&frame_unit_out);
+            }
+        }
+        Ok(unit_out)
+    }
+
+    /// Parses every temporal unit of `reader`, passing each rewritten unit to `write_unit`.
+    ///
+    /// Timestamps come from `frame_rate`, or else from the sequence header's `timing_info`.
+    fn process_obu_stream<R: Read>(
+        &mut self,
+        mut reader: ObuStreamReader<R>,
+        frame_rate: Option<Rational32>,
+        mut write_unit: impl FnMut(&[u8]) -> Result<()>,
+    ) -> Result<()> {
+        let framing = reader.framing();
+        let mut time_base = None;
+        let mut index = 0i64;
+        while let Some(unit) = reader.read_temporal_unit()? {
+            debug!("Parsing temporal unit {index} of {} bytes", unit.len());
+
+            // The first unit always starts at zero, and carries the sequence header
+            // that the time base of every later unit may come from.
+            let packet_ts = match time_base {
+                Some(time_base) => self.packet_ts(Some(index), time_base),
+                None => 0,
+            };
+            let unit_out = self.parse_temporal_unit(framing, &unit, packet_ts)?;
+            write_unit(&unit_out)?;
+
+            if time_base.is_none() {
+                let rate = frame_rate
+                    .or_else(|| self.signalled_frame_rate())
+                    .ok_or_else(|| {
+                        anyhow!("stream does not signal timing_info, so a frame rate must be given")
+                    })?;
+                time_base = Some(Rational32::new_raw(*rate.denom(), *rate.numer()));
+            }
+            index += 1;
+        }
+        Ok(())
+    }
+}
+
+impl BitstreamParser<false> {
+    /// Parses every temporal unit of an elementary stream and returns the film grain
+    /// applied to each displayed frame.
+    ///
+    /// Timestamps come from `frame_rate`, or else from the sequence header's `timing_info`.
+    ///
+    /// # Errors
+    /// Returns an error if the stream cannot be read or contains invalid OBUs, or if
+    /// `frame_rate` is `None` and the stream does not signal `timing_info`.
+    pub fn parse_obu_stream<R: Read>(
+        &mut self,
+        reader: ObuStreamReader<R>,
+        frame_rate: Option<Rational32>,
+    ) -> Result<&[FrameGrainHeader]> {
+        self.process_obu_stream(reader, frame_rate, |_| Ok(()))?;
+        Ok(self.grain_headers())
+    }
+}
+
+impl BitstreamParser<true> {
+    /// Rewrites the film grain of every temporal unit of an elementary stream into
+    /// `writer`, keeping the stream's framing.
+    ///
+    /// Timestamps come from `frame_rate`, or else from the sequence header's `timing_info`.
+    ///
+    /// # Errors
+    /// Returns an error if the input cannot be read or contains invalid OBUs, if the
+    /// output cannot be written, or if `frame_rate` is `None` and the stream does
+    /// not signal `timing_info`.
+    pub fn rewrite_obu_stream<R: Read, W: Write>(
+        &mut self,
+        reader: ObuStreamReader<R>,
+        writer: W,
+        frame_rate: Option<Rational32>,
+    ) -> Result<W> {
+        let mut writer = ObuStreamWriter::new(writer, reader.framing());
+        self.process_obu_stream(reader, frame_rate, |unit| writer.write_temporal_unit(unit))?;
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Temporal delimiter followed by a padding OBU with a one byte payload.
+    const SECTION5_UNIT: [u8; 5] = [0x12, 0x00, 0x7A, 0x01, 0xFF];
+    // The same OBUs without size fields, in a single Annex B frame unit.
+    const ANNEXB_UNIT: [u8; 6] = [0x05, 0x01, 0x10, 0x02, 0x78, 0xFF];
+
+    fn annexb_stream(units: usize) -> Vec<u8> {
+        let mut stream = Vec::new();
+        for _ in 0..units {
+            stream.push(u8::try_from(ANNEXB_UNIT.len()).unwrap());
+            stream.extend_from_slice(&ANNEXB_UNIT);
+        }
+        stream
+    }
+
+    #[test]
+    fn detects_section5() {
+        assert_eq!(
+            ObuFraming::detect(&SECTION5_UNIT),
+            Some(ObuFraming::Section5)
+        );
+    }
+
+    #[test]
+    fn detects_annexb() {
+        assert_eq!(
+            ObuFraming::detect(&annexb_stream(1)),
+            Some(ObuFraming::AnnexB)
+        );
+    }
+
+    #[test]
+    fn detect_rejects_other_data() {
+        assert_eq!(ObuFraming::detect(b"DKIF"), None);
+        assert_eq!(ObuFraming::detect(&[]), None);
+    }
+
+    #[test]
+    fn section5_splits_on_temporal_delimiters() {
+        let stream = [SECTION5_UNIT.as_slice(), &[0x12, 0x00], &SECTION5_UNIT].concat();
+        let mut reader = ObuStreamReader::new(stream.as_slice(), ObuFraming::Section5);
+
+        assert_eq!(
+            reader.read_temporal_unit().unwrap(),
+            Some(SECTION5_UNIT.to_vec())
+        );
+        assert_eq!(reader.read_temporal_unit().unwrap(), Some(vec![0x12, 0x00]));
+        assert_eq!(
+            reader.read_temporal_unit().unwrap(),
+            Some(SECTION5_UNIT.to_vec())
+        );
+        assert_eq!(reader.read_temporal_unit().unwrap(), None);
+    }
+
+    #[test]
+    fn section5_rejects_obu_without_size() {
+        let mut reader = ObuStreamReader::new([0x10u8].as_slice(), ObuFraming::Section5);
+
+        assert!(reader.read_temporal_unit().is_err());
+    }
+
+    #[test]
+    fn section5_rejects_truncated_obu() {
+        let mut reader = ObuStreamReader::new(&SECTION5_UNIT[..4], ObuFraming::Section5);
+
+        assert!(reader.read_temporal_unit().is_err());
+    }
+
+    #[test]
+    fn annexb_reads_temporal_units() {
+        let stream = annexb_stream(2);
+        let mut reader = ObuStreamReader::new(stream.as_slice(), ObuFraming::AnnexB);
+
+        assert_eq!(
+            reader.read_temporal_unit().unwrap(),
+            Some(ANNEXB_UNIT.to_vec())
+        );
+        assert_eq!(
+            reader.read_temporal_unit().unwrap(),
+            Some(ANNEXB_UNIT.to_vec())
+        );
+        assert_eq!(reader.read_temporal_unit().unwrap(), None);
+    }
+
+    #[test]
+    fn annexb_rejects_unit_size_beyond_end_of_stream() {
+        // temporal_unit_size = 2^56 - 1, far more than the stream holds
+        let stream = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0x00];
+        let mut reader = ObuStreamReader::new(&stream[..], ObuFraming::AnnexB);
+
+        assert!(reader.read_temporal_unit().is_err());
+    }
+
+    #[test]
+    fn parse_requires_a_frame_rate_without_timing_info() {
+        let stream = [SECTION5_UNIT, SECTION5_UNIT].concat();
+        let mut parser = BitstreamParser::<false>::without_container(None);
+
+        let result = parser.parse_obu_stream(
+            ObuStreamReader::new(stream.as_slice(), ObuFraming::Section5),
+            None,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_accepts_a_given_frame_rate() {
+        let stream = [SECTION5_UNIT, SECTION5_UNIT].concat();
+        let mut parser = BitstreamParser::<false>::without_container(None);
+
+        let headers = parser
+            .parse_obu_stream(
+                ObuStreamReader::new(stream.as_slice(), ObuFraming::Section5),
+                Some(Rational32::new(24, 1)),
+            )
+            .expect("stream should parse");
+
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn rewrite_section5_passes_through_units_without_headers() {
+        let stream = [SECTION5_UNIT, SECTION5_UNIT].concat();
+        let mut parser = BitstreamParser::<true>::without_container(None);
+
+        let output = parser
+            .rewrite_obu_stream(
+                ObuStreamReader::new(stream.as_slice(), ObuFraming::Section5),
+                Vec::new(),
+                Some(Rational32::new(24, 1)),
+            )
+            .expect("stream should rewrite");
+
+        assert_eq!(output, stream);
+    }
+
+    #[test]
+    fn rewrite_annexb_passes_through_units_without_headers() {
+        let stream = annexb_stream(2);
+        let mut parser = BitstreamParser::<true>::without_container(None);
+
+        let output = parser
+            .rewrite_obu_stream(
+                ObuStreamReader::new(stream.as_slice(), ObuFraming::AnnexB),
+                Vec::new(),
+                Some(Rational32::new(24, 1)),
+            )
+            .expect("stream should rewrite");
+
+        assert_eq!(output, stream);
+    }
+
+    #[test]
+    fn rewrite_annexb_keeps_sequence_header_trailing_bytes() {
+        // A temporal delimiter and a sequence header without size fields, whose last
+        // field ends on a byte boundary, with and without padding after its trailing bits.
+        let sequence_header = [0x08, 0x18, 0x1D, 0xCF, 0xCF, 0xC0, 0x00, 0x80];
+        for padding in [0, 2] {
+            let mut obu = sequence_header.to_vec();
+            obu.resize(obu.len() + padding, 0);
+            let mut frame_unit = vec![0x01, 0x10, u8::try_from(obu.len()).unwrap()];
+            frame_unit.extend_from_slice(&obu);
+            let mut stream = vec![u8::try_from(frame_unit.len() + 1).unwrap()];
+            stream.push(u8::try_from(frame_unit.len()).unwrap());
+            stream.extend_from_slice(&frame_unit);
+            let mut parser = BitstreamParser::<true>::without_container(None);
+
+            let output = parser
+                .rewrite_obu_stream(
+                    ObuStreamReader::new(stream.as_slice(), ObuFraming::AnnexB),
+                    Vec::new(),
+                    Some(Rational32::new(24, 1)),
+                )
+                .expect("stream should rewrite");
+
+            assert_eq!(output, stream);
+        }
+    }
+
+    #[test]
+    fn rewrite_annexb_drops_stripped_metadata_obus() {
+        // A temporal delimiter and a content light level OBU, without size fields.
+        let metadata_obu = [0x28, 0x01, 0x03, 0xE8, 0x01, 0x90, 0x80];
+        let mut frame_unit = vec![0x01, 0x10, u8::try_from(metadata_obu.len()).unwrap()];
+        frame_unit.extend_from_slice(&metadata_obu);
+        let mut stream = vec![u8::try_from(frame_unit.len() + 1).unwrap()];
+        stream.push(u8::try_from(frame_unit.len()).unwrap());
+        stream.extend_from_slice(&frame_unit);
+        let mut parser = BitstreamParser::<true>::without_container(None).with_stripped_metadata(1);
+
+        let output = parser
+            .rewrite_obu_stream(
+                ObuStreamReader::new(stream.as_slice(), ObuFraming::AnnexB),
+                Vec::new(),
+                Some(Rational32::new(24, 1)),
+            )
+            .expect("stream should rewrite");
+
+        assert_eq!(output, [0x03, 0x02, 0x01, 0x10]);
+    }
+}
@@ -1,72 +1,561 @@
-use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "ffmpeg")]
+use std::{cmp::Ordering, ptr, slice};
 
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, ensure};
+#[cfg(feature = "ffmpeg")]
 use ffmpeg::{
-    Dictionary, Packet, Rational, Stream, codec, encoder, format::context::Output, media,
+    Dictionary, Packet, Rational, Rescale, Stream, StreamMut, codec, encoder, ffi,
+    format::context::{Input, Output},
+    media,
 };
-use log::{debug, log_enabled, warn};
+use log::debug;
+#[cfg(feature = "ffmpeg")]
+use log::{log_enabled, warn};
 use nom::Finish;
+use num_rational::Rational32;
 
 use self::{
-    frame::{FrameHeader, NUM_REF_FRAMES, REFS_PER_FRAME, RefType},
-    grain::FilmGrainHeader,
+    frame::{
+        Dimensions, FrameHeader, FrameType, GmParams, NUM_REF_FRAMES, REFS_PER_FRAME, RefType,
+        TileInfo,
+    },
+    grain::{FilmGrainHeader, FrameGrainHeader},
+    metadata::{Metadata, TimedMetadata},
     obu::Obu,
-    sequence::SequenceHeader,
+    sequence::{ColorDescription, SequenceHeader, TimingInfo},
 };
-use crate::{GrainTableSegment, reader::BitstreamReader};
+#[cfg(feature = "ffmpeg")]
+use crate::reader::{BitstreamReader, stream_frame_rate};
+use crate::{GrainTableSegment, frame_records::FrameRecord};
 
 pub mod frame;
 pub mod grain;
+pub mod metadata;
 pub mod obu;
 pub mod sequence;
 pub mod tile_group;
 pub mod trace;
 pub mod util;
 
+/// Length of the fixed fields of an `AV1CodecConfigurationRecord`, before its `configOBUs`.
+const AV1C_HEADER_LEN: usize = 4;
+
+/// Parses the OBUs of an AV1 stream to read its film grain parameters,
+/// or, when `WRITE` is `true`, to rewrite them while remuxing to a new file.
+///
+/// Without the `ffmpeg` feature, packets are fed in by the caller with
+/// [`Self::parse_packet`] and [`Self::rewrite_packet`].
 pub struct BitstreamParser<const WRITE: bool> {
     // Borrow checker REEEE
+    #[cfg(feature = "ffmpeg")]
     reader: Option<BitstreamReader>,
+    #[cfg(feature = "ffmpeg")]
     writer: Option<Output>,
+    /// Container index of the stream this parser's own state belongs to.
+    #[cfg(feature = "ffmpeg")]
+    stream_index: usize,
+    /// Separate parser state for every other selected AV1 stream.
+    #[cfg(feature = "ffmpeg")]
+    other_streams: BTreeMap<usize, Self>,
+    /// Display times of each selected stream, handed to its parser state on setup.
+    #[cfg(feature = "ffmpeg")]
+    stream_display_times: BTreeMap<usize, BTreeMap<usize, u64>>,
     packet_out: Vec<u8>,
     incoming_grain_header: Option<Vec<GrainTableSegment>>,
+    /// Grain tables for single spatial layers, used instead of `incoming_grain_header`.
+    layer_grain_headers: BTreeMap<u8, Vec<GrainTableSegment>>,
+    /// Copy each frame's film grain params unchanged, ignoring the grain tables.
+    keep_grain: bool,
+    /// `metadata_type`s whose OBUs are dropped when rewriting.
+    stripped_metadata: BTreeSet<u64>,
+    /// Serialized metadata OBUs written after every sequence header.
+    inserted_metadata: Vec<Vec<u8>>,
+    /// Replacement color description for every sequence header.
+    color_description: Option<ColorDescription>,
+    /// Replacement `timing_info` for every sequence header.
+    timing_info: Option<TimingInfo>,
+    /// Frame rate to conform the output container's AV1 streams to.
+    container_frame_rate: Option<Rational32>,
+    /// Replacement render size for every frame header that codes one.
+    render_size: Option<Dimensions>,
+    #[cfg(feature = "ffmpeg")]
     parsed: bool,
+    absolute_timestamps: bool,
+    /// Index of the operating point whose layers are parsed and rewritten.
+    operating_point: usize,
+    start_pts: i64,
     size: usize,
     seen_frame_header: bool,
     sequence_header: Option<SequenceHeader>,
     previous_frame_header: Option<FrameHeader>,
+    frame_tile_info: Option<TileInfo>,
     ref_frame_idx: [usize; REFS_PER_FRAME],
     ref_order_hint: [u64; NUM_REF_FRAMES],
     big_ref_order_hint: [u64; NUM_REF_FRAMES],
     big_ref_valid: [bool; NUM_REF_FRAMES],
+    ref_film_grain: [FilmGrainHeader; NUM_REF_FRAMES],
+    ref_frame_type: [Option<FrameType>; NUM_REF_FRAMES],
+    ref_gm_params: [GmParams; NUM_REF_FRAMES],
+    /// Decode index of the frame held in each reference slot.
+    ref_decode_index: [usize; NUM_REF_FRAMES],
+    /// Number of frame headers parsed so far, which is the decode index of the next one.
+    decoded_frames: usize,
+    /// Timestamp at which each frame is first shown by `show_existing_frame`, by its
+    /// decode index. Collected when reading, and used when writing to pick the grain
+    /// of hidden frames for the time they are displayed rather than coded.
+    display_times: BTreeMap<usize, u64>,
     big_order_hints: [u64; RefType::Last as usize + REFS_PER_FRAME],
-    grain_headers: Vec<FilmGrainHeader>,
+    grain_headers: Vec<FrameGrainHeader>,
+    frame_records: Vec<FrameRecord>,
+    metadata: Vec<TimedMetadata>,
 }
 
 impl<const WRITE: bool> BitstreamParser<WRITE> {
+    /// Creates a parser that is not attached to a container.
+    ///
+    /// `incoming_grain_header` is the grain table to write into the stream,
+    /// or `None` to remove film grain. It is ignored unless `WRITE` is `true`.
     #[must_use]
-    pub fn new(reader: BitstreamReader) -> Self {
-        assert!(
-            !WRITE,
-            "Attempted to create a BitstreamReader with WRITE set to true, but without a writer. \
-             Probably not what you want."
-        );
-
+    pub fn without_container(incoming_grain_header: Option<Vec<GrainTableSegment>>) -> Self {
         Self {
-            reader: Some(reader),
+            #[cfg(feature = "ffmpeg")]
+            reader: None,
+            #[cfg(feature = "ffmpeg")]
             writer: None,
+            #[cfg(feature = "ffmpeg")]
+            stream_index: Default::default(),
+            #[cfg(feature = "ffmpeg")]
+            other_streams: BTreeMap::new(),
+            #[cfg(feature = "ffmpeg")]
+            stream_display_times: BTreeMap::new(),
             packet_out: Vec::new(),
+            incoming_grain_header,
+            layer_grain_headers: BTreeMap::new(),
+            keep_grain: Default::default(),
+            stripped_metadata: BTreeSet::new(),
+            inserted_metadata: Vec::new(),
+            color_description: None,
+            timing_info: None,
+            container_frame_rate: None,
+            render_size: None,
+            #[cfg(feature = "ffmpeg")]
             parsed: Default::default(),
+            absolute_timestamps: Default::default(),
+            operating_point: Default::default(),
+            start_pts: Default::default(),
             size: Default::default(),
             seen_frame_header: Default::default(),
             sequence_header: Default::default(),
             previous_frame_header: Default::default(),
+            frame_tile_info: Default::default(),
             ref_frame_idx: Default::default(),
             ref_order_hint: Default::default(),
             big_ref_order_hint: Default::default(),
             big_ref_valid: Default::default(),
+            ref_film_grain: Default::default(),
+            ref_frame_type: Default::default(),
+            ref_gm_params: Default::default(),
+            ref_decode_index: Default::default(),
+            decoded_frames: Default::default(),
+            display_times: BTreeMap::new(),
             big_order_hints: Default::default(),
             grain_headers: Default::default(),
-            incoming_grain_header: None,
+            frame_records: Vec::new(),
+            metadata: Default::default(),
+        }
+    }
+
+    /// Keeps packet timestamps as they are in the container, instead of offsetting them
+    /// by the video stream's start time.
+    ///
+    /// Grain tables written by `inspect` and `diff` start at zero, so this is only useful
+    /// for tables that were made against absolute timestamps.
+    #[must_use]
+    pub const fn with_absolute_timestamps(mut self, absolute_timestamps: bool) -> Self {
+        self.absolute_timestamps = absolute_timestamps;
+        self
+    }
+
+    /// Writes `segments` into the frames of one spatial layer, instead of the grain table
+    /// given at construction. Other layers keep using that table.
+    ///
+    /// In a scalable stream each spatial layer has its own resolution, so each can get
+    /// grain sized for it.
+    #[must_use]
+    pub fn with_layer_grain(mut self, spatial_id: u8, segments: Vec<GrainTableSegment>) -> Self {
+        self.layer_grain_headers.insert(spatial_id, segments);
+        self
+    }
+
+    /// Picks the grain of hidden frames for the time they are displayed, using the
+    /// [`Self::display_times`] of a read-only pass over the same stream.
+    ///
+    /// Without it, a hidden frame gets the grain of the segment its packet falls in,
+    /// which differs when it is shown in a later segment.
+    #[must_use]
+    pub fn with_display_times(mut self, display_times: BTreeMap<usize, u64>) -> Self {
+        self.display_times = display_times;
+        self
+    }
+
+    /// Whether any grain table is written into the stream, as opposed to removing grain.
+    pub(crate) fn writes_grain(&self) -> bool {
+        self.incoming_grain_header.is_some() || !self.layer_grain_headers.is_empty()
+    }
+
+    /// Leaves the film grain of every frame as it is, instead of applying or removing
+    /// grain. Grain tables given to the parser are ignored.
+    ///
+    /// Useful for rewriting only other parts of the stream, such as metadata.
+    #[must_use]
+    pub const fn with_original_grain(mut self) -> Self {
+        self.keep_grain = true;
+        self
+    }
+
+    /// Drops every metadata OBU of `metadata_type` when rewriting.
+    #[must_use]
+    pub fn with_stripped_metadata(mut self, metadata_type: u64) -> Self {
+        self.stripped_metadata.insert(metadata_type);
+        self
+    }
+
+    /// Writes a metadata OBU with `metadata` after every sequence header when rewriting.
+    ///
+    /// Encoders repeat the sequence header on key frames, so players that start
+    /// at any of them see the metadata. Combine with [`Self::with_stripped_metadata`]
+    /// to replace metadata that is already in the stream.
+    ///
+    /// # Errors
+    /// Returns an error if `metadata` is scalability or timecode metadata, which
+    /// cannot be written yet.
+    pub fn with_inserted_metadata(mut self, metadata: &Metadata) -> Result<Self> {
+        let obu = metadata.to_obu().ok_or_else(|| {
+            anyhow!(
+                "writing metadata of type {} is not supported",
+                metadata.metadata_type()
+            )
+        })?;
+        self.inserted_metadata.push(obu);
+        Ok(self)
+    }
+
+    /// Rewrites the color description of every sequence header, and of the output
+    /// container's streams when writing through FFmpeg.
+    ///
+    /// The rewrite fails if the new values would change which fields the sequence
+    /// header codes, such as switching to or from sRGB.
+    #[must_use]
+    pub const fn with_color_description(mut self, color_description: ColorDescription) -> Self {
+        self.color_description = Some(color_description);
+        self
+    }
+
+    /// Rewrites the `timing_info` of every sequence header, adding it where it is missing.
+    ///
+    /// Streams with a decoder model keep their coded `equal_picture_interval`, since
+    /// frame headers depend on it. The rewrite fails for reduced still picture headers,
+    /// which cannot signal timing info.
+    #[must_use]
+    pub const fn with_timing_info(mut self, timing_info: TimingInfo) -> Self {
+        self.timing_info = Some(timing_info);
+        self
+    }
+
+    /// Conforms the output container's AV1 streams to `frame_rate`, scaling their
+    /// timestamps so each frame keeps its place in the sequence. Other streams are
+    /// copied as they are.
+    #[must_use]
+    pub const fn with_container_frame_rate(mut self, frame_rate: Rational32) -> Self {
+        self.container_frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// Rewrites the render size of every frame header, which sets the display
+    /// dimensions without changing the coded frame size.
+    ///
+    /// Frames that copy their size from a reference frame also take its render size,
+    /// so they are left as they are.
+    ///
+    /// # Errors
+    /// Returns an error if either dimension is outside `1..=65536`, the range that
+    /// `render_size()` can code.
+    pub fn with_render_size(mut self, render_size: Dimensions) -> Result<Self> {
+        ensure!(
+            (1..=1 << 16).contains(&render_size.width)
+                && (1..=1 << 16).contains(&render_size.height),
+            "Render size {}x{} is outside 1x1 to 65536x65536",
+            render_size.width,
+            render_size.height
+        );
+        self.render_size = Some(render_size);
+        Ok(self)
+    }
+
+    /// The frame rate to conform the output container to, if any.
+    pub(crate) const fn container_frame_rate(&self) -> Option<Rational32> {
+        self.container_frame_rate
+    }
+
+    /// The metadata OBUs to write after every sequence header.
+    pub(crate) fn inserted_metadata(&self) -> &[Vec<u8>] {
+        &self.inserted_metadata
+    }
+
+    /// Selects the operating point to decode, by its index in the sequence header.
+    ///
+    /// OBUs outside of its temporal and spatial layers are skipped, and copied unchanged
+    /// when rewriting. Falls back to operating point 0 if the sequence header does not
+    /// signal this many.
+    #[must_use]
+    pub const fn with_operating_point(mut self, operating_point: usize) -> Self {
+        self.operating_point = operating_point;
+        self
+    }
+
+    /// Sets the PTS that maps to timestamp zero, unless absolute timestamps were requested.
+    pub const fn set_start_pts(&mut self, start_pts: i64) {
+        if !self.absolute_timestamps {
+            self.start_pts = start_pts;
+        }
+    }
+
+    /// Converts a packet PTS in `time_base` units to a grain table timestamp,
+    /// relative to the start PTS.
+    #[must_use]
+    pub fn packet_ts(&self, pts: Option<i64>, time_base: Rational32) -> u64 {
+        Self::pts_to_av1_ts(
+            pts.unwrap_or_default().saturating_sub(self.start_pts),
+            time_base,
+        )
+    }
+
+    fn pts_to_av1_ts(pts: i64, time_base: Rational32) -> u64 {
+        if pts < 0 {
+            return 0;
+        }
+
+        let pts = pts as u64;
+        let num = *time_base.numer() as u64;
+        let den = *time_base.denom() as u64;
+        if den == 0 {
+            return 0;
+        }
+
+        // Use ceiling so timestamps line up with the 100ns packet boundaries that
+        // are emitted by `aggregate_grain_headers` when generating the grain table.
+        (pts * num * 10_000_000u64).div_ceil(den)
+    }
+
+    /// Parses every OBU in one packet (temporal unit) with the given timestamp.
+    ///
+    /// When `WRITE` is `false`, the grain header of each displayed frame is
+    /// collected and can be read back with [`Self::grain_headers`].
+    ///
+    /// # Errors
+    /// Returns an error if any OBU in the packet fails to parse.
+    pub fn parse_packet(&mut self, mut input: &[u8], packet_ts: u64) -> Result<()> {
+        while !input.is_empty() {
+            let (inner_input, obu) = self
+                .parse_obu(input, packet_ts)
+                .finish()
+                .map_err(|e| anyhow!("{e:?}"))?;
+            input = inner_input;
+            if WRITE && matches!(obu, Some(Obu::SequenceHeader(_))) {
+                for metadata in &self.inserted_metadata {
+                    self.packet_out.extend_from_slice(metadata);
+                }
+            }
+            self.store_obu(obu, packet_ts);
+        }
+        Ok(())
+    }
+
+    /// Parses a single OBU whose length is given by the framing around it,
+    /// as in Annex B streams, where `obu_has_size_field` is usually 0.
+    ///
+    /// When `WRITE` is `true`, the rewritten OBU can be taken with [`Self::take_packet_out`].
+    ///
+    /// # Errors
+    /// Returns an error if the OBU fails to parse.
+    pub fn parse_sized_obu(&mut self, input: &[u8], packet_ts: u64) -> Result<()> {
+        self.size = input.len();
+        let (remaining, obu) = self
+            .parse_obu(input, packet_ts)
+            .finish()
+            .map_err(|e| anyhow!("{e:?}"))?;
+        // Without a size field, trailing bits are left for us to copy, except after a
+        // sequence header, which already wrote its trailing bits and padding.
+        if WRITE && !matches!(obu, Some(Obu::SequenceHeader(_))) {
+            self.packet_out.extend_from_slice(remaining);
+        }
+        self.store_obu(obu, packet_ts);
+        Ok(())
+    }
+
+    fn store_obu(&mut self, obu: Option<Obu>, packet_ts: u64) {
+        match obu {
+            Some(Obu::SequenceHeader(obu)) => {
+                if self
+                    .sequence_header
+                    .as_ref()
+                    .is_some_and(|previous| *previous != obu)
+                {
+                    debug!("Sequence header changed, starting a new coded video sequence");
+                    self.reset_reference_state();
+                }
+                self.sequence_header = Some(obu);
+            }
+            Some(Obu::FrameHeader(obu)) => {
+                if !WRITE {
+                    self.grain_headers.push(FrameGrainHeader {
+                        timestamp: packet_ts,
+                        film_grain_params: obu.film_grain_params.clone(),
+                        temporal_id: obu.temporal_id,
+                        spatial_id: obu.spatial_id,
+                    });
+                }
+                self.previous_frame_header = Some(obu);
+            }
+            Some(Obu::Metadata(metadata)) => {
+                self.metadata.push(TimedMetadata {
+                    timestamp: packet_ts,
+                    metadata,
+                });
+            }
+            None => (),
+        }
+    }
+
+    /// Forgets every reference frame, as a decoder does at the start of a new coded
+    /// video sequence. Its first frame is a key frame that refreshes all slots.
+    fn reset_reference_state(&mut self) {
+        self.seen_frame_header = false;
+        self.previous_frame_header = None;
+        self.frame_tile_info = None;
+        self.ref_frame_idx = Default::default();
+        self.ref_order_hint = Default::default();
+        self.big_ref_order_hint = Default::default();
+        self.big_ref_valid = Default::default();
+        self.ref_film_grain = Default::default();
+        self.ref_frame_type = Default::default();
+        self.ref_gm_params = Default::default();
+        self.ref_decode_index = Default::default();
+        self.big_order_hints = Default::default();
+    }
+
+    /// Parses one packet and returns it with its film grain headers rewritten.
+    ///
+    /// # Errors
+    /// Returns an error if any OBU in the packet fails to parse.
+    pub fn rewrite_packet(&mut self, input: &[u8], packet_ts: u64) -> Result<Vec<u8>> {
+        assert!(
+            WRITE,
+            "Can only rewrite packets if the WRITE generic is true"
+        );
+
+        self.parse_packet(input, packet_ts)?;
+        Ok(self.take_packet_out())
+    }
+
+    /// Rewrites the sequence header in a stream's codec extradata, so that it agrees
+    /// with the in-band sequence headers written by [`Self::rewrite_packet`].
+    ///
+    /// `extradata` is either an `AV1CodecConfigurationRecord`, as stored in the `av1C`
+    /// box of MP4 and the `CodecPrivate` of Matroska, or bare OBUs. Only sequence
+    /// headers are rewritten: metadata OBUs are kept as they are, and none are
+    /// inserted. The parser's own state is left untouched.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration record is truncated or its OBUs fail to parse.
+    pub fn rewrite_extradata(&mut self, extradata: &[u8]) -> Result<Vec<u8>> {
+        // `marker` and `version` of the configuration record. An OBU header always
+        // starts with the forbidden bit, so it can never be mistaken for this.
+        let record_len = if extradata.first() == Some(&0x81) {
+            ensure!(
+                extradata.len() >= AV1C_HEADER_LEN,
+                "av1C configuration record is truncated"
+            );
+            AV1C_HEADER_LEN
+        } else {
+            0
+        };
+        let (record, config_obus) = extradata.split_at(record_len);
+
+        // Only whether grain is written matters to the sequence header, so the grain
+        // tables themselves are not copied.
+        let mut scratch = Self {
+            incoming_grain_header: self.writes_grain().then(Vec::new),
+            keep_grain: self.keep_grain,
+            color_description: self.color_description,
+            timing_info: self.timing_info,
+            operating_point: self.operating_point,
+            ..Self::without_container(None)
+        };
+        let mut output = record.to_vec();
+        output.extend_from_slice(&scratch.rewrite_packet(config_obus, 0)?);
+        Ok(output)
+    }
+
+    /// Takes the bytes written since the last call, leaving the output buffer empty.
+    pub fn take_packet_out(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.packet_out)
+    }
+
+    /// The grain headers collected so far by [`Self::parse_packet`].
+    #[must_use]
+    pub fn grain_headers(&self) -> &[FrameGrainHeader] {
+        &self.grain_headers
+    }
+
+    /// A record of every frame header parsed so far, in decoding order, including
+    /// frames that are never shown.
+    #[must_use]
+    pub fn frame_records(&self) -> &[FrameRecord] {
+        &self.frame_records
+    }
+
+    /// The timestamp at which each frame shown by `show_existing_frame` is first
+    /// displayed, by its decode index as in [`Self::frame_records`].
+    #[must_use]
+    pub const fn display_times(&self) -> &BTreeMap<usize, u64> {
+        &self.display_times
+    }
+
+    /// The metadata OBUs parsed so far, such as HDR metadata, in stream order.
+    #[must_use]
+    pub fn metadata(&self) -> &[TimedMetadata] {
+        &self.metadata
+    }
+
+    /// The most recently parsed sequence header.
+    #[must_use]
+    pub const fn sequence_header(&self) -> Option<&SequenceHeader> {
+        self.sequence_header.as_ref()
+    }
+
+    /// The header of the most recently displayed frame, either shown directly or
+    /// through `show_existing_frame`.
+    #[must_use]
+    pub const fn frame_header(&self) -> Option<&FrameHeader> {
+        self.previous_frame_header.as_ref()
+    }
+}
+
+#[cfg(feature = "ffmpeg")]
+impl<const WRITE: bool> BitstreamParser<WRITE> {
+    #[must_use]
+    pub fn new(reader: BitstreamReader) -> Self {
+        assert!(
+            !WRITE,
+            "Attempted to create a BitstreamReader with WRITE set to true, but without a writer. \
+             Probably not what you want."
+        );
+
+        Self {
+            reader: Some(reader),
+            ..Self::without_container(None)
         }
     }
 
@@ -84,53 +573,87 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
         Self {
             reader: Some(reader),
             writer: Some(writer),
-            incoming_grain_header: incoming_frame_header,
-            packet_out: Vec::new(),
-            parsed: Default::default(),
-            size: Default::default(),
-            seen_frame_header: Default::default(),
-            sequence_header: Default::default(),
-            previous_frame_header: Default::default(),
-            ref_frame_idx: Default::default(),
-            ref_order_hint: Default::default(),
-            big_ref_order_hint: Default::default(),
-            big_ref_valid: Default::default(),
-            big_order_hints: Default::default(),
-            grain_headers: Default::default(),
+            ..Self::without_container(incoming_frame_header)
         }
     }
 
-    fn ffmpeg_pts_to_av1_ts(pts: i64, time_base: Rational) -> u64 {
-        if pts < 0 {
-            return 0;
+    /// The PTS that should map to timestamp zero for the given video stream.
+    fn stream_start_pts(stream: &Stream) -> i64 {
+        let start_time = stream.start_time();
+        if start_time == ffmpeg::ffi::AV_NOPTS_VALUE {
+            0
+        } else {
+            start_time
         }
+    }
 
-        let pts = pts as u64;
-        let num = time_base.0 as u64;
-        let den = time_base.1 as u64;
-        if den == 0 {
-            return 0;
+    fn stream_time_base(stream: &Stream) -> Rational32 {
+        let time_base = stream.time_base();
+        Rational32::new_raw(time_base.0, time_base.1)
+    }
+
+    /// Gives every selected stream of the reader its own parser state, with the
+    /// first one kept in `self`.
+    fn init_stream_parsers(&mut self, ictx: &Input, stream_indices: &[usize]) {
+        self.stream_index = stream_indices[0];
+        for &index in stream_indices {
+            let start_pts = Self::stream_start_pts(&ictx.stream(index).unwrap());
+            let display_times = self.stream_display_times.remove(&index).unwrap_or_default();
+            if index == self.stream_index {
+                self.set_start_pts(start_pts);
+                self.display_times = display_times;
+                continue;
+            }
+
+            let mut parser = Self::without_container(self.incoming_grain_header.clone())
+                .with_absolute_timestamps(self.absolute_timestamps)
+                .with_operating_point(self.operating_point);
+            parser.layer_grain_headers = self.layer_grain_headers.clone();
+            parser.keep_grain = self.keep_grain;
+            parser.stripped_metadata = self.stripped_metadata.clone();
+            parser.inserted_metadata = self.inserted_metadata.clone();
+            parser.color_description = self.color_description;
+            parser.timing_info = self.timing_info;
+            parser.container_frame_rate = self.container_frame_rate;
+            parser.render_size = self.render_size;
+            parser.display_times = display_times;
+            parser.set_start_pts(start_pts);
+            parser.stream_index = index;
+            self.other_streams.insert(index, parser);
         }
+    }
 
-        // Use ceiling so timestamps line up with the 100ns packet boundaries that
-        // are emitted by `aggregate_grain_headers` when generating the grain table.
-        (pts * num * 10_000_000u64).div_ceil(den)
+    /// The parser state for the stream at `index`, or `None` if it is not selected.
+    fn stream_parser(&mut self, index: usize) -> Option<&mut Self> {
+        if index == self.stream_index {
+            Some(self)
+        } else {
+            self.other_streams.get_mut(&index)
+        }
     }
 
-    pub fn get_grain_headers(&mut self) -> Result<&[FilmGrainHeader]> {
+    /// Returns the film grain applied to each displayed frame, in presentation order.
+    ///
+    /// Each header carries the real presentation timestamp of its packet, so tables
+    /// built from these line up with variable frame rate sources.
+    ///
+    /// Only the first selected stream is returned; the others are available from
+    /// [`Self::get_stream_grain_headers`].
+    pub fn get_grain_headers(&mut self) -> Result<&[FrameGrainHeader]> {
         if self.parsed {
             return Ok(&self.grain_headers);
         }
 
         let mut reader = self.reader.take().unwrap();
-        let stream = reader.get_video_stream()?;
-        let stream_idx = reader.get_video_stream()?.index();
-        let stream_time_base = stream.time_base();
-        for (stream, packet) in reader.input().packets().filter_map(Result::ok) {
-            if let Some(mut input) = packet.data() {
-                if stream.index() != stream_idx {
+        reader.ensure_av1()?;
+        let stream_indices = reader.stream_indices().to_vec();
+        let ictx = reader.input();
+        self.init_stream_parsers(ictx, &stream_indices);
+        for (stream, packet) in ictx.packets().filter_map(Result::ok) {
+            if let Some(input) = packet.data() {
+                let Some(parser) = self.stream_parser(stream.index()) else {
                     continue;
-                }
+                };
 
                 debug!(
                     target: "trace_headers",
@@ -140,28 +663,8 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                     packet.dts().unwrap_or_default(),
                 );
 
-                let packet_ts =
-                    Self::ffmpeg_pts_to_av1_ts(packet.pts().unwrap_or_default(), stream_time_base);
-                loop {
-                    let (inner_input, obu) = self
-                        .parse_obu(input, packet_ts)
-                        .finish()
-                        .map_err(|e| anyhow!("{e:?}"))?;
-                    input = inner_input;
-                    match obu {
-                        Some(Obu::SequenceHeader(obu)) => {
-                            self.sequence_header = Some(obu);
-                        }
-                        Some(Obu::FrameHeader(obu)) => {
-                            self.grain_headers.push(obu.film_grain_params.clone());
-                            self.previous_frame_header = Some(obu);
-                        }
-                        None => (),
-                    }
-                    if input.is_empty() {
-                        break;
-                    }
-                }
+                let packet_ts = parser.packet_ts(packet.pts(), Self::stream_time_base(&stream));
+                parser.parse_packet(input, packet_ts)?;
             } else {
                 break;
             }
@@ -172,6 +675,86 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
         Ok(&self.grain_headers)
     }
 
+    /// Returns the film grain headers of every selected stream, by container stream index.
+    ///
+    /// # Errors
+    /// Returns an error if any packet of a selected stream fails to parse.
+    pub fn get_stream_grain_headers(&mut self) -> Result<Vec<(usize, &[FrameGrainHeader])>> {
+        self.get_grain_headers()?;
+
+        let mut headers = vec![(self.stream_index, self.grain_headers.as_slice())];
+        headers.extend(
+            self.other_streams
+                .iter()
+                .map(|(&index, parser)| (index, parser.grain_headers.as_slice())),
+        );
+        headers.sort_by_key(|&(index, _)| index);
+        Ok(headers)
+    }
+
+    /// Returns the frame records of every selected stream, by container stream index.
+    ///
+    /// # Errors
+    /// Returns an error if any packet of a selected stream fails to parse.
+    pub fn get_stream_frame_records(&mut self) -> Result<Vec<(usize, &[FrameRecord])>> {
+        self.get_grain_headers()?;
+
+        let mut records = vec![(self.stream_index, self.frame_records.as_slice())];
+        records.extend(
+            self.other_streams
+                .iter()
+                .map(|(&index, parser)| (index, parser.frame_records.as_slice())),
+        );
+        records.sort_by_key(|&(index, _)| index);
+        Ok(records)
+    }
+
+    /// Returns the [`Self::display_times`] of every selected stream, by container
+    /// stream index.
+    ///
+    /// # Errors
+    /// Returns an error if any packet of a selected stream fails to parse.
+    pub fn get_stream_display_times(&mut self) -> Result<Vec<(usize, &BTreeMap<usize, u64>)>> {
+        self.get_grain_headers()?;
+
+        let mut display_times = vec![(self.stream_index, &self.display_times)];
+        display_times.extend(
+            self.other_streams
+                .iter()
+                .map(|(&index, parser)| (index, &parser.display_times)),
+        );
+        display_times.sort_by_key(|&(index, _)| index);
+        Ok(display_times)
+    }
+
+    /// The parser state of every selected stream, by container stream index, from which
+    /// each stream's [`Self::sequence_header`] and [`Self::metadata`] can be read.
+    ///
+    /// Before a container is parsed, and for IVF files and OBU streams, this is only
+    /// the parser itself.
+    #[must_use]
+    pub fn stream_parsers(&self) -> Vec<(usize, &Self)> {
+        let mut parsers = vec![(self.stream_index, self)];
+        parsers.extend(
+            self.other_streams
+                .iter()
+                .map(|(&index, parser)| (index, parser)),
+        );
+        parsers.sort_by_key(|&(index, _)| index);
+        parsers
+    }
+
+    /// Like [`Self::with_display_times`], for every selected stream by container
+    /// stream index, as returned by [`Self::get_stream_display_times`].
+    #[must_use]
+    pub fn with_stream_display_times(
+        mut self,
+        display_times: impl IntoIterator<Item = (usize, BTreeMap<usize, u64>)>,
+    ) -> Self {
+        self.stream_display_times = display_times.into_iter().collect();
+        self
+    }
+
     pub fn modify_grain_headers(&mut self) -> Result<()> {
         assert!(
             WRITE,
@@ -184,10 +767,15 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
         }
 
         let mut reader = self.reader.take().unwrap();
-        let stream_idx = reader.get_video_stream()?.index();
+        reader.ensure_av1()?;
+        let stream_indices = reader.stream_indices().to_vec();
         let ictx = reader.input();
+        self.init_stream_parsers(ictx, &stream_indices);
+        let stream_idx = self.stream_index;
         let mut stream_mapping = vec![0; ictx.nb_streams() as _];
         let mut ist_time_bases = vec![Rational(0, 1); ictx.nb_streams() as _];
+        // The old and new frame rates of streams being conformed to a new frame rate.
+        let mut conformed_rates = vec![None; ictx.nb_streams() as _];
         let mut ost_index = 0;
 
         let input_chapters: Vec<(i64, Rational, i64, i64, Dictionary)> = ictx
@@ -208,7 +796,13 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
             if ist_medium != media::Type::Audio
                 && ist_medium != media::Type::Video
                 && ist_medium != media::Type::Subtitle
+                && !self.output_supports_stream(ist_medium, ist.parameters().id())
             {
+                // e.g. fonts, which MP4 has no way to store.
+                warn!(
+                    "Dropping stream {ist_index} ({ist_medium:?}), which the output format \
+                     cannot hold"
+                );
                 stream_mapping[ist_index] = -1;
                 continue;
             }
@@ -218,6 +812,17 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
 
             let ist_metadata = ist.metadata().to_owned();
             let ist_sar = ist.sample_aspect_ratio();
+            // The container's copy of the sequence header has to match the in-band ones.
+            let extradata = match (Self::stream_extradata(&ist), self.stream_parser(ist_index)) {
+                (Some(extradata), Some(parser)) => Some(parser.rewrite_extradata(&extradata)?),
+                _ => None,
+            };
+            let color_description = self
+                .stream_parser(ist_index)
+                .and_then(|parser| parser.color_description);
+            let container_frame_rate = self
+                .stream_parser(ist_index)
+                .and_then(|parser| parser.container_frame_rate);
 
             let mut ost = self
                 .writer
@@ -225,6 +830,8 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 .unwrap()
                 .add_stream(encoder::find(codec::Id::None))
                 .unwrap();
+            // This also carries over the stream's side data, such as mastering display
+            // metadata and Dolby Vision configuration, which lives in `coded_side_data`.
             ost.set_parameters(ist.parameters());
             ost.metadata_mut().replace_with(ist_metadata);
             ost.set_sample_aspect_ratio(ist_sar);
@@ -234,6 +841,26 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 (*ost.parameters_mut().as_mut_ptr()).codec_tag = 0;
                 (*ost.as_mut_ptr()).disposition = (*ist.as_ptr()).disposition;
             }
+            if let Some(extradata) = extradata {
+                Self::set_stream_extradata(&mut ost, &extradata)?;
+            }
+            if let Some(color_description) = color_description {
+                Self::set_stream_color(&mut ost, color_description);
+            }
+            if let Some(frame_rate) = container_frame_rate {
+                let old_frame_rate = stream_frame_rate(&ist);
+                ensure!(
+                    *old_frame_rate.numer() > 0,
+                    "Stream {ist_index} has no frame rate to conform from"
+                );
+                let frame_rate = Rational(*frame_rate.numer(), *frame_rate.denom());
+                ost.set_avg_frame_rate(frame_rate);
+                ost.set_rate(frame_rate);
+                conformed_rates[ist_index] = Some((
+                    Rational(*old_frame_rate.numer(), *old_frame_rate.denom()),
+                    frame_rate,
+                ));
+            }
         }
 
         self.writer
@@ -252,13 +879,11 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
             out_chapter.metadata_mut().replace_with(metadata);
         }
 
-        let video_stream_time_base = ictx.stream(stream_idx as _).unwrap().time_base();
-
         self.writer.as_mut().unwrap().write_header()?;
 
         for (stream, mut packet) in ictx.packets().filter_map(Result::ok) {
-            if let Some(mut input) = packet.data() {
-                if stream.index() != stream_idx {
+            if let Some(input) = packet.data() {
+                let Some(parser) = self.stream_parser(stream.index()) else {
                     self.write_packet(
                         packet,
                         &stream,
@@ -267,7 +892,7 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                         stream_idx,
                     )?;
                     continue;
-                }
+                };
 
                 debug!(
                     target: "trace_headers",
@@ -277,57 +902,47 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                     packet.dts().unwrap_or_default(),
                 );
 
-                let packet_ts = Self::ffmpeg_pts_to_av1_ts(
-                    packet.pts().unwrap_or_default(),
-                    video_stream_time_base,
-                );
-
-                loop {
-                    let (inner_input, obu) = self
-                        .parse_obu(input, packet_ts)
-                        .finish()
-                        .map_err(|e| anyhow!("{e:?}"))?;
-                    input = inner_input;
-                    match obu {
-                        Some(Obu::SequenceHeader(obu)) => {
-                            self.sequence_header = Some(obu);
-                        }
-                        Some(Obu::FrameHeader(obu)) => {
-                            self.previous_frame_header = Some(obu);
-                        }
-                        None => (),
-                    }
-                    if input.is_empty() {
-                        break;
-                    }
-                }
+                let packet_ts = parser.packet_ts(packet.pts(), Self::stream_time_base(&stream));
+                parser.parse_packet(input, packet_ts)?;
+                let packet_out = parser.take_packet_out();
 
                 let orig_size = packet.size();
-                match self.packet_out.len().cmp(&orig_size) {
+                match packet_out.len().cmp(&orig_size) {
                     Ordering::Greater => {
-                        debug!(
-                            "Growing packet from {} to {}",
-                            orig_size,
-                            self.packet_out.len()
-                        );
+                        debug!("Growing packet from {} to {}", orig_size, packet_out.len());
                         // `av_grow_packet` takes the number of bytes to grow by.
-                        packet.grow(self.packet_out.len() - orig_size);
+                        packet.grow(packet_out.len() - orig_size);
                     }
                     Ordering::Less => {
                         debug!(
                             "Shrinking packet from {} to {}",
                             orig_size,
-                            self.packet_out.len()
+                            packet_out.len()
                         );
                         // `av_shrink_packet` takes the new size of the packet.
                         // because consistency.
-                        packet.shrink(self.packet_out.len());
+                        packet.shrink(packet_out.len());
                     }
                     Ordering::Equal => {
                         debug!("Packet sizes equal at {orig_size}");
                     }
                 }
-                packet.data_mut().unwrap().copy_from_slice(&self.packet_out);
+                packet.data_mut().unwrap().copy_from_slice(&packet_out);
+                if let Some((old_frame_rate, frame_rate)) = conformed_rates[stream.index()] {
+                    // Timestamps scale by the ratio of the frame rates, which `rescale`
+                    // computes without overflowing.
+                    packet.set_pts(
+                        packet
+                            .pts()
+                            .map(|ts| ts.rescale(old_frame_rate, frame_rate)),
+                    );
+                    packet.set_dts(
+                        packet
+                            .dts()
+                            .map(|ts| ts.rescale(old_frame_rate, frame_rate)),
+                    );
+                    packet.set_duration(packet.duration().rescale(old_frame_rate, frame_rate));
+                }
                 self.write_packet(
                     packet,
                     &stream,
@@ -335,7 +950,6 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                     &ist_time_bases,
                     stream_idx,
                 )?;
-                self.packet_out.clear();
             } else {
                 break;
             }
@@ -347,6 +961,79 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
         Ok(())
     }
 
+    /// Whether the output format can store a stream of `medium` and `codec_id`.
+    /// Formats that do not say are given the benefit of the doubt.
+    fn output_supports_stream(&self, medium: media::Type, codec_id: codec::Id) -> bool {
+        let format = self.writer.as_ref().unwrap().format();
+        // Matroska stores attachments and data of any codec, but only lists its audio,
+        // video and subtitle codecs when asked, which would drop fonts.
+        if format.name() == "matroska"
+            && matches!(medium, media::Type::Attachment | media::Type::Data)
+        {
+            return true;
+        }
+        // SAFETY: The output format is static and valid for the lifetime of the writer.
+        let supported = unsafe {
+            // 0 is `FF_COMPLIANCE_NORMAL`.
+            ffi::avformat_query_codec(format.as_ptr(), codec_id.into(), 0)
+        };
+        supported != 0
+    }
+
+    /// Copies out the codec extradata of `stream`, if it has any.
+    fn stream_extradata(stream: &Stream) -> Option<Vec<u8>> {
+        // SAFETY: `extradata` is either null or points to `extradata_size` bytes
+        // owned by the stream, which outlives this function.
+        unsafe {
+            let params = stream.parameters().as_ptr();
+            let size = usize::try_from((*params).extradata_size).ok()?;
+            if (*params).extradata.is_null() || size == 0 {
+                return None;
+            }
+            Some(slice::from_raw_parts((*params).extradata, size).to_vec())
+        }
+    }
+
+    /// Replaces the codec extradata of an output stream.
+    fn set_stream_extradata(stream: &mut StreamMut, extradata: &[u8]) -> Result<()> {
+        let size = i32::try_from(extradata.len())?;
+        // SAFETY: FFmpeg frees `extradata` with `av_free`, so it must be allocated with
+        // `av_malloc` and followed by zeroed padding, which `av_mallocz` provides.
+        unsafe {
+            let params = stream.parameters_mut().as_mut_ptr();
+            let buf = ffi::av_mallocz(extradata.len() + ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize)
+                .cast::<u8>();
+            ensure!(!buf.is_null(), "Failed to allocate codec extradata");
+            ptr::copy_nonoverlapping(extradata.as_ptr(), buf, extradata.len());
+            ffi::av_freep((&raw mut (*params).extradata).cast());
+            (*params).extradata = buf;
+            (*params).extradata_size = size;
+        }
+        Ok(())
+    }
+
+    /// Sets the colour parameters of an output stream, so the container matches the
+    /// rewritten sequence headers. Values that are not being changed are left alone.
+    fn set_stream_color(stream: &mut StreamMut, color_description: ColorDescription) {
+        // SAFETY: There is no high level API for setting these on stream parameters,
+        // and they are plain enum fields.
+        unsafe {
+            let params = stream.parameters_mut().as_mut_ptr();
+            if let Some(color_primaries) = color_description.color_primaries {
+                (*params).color_primaries = color_primaries.into();
+            }
+            if let Some(transfer_characteristics) = color_description.transfer_characteristics {
+                (*params).color_trc = transfer_characteristics.into();
+            }
+            if let Some(matrix_coefficients) = color_description.matrix_coefficients {
+                (*params).color_space = matrix_coefficients.into();
+            }
+            if let Some(color_range) = color_description.color_range {
+                (*params).color_range = color_range.into();
+            }
+        }
+    }
+
     fn write_packet(
         &mut self,
         mut packet: Packet,
@@ -372,24 +1059,28 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 packet.pts().unwrap_or_default(),
                 packet.dts().unwrap_or_default(),
             );
-            let packet_ts =
-                Self::ffmpeg_pts_to_av1_ts(packet.pts().unwrap_or_default(), stream.time_base());
+            let packet_ts = self.packet_ts(packet.pts(), Self::stream_time_base(stream));
             let mut read_parser = BitstreamParser::<false> {
-                reader: None,
-                writer: None,
-                packet_out: Vec::new(),
-                incoming_grain_header: None,
-                parsed: false,
+                stream_index: self.stream_index,
+                absolute_timestamps: self.absolute_timestamps,
+                operating_point: self.operating_point,
+                start_pts: self.start_pts,
                 size: self.size,
                 seen_frame_header: self.seen_frame_header,
                 sequence_header: self.sequence_header.clone(),
                 previous_frame_header: self.previous_frame_header.clone(),
+                frame_tile_info: self.frame_tile_info.clone(),
                 ref_frame_idx: self.ref_frame_idx,
                 ref_order_hint: self.ref_order_hint,
                 big_ref_order_hint: self.big_ref_order_hint,
                 big_ref_valid: self.big_ref_valid,
+                ref_film_grain: self.ref_film_grain.clone(),
+                ref_frame_type: self.ref_frame_type,
+                ref_gm_params: self.ref_gm_params,
+                ref_decode_index: self.ref_decode_index,
+                decoded_frames: self.decoded_frames,
                 big_order_hints: self.big_order_hints,
-                grain_headers: Vec::new(),
+                ..BitstreamParser::without_container(None)
             };
             let mut input = data;
             loop {
@@ -424,164 +1115,377 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
 
 #[cfg(test)]
 mod tests {
+    use super::frame::QuantizationParams;
+    use super::metadata::{ContentLightLevel, MetadataType};
     use super::*;
 
     // ===== Helpers =====
 
     fn make_parser<const WRITE: bool>() -> BitstreamParser<WRITE> {
-        BitstreamParser {
-            reader: None,
-            writer: None,
-            packet_out: Vec::new(),
-            incoming_grain_header: None,
-            parsed: false,
-            size: 0,
-            seen_frame_header: false,
-            sequence_header: None,
-            previous_frame_header: None,
-            ref_frame_idx: Default::default(),
-            ref_order_hint: Default::default(),
-            big_ref_order_hint: Default::default(),
-            big_ref_valid: Default::default(),
-            big_order_hints: Default::default(),
-            grain_headers: Vec::new(),
-        }
+        BitstreamParser::without_container(None)
     }
 
-    fn make_parsed_parser<const WRITE: bool>(
-        headers: Vec<FilmGrainHeader>,
-    ) -> BitstreamParser<WRITE> {
-        BitstreamParser {
-            reader: None,
-            writer: None,
-            packet_out: Vec::new(),
-            incoming_grain_header: None,
-            parsed: true,
-            size: 0,
-            seen_frame_header: false,
-            sequence_header: None,
-            previous_frame_header: None,
-            ref_frame_idx: Default::default(),
-            ref_order_hint: Default::default(),
-            big_ref_order_hint: Default::default(),
-            big_ref_valid: Default::default(),
-            big_order_hints: Default::default(),
-            grain_headers: headers,
-        }
-    }
-
-    fn sample_grain_params() -> grain::FilmGrainParams {
-        grain::FilmGrainParams {
-            grain_seed: 42,
-            scaling_points_y: Default::default(),
-            scaling_points_cb: Default::default(),
-            scaling_points_cr: Default::default(),
-            scaling_shift: 8,
-            ar_coeff_lag: 0,
-            ar_coeffs_y: Default::default(),
-            ar_coeffs_cb: Default::default(),
-            ar_coeffs_cr: Default::default(),
-            ar_coeff_shift: 6,
-            cb_mult: 0,
-            cb_luma_mult: 0,
-            cb_offset: 0,
-            cr_mult: 0,
-            cr_luma_mult: 0,
-            cr_offset: 0,
-            chroma_scaling_from_luma: false,
-            grain_scale_shift: 0,
-            overlap_flag: false,
-            clip_to_restricted_range: false,
-        }
+    // ===== Part 1: Pure Unit Tests =====
+
+    #[test]
+    fn packet_ts_is_offset_by_start_pts() {
+        let mut parser = make_parser::<false>();
+        parser.start_pts = 1001;
+
+        // 24000/1001 fps in a 1/24000 time base: the first frame starts at zero.
+        assert_eq!(
+            parser.packet_ts(Some(1001), Rational32::new_raw(1, 24000)),
+            0
+        );
+        assert_eq!(
+            parser.packet_ts(Some(2002), Rational32::new_raw(1, 24000)),
+            417_084
+        );
     }
 
-    // ===== Part 1: Pure Unit Tests =====
+    #[test]
+    fn packet_ts_clamps_pts_before_start_to_zero() {
+        let mut parser = make_parser::<false>();
+        parser.start_pts = 1001;
+
+        assert_eq!(parser.packet_ts(Some(0), Rational32::new_raw(1, 24000)), 0);
+    }
 
     #[test]
-    fn get_grain_headers_returns_cached_when_already_parsed() {
-        let headers = vec![
-            FilmGrainHeader::Disable,
-            FilmGrainHeader::UpdateGrain(sample_grain_params()),
-        ];
-        let mut parser = make_parsed_parser::<false>(headers);
+    fn packet_ts_without_start_pts_is_absolute() {
+        let parser = make_parser::<false>();
+
+        assert_eq!(
+            parser.packet_ts(Some(1001), Rational32::new_raw(1, 24000)),
+            417_084
+        );
+        assert_eq!(parser.packet_ts(None, Rational32::new_raw(1, 24000)), 0);
+    }
 
-        let result = parser
-            .get_grain_headers()
-            .expect("should return cached headers");
+    #[test]
+    fn with_absolute_timestamps_sets_flag() {
+        let parser = make_parser::<false>().with_absolute_timestamps(true);
 
-        assert_eq!(result.len(), 2);
-        assert_eq!(result[0], FilmGrainHeader::Disable);
+        assert!(parser.absolute_timestamps);
     }
 
     #[test]
-    fn get_grain_headers_returns_empty_when_parsed_with_no_grain() {
-        let mut parser = make_parsed_parser::<false>(Vec::new());
+    fn set_start_pts_is_ignored_with_absolute_timestamps() {
+        let mut parser = make_parser::<false>().with_absolute_timestamps(true);
+
+        parser.set_start_pts(1001);
+
+        assert_eq!(parser.start_pts, 0);
+    }
 
-        let result = parser.get_grain_headers().expect("should return empty");
+    #[test]
+    fn without_container_stores_incoming_grain_header() {
+        let parser = BitstreamParser::<true>::without_container(Some(Vec::new()));
 
-        assert!(result.is_empty());
+        #[cfg(feature = "ffmpeg")]
+        assert!(!parser.parsed);
+        assert!(parser.incoming_grain_header.is_some());
+        assert!(parser.sequence_header.is_none());
     }
 
     #[test]
-    fn get_grain_headers_preserves_all_grain_variants() {
-        let headers = vec![
-            FilmGrainHeader::Disable,
-            FilmGrainHeader::CopyRefFrame,
-            FilmGrainHeader::UpdateGrain(sample_grain_params()),
-        ];
-        let mut parser = make_parsed_parser::<false>(headers);
+    fn parse_packet_consumes_all_obus() {
+        let mut parser = make_parser::<false>();
+        // Two temporal delimiters and a padding OBU with a one byte payload.
+        let packet = [0x12, 0x00, 0x12, 0x00, 0x7A, 0x01, 0xFF];
 
-        let result = parser
-            .get_grain_headers()
-            .expect("should preserve variants");
+        parser
+            .parse_packet(&packet, 0)
+            .expect("packet should parse");
 
-        assert_eq!(result.len(), 3);
-        assert_eq!(result[0], FilmGrainHeader::Disable);
-        assert_eq!(result[1], FilmGrainHeader::CopyRefFrame);
-        assert!(matches!(result[2], FilmGrainHeader::UpdateGrain(_)));
+        assert!(parser.grain_headers().is_empty());
     }
 
     #[test]
-    fn get_grain_headers_second_call_returns_same_result() {
-        let headers = vec![FilmGrainHeader::CopyRefFrame, FilmGrainHeader::Disable];
-        let mut parser = make_parsed_parser::<false>(headers);
+    fn store_obu_keeps_displayed_frame_header() {
+        let mut parser = make_parser::<false>();
+        let header = FrameHeader {
+            show_frame: true,
+            quantization_params: QuantizationParams {
+                base_q_idx: 90,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        parser.store_obu(Some(Obu::FrameHeader(header)), 0);
+
+        let header = parser.frame_header().expect("frame header should be kept");
+        assert_eq!(header.quantization_params.base_q_idx, 90);
+    }
 
-        let first = parser.get_grain_headers().expect("first call").to_vec();
-        let second = parser.get_grain_headers().expect("second call").to_vec();
+    #[test]
+    fn parse_packet_accepts_empty_packet() {
+        let mut parser = make_parser::<false>();
 
-        assert_eq!(first, second);
+        parser
+            .parse_packet(&[], 0)
+            .expect("empty packet should parse");
     }
 
     #[test]
-    #[should_panic]
-    fn get_grain_headers_panics_when_reader_is_none() {
+    fn parse_packet_returns_error_for_truncated_obu() {
         let mut parser = make_parser::<false>();
-        // parsed=false, reader=None → .take().unwrap() panics
-        let _ = parser.get_grain_headers();
+
+        // OBU header with has_size_field set, but no size byte.
+        assert!(parser.parse_packet(&[0x12], 0).is_err());
+    }
+
+    #[test]
+    fn rewrite_packet_passes_through_unchanged_obus() {
+        let mut parser = make_parser::<true>();
+        let packet = [0x12, 0x00, 0x7A, 0x01, 0xFF];
+
+        let rewritten = parser
+            .rewrite_packet(&packet, 0)
+            .expect("packet should parse");
+
+        assert_eq!(rewritten, packet);
+        assert!(parser.packet_out.is_empty());
     }
 
     #[test]
-    #[should_panic(expected = "Can only modify headers")]
-    fn modify_grain_headers_panics_when_write_is_false() {
+    #[should_panic(expected = "Can only rewrite packets")]
+    fn rewrite_packet_panics_when_write_is_false() {
         let mut parser = make_parser::<false>();
-        let _ = parser.modify_grain_headers();
+        let _ = parser.rewrite_packet(&[0x12, 0x00], 0);
     }
 
     #[test]
-    fn modify_grain_headers_returns_ok_when_already_parsed() {
-        let mut parser = make_parsed_parser::<true>(Vec::new());
+    fn rewrite_extradata_keeps_av1c_record_fields() {
+        let mut parser = make_parser::<true>();
+        let extradata = [0x81, 0x08, 0x0C, 0x00, 0x7A, 0x01, 0xFF];
+
+        let rewritten = parser
+            .rewrite_extradata(&extradata)
+            .expect("extradata should parse");
+
+        assert_eq!(rewritten, extradata);
+    }
 
-        let result = parser.modify_grain_headers();
+    #[test]
+    fn rewrite_extradata_accepts_bare_obus() {
+        let mut parser = make_parser::<true>();
+        let extradata = [0x7A, 0x01, 0xFF];
+
+        let rewritten = parser
+            .rewrite_extradata(&extradata)
+            .expect("extradata should parse");
+
+        assert_eq!(rewritten, extradata);
+    }
 
-        assert!(result.is_ok());
+    #[test]
+    fn rewrite_extradata_leaves_metadata_and_parser_state_alone() {
+        let mut parser = make_parser::<true>()
+            .with_stripped_metadata(MetadataType::HdrCll as u64)
+            .with_inserted_metadata(&Metadata::HdrCll(ContentLightLevel {
+                max_cll: 4000,
+                max_fall: 1000,
+            }))
+            .unwrap();
+        let mut extradata = vec![0x81, 0x08, 0x0C, 0x00];
+        // A reduced still picture sequence header without film grain
+        extradata.extend_from_slice(&[0x0A, 0x05, 0x19, 0x00, 0x00, 0x00, 0x20]);
+        // Content light level metadata with max_cll 1000 and max_fall 400
+        extradata.extend_from_slice(&[0x2A, 0x06, 0x01, 0x03, 0xE8, 0x01, 0x90, 0x80]);
+
+        let rewritten = parser
+            .rewrite_extradata(&extradata)
+            .expect("extradata should parse");
+
+        assert_eq!(rewritten, extradata);
+        assert!(parser.sequence_header.is_none());
+        assert!(parser.metadata.is_empty());
     }
 
     #[test]
-    #[should_panic]
-    fn modify_grain_headers_panics_when_reader_is_none() {
+    fn rewrite_extradata_returns_error_for_truncated_av1c() {
         let mut parser = make_parser::<true>();
-        // WRITE=true, parsed=false, reader=None → .take().unwrap() panics
-        let _ = parser.modify_grain_headers();
+
+        assert!(parser.rewrite_extradata(&[0x81, 0x08]).is_err());
+    }
+
+    #[cfg(feature = "ffmpeg")]
+    mod container {
+        use super::*;
+
+        fn make_parsed_parser<const WRITE: bool>(
+            headers: Vec<FilmGrainHeader>,
+        ) -> BitstreamParser<WRITE> {
+            BitstreamParser {
+                parsed: true,
+                grain_headers: headers
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, film_grain_params)| FrameGrainHeader {
+                        timestamp: i as u64 * 417_083,
+                        film_grain_params,
+                        temporal_id: 0,
+                        spatial_id: 0,
+                    })
+                    .collect(),
+                ..BitstreamParser::without_container(None)
+            }
+        }
+
+        fn sample_grain_params() -> grain::FilmGrainParams {
+            grain::FilmGrainParams {
+                grain_seed: 42,
+                scaling_points_y: Default::default(),
+                scaling_points_cb: Default::default(),
+                scaling_points_cr: Default::default(),
+                scaling_shift: 8,
+                ar_coeff_lag: 0,
+                ar_coeffs_y: Default::default(),
+                ar_coeffs_cb: Default::default(),
+                ar_coeffs_cr: Default::default(),
+                ar_coeff_shift: 6,
+                cb_mult: 0,
+                cb_luma_mult: 0,
+                cb_offset: 0,
+                cr_mult: 0,
+                cr_luma_mult: 0,
+                cr_offset: 0,
+                chroma_scaling_from_luma: false,
+                grain_scale_shift: 0,
+                overlap_flag: false,
+                clip_to_restricted_range: false,
+            }
+        }
+
+        #[test]
+        fn get_grain_headers_returns_cached_when_already_parsed() {
+            let headers = vec![
+                FilmGrainHeader::Disable,
+                FilmGrainHeader::UpdateGrain(sample_grain_params()),
+            ];
+            let mut parser = make_parsed_parser::<false>(headers);
+
+            let result = parser
+                .get_grain_headers()
+                .expect("should return cached headers");
+
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0].film_grain_params, FilmGrainHeader::Disable);
+        }
+
+        #[test]
+        fn get_grain_headers_returns_empty_when_parsed_with_no_grain() {
+            let mut parser = make_parsed_parser::<false>(Vec::new());
+
+            let result = parser.get_grain_headers().expect("should return empty");
+
+            assert!(result.is_empty());
+        }
+
+        #[test]
+        fn get_grain_headers_preserves_all_grain_variants() {
+            let headers = vec![
+                FilmGrainHeader::Disable,
+                FilmGrainHeader::CopyRefFrame {
+                    ref_idx: 0,
+                    grain_seed: 0,
+                },
+                FilmGrainHeader::UpdateGrain(sample_grain_params()),
+            ];
+            let mut parser = make_parsed_parser::<false>(headers);
+
+            let result = parser
+                .get_grain_headers()
+                .expect("should preserve variants");
+
+            assert_eq!(result.len(), 3);
+            assert_eq!(result[0].film_grain_params, FilmGrainHeader::Disable);
+            assert!(matches!(
+                result[1].film_grain_params,
+                FilmGrainHeader::CopyRefFrame { .. }
+            ));
+            assert!(matches!(
+                result[2].film_grain_params,
+                FilmGrainHeader::UpdateGrain(_)
+            ));
+            assert_eq!(result[2].timestamp, 2 * 417_083);
+        }
+
+        #[test]
+        fn get_grain_headers_second_call_returns_same_result() {
+            let headers = vec![
+                FilmGrainHeader::CopyRefFrame {
+                    ref_idx: 0,
+                    grain_seed: 0,
+                },
+                FilmGrainHeader::Disable,
+            ];
+            let mut parser = make_parsed_parser::<false>(headers);
+
+            let first = parser.get_grain_headers().expect("first call").to_vec();
+            let second = parser.get_grain_headers().expect("second call").to_vec();
+
+            assert_eq!(first, second);
+        }
+
+        #[test]
+        #[should_panic]
+        fn get_grain_headers_panics_when_reader_is_none() {
+            let mut parser = make_parser::<false>();
+            // parsed=false, reader=None → .take().unwrap() panics
+            let _ = parser.get_grain_headers();
+        }
+
+        #[test]
+        fn stream_parsers_lists_every_stream_by_index() {
+            let mut parser = make_parser::<false>();
+            assert_eq!(parser.stream_parsers().len(), 1);
+
+            parser.stream_index = 2;
+            let mut other = make_parser::<false>();
+            other.metadata.push(TimedMetadata {
+                timestamp: 0,
+                metadata: Metadata::HdrCll(ContentLightLevel {
+                    max_cll: 1000,
+                    max_fall: 400,
+                }),
+            });
+            parser.other_streams.insert(1, other);
+
+            let parsers = parser.stream_parsers();
+            assert_eq!(
+                parsers.iter().map(|&(index, _)| index).collect::<Vec<_>>(),
+                [1, 2]
+            );
+            assert_eq!(parsers[0].1.metadata().len(), 1);
+            assert!(parsers[1].1.metadata().is_empty());
+        }
+
+        #[test]
+        #[should_panic(expected = "Can only modify headers")]
+        fn modify_grain_headers_panics_when_write_is_false() {
+            let mut parser = make_parser::<false>();
+            let _ = parser.modify_grain_headers();
+        }
+
+        #[test]
+        fn modify_grain_headers_returns_ok_when_already_parsed() {
+            let mut parser = make_parsed_parser::<true>(Vec::new());
+
+            let result = parser.modify_grain_headers();
+
+            assert!(result.is_ok());
+        }
+
+        #[test]
+        #[should_panic]
+        fn modify_grain_headers_panics_when_reader_is_none() {
+            let mut parser = make_parser::<true>();
+            // WRITE=true, parsed=false, reader=None → .take().unwrap() panics
+            let _ = parser.modify_grain_headers();
+        }
     }
 
     // ===== Part 2: I/O Tests =====
@@ -651,6 +1555,64 @@ mod tests {
             let _ = BitstreamParser::<false>::with_writer(reader, writer, None);
         }
 
+        #[test]
+        fn modify_grain_headers_keeps_matroska_attachments() {
+            let font = b"not really a font".to_vec();
+            let input = tempfile::Builder::new().suffix(".mkv").tempfile().unwrap();
+            {
+                let mut ictx = ffmpeg::format::input(test_data_path("8-bit/data/00000000.ivf"))
+                    .expect("test file should open");
+                let mut octx = ffmpeg::format::output(input.path()).expect("input should open");
+                let ist_time_base = ictx.stream(0).unwrap().time_base();
+                let mut ost = octx.add_stream(encoder::find(codec::Id::None)).unwrap();
+                ost.set_parameters(ictx.stream(0).unwrap().parameters());
+                // SAFETY: There is no high level API for these fields.
+                unsafe {
+                    (*ost.parameters_mut().as_mut_ptr()).codec_tag = 0;
+                }
+                let mut attachment = octx.add_stream(encoder::find(codec::Id::None)).unwrap();
+                // SAFETY: As above.
+                unsafe {
+                    let params = attachment.parameters_mut().as_mut_ptr();
+                    (*params).codec_type = ffi::AVMediaType::AVMEDIA_TYPE_ATTACHMENT;
+                    (*params).codec_id = ffi::AVCodecID::AV_CODEC_ID_TTF;
+                }
+                BitstreamParser::<true>::set_stream_extradata(&mut attachment, &font).unwrap();
+                attachment.metadata_mut().set("filename", "font.ttf");
+                attachment.metadata_mut().set("mimetype", "font/ttf");
+                octx.write_header().unwrap();
+                let ost_time_base = octx.stream(0).unwrap().time_base();
+                for (_, mut packet) in ictx.packets().filter_map(Result::ok) {
+                    packet.rescale_ts(ist_time_base, ost_time_base);
+                    packet.set_position(-1);
+                    packet.set_stream(0);
+                    packet.write_interleaved(&mut octx).unwrap();
+                }
+                octx.write_trailer().unwrap();
+            }
+
+            let output = tempfile::Builder::new().suffix(".mkv").tempfile().unwrap();
+            let reader = BitstreamReader::open(input.path()).expect("input should open");
+            let writer = ffmpeg::format::output(output.path()).expect("output should open");
+            let mut parser = BitstreamParser::<true>::with_writer(reader, writer, None);
+            parser.modify_grain_headers().expect("remux should succeed");
+            // Closes the output file.
+            drop(parser);
+
+            let ictx = ffmpeg::format::input(output.path()).expect("output should open");
+            let attachment = ictx
+                .streams()
+                .find(|stream| stream.parameters().medium() == media::Type::Attachment)
+                .expect("attachment should survive the remux");
+            assert_eq!(attachment.parameters().id(), codec::Id::TTF);
+            assert_eq!(
+                BitstreamParser::<true>::stream_extradata(&attachment),
+                Some(font)
+            );
+            assert_eq!(attachment.metadata().get("filename"), Some("font.ttf"));
+            assert_eq!(attachment.metadata().get("mimetype"), Some("font/ttf"));
+        }
+
         #[test]
         fn get_grain_headers_parses_valid_file() {
             let reader = BitstreamReader::open(test_data_path("8-bit/data/00000000.ivf"))
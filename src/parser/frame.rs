@@ -1,32 +1,34 @@
-use std::cmp::{max, min};
+use std::{
+    cmp::{max, min},
+    ops::Range,
+};
 
+use arrayvec::ArrayVec;
 use av1_grain::DEFAULT_GRAIN_SEED;
-use bit::BitIndex;
-use bitvec::{order::Msb0, view::BitView};
 use log::{debug, trace};
 use nom::{
     IResult, Parser,
     bits::{bits, complete as bit_parsers},
-    error::{Error, context},
+    error::{Error, ErrorKind, context},
 };
 use num_enum::TryFromPrimitive;
 use num_traits::{PrimInt, clamp};
 
 use super::{
     BitstreamParser,
-    grain::{FilmGrainHeader, film_grain_params},
+    grain::{FilmGrainHeader, film_grain_params, write_film_grain_params},
     obu::ObuHeader,
-    sequence::{SELECT_INTEGER_MV, SELECT_SCREEN_CONTENT_TOOLS},
+    sequence::{SELECT_INTEGER_MV, SELECT_SCREEN_CONTENT_TOOLS, SequenceHeader},
     trace::{
         TraceCtx, trace_bool, trace_byte_alignment, trace_field, trace_field_signed, trace_su,
         trace_take_u8, trace_take_u32, trace_take_u64, trace_take_usize,
     },
-    util::{BitInput, ns, su, take_bool_bit},
+    util::{BitInput, BitWriter, ns, su, take_bool_bit},
 };
-use crate::{GrainTableSegment, misc::to_binary_string};
+use crate::{GrainTableSegment, frame_records::FrameRecord, misc::to_binary_string};
 
 pub const REFS_PER_FRAME: usize = 7;
-const TOTAL_REFS_PER_FRAME: usize = 8;
+pub const TOTAL_REFS_PER_FRAME: usize = 8;
 pub const NUM_REF_FRAMES: usize = 8;
 const REFRESH_ALL_FRAMES: u8 = 0b1111_1111;
 const PRIMARY_REF_NONE: u8 = 7;
@@ -40,8 +42,8 @@ const MAX_TILE_COLS: u32 = 64;
 const MAX_TILE_ROWS: u32 = 64;
 const MAX_TILE_AREA: u32 = 4096 * 2304;
 
-const MAX_SEGMENTS: usize = 8;
-const SEG_LVL_MAX: usize = 8;
+pub const MAX_SEGMENTS: usize = 8;
+pub const SEG_LVL_MAX: usize = 8;
 const SEG_LVL_ALT_Q: usize = 0;
 const SEGMENTATION_FEATURE_BITS: [u8; SEG_LVL_MAX] = [8, 6, 6, 6, 6, 3, 0, 0];
 const SEGMENTATION_FEATURE_SIGNED: [bool; SEG_LVL_MAX] =
@@ -56,18 +58,441 @@ const SEGMENTATION_FEATURE_MAX: [u8; SEG_LVL_MAX] = [
     0,
     0,
 ];
-type SegmentationData = [[Option<i16>; SEG_LVL_MAX]; MAX_SEGMENTS];
+/// A value for each feature of each segment, indexed by segment and then feature.
+pub type SegmentationData = [[Option<i16>; SEG_LVL_MAX]; MAX_SEGMENTS];
 
 const INTERP_FILTER_SWITCHABLE: u8 = 4;
 const MAX_LOOP_FILTER: u8 = 63;
 const RESTORE_NONE: u8 = 0;
 
-#[derive(Debug, Clone)]
+/// Global motion parameters for every reference frame, indexed by [`RefType`].
+pub type GmParams = [[i32; 6]; TOTAL_REFS_PER_FRAME];
+
+/// The fields of a frame's `uncompressed_header()`, named as in the AV1
+/// specification.
+///
+/// Values that are not coded for a frame keep their defaults, except where the
+/// specification infers them, such as `refresh_frame_flags` for shown key frames.
+/// For `show_existing_frame`, only the fields up to `frame_to_show_map_idx` are read
+/// from the header; the film grain and tile layout are those of the shown frame.
+#[derive(Debug, Clone, Default)]
 pub struct FrameHeader {
     pub show_frame: bool,
     pub show_existing_frame: bool,
+    /// The film grain applied to this frame, with `update_grain = 0` resolved to the
+    /// parameters of the reference frame they are loaded from.
     pub film_grain_params: FilmGrainHeader,
     pub tile_info: TileInfo,
+    /// Layer of the OBU that carried this header. Both are 0 without an OBU extension.
+    pub temporal_id: u8,
+    pub spatial_id: u8,
+    pub frame_to_show_map_idx: u8,
+    pub display_frame_id: u64,
+    pub frame_type: FrameType,
+    pub showable_frame: bool,
+    /// Only coded with a decoder model and without `equal_picture_interval`.
+    pub frame_presentation_time: u64,
+    pub error_resilient_mode: bool,
+    pub disable_cdf_update: bool,
+    pub allow_screen_content_tools: bool,
+    pub force_integer_mv: bool,
+    pub current_frame_id: u64,
+    pub frame_size_override_flag: bool,
+    pub order_hint: u64,
+    /// Index into `ref_frame_idx` of the frame that contexts are loaded from, or 7
+    /// (`PRIMARY_REF_NONE`) for none.
+    pub primary_ref_frame: u8,
+    pub buffer_removal_time_present: bool,
+    /// `buffer_removal_time` for each operating point, if coded for it.
+    pub buffer_removal_time: ArrayVec<Option<u64>, { 1 << 5u8 }>,
+    /// Bit mask of the reference slots this frame is stored in.
+    pub refresh_frame_flags: u8,
+    /// Order hints of every reference slot, only coded for error resilient frames.
+    pub ref_order_hint: Option<[u64; NUM_REF_FRAMES]>,
+    pub frame_refs_short_signaling: bool,
+    pub last_frame_idx: u8,
+    pub gold_frame_idx: u8,
+    /// The reference slot used for each of `LAST_FRAME` to `ALTREF_FRAME`.
+    pub ref_frame_idx: [usize; REFS_PER_FRAME],
+    pub delta_frame_id_minus_1: [u64; REFS_PER_FRAME],
+    pub frame_size: FrameSize,
+    pub allow_intrabc: bool,
+    pub allow_high_precision_mv: bool,
+    /// `interpolation_filter`, where 4 means it is switchable per block.
+    pub interpolation_filter: u8,
+    pub is_motion_mode_switchable: bool,
+    pub use_ref_frame_mvs: bool,
+    pub disable_frame_end_update_cdf: bool,
+    pub quantization_params: QuantizationParams,
+    pub segmentation_params: SegmentationParams,
+    pub delta_q_present: bool,
+    pub delta_q_res: u8,
+    pub delta_lf_present: bool,
+    pub delta_lf_res: u8,
+    pub delta_lf_multi: bool,
+    pub loop_filter_params: LoopFilterParams,
+    pub cdef_params: CdefParams,
+    pub lr_params: LrParams,
+    /// Whether the transform size is chosen per block, which gives `TX_MODE_SELECT`
+    /// instead of `TX_MODE_LARGEST` for lossy frames.
+    pub tx_mode_select: bool,
+    /// Whether blocks may use compound prediction from two references.
+    pub reference_select: bool,
+    /// Whether `skip_mode_present` is coded, which depends on the reference frames.
+    pub skip_mode_allowed: bool,
+    pub skip_mode_present: bool,
+    pub allow_warped_motion: bool,
+    pub reduced_tx_set: bool,
+    pub global_motion_params: GlobalMotionParams,
+    /// `film_grain_params()` as coded, before `update_grain = 0` is resolved into
+    /// `film_grain_params`.
+    pub coded_film_grain_params: FilmGrainHeader,
+}
+
+/// `frame_size()`, `superres_params()` and `render_size()`, or the reference frame
+/// the size is copied from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameSize {
+    /// Index into `ref_frame_idx` of the reference whose size is used, if any.
+    pub found_ref: Option<u8>,
+    /// The coded size, after super-resolution downscaling.
+    pub frame_size: Dimensions,
+    /// The size after super-resolution upscaling, the same as `frame_size` without it.
+    pub upscaled_size: Dimensions,
+    pub use_superres: bool,
+    /// The super-resolution denominator minus 9, only coded with `use_superres`.
+    pub coded_denom: u8,
+    pub render_and_frame_size_different: bool,
+    pub render_size: Dimensions,
+}
+
+impl FrameSize {
+    fn new(
+        found_ref: Option<u8>,
+        frame_size: Dimensions,
+        upscaled_size: Dimensions,
+        coded_denom: Option<u8>,
+    ) -> Self {
+        Self {
+            found_ref,
+            frame_size,
+            upscaled_size,
+            use_superres: coded_denom.is_some(),
+            coded_denom: coded_denom.unwrap_or_default(),
+            render_and_frame_size_different: false,
+            render_size: Dimensions {
+                width: upscaled_size.width,
+                height: frame_size.height,
+            },
+        }
+    }
+
+    /// Writes `frame_size()` followed by `render_size()`.
+    fn write_bits(
+        &self,
+        writer: &mut BitWriter,
+        frame_size_override: bool,
+        enable_superres: bool,
+        frame_width_bits: usize,
+        frame_height_bits: usize,
+    ) {
+        if frame_size_override {
+            writer.write_bits(u64::from(self.upscaled_size.width - 1), frame_width_bits);
+            writer.write_bits(u64::from(self.frame_size.height - 1), frame_height_bits);
+        }
+        self.write_superres_params(writer, enable_superres);
+        writer.write_bool(self.render_and_frame_size_different);
+        if self.render_and_frame_size_different {
+            writer.write_bits(u64::from(self.render_size.width - 1), 16);
+            writer.write_bits(u64::from(self.render_size.height - 1), 16);
+        }
+    }
+
+    /// Writes `frame_size_with_refs()`.
+    fn write_bits_with_refs(
+        &self,
+        writer: &mut BitWriter,
+        frame_size_override: bool,
+        enable_superres: bool,
+        frame_width_bits: usize,
+        frame_height_bits: usize,
+    ) {
+        for i in 0..REFS_PER_FRAME {
+            let found_ref = self.found_ref == Some(i as u8);
+            writer.write_bool(found_ref);
+            if found_ref {
+                break;
+            }
+        }
+        if self.found_ref.is_some() {
+            self.write_superres_params(writer, enable_superres);
+        } else {
+            self.write_bits(
+                writer,
+                frame_size_override,
+                enable_superres,
+                frame_width_bits,
+                frame_height_bits,
+            );
+        }
+    }
+
+    fn write_superres_params(&self, writer: &mut BitWriter, enable_superres: bool) {
+        if enable_superres {
+            writer.write_bool(self.use_superres);
+            if self.use_superres {
+                writer.write_bits(u64::from(self.coded_denom), SUPERRES_DENOM_BITS);
+            }
+        }
+    }
+}
+
+impl FrameHeader {
+    /// Writes this header as `uncompressed_header()` syntax, without the
+    /// `trailing_bits()` or `byte_alignment()` that follow it.
+    ///
+    /// Film grain is written as `film_grain_params()` when `film_grain_params_present`,
+    /// which may differ from the flag in `sequence_header` when the sequence header is
+    /// rewritten along with the frame headers.
+    #[allow(clippy::too_many_lines)]
+    pub fn write_bits(
+        &self,
+        writer: &mut BitWriter,
+        sequence_header: &SequenceHeader,
+        film_grain_params_present: bool,
+    ) {
+        let id_len = sequence_header.frame_id_numbers_present.then(|| {
+            sequence_header.additional_frame_id_len_minus_1
+                + sequence_header.delta_frame_id_len_minus_2
+                + 3
+        });
+        let frame_is_intra = self.frame_type.is_intra();
+
+        if !sequence_header.reduced_still_picture_header {
+            writer.write_bool(self.show_existing_frame);
+            if self.show_existing_frame {
+                writer.write_bits(u64::from(self.frame_to_show_map_idx), 3);
+                if let Some(id_len) = id_len {
+                    writer.write_bits(self.display_frame_id, id_len);
+                }
+                return;
+            }
+            writer.write_bits(u64::from(self.frame_type as u8), 2);
+            writer.write_bool(self.show_frame);
+            if self.show_frame
+                && let Some(decoder_model_info) = sequence_header.decoder_model_info
+                && !sequence_header
+                    .timing_info
+                    .is_some_and(|ti| ti.equal_picture_interval)
+            {
+                writer.write_bits(
+                    self.frame_presentation_time,
+                    decoder_model_info.frame_presentation_time_length_minus_1 as usize + 1,
+                );
+            }
+            if !self.show_frame {
+                writer.write_bool(self.showable_frame);
+            }
+            if !(self.frame_type == FrameType::Switch
+                || (self.frame_type == FrameType::Key && self.show_frame))
+            {
+                writer.write_bool(self.error_resilient_mode);
+            }
+        }
+
+        writer.write_bool(self.disable_cdf_update);
+        if sequence_header.force_screen_content_tools == SELECT_SCREEN_CONTENT_TOOLS {
+            writer.write_bool(self.allow_screen_content_tools);
+        }
+        if self.allow_screen_content_tools && sequence_header.force_integer_mv == SELECT_INTEGER_MV
+        {
+            writer.write_bool(self.force_integer_mv);
+        }
+        if let Some(id_len) = id_len {
+            writer.write_bits(self.current_frame_id, id_len);
+        }
+        if self.frame_type != FrameType::Switch && !sequence_header.reduced_still_picture_header {
+            writer.write_bool(self.frame_size_override_flag);
+        }
+        writer.write_bits(self.order_hint, sequence_header.order_hint_bits);
+        if !(frame_is_intra || self.error_resilient_mode) {
+            writer.write_bits(u64::from(self.primary_ref_frame), 3);
+        }
+
+        if let Some(decoder_model_info) = sequence_header.decoder_model_info {
+            writer.write_bool(self.buffer_removal_time_present);
+            if self.buffer_removal_time_present {
+                let n = decoder_model_info.buffer_removal_time_length_minus_1 + 1;
+                for op_num in 0..=sequence_header.operating_points_cnt_minus_1 {
+                    if codes_buffer_removal_time(
+                        sequence_header,
+                        op_num,
+                        self.temporal_id,
+                        self.spatial_id,
+                    ) {
+                        let time = self.buffer_removal_time.get(op_num).copied().flatten();
+                        writer.write_bits(time.unwrap_or_default(), usize::from(n));
+                    }
+                }
+            }
+        }
+
+        if !(self.frame_type == FrameType::Switch
+            || (self.frame_type == FrameType::Key && self.show_frame))
+        {
+            writer.write_bits(u64::from(self.refresh_frame_flags), 8);
+        }
+        if (!frame_is_intra || self.refresh_frame_flags != REFRESH_ALL_FRAMES)
+            && self.error_resilient_mode
+            && sequence_header.enable_order_hint()
+        {
+            for hint in self.ref_order_hint.unwrap_or_default() {
+                writer.write_bits(hint, sequence_header.order_hint_bits);
+            }
+        }
+
+        let frame_width_bits = sequence_header.frame_width_bits_minus_1 + 1;
+        let frame_height_bits = sequence_header.frame_height_bits_minus_1 + 1;
+        if frame_is_intra {
+            self.frame_size.write_bits(
+                writer,
+                self.frame_size_override_flag,
+                sequence_header.enable_superres,
+                frame_width_bits,
+                frame_height_bits,
+            );
+            if self.allow_screen_content_tools
+                && self.frame_size.upscaled_size.width == self.frame_size.frame_size.width
+            {
+                writer.write_bool(self.allow_intrabc);
+            }
+        } else {
+            if sequence_header.enable_order_hint() {
+                writer.write_bool(self.frame_refs_short_signaling);
+                if self.frame_refs_short_signaling {
+                    writer.write_bits(u64::from(self.last_frame_idx), 3);
+                    writer.write_bits(u64::from(self.gold_frame_idx), 3);
+                }
+            }
+            for i in 0..REFS_PER_FRAME {
+                if !self.frame_refs_short_signaling {
+                    writer.write_bits(self.ref_frame_idx[i] as u64, 3);
+                }
+                if sequence_header.frame_id_numbers_present {
+                    writer.write_bits(
+                        self.delta_frame_id_minus_1[i],
+                        sequence_header.delta_frame_id_len_minus_2 + 2,
+                    );
+                }
+            }
+            if self.frame_size_override_flag && !self.error_resilient_mode {
+                self.frame_size.write_bits_with_refs(
+                    writer,
+                    self.frame_size_override_flag,
+                    sequence_header.enable_superres,
+                    frame_width_bits,
+                    frame_height_bits,
+                );
+            } else {
+                self.frame_size.write_bits(
+                    writer,
+                    self.frame_size_override_flag,
+                    sequence_header.enable_superres,
+                    frame_width_bits,
+                    frame_height_bits,
+                );
+            }
+            if !self.force_integer_mv {
+                writer.write_bool(self.allow_high_precision_mv);
+            }
+            let is_filter_switchable = self.interpolation_filter == INTERP_FILTER_SWITCHABLE;
+            writer.write_bool(is_filter_switchable);
+            if !is_filter_switchable {
+                writer.write_bits(u64::from(self.interpolation_filter), 2);
+            }
+            writer.write_bool(self.is_motion_mode_switchable);
+            if !self.error_resilient_mode && sequence_header.enable_ref_frame_mvs {
+                writer.write_bool(self.use_ref_frame_mvs);
+            }
+        }
+
+        if !(sequence_header.reduced_still_picture_header || self.disable_cdf_update) {
+            writer.write_bool(self.disable_frame_end_update_cdf);
+        }
+        let (mi_cols, mi_rows) = compute_image_size(self.frame_size.frame_size);
+        self.tile_info.write_bits(
+            writer,
+            sequence_header.use_128x128_superblock,
+            mi_cols,
+            mi_rows,
+        );
+        let color_config = &sequence_header.color_config;
+        self.quantization_params.write_bits(
+            writer,
+            color_config.num_planes,
+            color_config.separate_uv_delta_q,
+        );
+        self.segmentation_params
+            .write_bits(writer, self.primary_ref_frame);
+        if self.quantization_params.base_q_idx > 0 {
+            writer.write_bool(self.delta_q_present);
+        }
+        if self.delta_q_present {
+            writer.write_bits(u64::from(self.delta_q_res), 2);
+            if !self.allow_intrabc {
+                writer.write_bool(self.delta_lf_present);
+            }
+            if self.delta_lf_present {
+                writer.write_bits(u64::from(self.delta_lf_res), 2);
+                writer.write_bool(self.delta_lf_multi);
+            }
+        }
+
+        let coded_lossless = coded_lossless(&self.quantization_params, &self.segmentation_params);
+        let all_lossless = coded_lossless
+            && self.frame_size.frame_size.width == self.frame_size.upscaled_size.width;
+        if !(coded_lossless || self.allow_intrabc) {
+            self.loop_filter_params
+                .write_bits(writer, color_config.num_planes);
+        }
+        if !(coded_lossless || self.allow_intrabc || !sequence_header.enable_cdef) {
+            self.cdef_params.write_bits(writer, color_config.num_planes);
+        }
+        if !(all_lossless || self.allow_intrabc || !sequence_header.enable_restoration) {
+            self.lr_params.write_bits(
+                writer,
+                sequence_header.use_128x128_superblock,
+                color_config.num_planes,
+                color_config.subsampling,
+            );
+        }
+        if !coded_lossless {
+            writer.write_bool(self.tx_mode_select);
+        }
+        if !frame_is_intra {
+            writer.write_bool(self.reference_select);
+        }
+        if self.skip_mode_allowed {
+            writer.write_bool(self.skip_mode_present);
+        }
+        if !(frame_is_intra || self.error_resilient_mode || !sequence_header.enable_warped_motion) {
+            writer.write_bool(self.allow_warped_motion);
+        }
+        writer.write_bool(self.reduced_tx_set);
+        if !frame_is_intra {
+            self.global_motion_params
+                .write_bits(writer, self.allow_high_precision_mv);
+        }
+        if film_grain_params_present && (self.show_frame || self.showable_frame) {
+            write_film_grain_params(
+                writer,
+                &self.coded_film_grain_params,
+                self.frame_type,
+                color_config.num_planes == 1,
+                color_config.subsampling,
+            );
+        }
+    }
 }
 
 impl<const WRITE: bool> BitstreamParser<WRITE> {
@@ -89,27 +514,24 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
             self.parse_frame_header(input, obu_header, packet_ts, obu_bit_offset, true)
         })
         .parse(input)?;
-        let ref_frame_header = frame_header
-            .clone()
-            .or_else(|| self.previous_frame_header.clone())
-            .unwrap();
+        // The tile group uses the tiling of the header just parsed, which is not the
+        // previous shown frame's when this frame is hidden.
+        let Some(tile_info) = self.frame_tile_info.clone() else {
+            return Err(nom::Err::Error(Error::new(input, ErrorKind::Verify)));
+        };
         // A reminder that obu size is in bytes
         let tile_group_obu_size = self.size - (input_len - input.len());
         let (input, _) = context("Failed parsing tile group obu", |input| {
-            self.parse_tile_group_obu(input, tile_group_obu_size, ref_frame_header.tile_info, 0)
+            self.parse_tile_group_obu(input, tile_group_obu_size, &tile_info, 0)
         })
         .parse(input)?;
         Ok((input, frame_header))
     }
 
-    /// Parses the AV1 frame header section needed by grain processing.
+    /// Parses an AV1 frame header, keeping every field of its `uncompressed_header()`.
     ///
     /// Returns `None` when the parser has already consumed a frame header for
     /// the current temporal unit, or when the frame is not displayed.
-    ///
-    /// RATIONALE: film grain parameters live at the tail of the uncompressed
-    /// header, so this parser must walk the entire header bitstream to reach
-    /// them even when most fields are not otherwise used.
     pub fn parse_frame_header<'a>(
         &mut self,
         input: &'a [u8],
@@ -138,6 +560,18 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
             "Consumed {} bytes in uncompressed header",
             pre_len - input.len()
         );
+        self.decoded_frames += 1;
+        // Standalone tile group OBUs that follow this header need its tiling,
+        // including for frames that are not shown.
+        self.frame_tile_info = Some(header.tile_info.clone());
+        if !WRITE {
+            self.frame_records.push(FrameRecord::new(
+                self.frame_records.len(),
+                packet_ts,
+                self.size,
+                &header,
+            ));
+        }
         if header.show_existing_frame {
             let pre_len = input.len();
             let (input, _) = decode_frame_wrapup(input)?;
@@ -155,8 +589,9 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
 
     /// Parses `uncompressed_header()` fields and materializes frame state.
     ///
-    /// In write mode, this also rewrites film-grain syntax bits based on the
-    /// selected [`GrainTableSegment`] that matches `packet_ts`.
+    /// In write mode, this also serializes the header into the output packet,
+    /// with its film grain replaced by the selected [`GrainTableSegment`] for the
+    /// time the frame is displayed.
     #[allow(clippy::cognitive_complexity)]
     #[allow(clippy::too_many_lines)]
     fn uncompressed_header<'a>(
@@ -168,11 +603,12 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
         obu_bit_offset: usize,
         verify_byte_alignment: bool,
     ) -> IResult<&'a [u8], FrameHeader, Error<&'a [u8]>> {
-        let orig_input = input;
-
-        bits(|input| {
+        let (input, (mut header, ends_aligned)) = bits(|input| {
             let ctx = TraceCtx::new(input, obu_bit_offset);
             let sequence_header = self.sequence_header.as_ref().unwrap();
+            let (temporal_id, spatial_id) = obu_headers
+                .extension
+                .map_or((0, 0), |ext| (ext.temporal_id, ext.spatial_id));
             let id_len = sequence_header.frame_id_numbers_present.then(|| {
                 sequence_header.additional_frame_id_len_minus_1
                     + sequence_header.delta_frame_id_len_minus_2
@@ -184,47 +620,56 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 frame_type,
                 show_frame,
                 showable_frame,
-                show_existing_frame,
+                frame_presentation_time,
                 error_resilient_mode,
             ) = if sequence_header.reduced_still_picture_header {
-                (input, FrameType::Key, true, true, false, false)
+                (input, FrameType::Key, true, true, 0, false)
             } else {
                 let (input, show_existing_frame) = trace_bool(input, ctx, "show_existing_frame")?;
                 if show_existing_frame {
-                    let (input, _frame_to_show_map_idx) =
+                    let (input, frame_to_show_map_idx) =
                         trace_take_u8(input, ctx, 3, "frame_to_show_map_idx")?;
-                    let input = if let Some(id_len) = id_len {
-                        let (input, _display_frame_id) =
-                            trace_take_u64(input, ctx, id_len, "display_frame_id")?;
-                        input
+                    let (input, display_frame_id) = if let Some(id_len) = id_len {
+                        trace_take_u64(input, ctx, id_len, "display_frame_id")?
                     } else {
-                        input
+                        (input, 0)
                     };
 
-                    if WRITE {
-                        let len = orig_input.len() - input.0.len() + usize::from(input.1 > 0);
-                        self.packet_out.extend_from_slice(&orig_input[..len]);
-                        debug!("Uncompressed header extended by {} bytes", len);
-                    }
+                    let ends_aligned = input.1 == 0;
                     let input = if verify_byte_alignment {
                         trace_byte_alignment(input, ctx)?.0
                     } else {
                         input
                     };
-                    return Ok((
-                        input,
-                        FrameHeader {
-                            show_frame: true,
-                            show_existing_frame,
-                            film_grain_params: FilmGrainHeader::CopyRefFrame,
-                            tile_info: self.previous_frame_header.as_ref().unwrap().tile_info,
-                        },
-                    ));
+                    let film_grain_params =
+                        self.show_existing_frame_grain(frame_to_show_map_idx as usize, packet_ts);
+                    let header = FrameHeader {
+                        show_frame: true,
+                        show_existing_frame,
+                        film_grain_params,
+                        tile_info: self
+                            .frame_tile_info
+                            .clone()
+                            .or_else(|| {
+                                self.previous_frame_header
+                                    .as_ref()
+                                    .map(|fh| fh.tile_info.clone())
+                            })
+                            .unwrap(),
+                        temporal_id,
+                        spatial_id,
+                        frame_to_show_map_idx,
+                        display_frame_id,
+                        frame_type: self.ref_frame_type[frame_to_show_map_idx as usize]
+                            .unwrap_or_default(),
+                        ..Default::default()
+                    };
+                    return Ok((input, (header, ends_aligned)));
                 }
                 let (input, frame_type) = trace_take_u8(input, ctx, 2, "frame_type")?;
                 let frame_type = FrameType::try_from(frame_type).unwrap();
                 let (input, show_frame) = trace_bool(input, ctx, "show_frame")?;
-                let input = if show_frame
+                let (input, frame_presentation_time) = if show_frame
                     && let Some(decoder_model_info) = sequence_header.decoder_model_info
                     && !sequence_header
                         .timing_info
@@ -235,9 +680,8 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                         ctx,
                         decoder_model_info.frame_presentation_time_length_minus_1 as usize + 1,
                     )?
-                    .0
                 } else {
-                    input
+                    (input, 0)
                 };
                 let (input, showable_frame) = if show_frame {
                     (input, frame_type != FrameType::Key)
@@ -256,7 +700,7 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                     frame_type,
                     show_frame,
                     showable_frame,
-                    show_existing_frame,
+                    frame_presentation_time,
                     error_resilient_mode,
                 )
             };
@@ -278,19 +722,19 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 } else {
                     (input, sequence_header.force_screen_content_tools == 1)
                 };
-            let input = if allow_screen_content_tools
-                && sequence_header.force_integer_mv == SELECT_INTEGER_MV
-            {
-                trace_bool(input, ctx, "force_integer_mv")?.0
+            // Intra frames always use integer motion vectors, but they code no motion
+            // vector syntax, so only the signalled value is kept.
+            let (input, force_integer_mv) = if !allow_screen_content_tools {
+                (input, false)
+            } else if sequence_header.force_integer_mv == SELECT_INTEGER_MV {
+                trace_bool(input, ctx, "force_integer_mv")?
             } else {
-                input
+                (input, sequence_header.force_integer_mv == 1)
             };
-            let input = if sequence_header.frame_id_numbers_present {
-                let (input, _current_frame_id) =
-                    trace_take_usize(input, ctx, id_len.unwrap(), "current_frame_id")?;
-                input
+            let (input, current_frame_id) = if let Some(id_len) = id_len {
+                trace_take_u64(input, ctx, id_len, "current_frame_id")?
             } else {
-                input
+                (input, 0)
             };
             let (input, frame_size_override_flag) = if frame_type == FrameType::Switch {
                 (input, true)
@@ -307,35 +751,39 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 trace_take_u8(input, ctx, 3, "primary_ref_frame")?
             };
 
-            let mut input = input;
-            if let Some(decoder_model_info) = sequence_header.decoder_model_info {
-                let (inner_input, buffer_removal_time_present_flag) =
-                    trace_bool(input, ctx, "buffer_removal_time_present_flag")?;
-                if buffer_removal_time_present_flag {
-                    for op_num in 0..=sequence_header.operating_points_cnt_minus_1 {
-                        if sequence_header.decoder_model_present_for_op[op_num] {
-                            let op_pt_idc = sequence_header.operating_point_idc[op_num];
-                            let temporal_id =
-                                obu_headers.extension.map_or(0, |ext| ext.temporal_id);
-                            let spatial_id = obu_headers.extension.map_or(0, |ext| ext.spatial_id);
-                            let in_temporal_layer = (op_pt_idc >> temporal_id) & 1 > 0;
-                            let in_spatial_layer = (op_pt_idc >> (spatial_id + 8)) & 1 > 0;
-                            if op_pt_idc == 0 || (in_temporal_layer && in_spatial_layer) {
-                                let n = decoder_model_info.buffer_removal_time_length_minus_1 + 1;
-                                let (inner_input, _buffer_removal_time) = trace_take_u64(
-                                    inner_input,
-                                    ctx,
-                                    usize::from(n),
-                                    &format!("buffer_removal_time[{op_num}]"),
-                                )?;
-                                input = inner_input;
-                            }
-                        }
-                    }
+            let (mut input, buffer_removal_time_present) =
+                if sequence_header.decoder_model_info.is_some() {
+                    trace_bool(input, ctx, "buffer_removal_time_present_flag")?
+                } else {
+                    (input, false)
+                };
+            let mut buffer_removal_time = ArrayVec::new();
+            if buffer_removal_time_present
+                && let Some(decoder_model_info) = sequence_header.decoder_model_info
+            {
+                for op_num in 0..=sequence_header.operating_points_cnt_minus_1 {
+                    let time = if codes_buffer_removal_time(
+                        sequence_header,
+                        op_num,
+                        temporal_id,
+                        spatial_id,
+                    ) {
+                        let n = decoder_model_info.buffer_removal_time_length_minus_1 + 1;
+                        let (inner_input, time) = trace_take_u64(
+                            input,
+                            ctx,
+                            usize::from(n),
+                            &format!("buffer_removal_time[{op_num}]"),
+                        )?;
+                        input = inner_input;
+                        Some(time)
+                    } else {
+                        None
+                    };
+                    buffer_removal_time.push(time);
                 }
             }
 
-            let mut allow_intrabc = false;
             let (input, refresh_frame_flags) = if frame_type == FrameType::Switch
                 || (frame_type == FrameType::Key && show_frame)
             {
@@ -345,17 +793,20 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
             };
 
             let mut input = input;
+            let mut ref_order_hint = None;
             if (!frame_type.is_intra() || refresh_frame_flags != REFRESH_ALL_FRAMES)
                 && error_resilient_mode
                 && sequence_header.enable_order_hint()
             {
-                for i in 0..NUM_REF_FRAMES {
+                let mut hints = [0; NUM_REF_FRAMES];
+                for (i, hint) in hints.iter_mut().enumerate() {
                     let (inner_input, cur_ref_order_hint) = trace_take_u64(
                         input,
                         ctx,
                         sequence_header.order_hint_bits,
                         &format!("ref_order_hint[{i}]"),
                     )?;
+                    *hint = cur_ref_order_hint;
                     self.big_ref_order_hint[i] = self.ref_order_hint[i];
                     self.ref_order_hint[i] = cur_ref_order_hint;
                     if self.ref_order_hint[i] != self.big_ref_order_hint[i] {
@@ -363,57 +814,63 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                     }
                     input = inner_input;
                 }
+                ref_order_hint = Some(hints);
             }
 
             let max_frame_size = Dimensions {
                 width: sequence_header.max_frame_width_minus_1 + 1,
                 height: sequence_header.max_frame_height_minus_1 + 1,
             };
+            let frame_width_bits = sequence_header.frame_width_bits_minus_1 + 1;
+            let frame_height_bits = sequence_header.frame_height_bits_minus_1 + 1;
 
+            let mut allow_intrabc = false;
+            let mut frame_refs_short_signaling = false;
+            let mut last_frame_idx = 0;
+            let mut gold_frame_idx = 0;
+            let mut delta_frame_id_minus_1 = [0; REFS_PER_FRAME];
             let mut allow_high_precision_mv = false;
+            let mut interpolation_filter = 0;
+            let mut is_motion_mode_switchable = false;
+            let mut use_ref_frame_mvs = false;
 
-            let (input, use_ref_frame_mvs, frame_size, upscaled_size) = if frame_type.is_intra() {
-                let (input, frame_size) = frame_size(
+            let (input, frame_size) = if frame_type.is_intra() {
+                let (input, frame_size) = frame_and_render_size(
                     input,
                     ctx,
                     frame_size_override_flag,
                     sequence_header.enable_superres,
-                    sequence_header.frame_width_bits_minus_1 + 1,
-                    sequence_header.frame_height_bits_minus_1 + 1,
+                    frame_width_bits,
+                    frame_height_bits,
                     max_frame_size,
                 )?;
-                let upscaled_size = frame_size;
-                let (input, _render_size) = render_size(input, ctx, frame_size, upscaled_size)?;
-                (
-                    if allow_screen_content_tools && upscaled_size.width == frame_size.width {
-                        let (input, allow_intrabc_inner) = trace_bool(input, ctx, "allow_intrabc")?;
-                        allow_intrabc = allow_intrabc_inner;
-                        input
-                    } else {
-                        input
-                    },
-                    false,
-                    frame_size,
-                    upscaled_size,
-                )
-            } else {
-                let (mut input, frame_refs_short_signaling) = if sequence_header.enable_order_hint()
+                let input = if allow_screen_content_tools
+                    && frame_size.upscaled_size.width == frame_size.frame_size.width
                 {
-                    let (input, frame_refs_short_signaling) =
+                    let (input, allow_intrabc_inner) = trace_bool(input, ctx, "allow_intrabc")?;
+                    allow_intrabc = allow_intrabc_inner;
+                    input
+                } else {
+                    input
+                };
+                (input, frame_size)
+            } else {
+                if sequence_header.enable_order_hint() {
+                    let (inner_input, short_signaling) =
                         trace_bool(input, ctx, "frame_refs_short_signaling")?;
+                    input = inner_input;
+                    frame_refs_short_signaling = short_signaling;
                     if frame_refs_short_signaling {
-                        let (input, _last_frame_idx) =
+                        let (inner_input, last_frame_idx_inner) =
                             trace_take_u8(input, ctx, 3, "last_frame_idx")?;
-                        let (input, _gold_frame_idx) =
-                            trace_take_u8(input, ctx, 3, "gold_frame_idx")?;
-                        let (input, _) = set_frame_refs(input)?;
-                        (input, frame_refs_short_signaling)
-                    } else {
-                        (input, frame_refs_short_signaling)
+                        let (inner_input, gold_frame_idx_inner) =
+                            trace_take_u8(inner_input, ctx, 3, "gold_frame_idx")?;
+                        let (inner_input, _) = set_frame_refs(inner_input)?;
+                        input = inner_input;
+                        last_frame_idx = last_frame_idx_inner;
+                        gold_frame_idx = gold_frame_idx_inner;
                     }
-                } else {
-                    (input, false)
-                };
+                }
 
                 for (i, ref_frame_idx) in self.ref_frame_idx.iter_mut().enumerate() {
                     if frame_refs_short_signaling {
@@ -423,76 +880,69 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                             trace_take_usize(input, ctx, 3, &format!("ref_frame_idx[{i}]"))?;
                         input = inner_input;
                         *ref_frame_idx = this_ref_frame_idx;
-                        if sequence_header.frame_id_numbers_present {
-                            let n = sequence_header.delta_frame_id_len_minus_2 + 2;
-                            let (inner_input, _delta_frame_id_minus_1) = trace_take_u64(
-                                input,
-                                ctx,
-                                n,
-                                &format!("delta_frame_id_minus_1[{i}]"),
-                            )?;
-                            input = inner_input;
-                        }
+                    }
+                    if sequence_header.frame_id_numbers_present {
+                        let n = sequence_header.delta_frame_id_len_minus_2 + 2;
+                        let (inner_input, delta) =
+                            trace_take_u64(input, ctx, n, &format!("delta_frame_id_minus_1[{i}]"))?;
+                        input = inner_input;
+                        delta_frame_id_minus_1[i] = delta;
                     }
                 }
-                let (input, frame_size, upscaled_size) = if frame_size_override_flag
-                    && !error_resilient_mode
-                {
+                let (input, frame_size) = if frame_size_override_flag && !error_resilient_mode {
                     let mut frame_size = max_frame_size;
                     let mut upscaled_size = frame_size;
-                    let (input, frame_size) = frame_size_with_refs(
+                    frame_size_with_refs(
                         input,
                         ctx,
                         sequence_header.enable_superres,
                         frame_size_override_flag,
-                        sequence_header.frame_width_bits_minus_1 + 1,
-                        sequence_header.frame_height_bits_minus_1 + 1,
+                        frame_width_bits,
+                        frame_height_bits,
                         max_frame_size,
                         &mut frame_size,
                         &mut upscaled_size,
-                    )?;
-                    (input, frame_size, upscaled_size)
+                    )?
                 } else {
-                    let (input, frame_size) = frame_size(
+                    frame_and_render_size(
                         input,
                         ctx,
                         frame_size_override_flag,
                         sequence_header.enable_superres,
-                        sequence_header.frame_width_bits_minus_1 + 1,
-                        sequence_header.frame_height_bits_minus_1 + 1,
+                        frame_width_bits,
+                        frame_height_bits,
                         max_frame_size,
-                    )?;
-                    let upscaled_size = frame_size;
-                    let (input, _render_size) = render_size(input, ctx, frame_size, upscaled_size)?;
-                    (input, frame_size, upscaled_size)
+                    )?
                 };
-                let (input, allow_high_precision_mv_new) = if sequence_header.force_integer_mv == 1
-                {
+                let (input, allow_high_precision_mv_new) = if force_integer_mv {
                     (input, false)
                 } else {
                     trace_bool(input, ctx, "allow_high_precision_mv")?
                 };
                 allow_high_precision_mv = allow_high_precision_mv_new;
 
-                let (input, _) = read_interpolation_filter(input, ctx)?;
-                let (input, _is_motion_mode_switchable) =
+                let (input, interpolation_filter_new) = read_interpolation_filter(input, ctx)?;
+                interpolation_filter = interpolation_filter_new;
+                let (input, is_motion_mode_switchable_new) =
                     trace_bool(input, ctx, "is_motion_mode_switchable")?;
-                let (input, use_ref_frame_mvs) =
+                is_motion_mode_switchable = is_motion_mode_switchable_new;
+                let (input, use_ref_frame_mvs_new) =
                     if error_resilient_mode || !sequence_header.enable_ref_frame_mvs {
                         (input, false)
                     } else {
                         trace_bool(input, ctx, "use_ref_frame_mvs")?
                     };
+                use_ref_frame_mvs = use_ref_frame_mvs_new;
                 for i in 0..REFS_PER_FRAME {
                     let ref_frame = RefType::Last as usize + i;
                     let hint = self.big_ref_order_hint[self.ref_frame_idx[i]];
                     self.big_order_hints[ref_frame] = hint;
                 }
-                (input, use_ref_frame_mvs, frame_size, upscaled_size)
+                (input, frame_size)
             };
-            let (mi_cols, mi_rows) = compute_image_size(frame_size);
+            let (mi_cols, mi_rows) = compute_image_size(frame_size.frame_size);
 
-            let (input, _disable_frame_end_update_cdf) =
+            let (input, disable_frame_end_update_cdf) =
                 if sequence_header.reduced_still_picture_header || disable_cdf_update {
                     (input, true)
                 } else {
@@ -519,50 +969,34 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 mi_cols,
                 mi_rows,
             )?;
-            let (input, q_params) = quantization_params(
+            let (input, quantization_params) = quantization_params(
                 input,
                 ctx,
                 sequence_header.color_config.num_planes,
                 sequence_header.color_config.separate_uv_delta_q,
             )?;
-            let (input, segmentation_data) = segmentation_params(input, ctx, primary_ref_frame)?;
-            let (input, delta_q_present) = delta_q_params(input, ctx, q_params.base_q_idx)?;
-            let (input, _) = delta_lf_params(input, ctx, delta_q_present, allow_intrabc)?;
+            let (input, segmentation_params) = segmentation_params(input, ctx, primary_ref_frame)?;
+            let (input, (delta_q_present, delta_q_res)) =
+                delta_q_params(input, ctx, quantization_params.base_q_idx)?;
+            let (input, (delta_lf_present, delta_lf_res, delta_lf_multi)) =
+                delta_lf_params(input, ctx, delta_q_present, allow_intrabc)?;
             let input = if primary_ref_frame == PRIMARY_REF_NONE {
                 init_coeff_cdfs(input)?.0
             } else {
                 load_previous_segment_ids(input)?.0
             };
 
-            let mut coded_lossless = true;
-            for segment_id in 0..MAX_SEGMENTS {
-                let qindex = get_qindex(
-                    true,
-                    segment_id,
-                    q_params.base_q_idx,
-                    None,
-                    segmentation_data.as_ref(),
-                );
-                let lossless = qindex == 0
-                    && q_params.deltaq_y_dc == 0
-                    && q_params.deltaq_u_ac == 0
-                    && q_params.deltaq_u_dc == 0
-                    && q_params.deltaq_v_ac == 0
-                    && q_params.deltaq_v_dc == 0;
-                if !lossless {
-                    coded_lossless = false;
-                    break;
-                }
-            }
-            let all_losslesss = coded_lossless && frame_size.width == upscaled_size.width;
-            let (input, _) = loop_filter_params(
+            let coded_lossless = coded_lossless(&quantization_params, &segmentation_params);
+            let all_lossless =
+                coded_lossless && frame_size.frame_size.width == frame_size.upscaled_size.width;
+            let (input, loop_filter_params) = loop_filter_params(
                 input,
                 ctx,
                 coded_lossless,
                 allow_intrabc,
                 sequence_header.color_config.num_planes,
             )?;
-            let (input, _) = cdef_params(
+            let (input, cdef_params) = cdef_params(
                 input,
                 ctx,
                 coded_lossless,
@@ -570,20 +1004,20 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 sequence_header.enable_cdef,
                 sequence_header.color_config.num_planes,
             )?;
-            let (input, _) = lr_params(
+            let (input, lr_params) = lr_params(
                 input,
                 ctx,
-                all_losslesss,
+                all_lossless,
                 allow_intrabc,
                 sequence_header.enable_restoration,
                 sequence_header.use_128x128_superblock,
                 sequence_header.color_config.num_planes,
                 sequence_header.color_config.subsampling,
             )?;
-            let (input, _) = read_tx_mode(input, ctx, coded_lossless)?;
+            let (input, tx_mode_select) = read_tx_mode(input, ctx, coded_lossless)?;
             let (input, reference_select) =
                 frame_reference_mode(input, ctx, frame_type.is_intra())?;
-            let (input, _) = skip_mode_params(
+            let (input, (skip_mode_allowed, skip_mode_present)) = skip_mode_params(
                 input,
                 ctx,
                 frame_type.is_intra(),
@@ -593,7 +1027,7 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 &self.big_ref_order_hint,
                 &self.ref_frame_idx,
             )?;
-            let (input, _allow_warped_motion) = if frame_type.is_intra()
+            let (input, allow_warped_motion) = if frame_type.is_intra()
                 || error_resilient_mode
                 || !sequence_header.enable_warped_motion
             {
@@ -601,66 +1035,22 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
             } else {
                 trace_bool(input, ctx, "allow_warped_motion")?
             };
-            let (input, _reduced_tx_set) = trace_bool(input, ctx, "reduced_tx_set")?;
-            let (input, _) =
-                global_motion_params(input, ctx, frame_type.is_intra(), allow_high_precision_mv)?;
-
-            let film_grain_allowed = show_frame || showable_frame;
-            let written_film_grain_params = if WRITE {
-                let len = orig_input.len() - input.0.len();
-                self.packet_out.extend_from_slice(&orig_input[..len]);
-                debug!("Pre-film-grain header extended by {} bytes", len);
-                if sequence_header.new_film_grain_state && film_grain_allowed {
-                    // There will always be at least 1 bit left that we can read
-                    let extra_byte = orig_input[len];
-                    let extra_bits_used = input.1;
-                    if let Some(new_header) = self
-                        .incoming_grain_header
-                        .as_mut()
-                        .and_then(|segments| {
-                            let mut segment = segments.iter_mut().find(|seg| {
-                                seg.start_time <= packet_ts && packet_ts < seg.end_time
-                            });
-                            if let Some(segment) = segment.as_mut() {
-                                segment.grain_params.grain_seed = segment
-                                    .grain_params
-                                    .grain_seed
-                                    .wrapping_add(DEFAULT_GRAIN_SEED);
-                            }
-                            segment
-                        })
-                        .cloned()
-                    {
-                        self.write_film_grain_bits(
-                            extra_byte,
-                            extra_bits_used,
-                            &new_header,
-                            frame_type,
-                        )
-                    } else {
-                        // Sets "apply_grain" to false. We don't need to do anything else.
-                        self.write_film_grain_disabled_bit(extra_byte, extra_bits_used);
-                        FilmGrainHeader::Disable
-                    }
-                } else {
-                    // There won't be any bits remaining, and we don't need to append any bits,
-                    // so we have to handle this separately.
-                    if input.1 > 0 {
-                        let mut extra_byte = orig_input[len];
-                        let start_bit = 7 - input.1;
-                        for i in 0..=start_bit {
-                            extra_byte.set_bit(i, false);
-                        }
-                        self.packet_out.push(extra_byte);
-                    }
-                    FilmGrainHeader::Disable
-                }
+            let (input, reduced_tx_set) = trace_bool(input, ctx, "reduced_tx_set")?;
+            let prev_gm_params = if primary_ref_frame == PRIMARY_REF_NONE {
+                initialize_prev_gm_params()
             } else {
-                FilmGrainHeader::Disable
+                self.ref_gm_params[self.ref_frame_idx[primary_ref_frame as usize]]
             };
+            let (input, global_motion_params) = global_motion_params(
+                input,
+                ctx,
+                frame_type.is_intra(),
+                allow_high_precision_mv,
+                &prev_gm_params,
+            )?;
 
-            let sequence_header = self.sequence_header.as_ref().unwrap();
-            let (input, parsed_film_grain_params) = film_grain_params(
+            let film_grain_allowed = show_frame || showable_frame;
+            let (input, coded_film_grain_params) = film_grain_params(
                 input,
                 ctx,
                 sequence_header.film_grain_params_present && film_grain_allowed,
@@ -668,176 +1058,271 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 sequence_header.color_config.num_planes == 1,
                 sequence_header.color_config.subsampling,
             )?;
+            let film_grain_params = self.resolve_film_grain_ref(coded_film_grain_params.clone());
 
-            for i in 0..NUM_REF_FRAMES {
-                if (refresh_frame_flags >> i) & 1 == 1 {
-                    self.big_ref_valid[i] = true;
-                    self.big_ref_order_hint[i] = order_hint;
-                }
-            }
-
+            let ends_aligned = input.1 == 0;
             let input = if verify_byte_alignment {
                 trace_byte_alignment(input, ctx)?.0
             } else {
                 input
             };
 
-            Ok((
-                input,
-                FrameHeader {
-                    show_frame,
-                    show_existing_frame,
-                    film_grain_params: if WRITE {
-                        written_film_grain_params
-                    } else {
-                        parsed_film_grain_params
-                    },
-                    tile_info,
-                },
-            ))
-        })(input)
-    }
+            let header = FrameHeader {
+                show_frame,
+                show_existing_frame: false,
+                film_grain_params,
+                tile_info,
+                temporal_id,
+                spatial_id,
+                frame_to_show_map_idx: 0,
+                display_frame_id: 0,
+                frame_type,
+                showable_frame,
+                frame_presentation_time,
+                error_resilient_mode,
+                disable_cdf_update,
+                allow_screen_content_tools,
+                force_integer_mv,
+                current_frame_id,
+                frame_size_override_flag,
+                order_hint,
+                primary_ref_frame,
+                buffer_removal_time_present,
+                buffer_removal_time,
+                refresh_frame_flags,
+                ref_order_hint,
+                frame_refs_short_signaling,
+                last_frame_idx,
+                gold_frame_idx,
+                ref_frame_idx: self.ref_frame_idx,
+                delta_frame_id_minus_1,
+                frame_size,
+                allow_intrabc,
+                allow_high_precision_mv,
+                interpolation_filter,
+                is_motion_mode_switchable,
+                use_ref_frame_mvs,
+                disable_frame_end_update_cdf,
+                quantization_params,
+                segmentation_params,
+                delta_q_present,
+                delta_q_res,
+                delta_lf_present,
+                delta_lf_res,
+                delta_lf_multi,
+                loop_filter_params,
+                cdef_params,
+                lr_params,
+                tx_mode_select,
+                reference_select,
+                skip_mode_allowed,
+                skip_mode_present,
+                allow_warped_motion,
+                reduced_tx_set,
+                global_motion_params,
+                coded_film_grain_params,
+            };
+            Ok((input, (header, ends_aligned)))
+        })(input)?;
 
-    /// Serializes replacement film-grain syntax into the output packet buffer.
-    ///
-    /// `extra_byte` and `extra_bits_used` preserve already-consumed prefix bits
-    /// from the partially-read source byte before writing new grain fields.
-    fn write_film_grain_bits(
-        &mut self,
-        extra_byte: u8,
-        extra_bits_used: usize,
-        new_header: &GrainTableSegment,
-        frame_type: FrameType,
-    ) -> FilmGrainHeader {
-        let params = &new_header.grain_params;
-        let mut data = bitvec::bitvec![u8, Msb0;];
-
-        for i in 0..extra_bits_used {
-            data.push(extra_byte.bit(7 - i));
-        }
-
-        // Set "apply_grain" to true.
-        data.push(true);
-        // Grain seed (16 bits)
-        data.extend(params.grain_seed.view_bits::<Msb0>());
-        // update_grain flag (1 bit)
-        if frame_type == FrameType::Inter {
-            data.push(true);
-        }
-        // Y points
-        let num_y_points = params.scaling_points_y.len() as u8;
-        data.extend(&num_y_points.view_bits::<Msb0>()[4..]);
-        for point in &params.scaling_points_y {
-            data.extend(point[0].view_bits::<Msb0>());
-            data.extend(point[1].view_bits::<Msb0>());
-        }
-        // Chroma scaling from luma
-        let color_config = &self.sequence_header.as_ref().unwrap().color_config;
-        let monochrome = color_config.num_planes == 1;
-        let chroma_scaling_from_luma = if monochrome {
-            false
+        // A standalone frame header ends with `trailing_bits()`, which take a whole
+        // byte of their own when the header itself ends byte-aligned.
+        let (input, trailing_bits) = if verify_byte_alignment {
+            (input, false)
+        } else if !ends_aligned {
+            (input, true)
+        } else if let Some((0x80, rest)) = input.split_first() {
+            (rest, true)
         } else {
-            let scaling = params.chroma_scaling_from_luma;
-            data.push(scaling);
-            scaling
+            (input, false)
         };
-        // Chroma points
-        let (num_cb_points, num_cr_points) = if monochrome
-            || chroma_scaling_from_luma
-            || (color_config.subsampling == (1, 1) && num_y_points == 0)
-        {
-            (0, 0)
-        } else {
-            let cb_points = params.scaling_points_cb.len() as u8;
-            data.extend(&cb_points.view_bits::<Msb0>()[4..]);
-            for point in &params.scaling_points_cb {
-                data.extend(point[0].view_bits::<Msb0>());
-                data.extend(point[1].view_bits::<Msb0>());
-            }
 
-            let cr_points = params.scaling_points_cr.len() as u8;
-            data.extend(&cr_points.view_bits::<Msb0>()[4..]);
-            for point in &params.scaling_points_cr {
-                data.extend(point[0].view_bits::<Msb0>());
-                data.extend(point[1].view_bits::<Msb0>());
-            }
+        if WRITE {
+            self.write_uncompressed_header(&mut header, packet_ts, trailing_bits);
+        }
 
-            (cb_points, cr_points)
-        };
-        // Grain scaling minus 8 (2 bits)
-        data.extend(&(params.scaling_shift - 8).view_bits::<Msb0>()[6..]);
-        // ar_coeff_lag (2 bits)
-        data.extend(&(params.ar_coeff_lag).view_bits::<Msb0>()[6..]);
-        // ar_coeffs_y
-        let num_pos_luma = 2 * params.ar_coeff_lag as usize * (params.ar_coeff_lag as usize + 1);
-        let num_pos_chroma = if num_y_points > 0 {
-            for point in &params.ar_coeffs_y[..num_pos_luma] {
-                let point = (i16::from(*point) + 128) as u8;
-                data.extend(point.view_bits::<Msb0>());
-            }
-            num_pos_luma + 1
-        } else {
-            num_pos_luma
-        };
-        // ar_coeffs chroma
-        if chroma_scaling_from_luma || num_cb_points > 0 {
-            for point in &params.ar_coeffs_cb[..num_pos_chroma] {
-                let point = (i16::from(*point) + 128) as u8;
-                data.extend(point.view_bits::<Msb0>());
+        // In write mode the slots must hold what the output stream signals, since that
+        // is what a decoder will load for later `show_existing_frame` headers.
+        for i in 0..NUM_REF_FRAMES {
+            if (header.refresh_frame_flags >> i) & 1 == 1 {
+                self.big_ref_valid[i] = true;
+                self.big_ref_order_hint[i] = header.order_hint;
+                self.ref_film_grain[i] = header.film_grain_params.clone();
+                self.ref_frame_type[i] = Some(header.frame_type);
+                self.ref_gm_params[i] = header.global_motion_params.gm_params;
+                self.ref_decode_index[i] = self.decoded_frames;
             }
         }
-        if chroma_scaling_from_luma || num_cr_points > 0 {
-            for point in &params.ar_coeffs_cr[..num_pos_chroma] {
-                let point = (i16::from(*point) + 128) as u8;
-                data.extend(point.view_bits::<Msb0>());
-            }
+
+        Ok((input, header))
+    }
+
+    /// Serializes `header` into the output packet buffer, followed by `trailing_bits()`
+    /// if `trailing_bits` is set and `byte_alignment()` otherwise.
+    ///
+    /// Unless grain is kept, the film grain of `header` is first replaced with the
+    /// segment that matches the time the frame is displayed, and the render size with
+    /// the configured one, so that `header` holds what is written. A hidden frame is
+    /// displayed at its entry in `display_times`, if any, and otherwise at `packet_ts`.
+    fn write_uncompressed_header(
+        &mut self,
+        header: &mut FrameHeader,
+        packet_ts: u64,
+        trailing_bits: bool,
+    ) {
+        let sequence_header = self.sequence_header.as_ref().unwrap();
+        let new_film_grain_state = sequence_header.new_film_grain_state;
+        if !self.keep_grain && !header.show_existing_frame {
+            let display_ts = if header.show_frame {
+                packet_ts
+            } else {
+                self.display_times
+                    .get(&self.decoded_frames)
+                    .copied()
+                    .unwrap_or(packet_ts)
+            };
+            let new_header = if new_film_grain_state && (header.show_frame || header.showable_frame)
+            {
+                self.layer_grain_headers
+                    .get_mut(&header.spatial_id)
+                    .or(self.incoming_grain_header.as_mut())
+                    .and_then(|segments| {
+                        let mut segment = segments
+                            .iter_mut()
+                            .find(|seg| seg.start_time <= display_ts && display_ts < seg.end_time);
+                        if let Some(segment) = segment.as_mut() {
+                            segment.grain_params.grain_seed = segment
+                                .grain_params
+                                .grain_seed
+                                .wrapping_add(DEFAULT_GRAIN_SEED);
+                        }
+                        segment
+                    })
+                    .cloned()
+            } else {
+                None
+            };
+            (header.coded_film_grain_params, header.film_grain_params) = new_header.map_or(
+                (FilmGrainHeader::Disable, FilmGrainHeader::Disable),
+                |new_header| self.new_film_grain_params(&new_header, header),
+            );
+        }
+        // Not coded when the frame size is copied from a reference frame.
+        if let Some(render_size) = self.render_size
+            && header.frame_size.found_ref.is_none()
+            && !header.show_existing_frame
+        {
+            header.frame_size.render_and_frame_size_different = true;
+            header.frame_size.render_size = render_size;
+        }
+
+        let sequence_header = self.sequence_header.as_ref().unwrap();
+        let mut writer = BitWriter::new();
+        header.write_bits(&mut writer, sequence_header, new_film_grain_state);
+        if trailing_bits {
+            writer.trailing_bits();
+        } else {
+            writer.byte_alignment();
         }
-        // ar coeff shift minus 6 (2 bits)
-        data.extend(&(params.ar_coeff_shift - 6).view_bits::<Msb0>()[6..]);
-        // grain scale shift (2 bits)
-        data.extend(&(params.grain_scale_shift).view_bits::<Msb0>()[6..]);
-        // chroma multis
-        if num_cb_points > 0 {
-            data.extend((params.cb_mult).view_bits::<Msb0>());
-            data.extend((params.cb_luma_mult).view_bits::<Msb0>());
-            data.extend(&(params.cb_offset).view_bits::<Msb0>()[7..]);
-        }
-        if num_cr_points > 0 {
-            data.extend((params.cr_mult).view_bits::<Msb0>());
-            data.extend((params.cr_luma_mult).view_bits::<Msb0>());
-            data.extend(&(params.cr_offset).view_bits::<Msb0>()[7..]);
-        }
-        // overlap flag (1 bit)
-        data.push(params.overlap_flag);
-        // clip_to_restricted_range flag (1 bit)
-        data.push(params.clip_to_restricted_range);
-
-        self.packet_out.extend_from_slice(data.as_raw_slice());
+        let data = writer.into_bytes();
         trace!(
-            "Film grain packet contents: {}",
-            to_binary_string(data.as_raw_slice())
+            "Uncompressed header packet contents: {}",
+            to_binary_string(&data)
         );
+        debug!("Uncompressed header extended by {} bytes", data.len());
+        self.packet_out.extend_from_slice(&data);
+    }
 
-        FilmGrainHeader::UpdateGrain(params.clone())
+    /// Returns the coded and resolved film grain that signal `new_header` in `header`.
+    ///
+    /// On inter frames with explicitly signalled references, if one of them already
+    /// holds the same parameters, only a new seed and `film_grain_params_ref_idx` are
+    /// coded instead of the full parameter set.
+    fn new_film_grain_params(
+        &self,
+        new_header: &GrainTableSegment,
+        header: &FrameHeader,
+    ) -> (FilmGrainHeader, FilmGrainHeader) {
+        let params = &new_header.grain_params;
+        let update_grain = FilmGrainHeader::UpdateGrain(params.clone());
+        if header.frame_type == FrameType::Inter
+            && !header.frame_refs_short_signaling
+            && let Some(ref_slot) = header.ref_frame_idx.iter().copied().find(|&slot| {
+                matches!(
+                    &self.ref_film_grain[slot],
+                    FilmGrainHeader::UpdateGrain(stored) if stored == params
+                )
+            })
+        {
+            debug!("Loading unchanged film grain params from slot {ref_slot}");
+            let copy_ref_frame = FilmGrainHeader::CopyRefFrame {
+                ref_idx: ref_slot as u8,
+                grain_seed: params.grain_seed,
+            };
+            return (copy_ref_frame, update_grain);
+        }
+        (update_grain.clone(), update_grain)
     }
 
-    /// Writes an `apply_grain = 0` bit while preserving partial-byte alignment.
-    fn write_film_grain_disabled_bit(&mut self, extra_byte: u8, extra_bits_used: usize) {
-        let mut data = bitvec::bitvec![u8, Msb0;];
+    /// Resolves `update_grain = 0` into the parameters stored in `film_grain_params_ref_idx`.
+    ///
+    /// The decoder loads every parameter from that slot except `grain_seed`, which comes
+    /// from the current frame header. A slot without grain resolves to
+    /// [`FilmGrainHeader::Disable`].
+    fn resolve_film_grain_ref(&self, film_grain: FilmGrainHeader) -> FilmGrainHeader {
+        let FilmGrainHeader::CopyRefFrame {
+            ref_idx,
+            grain_seed,
+        } = film_grain
+        else {
+            return film_grain;
+        };
 
-        for i in 0..extra_bits_used {
-            data.push(extra_byte.bit(7 - i));
+        match &self.ref_film_grain[ref_idx as usize] {
+            FilmGrainHeader::UpdateGrain(params) => {
+                let mut params = params.clone();
+                params.grain_seed = grain_seed;
+                FilmGrainHeader::UpdateGrain(params)
+            }
+            ref_grain => ref_grain.clone(),
         }
-        // Set "apply_grain" to false.
-        data.push(false);
+    }
 
-        self.packet_out.extend_from_slice(data.as_raw_slice());
+    /// Returns the film grain a decoder applies for a `show_existing_frame` header.
+    ///
+    /// The grain is whatever was stored in `frame_to_show_map_idx` when that frame was
+    /// decoded, which may have been a hidden frame. Showing an existing key frame also
+    /// refreshes every reference slot with that frame's state, grain included.
+    ///
+    /// When reading, this also records `packet_ts` as the display time of that frame.
+    fn show_existing_frame_grain(
+        &mut self,
+        frame_to_show_map_idx: usize,
+        packet_ts: u64,
+    ) -> FilmGrainHeader {
+        let film_grain_params = self.ref_film_grain[frame_to_show_map_idx].clone();
+        let decode_index = self.ref_decode_index[frame_to_show_map_idx];
+        if !WRITE {
+            self.display_times.entry(decode_index).or_insert(packet_ts);
+        }
+        if self.ref_frame_type[frame_to_show_map_idx] == Some(FrameType::Key) {
+            let order_hint = self.big_ref_order_hint[frame_to_show_map_idx];
+            let gm_params = self.ref_gm_params[frame_to_show_map_idx];
+            self.big_ref_valid.fill(true);
+            self.big_ref_order_hint.fill(order_hint);
+            self.ref_film_grain.fill(film_grain_params.clone());
+            self.ref_frame_type.fill(Some(FrameType::Key));
+            self.ref_gm_params.fill(gm_params);
+            self.ref_decode_index.fill(decode_index);
+        }
+        film_grain_params
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum FrameType {
+    #[default]
     Key,
     Inter,
     IntraOnly,
@@ -861,28 +1346,49 @@ const fn decode_frame_wrapup(input: &[u8]) -> IResult<&[u8], (), Error<&[u8]>> {
     Ok((input, ()))
 }
 
-/// Reads and discards temporal-point timing metadata when present.
+/// Parses temporal-point timing metadata and returns `frame_presentation_time`.
 fn temporal_point_info<'a>(
     input: BitInput<'a>,
     ctx: TraceCtx<'a>,
     frame_presentation_time_length: usize,
-) -> IResult<BitInput<'a>, (), Error<BitInput<'a>>> {
-    let (input, _frame_presentation_time) = trace_take_u64(
+) -> IResult<BitInput<'a>, u64, Error<BitInput<'a>>> {
+    trace_take_u64(
         input,
         ctx,
         frame_presentation_time_length,
         "frame_presentation_time",
-    )?;
-    Ok((input, ()))
+    )
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Returns whether `buffer_removal_time` is coded for operating point `op_num` in a
+/// frame of the given layer.
+#[must_use]
+fn codes_buffer_removal_time(
+    sequence_header: &SequenceHeader,
+    op_num: usize,
+    temporal_id: u8,
+    spatial_id: u8,
+) -> bool {
+    if !sequence_header.decoder_model_present_for_op[op_num] {
+        return false;
+    }
+    let op_pt_idc = sequence_header.operating_point_idc[op_num];
+    let in_temporal_layer = (op_pt_idc >> temporal_id) & 1 > 0;
+    let in_spatial_layer = (op_pt_idc >> (spatial_id + 8)) & 1 > 0;
+    op_pt_idc == 0 || (in_temporal_layer && in_spatial_layer)
+}
+
+/// A width and height in pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Dimensions {
     pub width: u32,
     pub height: u32,
 }
 
 /// Parses coded frame dimensions and applies super-resolution scaling.
+///
+/// The returned [`FrameSize`] keeps the default render size, which `render_size()`
+/// follows with.
 fn frame_size<'a>(
     input: BitInput<'a>,
     ctx: TraceCtx<'a>,
@@ -891,7 +1397,7 @@ fn frame_size<'a>(
     frame_width_bits: usize,
     frame_height_bits: usize,
     max_frame_size: Dimensions,
-) -> IResult<BitInput<'a>, Dimensions, Error<BitInput<'a>>> {
+) -> IResult<BitInput<'a>, FrameSize, Error<BitInput<'a>>> {
     let (input, width, height) = if frame_size_override {
         let (input, width_minus_1) =
             trace_take_u32(input, ctx, frame_width_bits, "frame_width_minus_1")?;
@@ -903,23 +1409,28 @@ fn frame_size<'a>(
     };
     let mut frame_size = Dimensions { width, height };
     let mut upscaled_size = frame_size;
-    let (input, _) = superres_params(
+    let (input, coded_denom) = superres_params(
         input,
         ctx,
         enable_superres,
         &mut frame_size,
         &mut upscaled_size,
     )?;
-    Ok((input, frame_size))
+    Ok((
+        input,
+        FrameSize::new(None, frame_size, upscaled_size, coded_denom),
+    ))
 }
 
 /// Parses optional render dimensions for display sizing.
+///
+/// Returns `render_and_frame_size_different` and the resulting render size.
 fn render_size<'a>(
     input: BitInput<'a>,
     ctx: TraceCtx<'a>,
     frame_size: Dimensions,
     upscaled_size: Dimensions,
-) -> IResult<BitInput<'a>, Dimensions, Error<BitInput<'a>>> {
+) -> IResult<BitInput<'a>, (bool, Dimensions), Error<BitInput<'a>>> {
     let (input, render_and_frame_size_different) =
         trace_bool(input, ctx, "render_and_frame_size_different")?;
     let (input, width, height) = if render_and_frame_size_different {
@@ -930,7 +1441,39 @@ fn render_size<'a>(
     } else {
         (input, upscaled_size.width, frame_size.height)
     };
-    Ok((input, Dimensions { width, height }))
+    Ok((
+        input,
+        (
+            render_and_frame_size_different,
+            Dimensions { width, height },
+        ),
+    ))
+}
+
+/// Parses `frame_size()` followed by `render_size()`.
+fn frame_and_render_size<'a>(
+    input: BitInput<'a>,
+    ctx: TraceCtx<'a>,
+    frame_size_override: bool,
+    enable_superres: bool,
+    frame_width_bits: usize,
+    frame_height_bits: usize,
+    max_frame_size: Dimensions,
+) -> IResult<BitInput<'a>, FrameSize, Error<BitInput<'a>>> {
+    let (input, mut frame_size) = frame_size(
+        input,
+        ctx,
+        frame_size_override,
+        enable_superres,
+        frame_width_bits,
+        frame_height_bits,
+        max_frame_size,
+    )?;
+    let (input, (render_and_frame_size_different, render_size)) =
+        render_size(input, ctx, frame_size.frame_size, frame_size.upscaled_size)?;
+    frame_size.render_and_frame_size_different = render_and_frame_size_different;
+    frame_size.render_size = render_size;
+    Ok((input, frame_size))
 }
 
 /// Placeholder for AV1 `set_frame_refs()`.
@@ -944,7 +1487,8 @@ const fn set_frame_refs(input: BitInput) -> IResult<BitInput, (), Error<BitInput
 
 /// Parses frame size using reference-frame signaling when available.
 ///
-/// If no reference size is selected, falls back to explicit frame-size syntax.
+/// If no reference size is selected, falls back to explicit frame-size syntax
+/// followed by `render_size()`.
 #[allow(clippy::too_many_arguments)]
 fn frame_size_with_refs<'a, 'b>(
     input: BitInput<'a>,
@@ -956,28 +1500,31 @@ fn frame_size_with_refs<'a, 'b>(
     max_frame_size: Dimensions,
     ref_frame_size: &'b mut Dimensions,
     ref_upscaled_size: &'b mut Dimensions,
-) -> IResult<BitInput<'a>, Dimensions, Error<BitInput<'a>>> {
-    let mut found_ref = false;
+) -> IResult<BitInput<'a>, FrameSize, Error<BitInput<'a>>> {
+    let mut found_ref = None;
     let mut input = input;
     for i in 0..REFS_PER_FRAME {
         let (inner_input, found_this_ref) = trace_bool(input, ctx, &format!("found_ref[{i}]"))?;
         input = inner_input;
         if found_this_ref {
-            found_ref = true;
+            found_ref = Some(i as u8);
             break;
         }
     }
-    let (input, frame_size) = if found_ref {
-        let (input, _) = superres_params(
+    if found_ref.is_some() {
+        let (input, coded_denom) = superres_params(
             input,
             ctx,
             enable_superres,
             ref_frame_size,
             ref_upscaled_size,
         )?;
-        (input, *ref_frame_size)
+        Ok((
+            input,
+            FrameSize::new(found_ref, *ref_frame_size, *ref_upscaled_size, coded_denom),
+        ))
     } else {
-        let (input, frame_size) = frame_size(
+        frame_and_render_size(
             input,
             ctx,
             frame_size_override,
@@ -985,35 +1532,37 @@ fn frame_size_with_refs<'a, 'b>(
             frame_width_bits,
             frame_height_bits,
             max_frame_size,
-        )?;
-        let (input, _) = render_size(input, ctx, frame_size, *ref_upscaled_size)?;
-        (input, frame_size)
-    };
-    Ok((input, frame_size))
+        )
+    }
 }
 
 /// Parses super-resolution parameters and updates frame/upscaled dimensions.
+///
+/// Returns `coded_denom` if `use_superres` is set.
 fn superres_params<'a, 'b>(
     input: BitInput<'a>,
     ctx: TraceCtx<'a>,
     enable_superres: bool,
     frame_size: &'b mut Dimensions,
     upscaled_size: &'b mut Dimensions,
-) -> IResult<BitInput<'a>, (), Error<BitInput<'a>>> {
+) -> IResult<BitInput<'a>, Option<u8>, Error<BitInput<'a>>> {
     let (input, use_superres) = if enable_superres {
         trace_bool(input, ctx, "use_superres")?
     } else {
         (input, false)
     };
-    let (input, superres_denom) = if use_superres {
-        let (input, coded_denom) = trace_take_u32(input, ctx, SUPERRES_DENOM_BITS, "coded_denom")?;
-        (input, coded_denom + SUPERRES_DENOM_MIN)
+    let (input, coded_denom) = if use_superres {
+        let (input, coded_denom) = trace_take_u8(input, ctx, SUPERRES_DENOM_BITS, "coded_denom")?;
+        (input, Some(coded_denom))
     } else {
-        (input, SUPERRES_NUM)
+        (input, None)
     };
+    let superres_denom = coded_denom.map_or(SUPERRES_NUM, |coded_denom| {
+        u32::from(coded_denom) + SUPERRES_DENOM_MIN
+    });
     upscaled_size.width = frame_size.width;
     frame_size.width = (upscaled_size.width * SUPERRES_NUM + (superres_denom / 2)) / superres_denom;
-    Ok((input, ()))
+    Ok((input, coded_denom))
 }
 
 /// Converts frame dimensions into MI (mode info) grid dimensions.
@@ -1025,17 +1574,19 @@ const fn compute_image_size(frame_size: Dimensions) -> (u32, u32) {
 }
 
 /// Parses interpolation-filter selection syntax.
+///
+/// Returns `interpolation_filter`, which is `SWITCHABLE` when `is_filter_switchable`
+/// is set.
 fn read_interpolation_filter<'a>(
     input: BitInput<'a>,
     ctx: TraceCtx<'a>,
-) -> IResult<BitInput<'a>, (), Error<BitInput<'a>>> {
+) -> IResult<BitInput<'a>, u8, Error<BitInput<'a>>> {
     let (input, is_filter_switchable) = trace_bool(input, ctx, "is_filter_switchable")?;
-    let (input, _interpolation_filter) = if is_filter_switchable {
-        (input, INTERP_FILTER_SWITCHABLE)
+    if is_filter_switchable {
+        Ok((input, INTERP_FILTER_SWITCHABLE))
     } else {
-        trace_take_u8(input, ctx, 2, "interpolation_filter")?
-    };
-    Ok((input, ()))
+        trace_take_u8(input, ctx, 2, "interpolation_filter")
+    }
 }
 
 /// Placeholder for CDF initialization when no primary reference is used.
@@ -1068,6 +1619,62 @@ const fn motion_field_estimation(input: BitInput) -> IResult<BitInput, (), Error
     Ok((input, ()))
 }
 
+/// Superblock-grid limits that `tile_info()` codes the tile layout against.
+struct TileLimits {
+    sb_cols: u32,
+    sb_rows: u32,
+    max_tile_width_sb: u32,
+    max_tile_area_sb: u32,
+    min_log2_tile_cols: u32,
+    max_log2_tile_cols: u32,
+    max_log2_tile_rows: u32,
+    min_log2_tiles: u32,
+}
+
+impl TileLimits {
+    fn new(use_128x128_superblock: bool, mi_cols: u32, mi_rows: u32) -> Self {
+        let sb_cols = if use_128x128_superblock {
+            (mi_cols + 31) >> 5u8
+        } else {
+            (mi_cols + 15) >> 4u8
+        };
+        let sb_rows = if use_128x128_superblock {
+            (mi_rows + 31) >> 5u8
+        } else {
+            (mi_rows + 15) >> 4u8
+        };
+        let sb_shift = if use_128x128_superblock { 5u8 } else { 4u8 };
+        let sb_size = sb_shift + 2;
+        let max_tile_width_sb = MAX_TILE_WIDTH >> sb_size;
+        let max_tile_area_sb = MAX_TILE_AREA >> (2u8 * sb_size);
+        let min_log2_tile_cols = tile_log2(max_tile_width_sb, sb_cols);
+        Self {
+            sb_cols,
+            sb_rows,
+            max_tile_width_sb,
+            max_tile_area_sb,
+            min_log2_tile_cols,
+            max_log2_tile_cols: tile_log2(1, min(sb_cols, MAX_TILE_COLS)),
+            max_log2_tile_rows: tile_log2(1, min(sb_rows, MAX_TILE_ROWS)),
+            min_log2_tiles: max(
+                min_log2_tile_cols,
+                tile_log2(max_tile_area_sb, sb_rows * sb_cols),
+            ),
+        }
+    }
+
+    /// Returns `minLog2TileRows` for uniform tiles with `tile_cols_log2`.
+    const fn min_log2_tile_rows(&self, tile_cols_log2: u32) -> u32 {
+        self.min_log2_tiles.saturating_sub(tile_cols_log2)
+    }
+
+    /// Returns `maxTileHeightSb` for non-uniform tiles whose widest column is
+    /// `widest_tile_sb` superblocks.
+    fn max_tile_height_sb(&self, widest_tile_sb: u32) -> u32 {
+        max(self.max_tile_area_sb / widest_tile_sb, 1)
+    }
+}
+
 #[allow(clippy::too_many_lines)]
 /// Parses tile layout syntax and returns derived tile-grid metadata.
 ///
@@ -1080,35 +1687,19 @@ fn tile_info<'a>(
     mi_cols: u32,
     mi_rows: u32,
 ) -> IResult<BitInput<'a>, TileInfo, Error<BitInput<'a>>> {
-    let sb_cols = if use_128x128_superblock {
-        (mi_cols + 31) >> 5u8
-    } else {
-        (mi_cols + 15) >> 4u8
-    };
-    let sb_rows = if use_128x128_superblock {
-        (mi_rows + 31) >> 5u8
-    } else {
-        (mi_rows + 15) >> 4u8
-    };
-    let sb_shift = if use_128x128_superblock { 5u8 } else { 4u8 };
-    let sb_size = sb_shift + 2;
-    let max_tile_width_sb = MAX_TILE_WIDTH >> sb_size;
-    let max_tile_area_sb = MAX_TILE_AREA >> (2u8 * sb_size);
-    let min_log2_tile_cols = tile_log2(max_tile_width_sb, sb_cols);
-    let max_log2_tile_cols = tile_log2(1, min(sb_cols, MAX_TILE_COLS));
-    let max_log2_tile_rows = tile_log2(1, min(sb_rows, MAX_TILE_ROWS));
-    let min_log2_tiles = max(
-        min_log2_tile_cols,
-        tile_log2(max_tile_area_sb, sb_rows * sb_cols),
-    );
+    let limits = TileLimits::new(use_128x128_superblock, mi_cols, mi_rows);
+    let sb_cols = limits.sb_cols;
+    let sb_rows = limits.sb_rows;
     let tile_rows;
     let tile_cols;
+    let mut width_in_sbs_minus_1 = ArrayVec::new();
+    let mut height_in_sbs_minus_1 = ArrayVec::new();
 
     let (mut input, uniform_tile_spacing_flag) =
         trace_bool(input, ctx, "uniform_tile_spacing_flag")?;
     let (tile_cols_log2, tile_rows_log2) = if uniform_tile_spacing_flag {
-        let mut tile_cols_log2 = min_log2_tile_cols;
-        while tile_cols_log2 < max_log2_tile_cols {
+        let mut tile_cols_log2 = limits.min_log2_tile_cols;
+        while tile_cols_log2 < limits.max_log2_tile_cols {
             let (inner_input, increment_tile_cols_log2) =
                 trace_bool(input, ctx, "increment_tile_cols_log2")?;
             input = inner_input;
@@ -1121,9 +1712,8 @@ fn tile_info<'a>(
         let tile_width_sb = (sb_cols + (1 << tile_cols_log2) - 1) >> tile_cols_log2;
         tile_cols = sb_cols / tile_width_sb;
 
-        let min_log2_tile_rows = max(min_log2_tiles as i32 - tile_cols_log2 as i32, 0i32) as u32;
-        let mut tile_rows_log2 = min_log2_tile_rows;
-        while tile_rows_log2 < max_log2_tile_rows {
+        let mut tile_rows_log2 = limits.min_log2_tile_rows(tile_cols_log2);
+        while tile_rows_log2 < limits.max_log2_tile_rows {
             let (inner_input, increment_tile_rows_log2) =
                 trace_bool(input, ctx, "increment_tile_rows_log2")?;
             input = inner_input;
@@ -1142,18 +1732,21 @@ fn tile_info<'a>(
         let mut start_sb = 0;
         let mut i = 0;
         while start_sb < sb_cols {
-            let max_width = min(sb_cols - start_sb, max_tile_width_sb);
+            let max_width = min(sb_cols - start_sb, limits.max_tile_width_sb);
             let pos = ctx.pos(input);
-            let (inner_input, width_in_sbs_minus_1) = ns(input, max_width as usize)?;
+            let (inner_input, width_minus_1) = ns(input, max_width as usize)?;
             let bits_consumed = ctx.pos(inner_input) - pos;
             trace_field(
                 pos,
                 &format!("width_in_sbs_minus_1[{i}]"),
                 bits_consumed,
-                width_in_sbs_minus_1,
+                width_minus_1,
             );
+            if width_in_sbs_minus_1.try_push(width_minus_1 as u32).is_err() {
+                return Err(nom::Err::Error(Error::new(input, ErrorKind::TooLarge)));
+            }
             input = inner_input;
-            let size_sb = width_in_sbs_minus_1 + 1;
+            let size_sb = width_minus_1 + 1;
             widest_tile_sb = max(size_sb as u32, widest_tile_sb);
             start_sb += size_sb as u32;
             i += 1;
@@ -1162,20 +1755,26 @@ fn tile_info<'a>(
 
         let mut start_sb = 0;
         let mut i = 0;
-        let max_tile_height_sb = max(max_tile_area_sb / widest_tile_sb, 1);
+        let max_tile_height_sb = limits.max_tile_height_sb(widest_tile_sb);
         while start_sb < sb_rows {
             let max_height = min(sb_rows - start_sb, max_tile_height_sb);
             let pos = ctx.pos(input);
-            let (inner_input, height_in_sbs_minus_1) = ns(input, max_height as usize)?;
+            let (inner_input, height_minus_1) = ns(input, max_height as usize)?;
             let bits_consumed = ctx.pos(inner_input) - pos;
             trace_field(
                 pos,
                 &format!("height_in_sbs_minus_1[{i}]"),
                 bits_consumed,
-                height_in_sbs_minus_1,
+                height_minus_1,
             );
+            if height_in_sbs_minus_1
+                .try_push(height_minus_1 as u32)
+                .is_err()
+            {
+                return Err(nom::Err::Error(Error::new(input, ErrorKind::TooLarge)));
+            }
             input = inner_input;
-            let size_sb = height_in_sbs_minus_1 + 1;
+            let size_sb = height_minus_1 + 1;
             start_sb += size_sb as u32;
             i += 1;
         }
@@ -1189,19 +1788,20 @@ fn tile_info<'a>(
     assert!(tile_cols > 0);
     assert!(tile_rows > 0);
 
-    let input = if tile_cols_log2 > 0 || tile_rows_log2 > 0 {
-        let (input, _context_update_tile_id) = trace_take_u64(
-            input,
-            ctx,
-            (tile_rows_log2 + tile_cols_log2) as usize,
-            "context_update_tile_id",
-        )?;
-        let (input, _tile_size_bytes_minus_1) =
-            trace_take_u8(input, ctx, 2, "tile_size_bytes_minus_1")?;
-        input
-    } else {
-        input
-    };
+    let (input, context_update_tile_id, tile_size_bytes_minus_1) =
+        if tile_cols_log2 > 0 || tile_rows_log2 > 0 {
+            let (input, context_update_tile_id) = trace_take_u64(
+                input,
+                ctx,
+                (tile_rows_log2 + tile_cols_log2) as usize,
+                "context_update_tile_id",
+            )?;
+            let (input, tile_size_bytes_minus_1) =
+                trace_take_u8(input, ctx, 2, "tile_size_bytes_minus_1")?;
+            (input, context_update_tile_id, tile_size_bytes_minus_1)
+        } else {
+            (input, 0, 0)
+        };
 
     Ok((
         input,
@@ -1210,16 +1810,89 @@ fn tile_info<'a>(
             tile_rows,
             tile_cols_log2,
             tile_rows_log2,
+            uniform_tile_spacing_flag,
+            width_in_sbs_minus_1,
+            height_in_sbs_minus_1,
+            context_update_tile_id,
+            tile_size_bytes_minus_1,
         },
     ))
 }
 
-#[derive(Debug, Clone, Copy)]
+/// `tile_info()`, the layout of the frame's tiles.
+#[derive(Debug, Clone, Default)]
 pub struct TileInfo {
     pub tile_cols: u32,
     pub tile_rows: u32,
     pub tile_cols_log2: u32,
     pub tile_rows_log2: u32,
+    pub uniform_tile_spacing_flag: bool,
+    /// Tile sizes in superblocks, only coded without uniform tile spacing.
+    pub width_in_sbs_minus_1: ArrayVec<u32, { MAX_TILE_COLS as usize }>,
+    pub height_in_sbs_minus_1: ArrayVec<u32, { MAX_TILE_ROWS as usize }>,
+    pub context_update_tile_id: u64,
+    pub tile_size_bytes_minus_1: u8,
+}
+
+impl TileInfo {
+    /// Writes this layout as `tile_info()` syntax for a frame of `mi_cols` by `mi_rows`.
+    fn write_bits(
+        &self,
+        writer: &mut BitWriter,
+        use_128x128_superblock: bool,
+        mi_cols: u32,
+        mi_rows: u32,
+    ) {
+        let limits = TileLimits::new(use_128x128_superblock, mi_cols, mi_rows);
+        writer.write_bool(self.uniform_tile_spacing_flag);
+        if self.uniform_tile_spacing_flag {
+            write_increments(
+                writer,
+                limits.min_log2_tile_cols..limits.max_log2_tile_cols,
+                self.tile_cols_log2,
+            );
+            write_increments(
+                writer,
+                limits.min_log2_tile_rows(self.tile_cols_log2)..limits.max_log2_tile_rows,
+                self.tile_rows_log2,
+            );
+        } else {
+            let mut widest_tile_sb = 0;
+            let mut start_sb = 0;
+            for &width_minus_1 in &self.width_in_sbs_minus_1 {
+                let max_width = min(limits.sb_cols - start_sb, limits.max_tile_width_sb);
+                writer.write_ns(u64::from(width_minus_1), max_width as usize);
+                widest_tile_sb = max(width_minus_1 + 1, widest_tile_sb);
+                start_sb += width_minus_1 + 1;
+            }
+            let mut start_sb = 0;
+            let max_tile_height_sb = limits.max_tile_height_sb(widest_tile_sb);
+            for &height_minus_1 in &self.height_in_sbs_minus_1 {
+                let max_height = min(limits.sb_rows - start_sb, max_tile_height_sb);
+                writer.write_ns(u64::from(height_minus_1), max_height as usize);
+                start_sb += height_minus_1 + 1;
+            }
+        }
+        if self.tile_cols_log2 > 0 || self.tile_rows_log2 > 0 {
+            writer.write_bits(
+                self.context_update_tile_id,
+                (self.tile_rows_log2 + self.tile_cols_log2) as usize,
+            );
+            writer.write_bits(u64::from(self.tile_size_bytes_minus_1), 2);
+        }
+    }
+}
+
+/// Writes the `increment_tile_*_log2` flags that count up from the start of `range`
+/// to `log2`.
+fn write_increments(writer: &mut BitWriter, range: Range<u32>, log2: u32) {
+    for value in range {
+        let increment = value < log2;
+        writer.write_bool(increment);
+        if !increment {
+            break;
+        }
+    }
 }
 
 /// Returns the smallest `k` where `blk_size << k >= target`.
@@ -1244,38 +1917,46 @@ fn quantization_params<'a>(
 ) -> IResult<BitInput<'a>, QuantizationParams, Error<BitInput<'a>>> {
     let (input, base_q_idx) = trace_take_u8(input, ctx, 8, "base_q_idx")?;
     let (input, deltaq_y_dc) = read_delta_q(input, ctx, "delta_q_y_dc")?;
-    let (input, deltaq_u_dc, deltaq_u_ac, deltaq_v_dc, deltaq_v_ac) = if num_planes > 1 {
-        let (input, diff_uv_delta) = if separate_uv_delta_q {
-            trace_bool(input, ctx, "diff_uv_delta")?
-        } else {
-            (input, false)
-        };
-        let (input, deltaq_u_dc) = read_delta_q(input, ctx, "delta_q_u_dc")?;
-        let (input, deltaq_u_ac) = read_delta_q(input, ctx, "delta_q_u_ac")?;
-        let (input, deltaq_v_dc, deltaq_v_ac) = if diff_uv_delta {
-            let (input, deltaq_v_dc) = read_delta_q(input, ctx, "delta_q_v_dc")?;
-            let (input, deltaq_v_ac) = read_delta_q(input, ctx, "delta_q_v_ac")?;
-            (input, deltaq_v_dc, deltaq_v_ac)
+    let (input, diff_uv_delta, deltaq_u_dc, deltaq_u_ac, deltaq_v_dc, deltaq_v_ac) =
+        if num_planes > 1 {
+            let (input, diff_uv_delta) = if separate_uv_delta_q {
+                trace_bool(input, ctx, "diff_uv_delta")?
+            } else {
+                (input, false)
+            };
+            let (input, deltaq_u_dc) = read_delta_q(input, ctx, "delta_q_u_dc")?;
+            let (input, deltaq_u_ac) = read_delta_q(input, ctx, "delta_q_u_ac")?;
+            let (input, deltaq_v_dc, deltaq_v_ac) = if diff_uv_delta {
+                let (input, deltaq_v_dc) = read_delta_q(input, ctx, "delta_q_v_dc")?;
+                let (input, deltaq_v_ac) = read_delta_q(input, ctx, "delta_q_v_ac")?;
+                (input, deltaq_v_dc, deltaq_v_ac)
+            } else {
+                (input, deltaq_u_dc, deltaq_u_ac)
+            };
+            (
+                input,
+                diff_uv_delta,
+                deltaq_u_dc,
+                deltaq_u_ac,
+                deltaq_v_dc,
+                deltaq_v_ac,
+            )
         } else {
-            (input, deltaq_u_dc, deltaq_u_ac)
+            (input, false, 0, 0, 0, 0)
         };
-        (input, deltaq_u_dc, deltaq_u_ac, deltaq_v_dc, deltaq_v_ac)
-    } else {
-        (input, 0, 0, 0, 0)
-    };
     let (input, using_qmatrix) = trace_bool(input, ctx, "using_qmatrix")?;
-    let input = if using_qmatrix {
-        let (input, _qm_y) = trace_take_u8(input, ctx, 4, "qm_y")?;
+    let (input, qm_y, qm_u, qm_v) = if using_qmatrix {
+        let (input, qm_y) = trace_take_u8(input, ctx, 4, "qm_y")?;
         let (input, qm_u) = trace_take_u8(input, ctx, 4, "qm_u")?;
-        let (input, _qm_v) = if separate_uv_delta_q {
+        let (input, qm_v) = if separate_uv_delta_q {
             trace_take_u8(input, ctx, 4, "qm_v")?
         } else {
             (input, qm_u)
         };
 
-        input
+        (input, qm_y, qm_u, qm_v)
     } else {
-        input
+        (input, 0, 0, 0)
     };
 
     Ok((
@@ -1287,6 +1968,11 @@ fn quantization_params<'a>(
             deltaq_u_dc,
             deltaq_v_ac,
             deltaq_v_dc,
+            diff_uv_delta,
+            using_qmatrix,
+            qm_y,
+            qm_u,
+            qm_v,
         },
     ))
 }
@@ -1311,7 +1997,16 @@ fn read_delta_q<'a>(
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Writes a quantizer delta, coding it only when it is nonzero.
+fn write_delta_q(writer: &mut BitWriter, value: i64) {
+    writer.write_bool(value != 0);
+    if value != 0 {
+        writer.write_su(value, 1 + 6);
+    }
+}
+
+/// `quantization_params()`, the frame's base quantizer index and its per-plane deltas.
+#[derive(Debug, Clone, Copy, Default)]
 pub struct QuantizationParams {
     pub base_q_idx: u8,
     pub deltaq_y_dc: i64,
@@ -1319,34 +2014,71 @@ pub struct QuantizationParams {
     pub deltaq_u_ac: i64,
     pub deltaq_v_dc: i64,
     pub deltaq_v_ac: i64,
+    pub diff_uv_delta: bool,
+    pub using_qmatrix: bool,
+    /// Quantizer matrix levels, only coded with `using_qmatrix`.
+    pub qm_y: u8,
+    pub qm_u: u8,
+    pub qm_v: u8,
+}
+
+impl QuantizationParams {
+    /// Writes these parameters as `quantization_params()` syntax.
+    fn write_bits(&self, writer: &mut BitWriter, num_planes: u8, separate_uv_delta_q: bool) {
+        writer.write_bits(u64::from(self.base_q_idx), 8);
+        write_delta_q(writer, self.deltaq_y_dc);
+        if num_planes > 1 {
+            if separate_uv_delta_q {
+                writer.write_bool(self.diff_uv_delta);
+            }
+            write_delta_q(writer, self.deltaq_u_dc);
+            write_delta_q(writer, self.deltaq_u_ac);
+            if self.diff_uv_delta {
+                write_delta_q(writer, self.deltaq_v_dc);
+                write_delta_q(writer, self.deltaq_v_ac);
+            }
+        }
+        writer.write_bool(self.using_qmatrix);
+        if self.using_qmatrix {
+            writer.write_bits(u64::from(self.qm_y), 4);
+            writer.write_bits(u64::from(self.qm_u), 4);
+            if separate_uv_delta_q {
+                writer.write_bits(u64::from(self.qm_v), 4);
+            }
+        }
+    }
 }
 
 /// Parses segmentation flags and per-segment feature payloads.
-///
-/// Returns `None` when segmentation is disabled for the frame.
 fn segmentation_params<'a>(
     input: BitInput<'a>,
     ctx: TraceCtx<'a>,
     primary_ref_frame: u8,
-) -> IResult<BitInput<'a>, Option<SegmentationData>, Error<BitInput<'a>>> {
-    let mut segmentation_data: SegmentationData = Default::default();
+) -> IResult<BitInput<'a>, SegmentationParams, Error<BitInput<'a>>> {
+    let mut segmentation_params = SegmentationParams::default();
     let (input, segmentation_enabled) = trace_bool(input, ctx, "segmentation_enabled")?;
+    segmentation_params.segmentation_enabled = segmentation_enabled;
     let input = if segmentation_enabled {
-        let (input, segmentation_update_data) = if primary_ref_frame == PRIMARY_REF_NONE {
-            (input, true)
+        let input = if primary_ref_frame == PRIMARY_REF_NONE {
+            segmentation_params.segmentation_update_map = true;
+            segmentation_params.segmentation_update_data = true;
+            input
         } else {
             let (input, segmentation_update_map) =
                 trace_bool(input, ctx, "segmentation_update_map")?;
-            let input = if segmentation_update_map {
-                let (input, _segmentation_temporal_update) =
-                    trace_bool(input, ctx, "segmentation_temporal_update")?;
-                input
+            let (input, segmentation_temporal_update) = if segmentation_update_map {
+                trace_bool(input, ctx, "segmentation_temporal_update")?
             } else {
-                input
+                (input, false)
             };
-            trace_bool(input, ctx, "segmentation_update_data")?
+            let (input, segmentation_update_data) =
+                trace_bool(input, ctx, "segmentation_update_data")?;
+            segmentation_params.segmentation_update_map = segmentation_update_map;
+            segmentation_params.segmentation_temporal_update = segmentation_temporal_update;
+            segmentation_params.segmentation_update_data = segmentation_update_data;
+            input
         };
-        if segmentation_update_data {
+        if segmentation_params.segmentation_update_data {
             let mut input = input;
             #[allow(clippy::needless_range_loop)]
             for i in 0..MAX_SEGMENTS {
@@ -1355,7 +2087,6 @@ fn segmentation_params<'a>(
                         trace_bool(input, ctx, &format!("feature_enabled[{i}][{j}]"))?;
                     input = if feature_enabled {
                         let bits_to_read = SEGMENTATION_FEATURE_BITS[j] as usize;
-                        let limit = i16::from(SEGMENTATION_FEATURE_MAX[j]);
                         let (inner_input, feature_value) = if SEGMENTATION_FEATURE_SIGNED[j] {
                             let (input, value) = trace_su(
                                 inner_input,
@@ -1363,18 +2094,17 @@ fn segmentation_params<'a>(
                                 1 + bits_to_read,
                                 &format!("feature_value[{i}][{j}]"),
                             )?;
-                            (input, clamp(value as i16, -limit, limit))
+                            (input, value as i16)
                         } else {
-                            let (input, value): (_, i16) = trace_take_u32(
+                            trace_take_u32(
                                 inner_input,
                                 ctx,
                                 bits_to_read,
                                 &format!("feature_value[{i}][{j}]"),
                             )
-                            .map(|(i, v)| (i, v as i16))?;
-                            (input, clamp(value, 0, limit))
+                            .map(|(i, v)| (i, v as i16))?
                         };
-                        segmentation_data[i][j] = Some(feature_value);
+                        segmentation_params.feature_value[i][j] = Some(feature_value);
                         inner_input
                     } else {
                         inner_input
@@ -1390,54 +2120,145 @@ fn segmentation_params<'a>(
     };
 
     // The rest of the stuff in this method doesn't read any input, so return
-    Ok((input, segmentation_enabled.then_some(segmentation_data)))
+    Ok((input, segmentation_params))
+}
+
+/// `segmentation_params()`. Use [`Self::feature_data`] for the values in effect.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SegmentationParams {
+    pub segmentation_enabled: bool,
+    pub segmentation_update_map: bool,
+    pub segmentation_temporal_update: bool,
+    pub segmentation_update_data: bool,
+    /// `feature_value` of each enabled feature as coded, indexed by segment and feature.
+    pub feature_value: SegmentationData,
+}
+
+impl SegmentationParams {
+    /// Returns `FeatureData`, the feature values clamped to their valid range, or `None`
+    /// when segmentation is disabled for the frame.
+    #[must_use]
+    pub fn feature_data(&self) -> Option<SegmentationData> {
+        if !self.segmentation_enabled {
+            return None;
+        }
+        let mut feature_data = self.feature_value;
+        for segment in &mut feature_data {
+            for (j, value) in segment.iter_mut().enumerate() {
+                if let Some(value) = value.as_mut() {
+                    let limit = i16::from(SEGMENTATION_FEATURE_MAX[j]);
+                    let min = if SEGMENTATION_FEATURE_SIGNED[j] {
+                        -limit
+                    } else {
+                        0
+                    };
+                    *value = clamp(*value, min, limit);
+                }
+            }
+        }
+        Some(feature_data)
+    }
+
+    /// Writes these parameters as `segmentation_params()` syntax.
+    fn write_bits(&self, writer: &mut BitWriter, primary_ref_frame: u8) {
+        writer.write_bool(self.segmentation_enabled);
+        if !self.segmentation_enabled {
+            return;
+        }
+        if primary_ref_frame != PRIMARY_REF_NONE {
+            writer.write_bool(self.segmentation_update_map);
+            if self.segmentation_update_map {
+                writer.write_bool(self.segmentation_temporal_update);
+            }
+            writer.write_bool(self.segmentation_update_data);
+        }
+        if self.segmentation_update_data {
+            for segment in &self.feature_value {
+                for (j, value) in segment.iter().enumerate() {
+                    writer.write_bool(value.is_some());
+                    if let Some(value) = *value {
+                        let bits_to_write = SEGMENTATION_FEATURE_BITS[j] as usize;
+                        if SEGMENTATION_FEATURE_SIGNED[j] {
+                            writer.write_su(i64::from(value), 1 + bits_to_write);
+                        } else {
+                            writer.write_bits(value as u64, bits_to_write);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns whether every segment is coded losslessly, i.e. `CodedLossless`.
+#[must_use]
+fn coded_lossless(
+    quantization_params: &QuantizationParams,
+    segmentation_params: &SegmentationParams,
+) -> bool {
+    let feature_data = segmentation_params.feature_data();
+    (0..MAX_SEGMENTS).all(|segment_id| {
+        let qindex = get_qindex(
+            true,
+            segment_id,
+            quantization_params.base_q_idx,
+            None,
+            feature_data.as_ref(),
+        );
+        qindex == 0
+            && quantization_params.deltaq_y_dc == 0
+            && quantization_params.deltaq_u_ac == 0
+            && quantization_params.deltaq_u_dc == 0
+            && quantization_params.deltaq_v_ac == 0
+            && quantization_params.deltaq_v_dc == 0
+    })
 }
 
 /// Parses delta-quantization enablement and resolution.
 ///
-/// Returns whether `delta_q` is present for subsequent syntax sections.
+/// Returns `delta_q_present` and `delta_q_res`.
 fn delta_q_params<'a>(
     input: BitInput<'a>,
     ctx: TraceCtx<'a>,
     base_q_idx: u8,
-) -> IResult<BitInput<'a>, bool, Error<BitInput<'a>>> {
+) -> IResult<BitInput<'a>, (bool, u8), Error<BitInput<'a>>> {
     let (input, delta_q_present) = if base_q_idx > 0 {
         trace_bool(input, ctx, "delta_q_present")?
     } else {
         (input, false)
     };
-    let (input, _delta_q_res) = if delta_q_present {
+    let (input, delta_q_res) = if delta_q_present {
         trace_take_u8(input, ctx, 2, "delta_q_res")?
     } else {
         (input, 0)
     };
-    Ok((input, delta_q_present))
+    Ok((input, (delta_q_present, delta_q_res)))
 }
 
 /// Parses delta loop-filter parameters when enabled.
+///
+/// Returns `delta_lf_present`, `delta_lf_res` and `delta_lf_multi`.
 fn delta_lf_params<'a>(
     input: BitInput<'a>,
     ctx: TraceCtx<'a>,
     delta_q_present: bool,
     allow_intrabc: bool,
-) -> IResult<BitInput<'a>, (), Error<BitInput<'a>>> {
-    let input = if delta_q_present {
-        let (input, delta_lf_present) = if allow_intrabc {
-            (input, false)
-        } else {
-            trace_bool(input, ctx, "delta_lf_present")?
-        };
-        if delta_lf_present {
-            let (input, _delta_lf_res) = trace_take_u8(input, ctx, 2, "delta_lf_res")?;
-            let (input, _delta_lf_multi) = trace_bool(input, ctx, "delta_lf_multi")?;
-            input
-        } else {
-            input
-        }
+) -> IResult<BitInput<'a>, (bool, u8, bool), Error<BitInput<'a>>> {
+    if !delta_q_present {
+        return Ok((input, (false, 0, false)));
+    }
+    let (input, delta_lf_present) = if allow_intrabc {
+        (input, false)
     } else {
-        input
+        trace_bool(input, ctx, "delta_lf_present")?
     };
-    Ok((input, ()))
+    if delta_lf_present {
+        let (input, delta_lf_res) = trace_take_u8(input, ctx, 2, "delta_lf_res")?;
+        let (input, delta_lf_multi) = trace_bool(input, ctx, "delta_lf_multi")?;
+        Ok((input, (delta_lf_present, delta_lf_res, delta_lf_multi)))
+    } else {
+        Ok((input, (false, 0, false)))
+    }
 }
 
 /// Placeholder for coefficient CDF initialization.
@@ -1459,53 +2280,66 @@ fn loop_filter_params<'a>(
     coded_lossless: bool,
     allow_intrabc: bool,
     num_planes: u8,
-) -> IResult<BitInput<'a>, (), Error<BitInput<'a>>> {
+) -> IResult<BitInput<'a>, LoopFilterParams, Error<BitInput<'a>>> {
+    let mut loop_filter_params = LoopFilterParams::default();
     if coded_lossless || allow_intrabc {
-        return Ok((input, ()));
+        return Ok((input, loop_filter_params));
     }
 
+    let level = &mut loop_filter_params.loop_filter_level;
     let (input, loop_filter_l0) = trace_take_u8(input, ctx, 6, "loop_filter_level[0]")?;
     let (input, loop_filter_l1) = trace_take_u8(input, ctx, 6, "loop_filter_level[1]")?;
+    level[0] = loop_filter_l0;
+    level[1] = loop_filter_l1;
     let input = if num_planes > 1 && (loop_filter_l0 > 0 || loop_filter_l1 > 0) {
-        let (input, _loop_filter_l2) = trace_take_u8(input, ctx, 6, "loop_filter_level[2]")?;
-        let (input, _loop_filter_l3) = trace_take_u8(input, ctx, 6, "loop_filter_level[3]")?;
+        let (input, loop_filter_l2) = trace_take_u8(input, ctx, 6, "loop_filter_level[2]")?;
+        let (input, loop_filter_l3) = trace_take_u8(input, ctx, 6, "loop_filter_level[3]")?;
+        level[2] = loop_filter_l2;
+        level[3] = loop_filter_l3;
         input
     } else {
         input
     };
-    let (input, _loop_filter_sharpness) = trace_take_u8(input, ctx, 3, "loop_filter_sharpness")?;
+    let (input, loop_filter_sharpness) = trace_take_u8(input, ctx, 3, "loop_filter_sharpness")?;
+    loop_filter_params.loop_filter_sharpness = loop_filter_sharpness;
     let (mut input, loop_filter_delta_enabled) =
         trace_bool(input, ctx, "loop_filter_delta_enabled")?;
+    loop_filter_params.loop_filter_delta_enabled = loop_filter_delta_enabled;
     if loop_filter_delta_enabled {
         let (inner_input, loop_filter_delta_update) =
             trace_bool(input, ctx, "loop_filter_delta_update")?;
         input = inner_input;
+        loop_filter_params.loop_filter_delta_update = loop_filter_delta_update;
         if loop_filter_delta_update {
             for i in 0..TOTAL_REFS_PER_FRAME {
                 let (inner_input, update_ref_delta) =
                     trace_bool(input, ctx, &format!("update_ref_delta[{i}]"))?;
                 input = if update_ref_delta {
-                    let (inner_input, _loop_filter_ref_delta) = trace_su(
+                    let (inner_input, loop_filter_ref_delta) = trace_su(
                         inner_input,
                         ctx,
                         1 + 6,
                         &format!("loop_filter_ref_deltas[{i}]"),
                     )?;
+                    loop_filter_params.loop_filter_ref_deltas[i] =
+                        Some(loop_filter_ref_delta as i8);
                     inner_input
                 } else {
                     inner_input
                 };
             }
-            for i in 0..2u8 {
+            for i in 0..2 {
                 let (inner_input, update_mode_delta) =
                     trace_bool(input, ctx, &format!("update_mode_delta[{i}]"))?;
                 input = if update_mode_delta {
-                    let (inner_input, _loop_filter_mode_delta) = trace_su(
+                    let (inner_input, loop_filter_mode_delta) = trace_su(
                         inner_input,
                         ctx,
                         1 + 6,
                         &format!("loop_filter_mode_deltas[{i}]"),
                     )?;
+                    loop_filter_params.loop_filter_mode_deltas[i] =
+                        Some(loop_filter_mode_delta as i8);
                     inner_input
                 } else {
                     inner_input
@@ -1514,7 +2348,51 @@ fn loop_filter_params<'a>(
         }
     }
 
-    Ok((input, ()))
+    Ok((input, loop_filter_params))
+}
+
+/// `loop_filter_params()`, the deblocking filter strengths.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoopFilterParams {
+    /// Vertical and horizontal luma levels, then the U and V levels.
+    pub loop_filter_level: [u8; 4],
+    pub loop_filter_sharpness: u8,
+    pub loop_filter_delta_enabled: bool,
+    pub loop_filter_delta_update: bool,
+    /// `loop_filter_ref_deltas` for each reference frame, if `update_ref_delta` is set.
+    pub loop_filter_ref_deltas: [Option<i8>; TOTAL_REFS_PER_FRAME],
+    /// `loop_filter_mode_deltas`, if `update_mode_delta` is set.
+    pub loop_filter_mode_deltas: [Option<i8>; 2],
+}
+
+impl LoopFilterParams {
+    /// Writes these parameters as `loop_filter_params()` syntax.
+    fn write_bits(&self, writer: &mut BitWriter, num_planes: u8) {
+        let level = self.loop_filter_level;
+        writer.write_bits(u64::from(level[0]), 6);
+        writer.write_bits(u64::from(level[1]), 6);
+        if num_planes > 1 && (level[0] > 0 || level[1] > 0) {
+            writer.write_bits(u64::from(level[2]), 6);
+            writer.write_bits(u64::from(level[3]), 6);
+        }
+        writer.write_bits(u64::from(self.loop_filter_sharpness), 3);
+        writer.write_bool(self.loop_filter_delta_enabled);
+        if self.loop_filter_delta_enabled {
+            writer.write_bool(self.loop_filter_delta_update);
+            if self.loop_filter_delta_update {
+                for delta in self
+                    .loop_filter_ref_deltas
+                    .iter()
+                    .chain(&self.loop_filter_mode_deltas)
+                {
+                    writer.write_bool(delta.is_some());
+                    if let Some(delta) = *delta {
+                        writer.write_su(i64::from(delta), 1 + 6);
+                    }
+                }
+            }
+        }
+    }
 }
 
 /// Parses CDEF (constrained directional enhancement filter) parameters.
@@ -1525,30 +2403,64 @@ fn cdef_params<'a>(
     allow_intrabc: bool,
     enable_cdef: bool,
     num_planes: u8,
-) -> IResult<BitInput<'a>, (), Error<BitInput<'a>>> {
+) -> IResult<BitInput<'a>, CdefParams, Error<BitInput<'a>>> {
+    let mut cdef_params = CdefParams::default();
     if coded_lossless || allow_intrabc || !enable_cdef {
-        return Ok((input, ()));
+        return Ok((input, cdef_params));
     }
 
-    let (input, _cdef_damping_minus_3) = trace_take_u8(input, ctx, 2, "cdef_damping_minus_3")?;
+    let (input, cdef_damping_minus_3) = trace_take_u8(input, ctx, 2, "cdef_damping_minus_3")?;
     let (mut input, cdef_bits) = trace_take_u8(input, ctx, 2, "cdef_bits")?;
+    cdef_params.cdef_damping_minus_3 = cdef_damping_minus_3;
+    cdef_params.cdef_bits = cdef_bits;
     for i in 0..(1usize << cdef_bits) {
-        let (inner_input, _cdef_y_pri_str) =
+        let (inner_input, cdef_y_pri_str) =
             trace_take_u8(input, ctx, 4, &format!("cdef_y_pri_strength[{i}]"))?;
-        let (inner_input, _cdef_y_sec_str) =
+        let (inner_input, cdef_y_sec_str) =
             trace_take_u8(inner_input, ctx, 2, &format!("cdef_y_sec_strength[{i}]"))?;
+        cdef_params.cdef_y_pri_strength[i] = cdef_y_pri_str;
+        cdef_params.cdef_y_sec_strength[i] = cdef_y_sec_str;
         input = if num_planes > 1 {
-            let (inner_input, _cdef_uv_pri_str) =
+            let (inner_input, cdef_uv_pri_str) =
                 trace_take_u8(inner_input, ctx, 4, &format!("cdef_uv_pri_strength[{i}]"))?;
-            let (inner_input, _cdef_uv_sec_str) =
+            let (inner_input, cdef_uv_sec_str) =
                 trace_take_u8(inner_input, ctx, 2, &format!("cdef_uv_sec_strength[{i}]"))?;
+            cdef_params.cdef_uv_pri_strength[i] = cdef_uv_pri_str;
+            cdef_params.cdef_uv_sec_strength[i] = cdef_uv_sec_str;
             inner_input
         } else {
             inner_input
         }
     }
 
-    Ok((input, ()))
+    Ok((input, cdef_params))
+}
+
+/// CDEF strengths as coded, so a secondary strength of 3 stands for 4.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CdefParams {
+    pub cdef_damping_minus_3: u8,
+    pub cdef_bits: u8,
+    pub cdef_y_pri_strength: [u8; 8],
+    pub cdef_y_sec_strength: [u8; 8],
+    pub cdef_uv_pri_strength: [u8; 8],
+    pub cdef_uv_sec_strength: [u8; 8],
+}
+
+impl CdefParams {
+    /// Writes these parameters as `cdef_params()` syntax.
+    fn write_bits(&self, writer: &mut BitWriter, num_planes: u8) {
+        writer.write_bits(u64::from(self.cdef_damping_minus_3), 2);
+        writer.write_bits(u64::from(self.cdef_bits), 2);
+        for i in 0..(1usize << self.cdef_bits) {
+            writer.write_bits(u64::from(self.cdef_y_pri_strength[i]), 4);
+            writer.write_bits(u64::from(self.cdef_y_sec_strength[i]), 2);
+            if num_planes > 1 {
+                writer.write_bits(u64::from(self.cdef_uv_pri_strength[i]), 4);
+                writer.write_bits(u64::from(self.cdef_uv_sec_strength[i]), 2);
+            }
+        }
+    }
 }
 
 /// Parses loop-restoration parameters for all active planes.
@@ -1562,9 +2474,10 @@ fn lr_params<'a>(
     use_128x128_superblock: bool,
     num_planes: u8,
     subsampling: (u8, u8),
-) -> IResult<BitInput<'a>, (), Error<BitInput<'a>>> {
+) -> IResult<BitInput<'a>, LrParams, Error<BitInput<'a>>> {
+    let mut lr_params = LrParams::default();
     if all_lossless || allow_intrabc || !enable_restoration {
-        return Ok((input, ()));
+        return Ok((input, lr_params));
     }
 
     let mut input = input;
@@ -1578,24 +2491,23 @@ fn lr_params<'a>(
                 uses_chroma_lr = true;
             }
         }
+        lr_params.lr_type[usize::from(i)] = lr_type;
         input = inner_input;
     }
 
     let input = if uses_lr {
-        let input = if use_128x128_superblock {
-            let (input, _lr_unit_shift) = trace_bool(input, ctx, "lr_unit_shift")?;
+        let (input, lr_unit_shift) = trace_bool(input, ctx, "lr_unit_shift")?;
+        lr_params.lr_unit_shift = lr_unit_shift;
+        let input = if !use_128x128_superblock && lr_unit_shift {
+            let (input, lr_unit_extra_shift) = trace_bool(input, ctx, "lr_unit_extra_shift")?;
+            lr_params.lr_unit_extra_shift = lr_unit_extra_shift;
             input
         } else {
-            let (input, lr_unit_shift) = trace_bool(input, ctx, "lr_unit_shift")?;
-            if lr_unit_shift {
-                let (input, _lr_unit_extra_shift) = trace_bool(input, ctx, "lr_unit_extra_shift")?;
-                input
-            } else {
-                input
-            }
+            input
         };
         if subsampling.0 > 0 && subsampling.1 > 0 && uses_chroma_lr {
-            let (input, _lr_uv_shift) = trace_bool(input, ctx, "lr_uv_shift")?;
+            let (input, lr_uv_shift) = trace_bool(input, ctx, "lr_uv_shift")?;
+            lr_params.lr_uv_shift = lr_uv_shift;
             input
         } else {
             input
@@ -1604,22 +2516,63 @@ fn lr_params<'a>(
         input
     };
 
-    Ok((input, ()))
+    Ok((input, lr_params))
+}
+
+/// Loop-restoration parameters, with `lr_type` as coded rather than remapped to
+/// `FrameRestorationType`: 0 for none, 1 for Wiener, 2 for self-guided and 3 for
+/// switchable.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LrParams {
+    pub lr_type: [u8; 3],
+    pub lr_unit_shift: bool,
+    pub lr_unit_extra_shift: bool,
+    pub lr_uv_shift: bool,
+}
+
+impl LrParams {
+    /// Writes these parameters as `lr_params()` syntax.
+    fn write_bits(
+        self,
+        writer: &mut BitWriter,
+        use_128x128_superblock: bool,
+        num_planes: u8,
+        subsampling: (u8, u8),
+    ) {
+        let lr_type = &self.lr_type[..usize::from(num_planes)];
+        for &lr_type in lr_type {
+            writer.write_bits(u64::from(lr_type), 2);
+        }
+        let uses_lr = lr_type.iter().any(|&lr_type| lr_type != RESTORE_NONE);
+        let uses_chroma_lr = lr_type
+            .iter()
+            .skip(1)
+            .any(|&lr_type| lr_type != RESTORE_NONE);
+        if uses_lr {
+            writer.write_bool(self.lr_unit_shift);
+            if !use_128x128_superblock && self.lr_unit_shift {
+                writer.write_bool(self.lr_unit_extra_shift);
+            }
+            if subsampling.0 > 0 && subsampling.1 > 0 && uses_chroma_lr {
+                writer.write_bool(self.lr_uv_shift);
+            }
+        }
+    }
 }
 
 /// Parses transform-mode signaling.
+///
+/// Returns `tx_mode_select`, which is not coded for lossless frames.
 fn read_tx_mode<'a>(
     input: BitInput<'a>,
     ctx: TraceCtx<'a>,
     coded_lossless: bool,
-) -> IResult<BitInput<'a>, (), Error<BitInput<'a>>> {
-    let input = if coded_lossless {
-        input
+) -> IResult<BitInput<'a>, bool, Error<BitInput<'a>>> {
+    if coded_lossless {
+        Ok((input, false))
     } else {
-        let (input, _tx_mode_select) = trace_bool(input, ctx, "tx_mode_select")?;
-        input
-    };
-    Ok((input, ()))
+        trace_bool(input, ctx, "tx_mode_select")
+    }
 }
 
 /// Parses reference-mode signaling.
@@ -1638,6 +2591,8 @@ fn frame_reference_mode<'a>(
 }
 
 /// Parses skip-mode signaling after evaluating spec eligibility conditions.
+///
+/// Returns whether skip mode is allowed, and `skip_mode_present` if so.
 #[allow(clippy::too_many_arguments)]
 fn skip_mode_params<'a, 'b>(
     input: BitInput<'a>,
@@ -1648,7 +2603,7 @@ fn skip_mode_params<'a, 'b>(
     order_hint: u64,
     ref_order_hint: &'b [u64],
     ref_frame_idx: &'b [usize],
-) -> IResult<BitInput<'a>, (), Error<BitInput<'a>>> {
+) -> IResult<BitInput<'a>, (bool, bool), Error<BitInput<'a>>> {
     let skip_mode_allowed;
     let mut forward_hint = -1;
     let mut backward_hint = -1;
@@ -1698,13 +2653,13 @@ fn skip_mode_params<'a, 'b>(
         }
     }
 
-    let (input, _skip_mode_present) = if skip_mode_allowed {
+    let (input, skip_mode_present) = if skip_mode_allowed {
         trace_bool(input, ctx, "skip_mode_present")?
     } else {
         (input, false)
     };
 
-    Ok((input, ()))
+    Ok((input, (skip_mode_allowed, skip_mode_present)))
 }
 
 /// Computes wrapped signed distance between two order hints.
@@ -1728,15 +2683,15 @@ const GM_TRANS_ONLY_PREC_BITS: usize = 3;
 const GM_ABS_TRANS_BITS: usize = 12;
 const GM_TRANS_PREC_BITS: usize = 6;
 const WARPEDMODEL_PREC_BITS: usize = 16;
-const IDENTITY: usize = 0;
-const TRANSLATION: usize = 1;
-const ROTZOOM: usize = 2;
-const AFFINE: usize = 3;
+pub const IDENTITY: u8 = 0;
+pub const TRANSLATION: u8 = 1;
+pub const ROTZOOM: u8 = 2;
+pub const AFFINE: u8 = 3;
 
 /// Initializes global motion parameters to identity transforms.
 #[must_use]
-fn initialize_prev_gm_params() -> Vec<Vec<i32>> {
-    let mut prev_gm_params = vec![vec![0i32; 6]; 8]; // Assuming 8 references and 6 indices
+fn initialize_prev_gm_params() -> GmParams {
+    let mut prev_gm_params = [[0i32; 6]; TOTAL_REFS_PER_FRAME];
 
     for param_set in &mut prev_gm_params {
         for (i, param) in param_set.iter_mut().enumerate() {
@@ -1751,31 +2706,35 @@ fn initialize_prev_gm_params() -> Vec<Vec<i32>> {
     prev_gm_params
 }
 
+/// Returns the `absBits` and `precBits` that global-motion parameter `idx` is coded
+/// with.
+#[must_use]
+fn global_param_bits(allow_high_precision_mv: bool, type_: u8, idx: usize) -> (usize, usize) {
+    if idx >= 2 {
+        (GM_ABS_ALPHA_BITS, GM_ALPHA_PREC_BITS)
+    } else if type_ == TRANSLATION {
+        (
+            GM_ABS_TRANS_ONLY_BITS - usize::from(!allow_high_precision_mv),
+            GM_TRANS_ONLY_PREC_BITS - usize::from(!allow_high_precision_mv),
+        )
+    } else {
+        (GM_ABS_TRANS_BITS, GM_TRANS_PREC_BITS)
+    }
+}
+
 /// Parses one global-motion parameter using subexponential coding.
 ///
 /// The decoded value is interpreted relative to the previous parameter value
 /// for the same reference frame and coefficient index.
-fn read_global_param(
-    input: BitInput,
+fn read_global_param<'a>(
+    input: BitInput<'a>,
     allow_high_precision_mv: bool,
-    type_: usize,
+    type_: u8,
     ref_: usize,
     idx: usize,
-) -> IResult<BitInput, (), Error<BitInput>> {
-    let mut abs_bits = GM_ABS_ALPHA_BITS;
-    let mut prec_bits = GM_ALPHA_PREC_BITS;
-    let mut gm_params = initialize_prev_gm_params();
-
-    if idx < 2 {
-        if type_ == TRANSLATION {
-            abs_bits = GM_ABS_TRANS_ONLY_BITS - usize::from(!allow_high_precision_mv);
-            prec_bits = GM_TRANS_ONLY_PREC_BITS - usize::from(!allow_high_precision_mv);
-        } else {
-            abs_bits = GM_ABS_TRANS_BITS;
-            prec_bits = GM_TRANS_PREC_BITS;
-        }
-    }
-
+    prev_gm_params: &GmParams,
+) -> IResult<BitInput<'a>, i32, Error<BitInput<'a>>> {
+    let (abs_bits, prec_bits) = global_param_bits(allow_high_precision_mv, type_, idx);
     let prec_diff = WARPEDMODEL_PREC_BITS - prec_bits;
     let round = if idx % 3 == 2 {
         1i32 << WARPEDMODEL_PREC_BITS
@@ -1789,12 +2748,38 @@ fn read_global_param(
     };
 
     let mx = 1i32 << abs_bits;
-    let r = (gm_params[ref_][idx] >> prec_diff) - sub;
+    let r = (prev_gm_params[ref_][idx] >> prec_diff) - sub;
     let (input, result) = decode_signed_subexp_with_ref(input, -mx, mx + 1, r)?;
 
-    gm_params[ref_][idx] = (result << prec_diff) + round;
+    Ok((input, (result << prec_diff) + round))
+}
 
-    Ok((input, ()))
+/// Writes one global-motion parameter, the counterpart of [`read_global_param`].
+fn write_global_param(
+    writer: &mut BitWriter,
+    allow_high_precision_mv: bool,
+    type_: u8,
+    ref_: usize,
+    idx: usize,
+    global_motion_params: &GlobalMotionParams,
+) {
+    let (abs_bits, prec_bits) = global_param_bits(allow_high_precision_mv, type_, idx);
+    let prec_diff = WARPEDMODEL_PREC_BITS - prec_bits;
+    let round = if idx % 3 == 2 {
+        1i32 << WARPEDMODEL_PREC_BITS
+    } else {
+        0i32
+    };
+    let sub = if idx % 3 == 2 {
+        1i32 << prec_bits
+    } else {
+        0i32
+    };
+
+    let mx = 1i32 << abs_bits;
+    let r = (global_motion_params.prev_gm_params[ref_][idx] >> prec_diff) - sub;
+    let value = (global_motion_params.gm_params[ref_][idx] - round) >> prec_diff;
+    encode_signed_subexp_with_ref(writer, -mx, mx + 1, r, value);
 }
 
 /// Decodes a signed subexponential-coded value anchored around reference `r`.
@@ -1847,9 +2832,9 @@ fn decode_subexp(input: BitInput, num_syms: i32) -> IResult<BitInput, i32, Error
             i += 1;
             mk += a;
         } else {
-            let (inner_input, subexp_bits): (_, u8) = bit_parsers::take(b2 as u32)(input)?;
+            let (inner_input, subexp_bits): (_, u32) = bit_parsers::take(b2 as u32)(input)?;
             input = inner_input;
-            return Ok((input, i32::from(subexp_bits) + mk));
+            return Ok((input, subexp_bits as i32 + mk));
         }
         outer_input = input;
     }
@@ -1867,25 +2852,90 @@ const fn inverse_recenter(r: i32, v: i32) -> i32 {
     }
 }
 
-/// Parses global-motion model syntax for each inter reference frame.
-fn global_motion_params<'a>(
-    input: BitInput<'a>,
-    ctx: TraceCtx<'a>,
-    frame_is_intra: bool,
-    allow_high_precision_mv: bool,
-) -> IResult<BitInput<'a>, (), Error<BitInput<'a>>> {
-    if frame_is_intra {
-        return Ok((input, ()));
-    }
+/// Encodes a signed value with [`encode_unsigned_subexp_with_ref`], the counterpart of
+/// [`decode_signed_subexp_with_ref`].
+fn encode_signed_subexp_with_ref(writer: &mut BitWriter, low: i32, high: i32, r: i32, x: i32) {
+    encode_unsigned_subexp_with_ref(writer, high - low, r - low, x - low);
+}
 
-    let mut input = input;
+/// Recenters `x` around `r` and encodes it, the counterpart of
+/// [`decode_unsigned_subexp_with_ref`].
+fn encode_unsigned_subexp_with_ref(writer: &mut BitWriter, mx: i32, r: i32, x: i32) {
+    let v = if (r << 1) <= mx {
+        recenter(r, x)
+    } else {
+        recenter(mx - 1 - r, mx - 1 - x)
+    };
+    encode_subexp(writer, mx, v);
+}
 
-    for ref_ in (RefType::Last as u8)..=(RefType::Altref as u8) {
-        let mut type_ = IDENTITY;
+/// Encodes `v` in `[0, num_syms)` with subexponential coding, the counterpart of
+/// [`decode_subexp`].
+fn encode_subexp(writer: &mut BitWriter, num_syms: i32, v: i32) {
+    let mut i = 0i32;
+    let mut mk = 0i32;
+    let k = 3i32;
 
-        let (inner_input, is_global) = trace_bool(input, ctx, &format!("is_global[{ref_}]"))?;
-        input = inner_input;
-        if is_global {
+    loop {
+        let b2 = if i != 0 { k + i - 1 } else { k };
+        let a = 1 << b2;
+
+        if num_syms <= mk + 3 * a {
+            writer.write_ns((v - mk) as u64, (num_syms - mk) as usize);
+            return;
+        }
+
+        let subexp_more_bits = v >= mk + a;
+        writer.write_bool(subexp_more_bits);
+        if subexp_more_bits {
+            i += 1;
+            mk += a;
+        } else {
+            writer.write_bits((v - mk) as u64, b2 as usize);
+            return;
+        }
+    }
+}
+
+/// Applies AV1 recenter mapping, the inverse of [`inverse_recenter`].
+#[must_use]
+const fn recenter(r: i32, v: i32) -> i32 {
+    if v > 2 * r {
+        v
+    } else if v >= r {
+        (v - r) << 1
+    } else {
+        ((r - v) << 1) - 1
+    }
+}
+
+/// Parses global-motion model syntax for each inter reference frame.
+///
+/// Parameters are coded relative to `prev_gm_params`, those of the primary reference
+/// frame.
+fn global_motion_params<'a>(
+    input: BitInput<'a>,
+    ctx: TraceCtx<'a>,
+    frame_is_intra: bool,
+    allow_high_precision_mv: bool,
+    prev_gm_params: &GmParams,
+) -> IResult<BitInput<'a>, GlobalMotionParams, Error<BitInput<'a>>> {
+    let mut global_motion_params = GlobalMotionParams {
+        prev_gm_params: *prev_gm_params,
+        ..Default::default()
+    };
+    if frame_is_intra {
+        return Ok((input, global_motion_params));
+    }
+
+    let mut input = input;
+
+    for ref_ in (RefType::Last as usize)..=(RefType::Altref as usize) {
+        let mut type_ = IDENTITY;
+
+        let (inner_input, is_global) = trace_bool(input, ctx, &format!("is_global[{ref_}]"))?;
+        input = inner_input;
+        if is_global {
             let (inner_input, is_rot_zoom) =
                 trace_bool(input, ctx, &format!("is_rot_zoom[{ref_}]"))?;
             input = inner_input;
@@ -1902,35 +2952,81 @@ fn global_motion_params<'a>(
                 }
             }
         }
+        global_motion_params.gm_type[ref_] = type_;
 
-        if type_ >= ROTZOOM {
-            let (inner_input, _) =
-                read_global_param(input, allow_high_precision_mv, type_, ref_ as usize, 2)?;
-            input = inner_input;
-            let (inner_input, _) =
-                read_global_param(input, allow_high_precision_mv, type_, ref_ as usize, 3)?;
+        for &idx in global_param_idxs(type_) {
+            let (inner_input, param) = read_global_param(
+                input,
+                allow_high_precision_mv,
+                type_,
+                ref_,
+                idx,
+                prev_gm_params,
+            )?;
             input = inner_input;
-
-            if type_ == AFFINE {
-                let (inner_input, _) =
-                    read_global_param(input, allow_high_precision_mv, type_, ref_ as usize, 4)?;
-                input = inner_input;
-                let (inner_input, _) =
-                    read_global_param(input, allow_high_precision_mv, type_, ref_ as usize, 5)?;
-                input = inner_input;
-            }
+            global_motion_params.gm_params[ref_][idx] = param;
         }
-        if type_ >= TRANSLATION {
-            let (inner_input, _) =
-                read_global_param(input, allow_high_precision_mv, type_, ref_ as usize, 0)?;
-            input = inner_input;
-            let (inner_input, _) =
-                read_global_param(input, allow_high_precision_mv, type_, ref_ as usize, 1)?;
-            input = inner_input;
+        if type_ == ROTZOOM {
+            let params = &mut global_motion_params.gm_params[ref_];
+            params[4] = -params[3];
+            params[5] = params[2];
         }
     }
 
-    Ok((input, ()))
+    Ok((input, global_motion_params))
+}
+
+/// Returns the indices of the global-motion parameters coded for a model of `type_`,
+/// in coding order.
+#[must_use]
+const fn global_param_idxs(type_: u8) -> &'static [usize] {
+    match type_ {
+        TRANSLATION => &[0, 1],
+        ROTZOOM => &[2, 3, 0, 1],
+        AFFINE => &[2, 3, 4, 5, 0, 1],
+        _ => &[],
+    }
+}
+
+/// `global_motion_params()`, the warp model of each reference frame.
+#[derive(Debug, Clone, Copy)]
+pub struct GlobalMotionParams {
+    /// The model type of each reference frame, indexed by [`RefType`]: one of
+    /// [`IDENTITY`], [`TRANSLATION`], [`ROTZOOM`] or [`AFFINE`].
+    pub gm_type: [u8; TOTAL_REFS_PER_FRAME],
+    pub gm_params: GmParams,
+    /// The parameters of the primary reference frame, which `gm_params` are coded
+    /// relative to.
+    pub prev_gm_params: GmParams,
+}
+
+impl Default for GlobalMotionParams {
+    fn default() -> Self {
+        Self {
+            gm_type: [IDENTITY; TOTAL_REFS_PER_FRAME],
+            gm_params: initialize_prev_gm_params(),
+            prev_gm_params: initialize_prev_gm_params(),
+        }
+    }
+}
+
+impl GlobalMotionParams {
+    /// Writes these parameters as `global_motion_params()` syntax for an inter frame.
+    fn write_bits(&self, writer: &mut BitWriter, allow_high_precision_mv: bool) {
+        for ref_ in (RefType::Last as usize)..=(RefType::Altref as usize) {
+            let type_ = self.gm_type[ref_];
+            writer.write_bool(type_ != IDENTITY);
+            if type_ != IDENTITY {
+                writer.write_bool(type_ == ROTZOOM);
+                if type_ != ROTZOOM {
+                    writer.write_bool(type_ == TRANSLATION);
+                }
+            }
+            for &idx in global_param_idxs(type_) {
+                write_global_param(writer, allow_high_precision_mv, type_, ref_, idx, self);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -1993,18 +3089,19 @@ const fn seg_feature_active_idx(
 #[cfg(test)]
 mod tests {
     use super::super::{
+        sequence::{SELECT_INTEGER_MV, SELECT_SCREEN_CONTENT_TOOLS},
         trace::TraceCtx,
         util::{BitInput, ns, su},
     };
     use super::{
-        Dimensions, FrameType, REFS_PER_FRAME, WARPEDMODEL_PREC_BITS, cdef_params,
-        compute_image_size, decode_signed_subexp_with_ref, decode_subexp,
-        decode_unsigned_subexp_with_ref, delta_lf_params, delta_q_params, frame_reference_mode,
-        frame_size, frame_size_with_refs, get_qindex, get_relative_dist, global_motion_params,
-        initialize_prev_gm_params, inverse_recenter, loop_filter_params, lr_params,
-        quantization_params, read_delta_q, read_interpolation_filter, read_tx_mode, render_size,
-        seg_feature_active_idx, segmentation_params, skip_mode_params, superres_params,
-        temporal_point_info, tile_info, tile_log2,
+        Dimensions, FrameType, REFS_PER_FRAME, ROTZOOM, RefType, TOTAL_REFS_PER_FRAME, TRANSLATION,
+        WARPEDMODEL_PREC_BITS, cdef_params, compute_image_size, decode_signed_subexp_with_ref,
+        decode_subexp, decode_unsigned_subexp_with_ref, delta_lf_params, delta_q_params,
+        frame_reference_mode, frame_size, frame_size_with_refs, get_qindex, get_relative_dist,
+        global_motion_params, initialize_prev_gm_params, inverse_recenter, loop_filter_params,
+        lr_params, quantization_params, read_delta_q, read_interpolation_filter, read_tx_mode,
+        render_size, seg_feature_active_idx, segmentation_params, skip_mode_params,
+        superres_params, temporal_point_info, tile_info, tile_log2,
     };
 
     fn test_ctx(input: BitInput) -> TraceCtx {
@@ -2263,8 +3360,8 @@ mod tests {
         };
         let input: BitInput = (&data, 0);
         let (rem, fs) = frame_size(input, test_ctx(input), false, false, 11, 11, max).unwrap();
-        assert_eq!(fs.width, 1920);
-        assert_eq!(fs.height, 1080);
+        assert_eq!(fs.frame_size.width, 1920);
+        assert_eq!(fs.frame_size.height, 1080);
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -2280,8 +3377,8 @@ mod tests {
         };
         let input: BitInput = (&data, 0);
         let (rem, fs) = frame_size(input, test_ctx(input), true, false, 11, 11, max).unwrap();
-        assert_eq!(fs.width, 960);
-        assert_eq!(fs.height, 540);
+        assert_eq!(fs.frame_size.width, 960);
+        assert_eq!(fs.frame_size.height, 540);
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -2299,8 +3396,8 @@ mod tests {
         };
         let input: BitInput = (&data, 0);
         let (rem, fs) = frame_size(input, test_ctx(input), true, true, 11, 11, max).unwrap();
-        assert_eq!(fs.width, (1920 * 8 + 4) / 9);
-        assert_eq!(fs.height, 1080);
+        assert_eq!(fs.frame_size.width, (1920 * 8 + 4) / 9);
+        assert_eq!(fs.frame_size.height, 1080);
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -2322,7 +3419,8 @@ mod tests {
             height: 1080,
         };
         let input: BitInput = (&data, 0);
-        let (rem, rs) = render_size(input, test_ctx(input), fs, us).unwrap();
+        let (rem, (different, rs)) = render_size(input, test_ctx(input), fs, us).unwrap();
+        assert!(!different);
         assert_eq!(rs.width, 1920);
         assert_eq!(rs.height, 1080);
         assert_remaining_position(rem, &data, consumed);
@@ -2344,7 +3442,8 @@ mod tests {
             height: 1080,
         };
         let input: BitInput = (&data, 0);
-        let (rem, rs) = render_size(input, test_ctx(input), fs, us).unwrap();
+        let (rem, (different, rs)) = render_size(input, test_ctx(input), fs, us).unwrap();
+        assert!(different);
         assert_eq!(rs.width, 1280);
         assert_eq!(rs.height, 720);
         assert_remaining_position(rem, &data, consumed);
@@ -2382,8 +3481,9 @@ mod tests {
             &mut ref_us,
         )
         .unwrap();
-        assert_eq!(fs.width, 1280);
-        assert_eq!(fs.height, 720);
+        assert_eq!(fs.frame_size.width, 1280);
+        assert_eq!(fs.frame_size.height, 720);
+        assert_eq!(fs.found_ref, Some(0));
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -2416,8 +3516,9 @@ mod tests {
             &mut ref_us,
         )
         .unwrap();
-        assert_eq!(fs.width, 640);
-        assert_eq!(fs.height, 480);
+        assert_eq!(fs.frame_size.width, 640);
+        assert_eq!(fs.frame_size.height, 480);
+        assert_eq!(fs.found_ref, Some(2));
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -2456,8 +3557,10 @@ mod tests {
             &mut ref_us,
         )
         .unwrap();
-        assert_eq!(fs.width, 960);
-        assert_eq!(fs.height, 540);
+        assert_eq!(fs.frame_size.width, 960);
+        assert_eq!(fs.frame_size.height, 540);
+        assert_eq!(fs.found_ref, None);
+        assert!(!fs.render_and_frame_size_different);
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -2859,7 +3962,7 @@ mod tests {
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
         let (rem, result) = segmentation_params(input, test_ctx(input), 7).unwrap();
-        assert!(result.is_none());
+        assert!(result.feature_data().is_none());
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -2875,7 +3978,7 @@ mod tests {
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
         let (rem, result) = segmentation_params(input, test_ctx(input), 7).unwrap();
-        let seg_data = result.unwrap();
+        let seg_data = result.feature_data().unwrap();
         for seg in &seg_data {
             for feat in seg {
                 assert!(feat.is_none());
@@ -2903,7 +4006,7 @@ mod tests {
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
         let (rem, result) = segmentation_params(input, test_ctx(input), 7).unwrap();
-        let seg_data = result.unwrap();
+        let seg_data = result.feature_data().unwrap();
         assert_eq!(seg_data[0][5], Some(5));
         assert!(seg_data[0][0].is_none());
         assert_remaining_position(rem, &data, consumed);
@@ -2927,7 +4030,7 @@ mod tests {
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
         let (rem, result) = segmentation_params(input, test_ctx(input), 7).unwrap();
-        let seg_data = result.unwrap();
+        let seg_data = result.feature_data().unwrap();
         assert_eq!(seg_data[0][0], Some(-50));
         assert_remaining_position(rem, &data, consumed);
     }
@@ -2947,7 +4050,10 @@ mod tests {
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
         let (rem, result) = segmentation_params(input, test_ctx(input), 0).unwrap();
-        assert!(result.is_some());
+        assert!(result.segmentation_enabled);
+        assert!(result.segmentation_update_map);
+        assert!(result.segmentation_temporal_update);
+        assert!(result.segmentation_update_data);
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -2960,7 +4066,7 @@ mod tests {
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
         let (rem, result) = segmentation_params(input, test_ctx(input), 0).unwrap();
-        assert!(result.is_some());
+        assert!(result.segmentation_enabled);
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -2977,7 +4083,7 @@ mod tests {
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
         let (rem, result) = segmentation_params(input, test_ctx(input), 0).unwrap();
-        assert!(result.is_some());
+        assert!(result.segmentation_enabled);
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -2990,7 +4096,7 @@ mod tests {
         let bits = BitBuilder::default();
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
-        let (rem, present) = delta_q_params(input, test_ctx(input), 0).unwrap();
+        let (rem, (present, _)) = delta_q_params(input, test_ctx(input), 0).unwrap();
         assert!(!present);
         assert_remaining_position(rem, &data, consumed);
     }
@@ -3002,8 +4108,9 @@ mod tests {
         bits.push_bits(2, 2); // delta_q_res = 2
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
-        let (rem, present) = delta_q_params(input, test_ctx(input), 100).unwrap();
+        let (rem, (present, delta_q_res)) = delta_q_params(input, test_ctx(input), 100).unwrap();
         assert!(present);
+        assert_eq!(delta_q_res, 2);
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -3013,7 +4120,7 @@ mod tests {
         bits.push_bool(false); // delta_q_present = false
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
-        let (rem, present) = delta_q_params(input, test_ctx(input), 100).unwrap();
+        let (rem, (present, _)) = delta_q_params(input, test_ctx(input), 100).unwrap();
         assert!(!present);
         assert_remaining_position(rem, &data, consumed);
     }
@@ -3763,7 +4870,14 @@ mod tests {
         let bits = BitBuilder::default();
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
-        let (rem, _) = global_motion_params(input, test_ctx(input), true, true).unwrap();
+        let (rem, _) = global_motion_params(
+            input,
+            test_ctx(input),
+            true,
+            true,
+            &initialize_prev_gm_params(),
+        )
+        .unwrap();
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -3776,7 +4890,14 @@ mod tests {
         }
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
-        let (rem, _) = global_motion_params(input, test_ctx(input), false, true).unwrap();
+        let (rem, _) = global_motion_params(
+            input,
+            test_ctx(input),
+            false,
+            true,
+            &initialize_prev_gm_params(),
+        )
+        .unwrap();
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -3802,7 +4923,14 @@ mod tests {
         }
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
-        let (rem, _) = global_motion_params(input, test_ctx(input), false, true).unwrap();
+        let (rem, _) = global_motion_params(
+            input,
+            test_ctx(input),
+            false,
+            true,
+            &initialize_prev_gm_params(),
+        )
+        .unwrap();
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -3826,7 +4954,14 @@ mod tests {
         }
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
-        let (rem, _) = global_motion_params(input, test_ctx(input), false, true).unwrap();
+        let (rem, _) = global_motion_params(
+            input,
+            test_ctx(input),
+            false,
+            true,
+            &initialize_prev_gm_params(),
+        )
+        .unwrap();
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -3849,7 +4984,14 @@ mod tests {
         }
         let (data, consumed) = with_trailer(bits);
         let input: BitInput = (&data, 0);
-        let (rem, _) = global_motion_params(input, test_ctx(input), false, true).unwrap();
+        let (rem, _) = global_motion_params(
+            input,
+            test_ctx(input),
+            false,
+            true,
+            &initialize_prev_gm_params(),
+        )
+        .unwrap();
         assert_remaining_position(rem, &data, consumed);
     }
 
@@ -3936,7 +5078,7 @@ mod tests {
     use super::super::{
         BitstreamParser,
         grain::{FilmGrainHeader, FilmGrainParams, film_grain_params},
-        obu::{ObuHeader, ObuType},
+        obu::{ObuExtension, ObuHeader, ObuType},
         sequence::{
             ColorConfig, ColorPrimaries, ColorRange, MatrixCoefficients, SequenceHeader,
             TransferCharacteristics,
@@ -3944,35 +5086,24 @@ mod tests {
     };
     use super::{FrameHeader, NUM_REF_FRAMES, TileInfo};
     use crate::GrainTableSegment;
+    use crate::parser::{grain::write_film_grain_params, util::BitWriter};
     use arrayvec::ArrayVec;
     use av1_grain::DEFAULT_GRAIN_SEED;
+    use bitvec::{order::Msb0, view::BitView};
+    use std::collections::BTreeMap;
 
     fn grain_test_ctx(input: BitInput) -> TraceCtx {
         TraceCtx::new(input, 0)
     }
 
     fn make_parser<const WRITE: bool>() -> BitstreamParser<WRITE> {
-        BitstreamParser {
-            reader: None,
-            writer: None,
-            packet_out: Vec::new(),
-            incoming_grain_header: None,
-            parsed: false,
-            size: 0,
-            seen_frame_header: false,
-            sequence_header: None,
-            previous_frame_header: None,
-            ref_frame_idx: Default::default(),
-            ref_order_hint: Default::default(),
-            big_ref_order_hint: Default::default(),
-            big_ref_valid: Default::default(),
-            big_order_hints: Default::default(),
-            grain_headers: Vec::new(),
-        }
+        BitstreamParser::without_container(None)
     }
 
     fn minimal_sequence_header() -> SequenceHeader {
         SequenceHeader {
+            seq_profile: 0,
+            still_picture: false,
             reduced_still_picture_header: false,
             frame_id_numbers_present: false,
             additional_frame_id_len_minus_1: 0,
@@ -3990,8 +5121,20 @@ mod tests {
             decoder_model_present_for_op: ArrayVec::new(),
             operating_points_cnt_minus_1: 0,
             operating_point_idc: ArrayVec::new(),
+            seq_level_idx: ArrayVec::new(),
+            seq_tier: ArrayVec::new(),
+            operating_parameters_info: ArrayVec::new(),
+            initial_display_delay_present: false,
+            initial_display_delay_minus_1: ArrayVec::new(),
+            operating_point: 0,
             cur_operating_point_idc: 0,
             timing_info: None,
+            enable_filter_intra: false,
+            enable_intra_edge_filter: false,
+            enable_interintra_compound: false,
+            enable_masked_compound: false,
+            enable_dual_filter: false,
+            enable_jnt_comp: false,
             enable_ref_frame_mvs: false,
             enable_warped_motion: false,
             enable_superres: false,
@@ -3999,6 +5142,8 @@ mod tests {
             enable_restoration: false,
             use_128x128_superblock: false,
             color_config: ColorConfig {
+                bit_depth: 8,
+                color_description_present: false,
                 color_primaries: ColorPrimaries::Unspecified,
                 transfer_characteristics: TransferCharacteristics::Unspecified,
                 matrix_coefficients: MatrixCoefficients::Unspecified,
@@ -4006,6 +5151,7 @@ mod tests {
                 num_planes: 1,
                 separate_uv_delta_q: false,
                 subsampling: (0, 0),
+                chroma_sample_position: 0,
             },
         }
     }
@@ -4100,50 +5246,52 @@ mod tests {
         bits
     }
 
-    // -----------------------------------------------------------------------
-    // Group A: write_film_grain_disabled_bit
-    // -----------------------------------------------------------------------
-
-    #[test]
-    fn write_grain_disabled_no_extra_bits() {
-        let mut parser = make_parser::<true>();
-        parser.sequence_header = Some(minimal_sequence_header());
-        parser.write_film_grain_disabled_bit(0x00, 0);
-        assert_eq!(parser.packet_out, vec![0x00]);
-    }
-
-    #[test]
-    fn write_grain_disabled_3_extra_bits() {
-        let mut parser = make_parser::<true>();
-        parser.sequence_header = Some(minimal_sequence_header());
-        // extra_byte=0b1101_0000, 3 bits → 1,1,0 then apply_grain=0
-        parser.write_film_grain_disabled_bit(0b1101_0000, 3);
-        // Result: bits 1,1,0,0 → 0b1100_0000 = 0xC0
-        assert_eq!(parser.packet_out, vec![0xC0]);
+    /// Picks the film grain for a new frame of `frame_type` as `uncompressed_header()`
+    /// does, and writes it as `film_grain_params()`.
+    fn write_new_grain(
+        parser: &BitstreamParser<true>,
+        segment: &GrainTableSegment,
+        frame_type: FrameType,
+        ref_frame_idx: [usize; REFS_PER_FRAME],
+    ) -> (FilmGrainHeader, Vec<u8>) {
+        let header = FrameHeader {
+            frame_type,
+            ref_frame_idx,
+            ..Default::default()
+        };
+        let (coded, resolved) = parser.new_film_grain_params(segment, &header);
+        let color_config = &parser.sequence_header.as_ref().unwrap().color_config;
+        let mut writer = BitWriter::new();
+        write_film_grain_params(
+            &mut writer,
+            &coded,
+            frame_type,
+            color_config.num_planes == 1,
+            color_config.subsampling,
+        );
+        (resolved, writer.into_bytes())
     }
 
-    #[test]
-    fn write_grain_disabled_7_extra_bits() {
-        let mut parser = make_parser::<true>();
-        parser.sequence_header = Some(minimal_sequence_header());
-        // extra_byte=0b1111_1110, 7 bits → 1,1,1,1,1,1,1 then apply_grain=0
-        parser.write_film_grain_disabled_bit(0b1111_1110, 7);
-        // Result: bits 1,1,1,1,1,1,1,0 → 0b1111_1110 = 0xFE
-        assert_eq!(parser.packet_out, vec![0xFE]);
-    }
+    // -----------------------------------------------------------------------
+    // Group A: disabled film grain
+    // -----------------------------------------------------------------------
 
     #[test]
-    fn write_grain_disabled_1_extra_bit() {
-        let mut parser = make_parser::<true>();
-        parser.sequence_header = Some(minimal_sequence_header());
-        // extra_byte=0b1000_0000, 1 bit → 1 then apply_grain=0
-        parser.write_film_grain_disabled_bit(0b1000_0000, 1);
-        // Result: bits 1,0 → 0b1000_0000 = 0x80
-        assert_eq!(parser.packet_out, vec![0x80]);
+    fn write_grain_disabled_writes_only_apply_grain() {
+        let mut writer = BitWriter::new();
+        write_film_grain_params(
+            &mut writer,
+            &FilmGrainHeader::Disable,
+            FrameType::Key,
+            true,
+            (1, 1),
+        );
+        assert_eq!(writer.len(), 1);
+        assert_eq!(writer.into_bytes(), vec![0x00]);
     }
 
     // -----------------------------------------------------------------------
-    // Group B: write_film_grain_bits roundtrip tests
+    // Group B: film grain write roundtrip tests
     // -----------------------------------------------------------------------
 
     #[test]
@@ -4157,11 +5305,10 @@ mod tests {
             end_time: 0,
             grain_params: params.clone(),
         };
-        let result = parser.write_film_grain_bits(0, 0, &segment, FrameType::Key);
+        let (result, data) = write_new_grain(&parser, &segment, FrameType::Key, Default::default());
         assert!(matches!(result, FilmGrainHeader::UpdateGrain(_)));
 
         // Parse the written output as grain params
-        let data = &parser.packet_out;
         let grain_input: BitInput = (data.as_slice(), 0);
         let (_, parsed) = film_grain_params(
             grain_input,
@@ -4190,10 +5337,10 @@ mod tests {
             end_time: 0,
             grain_params: params.clone(),
         };
-        let result = parser.write_film_grain_bits(0, 0, &segment, FrameType::Inter);
+        let (result, data) =
+            write_new_grain(&parser, &segment, FrameType::Inter, Default::default());
         assert!(matches!(result, FilmGrainHeader::UpdateGrain(_)));
 
-        let data = &parser.packet_out;
         let grain_input: BitInput = (data.as_slice(), 0);
         let (_, parsed) = film_grain_params(
             grain_input,
@@ -4211,6 +5358,100 @@ mod tests {
         }
     }
 
+    #[test]
+    fn write_grain_inter_reuses_matching_ref_slot() {
+        let mut parser = make_parser::<true>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        let mut stored = minimal_grain_params();
+        stored.grain_seed = 1;
+        parser.ref_film_grain[4] = FilmGrainHeader::UpdateGrain(stored);
+        let mut params = minimal_grain_params();
+        params.grain_seed = 0x1234;
+        let segment = GrainTableSegment {
+            start_time: 0,
+            end_time: 0,
+            grain_params: params.clone(),
+        };
+
+        let (result, data) =
+            write_new_grain(&parser, &segment, FrameType::Inter, [0, 2, 4, 4, 5, 6, 7]);
+        assert_eq!(result, FilmGrainHeader::UpdateGrain(params));
+
+        // apply_grain (1) + grain_seed (16) + update_grain (1) + ref_idx (3)
+        assert_eq!(data.len(), 3);
+        let grain_input: BitInput = (data.as_slice(), 0);
+        let (_, parsed) = film_grain_params(
+            grain_input,
+            grain_test_ctx(grain_input),
+            true,
+            FrameType::Inter,
+            true,
+            (0, 0),
+        )
+        .unwrap();
+        assert_eq!(
+            parsed,
+            FilmGrainHeader::CopyRefFrame {
+                ref_idx: 4,
+                grain_seed: 0x1234,
+            }
+        );
+    }
+
+    #[test]
+    fn write_grain_inter_ignores_matching_slot_outside_ref_slots() {
+        let mut parser = make_parser::<true>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        parser.ref_film_grain[4] = FilmGrainHeader::UpdateGrain(minimal_grain_params());
+        let segment = GrainTableSegment {
+            start_time: 0,
+            end_time: 0,
+            grain_params: minimal_grain_params(),
+        };
+
+        let (_, data) = write_new_grain(&parser, &segment, FrameType::Inter, [0, 1, 2, 3, 5, 6, 7]);
+
+        let grain_input: BitInput = (data.as_slice(), 0);
+        let (_, parsed) = film_grain_params(
+            grain_input,
+            grain_test_ctx(grain_input),
+            true,
+            FrameType::Inter,
+            true,
+            (0, 0),
+        )
+        .unwrap();
+        assert!(matches!(parsed, FilmGrainHeader::UpdateGrain(_)));
+    }
+
+    #[test]
+    fn write_grain_key_frame_never_reuses_ref_slot() {
+        let mut parser = make_parser::<true>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        parser
+            .ref_film_grain
+            .fill(FilmGrainHeader::UpdateGrain(minimal_grain_params()));
+        let segment = GrainTableSegment {
+            start_time: 0,
+            end_time: 0,
+            grain_params: minimal_grain_params(),
+        };
+
+        let (_, data) = write_new_grain(&parser, &segment, FrameType::Key, [0, 1, 2, 3, 4, 5, 6]);
+
+        let grain_input: BitInput = (data.as_slice(), 0);
+        let (_, parsed) = film_grain_params(
+            grain_input,
+            grain_test_ctx(grain_input),
+            true,
+            FrameType::Key,
+            true,
+            (0, 0),
+        )
+        .unwrap();
+        assert!(matches!(parsed, FilmGrainHeader::UpdateGrain(_)));
+    }
+
     #[test]
     fn write_grain_roundtrip_y_scaling_points() {
         let mut parser = make_parser::<true>();
@@ -4224,10 +5465,9 @@ mod tests {
             end_time: 0,
             grain_params: params.clone(),
         };
-        let result = parser.write_film_grain_bits(0, 0, &segment, FrameType::Key);
+        let (result, data) = write_new_grain(&parser, &segment, FrameType::Key, Default::default());
         assert!(matches!(result, FilmGrainHeader::UpdateGrain(_)));
 
-        let data = &parser.packet_out;
         let grain_input: BitInput = (data.as_slice(), 0);
         let (_, parsed) = film_grain_params(
             grain_input,
@@ -4271,10 +5511,9 @@ mod tests {
             end_time: 0,
             grain_params: params.clone(),
         };
-        let result = parser.write_film_grain_bits(0, 0, &segment, FrameType::Key);
+        let (result, data) = write_new_grain(&parser, &segment, FrameType::Key, Default::default());
         assert!(matches!(result, FilmGrainHeader::UpdateGrain(_)));
 
-        let data = &parser.packet_out;
         let grain_input: BitInput = (data.as_slice(), 0);
         let (_, parsed) = film_grain_params(
             grain_input,
@@ -4325,10 +5564,9 @@ mod tests {
             end_time: 0,
             grain_params: params.clone(),
         };
-        let result = parser.write_film_grain_bits(0, 0, &segment, FrameType::Key);
+        let (result, data) = write_new_grain(&parser, &segment, FrameType::Key, Default::default());
         assert!(matches!(result, FilmGrainHeader::UpdateGrain(_)));
 
-        let data = &parser.packet_out;
         let grain_input: BitInput = (data.as_slice(), 0);
         let (_, parsed) = film_grain_params(
             grain_input,
@@ -4349,25 +5587,6 @@ mod tests {
         }
     }
 
-    #[test]
-    fn write_grain_extra_bits_prefix_preserved() {
-        let mut parser = make_parser::<true>();
-        parser.sequence_header = Some(minimal_sequence_header());
-        let mut params = minimal_grain_params();
-        params.grain_seed = 0;
-        let segment = GrainTableSegment {
-            start_time: 0,
-            end_time: 0,
-            grain_params: params,
-        };
-        // extra_byte=0b11100000, extra_bits_used=3 → prefix bits: 1,1,1
-        parser.write_film_grain_bits(0b1110_0000, 3, &segment, FrameType::Key);
-        // First 3 bits should be 111, bit 3 should be apply_grain=1
-        let first_byte = parser.packet_out[0];
-        assert_eq!(first_byte >> 5, 0b111); // top 3 bits
-        assert_eq!((first_byte >> 4) & 1, 1); // apply_grain=1 at bit 3
-    }
-
     // -----------------------------------------------------------------------
     // Group C: parse_frame_header
     // -----------------------------------------------------------------------
@@ -4402,7 +5621,11 @@ mod tests {
                 tile_rows: 3,
                 tile_cols_log2: 1,
                 tile_rows_log2: 2,
+                ..Default::default()
             },
+            temporal_id: 0,
+            spatial_id: 0,
+            ..Default::default()
         });
         // show_existing_frame=1 (1 bit), frame_to_show_map_idx=000 (3 bits)
         let mut bits = BitBuilder::default();
@@ -4414,7 +5637,8 @@ mod tests {
             .unwrap();
         let header = result.expect("shown existing frame should return Some");
         assert!(header.show_existing_frame);
-        assert_eq!(header.film_grain_params, FilmGrainHeader::CopyRefFrame);
+        // Slot 0 has never been refreshed, so it holds no grain
+        assert_eq!(header.film_grain_params, FilmGrainHeader::Disable);
         // seen_frame_header should be cleared for show_existing_frame
         assert!(!parser.seen_frame_header);
     }
@@ -4447,6 +5671,29 @@ mod tests {
         assert!(parser.seen_frame_header);
     }
 
+    #[test]
+    fn parse_frame_header_records_every_frame() {
+        let mut parser = make_parser::<false>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        parser.size = 42;
+        for (packet_ts, show_frame) in [(0, false), (417_083, true)] {
+            let (data, _) = with_trailer(build_minimal_key_frame_bits(show_frame));
+            parser
+                .parse_frame_header(&data, simple_obu_header(), packet_ts, 0, false)
+                .unwrap();
+            parser.seen_frame_header = false;
+        }
+
+        let records = parser.frame_records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].decode_index, 0);
+        assert!(!records[0].show_frame);
+        assert_eq!(records[1].decode_index, 1);
+        assert_eq!(records[1].timestamp, 417_083);
+        assert_eq!(records[1].obu_size, 42);
+        assert_eq!(records[1].frame_type, FrameType::Key);
+    }
+
     #[test]
     fn parse_frame_header_show_existing_carries_tile_info() {
         let mut parser = make_parser::<false>();
@@ -4456,12 +5703,16 @@ mod tests {
             tile_rows: 5,
             tile_cols_log2: 2,
             tile_rows_log2: 3,
+            ..Default::default()
         };
         parser.previous_frame_header = Some(FrameHeader {
             show_frame: true,
             show_existing_frame: false,
             film_grain_params: FilmGrainHeader::Disable,
-            tile_info: expected_tile_info,
+            tile_info: expected_tile_info.clone(),
+            temporal_id: 0,
+            spatial_id: 0,
+            ..Default::default()
         });
         let mut bits = BitBuilder::default();
         bits.push_bool(true); // show_existing_frame
@@ -4475,26 +5726,157 @@ mod tests {
         assert_eq!(header.tile_info.tile_rows, expected_tile_info.tile_rows);
     }
 
-    // -----------------------------------------------------------------------
-    // Group D: uncompressed_header specifics
-    // -----------------------------------------------------------------------
+    #[test]
+    fn resolve_film_grain_ref_loads_slot_params_with_new_seed() {
+        let mut parser = make_parser::<false>();
+        let mut stored = minimal_grain_params();
+        stored.grain_seed = 1;
+        parser.ref_film_grain[6] = FilmGrainHeader::UpdateGrain(stored.clone());
+
+        let resolved = parser.resolve_film_grain_ref(FilmGrainHeader::CopyRefFrame {
+            ref_idx: 6,
+            grain_seed: 0xBEEF,
+        });
+
+        let FilmGrainHeader::UpdateGrain(params) = resolved else {
+            panic!("expected UpdateGrain, got {resolved:?}");
+        };
+        assert_eq!(params, stored);
+        assert_eq!(params.grain_seed, 0xBEEF);
+    }
 
     #[test]
-    fn uncompressed_header_key_show_resets_refs() {
+    fn resolve_film_grain_ref_slot_without_grain_disables() {
+        let parser = make_parser::<false>();
+
+        let resolved = parser.resolve_film_grain_ref(FilmGrainHeader::CopyRefFrame {
+            ref_idx: 2,
+            grain_seed: 7,
+        });
+
+        assert_eq!(resolved, FilmGrainHeader::Disable);
+    }
+
+    #[test]
+    fn resolve_film_grain_ref_passes_through_updates() {
+        let parser = make_parser::<false>();
+        let update = FilmGrainHeader::UpdateGrain(minimal_grain_params());
+
+        assert_eq!(parser.resolve_film_grain_ref(update.clone()), update);
+    }
+
+    fn show_existing_frame_bits(frame_to_show_map_idx: u64) -> Vec<u8> {
+        let mut bits = BitBuilder::default();
+        bits.push_bool(true); // show_existing_frame
+        bits.push_bits(frame_to_show_map_idx, 3);
+        with_trailer(bits).0
+    }
+
+    #[test]
+    fn parse_frame_header_show_existing_loads_slot_grain() {
         let mut parser = make_parser::<false>();
         parser.sequence_header = Some(minimal_sequence_header());
-        // Pre-populate ref state
-        for i in 0..NUM_REF_FRAMES {
-            parser.big_ref_valid[i] = true;
-            parser.big_ref_order_hint[i] = 99;
-        }
-        let bits = build_minimal_key_frame_bits(true);
-        let (data, _) = with_trailer(bits);
-        let _ = parser
+        parser.frame_tile_info = Some(TileInfo {
+            tile_cols: 1,
+            tile_rows: 1,
+            tile_cols_log2: 0,
+            tile_rows_log2: 0,
+            ..Default::default()
+        });
+        parser.ref_film_grain[3] = FilmGrainHeader::UpdateGrain(minimal_grain_params());
+        parser.ref_frame_type[3] = Some(FrameType::Inter);
+
+        let data = show_existing_frame_bits(3);
+        let (_, result) = parser
             .parse_frame_header(&data, simple_obu_header(), 0, 0, false)
             .unwrap();
-        // Key+show clears refs first, then refreshes all (order_hint=0, valid=true)
-        for i in 0..NUM_REF_FRAMES {
+
+        let header = result.expect("shown existing frame should return Some");
+        assert_eq!(
+            header.film_grain_params,
+            FilmGrainHeader::UpdateGrain(minimal_grain_params())
+        );
+        // Showing a non-key frame does not refresh any other slot
+        assert_eq!(parser.ref_film_grain[0], FilmGrainHeader::Disable);
+    }
+
+    #[test]
+    fn parse_frame_header_show_existing_key_frame_refreshes_all_slots() {
+        let mut parser = make_parser::<false>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        parser.frame_tile_info = Some(TileInfo {
+            tile_cols: 1,
+            tile_rows: 1,
+            tile_cols_log2: 0,
+            tile_rows_log2: 0,
+            ..Default::default()
+        });
+        parser.ref_film_grain[5] = FilmGrainHeader::UpdateGrain(minimal_grain_params());
+        parser.ref_frame_type[5] = Some(FrameType::Key);
+        parser.big_ref_order_hint[5] = 9;
+
+        let data = show_existing_frame_bits(5);
+        let _ = parser
+            .parse_frame_header(&data, simple_obu_header(), 0, 0, false)
+            .unwrap();
+
+        let expected = FilmGrainHeader::UpdateGrain(minimal_grain_params());
+        assert!(parser.ref_film_grain.iter().all(|grain| *grain == expected));
+        assert_eq!(
+            parser.ref_frame_type,
+            [Some(FrameType::Key); NUM_REF_FRAMES]
+        );
+        assert_eq!(parser.big_ref_valid, [true; NUM_REF_FRAMES]);
+        assert_eq!(parser.big_ref_order_hint, [9; NUM_REF_FRAMES]);
+    }
+
+    #[test]
+    fn parse_frame_header_hidden_frame_stores_grain_in_refreshed_slots() {
+        let mut parser = make_parser::<false>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        parser
+            .ref_film_grain
+            .fill(FilmGrainHeader::UpdateGrain(minimal_grain_params()));
+        // Hidden key frame with refresh_frame_flags = 0xFF and no grain signalled
+        let bits = build_minimal_key_frame_bits(false);
+        let (data, _) = with_trailer(bits);
+        let (_, result) = parser
+            .parse_frame_header(&data, simple_obu_header(), 0, 0, false)
+            .unwrap();
+
+        assert!(result.is_none());
+        assert!(
+            parser
+                .ref_film_grain
+                .iter()
+                .all(|grain| *grain == FilmGrainHeader::Disable)
+        );
+        assert_eq!(
+            parser.ref_frame_type,
+            [Some(FrameType::Key); NUM_REF_FRAMES]
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Group D: uncompressed_header specifics
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn uncompressed_header_key_show_resets_refs() {
+        let mut parser = make_parser::<false>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        // Pre-populate ref state
+        for i in 0..NUM_REF_FRAMES {
+            parser.big_ref_valid[i] = true;
+            parser.big_ref_order_hint[i] = 99;
+        }
+        let bits = build_minimal_key_frame_bits(true);
+        let (data, _) = with_trailer(bits);
+        let _ = parser
+            .parse_frame_header(&data, simple_obu_header(), 0, 0, false)
+            .unwrap();
+        // Key+show clears refs first, then refreshes all (order_hint=0, valid=true)
+        for i in 0..NUM_REF_FRAMES {
             assert!(
                 parser.big_ref_valid[i],
                 "ref {i} should be valid after refresh"
@@ -4564,7 +5946,11 @@ mod tests {
                 tile_rows: 1,
                 tile_cols_log2: 0,
                 tile_rows_log2: 0,
+                ..Default::default()
             },
+            temporal_id: 0,
+            spatial_id: 0,
+            ..Default::default()
         });
         let mut bits = BitBuilder::default();
         bits.push_bool(true); // show_existing_frame
@@ -4611,6 +5997,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn uncompressed_header_write_uses_spatial_layer_table() {
+        let mut parser = make_parser::<true>();
+        let mut seq = minimal_sequence_header();
+        seq.film_grain_params_present = true;
+        seq.new_film_grain_state = true;
+        parser.sequence_header = Some(seq);
+        let mut base_grain = minimal_grain_params();
+        base_grain.grain_seed = 100;
+        let mut layer_grain = minimal_grain_params();
+        layer_grain.grain_seed = 200;
+        parser.incoming_grain_header = Some(vec![GrainTableSegment {
+            start_time: 0,
+            end_time: 1000,
+            grain_params: base_grain,
+        }]);
+        parser = parser.with_layer_grain(
+            1,
+            vec![GrainTableSegment {
+                start_time: 0,
+                end_time: 1000,
+                grain_params: layer_grain,
+            }],
+        );
+        let obu_header = ObuHeader {
+            extension: Some(ObuExtension {
+                temporal_id: 0,
+                spatial_id: 1,
+            }),
+            ..simple_obu_header()
+        };
+        let mut bits = build_minimal_key_frame_bits(true);
+        bits.push_bool(false); // apply_grain = false
+        let (data, _) = with_trailer(bits);
+        let (_, result) = parser
+            .parse_frame_header(&data, obu_header, 500, 0, false)
+            .unwrap();
+        let header = result.unwrap();
+        assert_eq!(header.spatial_id, 1);
+        match &header.film_grain_params {
+            FilmGrainHeader::UpdateGrain(params) => {
+                assert_eq!(params.grain_seed, 200u16.wrapping_add(DEFAULT_GRAIN_SEED));
+            }
+            other => panic!("expected UpdateGrain, got {other:?}"),
+        }
+    }
+
+    /// A hidden key frame with `showable_frame = 1` and `apply_grain = 0`, for a
+    /// sequence header with film grain params present.
+    fn showable_hidden_key_frame_bits() -> Vec<u8> {
+        let mut bits = build_minimal_key_frame_bits(false);
+        bits.bits[4] = true; // showable_frame = 1
+        bits.push_bool(false); // apply_grain = false in original stream
+        with_trailer(bits).0
+    }
+
+    #[test]
+    fn parse_frame_header_records_display_time_of_shown_hidden_frame() {
+        let mut parser = make_parser::<false>();
+        let mut seq = minimal_sequence_header();
+        seq.film_grain_params_present = true;
+        parser.sequence_header = Some(seq);
+        let data = showable_hidden_key_frame_bits();
+        let _ = parser
+            .parse_frame_header(&data, simple_obu_header(), 500, 0, false)
+            .unwrap();
+        parser.seen_frame_header = false;
+        let data = show_existing_frame_bits(2);
+        let _ = parser
+            .parse_frame_header(&data, simple_obu_header(), 1500, 0, false)
+            .unwrap();
+
+        assert_eq!(parser.display_times(), &BTreeMap::from([(0, 1500)]));
+    }
+
+    #[test]
+    fn uncompressed_header_write_picks_hidden_frame_grain_at_display_time() {
+        let mut seq = minimal_sequence_header();
+        seq.film_grain_params_present = true;
+        seq.new_film_grain_state = true;
+        let mut coded_grain = minimal_grain_params();
+        coded_grain.grain_seed = 100;
+        let mut displayed_grain = minimal_grain_params();
+        displayed_grain.grain_seed = 200;
+        let mut parser = BitstreamParser::<true>::without_container(Some(vec![
+            GrainTableSegment {
+                start_time: 0,
+                end_time: 1000,
+                grain_params: coded_grain,
+            },
+            GrainTableSegment {
+                start_time: 1000,
+                end_time: 2000,
+                grain_params: displayed_grain,
+            },
+        ]))
+        .with_display_times(BTreeMap::from([(0, 1500)]));
+        parser.sequence_header = Some(seq);
+
+        // Coded in a packet of the first segment, shown in the second
+        let data = showable_hidden_key_frame_bits();
+        let _ = parser
+            .parse_frame_header(&data, simple_obu_header(), 500, 0, false)
+            .unwrap();
+        parser.seen_frame_header = false;
+        let data = show_existing_frame_bits(2);
+        let (_, result) = parser
+            .parse_frame_header(&data, simple_obu_header(), 1500, 0, false)
+            .unwrap();
+
+        match &result.unwrap().film_grain_params {
+            FilmGrainHeader::UpdateGrain(params) => {
+                assert_eq!(params.grain_seed, 200u16.wrapping_add(DEFAULT_GRAIN_SEED));
+            }
+            other => panic!("expected UpdateGrain, got {other:?}"),
+        }
+    }
+
     #[test]
     fn uncompressed_header_write_disables_grain_no_matching_segment() {
         let mut parser = make_parser::<true>();
@@ -4654,6 +6158,116 @@ mod tests {
         assert_eq!(header.film_grain_params, FilmGrainHeader::Disable);
     }
 
+    #[test]
+    fn uncompressed_header_write_keeps_original_grain() {
+        let mut parser = make_parser::<true>().with_original_grain();
+        let mut seq = minimal_sequence_header();
+        seq.film_grain_params_present = true;
+        seq.new_film_grain_state = true;
+        parser.sequence_header = Some(seq);
+        parser.incoming_grain_header = Some(vec![GrainTableSegment {
+            start_time: 0,
+            end_time: 1000,
+            grain_params: minimal_grain_params(),
+        }]);
+        let mut bits = build_minimal_key_frame_bits(true);
+        bits.push_bool(false); // apply_grain = false
+        let (data, consumed_bits) = with_trailer(bits);
+        let (_, result) = parser
+            .parse_frame_header(&data, simple_obu_header(), 500, 0, false)
+            .unwrap();
+        let header = result.unwrap();
+        assert_eq!(header.film_grain_params, FilmGrainHeader::Disable);
+        assert_eq!(parser.packet_out, data[..consumed_bits.div_ceil(8)]);
+    }
+
+    /// The minimal key frame with `render_size()` coded as 16x9. It starts after
+    /// `show_existing_frame`, `frame_type`, `show_frame`, `disable_cdf_update` and
+    /// `frame_size_override_flag`.
+    fn with_16x9_render_size(header: &[u8]) -> Vec<u8> {
+        let header = header.view_bits::<Msb0>();
+        let mut expected = header[..6].to_bitvec();
+        expected.push(true); // render_and_frame_size_different
+        expected.extend(15u16.view_bits::<Msb0>());
+        expected.extend(8u16.view_bits::<Msb0>());
+        expected.extend_from_bitslice(&header[7..]);
+        expected.into_vec()
+    }
+
+    #[test]
+    fn uncompressed_header_write_inserts_render_size() {
+        let render_size = Dimensions {
+            width: 16,
+            height: 9,
+        };
+        let mut parser = make_parser::<true>()
+            .with_original_grain()
+            .with_render_size(render_size)
+            .unwrap();
+        parser.sequence_header = Some(minimal_sequence_header());
+        let (data, consumed_bits) = with_trailer(build_minimal_key_frame_bits(true));
+        parser
+            .parse_frame_header(&data, simple_obu_header(), 0, 0, false)
+            .unwrap();
+
+        assert_eq!(
+            parser.packet_out,
+            with_16x9_render_size(&data[..consumed_bits.div_ceil(8)])
+        );
+    }
+
+    #[test]
+    fn uncompressed_header_write_inserts_render_size_before_new_grain() {
+        let segments = vec![GrainTableSegment {
+            start_time: 0,
+            end_time: 1000,
+            grain_params: minimal_grain_params(),
+        }];
+        let mut seq = minimal_sequence_header();
+        seq.film_grain_params_present = true;
+        seq.new_film_grain_state = true;
+        let mut bits = build_minimal_key_frame_bits(true);
+        bits.push_bool(false); // apply_grain = false
+        let (data, _) = with_trailer(bits);
+        let mut plain = make_parser::<true>();
+        plain.sequence_header = Some(seq.clone());
+        plain.incoming_grain_header = Some(segments.clone());
+        plain
+            .parse_frame_header(&data, simple_obu_header(), 500, 0, false)
+            .unwrap();
+
+        let mut parser = make_parser::<true>()
+            .with_render_size(Dimensions {
+                width: 16,
+                height: 9,
+            })
+            .unwrap();
+        parser.sequence_header = Some(seq);
+        parser.incoming_grain_header = Some(segments);
+        let (_, result) = parser
+            .parse_frame_header(&data, simple_obu_header(), 500, 0, false)
+            .unwrap();
+
+        assert!(matches!(
+            result.unwrap().film_grain_params,
+            FilmGrainHeader::UpdateGrain(_)
+        ));
+        assert_eq!(parser.packet_out, with_16x9_render_size(&plain.packet_out));
+    }
+
+    #[test]
+    fn with_render_size_rejects_sizes_render_size_cannot_code() {
+        let parser = make_parser::<true>();
+        assert!(
+            parser
+                .with_render_size(Dimensions {
+                    width: 1 << 16 | 1,
+                    height: 9,
+                })
+                .is_err()
+        );
+    }
+
     // -----------------------------------------------------------------------
     // Group E: parse_frame_obu
     // -----------------------------------------------------------------------
@@ -4675,20 +6289,16 @@ mod tests {
     }
 
     #[test]
-    fn parse_frame_obu_seen_header_uses_previous() {
+    fn parse_frame_obu_seen_header_uses_frame_tile_info() {
         let mut parser = make_parser::<false>();
         parser.sequence_header = Some(minimal_sequence_header());
         parser.seen_frame_header = true;
-        parser.previous_frame_header = Some(FrameHeader {
-            show_frame: true,
-            show_existing_frame: false,
-            film_grain_params: FilmGrainHeader::Disable,
-            tile_info: TileInfo {
-                tile_cols: 1,
-                tile_rows: 1,
-                tile_cols_log2: 0,
-                tile_rows_log2: 0,
-            },
+        parser.frame_tile_info = Some(TileInfo {
+            tile_cols: 1,
+            tile_rows: 1,
+            tile_cols_log2: 0,
+            tile_rows_log2: 0,
+            ..Default::default()
         });
         let data = vec![0xAA, 0xBB]; // just tile payload
         parser.size = data.len();
@@ -4704,6 +6314,72 @@ mod tests {
         assert!(!parser.seen_frame_header);
     }
 
+    #[test]
+    fn parse_frame_obu_hidden_frame_uses_its_own_tiling() {
+        let mut parser = make_parser::<false>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        // The previous shown frame had 2x2 tiles, so reading this tile group with its
+        // tiling would take tile_start_and_end_present_flag=1, tg_start=0, tg_end=0
+        // and leave the frame unfinished.
+        parser.previous_frame_header = Some(FrameHeader {
+            show_frame: true,
+            tile_info: TileInfo {
+                tile_cols: 2,
+                tile_rows: 2,
+                tile_cols_log2: 1,
+                tile_rows_log2: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut data = build_minimal_key_frame_bits(false).into_bytes();
+        data.push(0x80); // tile payload
+        parser.size = data.len();
+        let (remaining, result) = parser
+            .parse_frame_obu(&data, simple_obu_header(), 0, 0)
+            .unwrap();
+        assert!(result.is_none(), "hidden frame should yield None");
+        assert!(remaining.is_empty());
+        assert_eq!(parser.frame_tile_info.as_ref().unwrap().tile_cols, 1);
+        // The hidden frame's single tile completes the frame
+        assert!(!parser.seen_frame_header);
+    }
+
+    #[test]
+    fn parse_frame_obu_new_sequence_starting_hidden_parses() {
+        let mut parser = make_parser::<false>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        parser.previous_frame_header = Some(FrameHeader {
+            show_frame: true,
+            ..Default::default()
+        });
+        parser.reset_reference_state();
+        let mut data = build_minimal_key_frame_bits(false).into_bytes();
+        data.extend_from_slice(&[0xAA, 0xBB]); // tile payload
+        parser.size = data.len();
+        let (remaining, result) = parser
+            .parse_frame_obu(&data, simple_obu_header(), 0, 0)
+            .unwrap();
+        assert!(result.is_none());
+        assert!(remaining.is_empty());
+        assert!(!parser.seen_frame_header);
+    }
+
+    #[test]
+    fn parse_frame_obu_seen_header_without_tiling_errors() {
+        let mut parser = make_parser::<false>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        parser.reset_reference_state();
+        parser.seen_frame_header = true;
+        let data = vec![0xAA, 0xBB]; // just tile payload
+        parser.size = data.len();
+        assert!(
+            parser
+                .parse_frame_obu(&data, simple_obu_header(), 0, 0)
+                .is_err()
+        );
+    }
+
     #[test]
     fn parse_frame_obu_size_calculation() {
         let mut parser = make_parser::<false>();
@@ -4799,4 +6475,419 @@ mod tests {
             "standalone FrameHeader should skip alignment verification"
         );
     }
+
+    // -----------------------------------------------------------------------
+    // Group G: FrameHeader::write_bits roundtrips
+    // -----------------------------------------------------------------------
+
+    /// Parses `bits` as a frame header and checks that writing the parsed header
+    /// back gives exactly the same bits.
+    fn assert_frame_header_round_trips(
+        parser: &mut BitstreamParser<false>,
+        bits: BitBuilder,
+    ) -> FrameHeader {
+        let len = bits.len();
+        let (data, _) = with_trailer(bits);
+        let (_, header) = parser
+            .uncompressed_header(&data, simple_obu_header(), 0, 0, false)
+            .unwrap();
+
+        let sequence_header = parser.sequence_header.as_ref().unwrap();
+        let mut writer = BitWriter::new();
+        header.write_bits(
+            &mut writer,
+            sequence_header,
+            sequence_header.film_grain_params_present,
+        );
+        assert_eq!(writer.len(), len);
+        let mut expected = data[..len.div_ceil(8)].to_vec();
+        if !len.is_multiple_of(8) {
+            *expected.last_mut().unwrap() &= 0xFF << (8 - len % 8);
+        }
+        assert_eq!(writer.into_bytes(), expected);
+        header
+    }
+
+    /// A 512x256 4:2:0 sequence with order hints and every optional tool enabled.
+    fn feature_rich_sequence_header() -> SequenceHeader {
+        let mut seq = minimal_sequence_header();
+        seq.force_screen_content_tools = SELECT_SCREEN_CONTENT_TOOLS;
+        seq.force_integer_mv = SELECT_INTEGER_MV;
+        seq.order_hint_bits = 7;
+        seq.frame_width_bits_minus_1 = 8;
+        seq.frame_height_bits_minus_1 = 8;
+        seq.max_frame_width_minus_1 = 511;
+        seq.max_frame_height_minus_1 = 255;
+        seq.enable_ref_frame_mvs = true;
+        seq.enable_warped_motion = true;
+        seq.enable_cdef = true;
+        seq.enable_restoration = true;
+        seq.film_grain_params_present = true;
+        seq.color_config.num_planes = 3;
+        seq.color_config.subsampling = (1, 1);
+        seq.color_config.separate_uv_delta_q = true;
+        seq
+    }
+
+    #[test]
+    fn write_bits_round_trips_minimal_key_frame() {
+        let mut parser = make_parser::<false>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        for show_frame in [true, false] {
+            assert_frame_header_round_trips(&mut parser, build_minimal_key_frame_bits(show_frame));
+        }
+    }
+
+    #[test]
+    fn write_bits_round_trips_show_existing_frame() {
+        let mut parser = make_parser::<false>();
+        parser.sequence_header = Some(minimal_sequence_header());
+        parser.frame_tile_info = Some(TileInfo::default());
+        let mut bits = BitBuilder::default();
+        bits.push_bool(true); // show_existing_frame
+        bits.push_bits(6, 3); // frame_to_show_map_idx
+
+        let header = assert_frame_header_round_trips(&mut parser, bits);
+        assert_eq!(header.frame_to_show_map_idx, 6);
+    }
+
+    #[test]
+    #[allow(clippy::too_many_lines)]
+    fn write_bits_round_trips_inter_frame_with_every_tool() {
+        let mut parser = make_parser::<false>();
+        parser.sequence_header = Some(feature_rich_sequence_header());
+        // Slot 1 holds an earlier frame than slot 0, so skip mode is allowed.
+        parser.big_ref_order_hint[1] = 3;
+        parser.ref_gm_params.fill(initialize_prev_gm_params());
+
+        let mut bits = BitBuilder::default();
+        bits.push_bool(false); // show_existing_frame
+        bits.push_bits(1, 2); // frame_type = Inter
+        bits.push_bool(true); // show_frame
+        bits.push_bool(false); // error_resilient_mode
+        bits.push_bool(false); // disable_cdf_update
+        bits.push_bool(true); // allow_screen_content_tools
+        bits.push_bool(false); // force_integer_mv
+        bits.push_bool(true); // frame_size_override_flag
+        bits.push_bits(5, 7); // order_hint
+        bits.push_bits(0, 3); // primary_ref_frame
+        bits.push_bits(0b0000_0100, 8); // refresh_frame_flags
+        bits.push_bool(false); // frame_refs_short_signaling
+        for i in 0..REFS_PER_FRAME {
+            bits.push_bits(i as u64, 3); // ref_frame_idx[i]
+        }
+        for _ in 0..REFS_PER_FRAME {
+            bits.push_bool(false); // found_ref
+        }
+        bits.push_bits(511, 9); // frame_width_minus_1
+        bits.push_bits(255, 9); // frame_height_minus_1
+        bits.push_bool(true); // render_and_frame_size_different
+        bits.push_bits(499, 16); // render_width_minus_1
+        bits.push_bits(249, 16); // render_height_minus_1
+        bits.push_bool(true); // allow_high_precision_mv
+        bits.push_bool(false); // is_filter_switchable
+        bits.push_bits(2, 2); // interpolation_filter
+        bits.push_bool(true); // is_motion_mode_switchable
+        bits.push_bool(true); // use_ref_frame_mvs
+        bits.push_bool(false); // disable_frame_end_update_cdf
+        // 8x4 superblocks split into tiles of 3 and 5 columns, 1 and 3 rows.
+        bits.push_bool(false); // uniform_tile_spacing_flag
+        bits.push_ns(2, 8); // width_in_sbs_minus_1[0]
+        bits.push_ns(4, 5); // width_in_sbs_minus_1[1]
+        bits.push_ns(0, 4); // height_in_sbs_minus_1[0]
+        bits.push_ns(2, 3); // height_in_sbs_minus_1[1]
+        bits.push_bits(3, 2); // context_update_tile_id
+        bits.push_bits(2, 2); // tile_size_bytes_minus_1
+        bits.push_bits(100, 8); // base_q_idx
+        bits.push_bool(true); // delta_q_y_dc coded
+        bits.push_su(-4, 7);
+        bits.push_bool(true); // diff_uv_delta
+        bits.push_bool(false); // delta_q_u_dc
+        bits.push_bool(true); // delta_q_u_ac coded
+        bits.push_su(3, 7);
+        bits.push_bool(true); // delta_q_v_dc coded
+        bits.push_su(-1, 7);
+        bits.push_bool(false); // delta_q_v_ac
+        bits.push_bool(true); // using_qmatrix
+        bits.push_bits(4, 4); // qm_y
+        bits.push_bits(5, 4); // qm_u
+        bits.push_bits(6, 4); // qm_v
+        bits.push_bool(true); // segmentation_enabled
+        bits.push_bool(true); // segmentation_update_map
+        bits.push_bool(false); // segmentation_temporal_update
+        bits.push_bool(true); // segmentation_update_data
+        bits.push_bool(true); // feature_enabled[0][0]
+        bits.push_su(-20, 9);
+        bits.push_bool(true); // feature_enabled[0][1]
+        bits.push_su(10, 7);
+        for _ in 2..(8 * 8) {
+            bits.push_bool(false);
+        }
+        bits.push_bool(true); // delta_q_present
+        bits.push_bits(1, 2); // delta_q_res
+        bits.push_bool(true); // delta_lf_present
+        bits.push_bits(2, 2); // delta_lf_res
+        bits.push_bool(true); // delta_lf_multi
+        bits.push_bits(10, 6); // loop_filter_level[0]
+        bits.push_bits(12, 6); // loop_filter_level[1]
+        bits.push_bits(4, 6); // loop_filter_level[2]
+        bits.push_bits(5, 6); // loop_filter_level[3]
+        bits.push_bits(2, 3); // loop_filter_sharpness
+        bits.push_bool(true); // loop_filter_delta_enabled
+        bits.push_bool(true); // loop_filter_delta_update
+        for i in 0..TOTAL_REFS_PER_FRAME {
+            bits.push_bool(i == 1); // update_ref_delta
+            if i == 1 {
+                bits.push_su(-3, 7);
+            }
+        }
+        bits.push_bool(true); // update_mode_delta[0]
+        bits.push_su(2, 7);
+        bits.push_bool(false); // update_mode_delta[1]
+        bits.push_bits(1, 2); // cdef_damping_minus_3
+        bits.push_bits(1, 2); // cdef_bits
+        for i in 0..2 {
+            bits.push_bits(3 + i, 4); // cdef_y_pri_strength
+            bits.push_bits(i, 2); // cdef_y_sec_strength
+            bits.push_bits(7 - i, 4); // cdef_uv_pri_strength
+            bits.push_bits(3, 2); // cdef_uv_sec_strength
+        }
+        bits.push_bits(1, 2); // lr_type[0]
+        bits.push_bits(0, 2); // lr_type[1]
+        bits.push_bits(2, 2); // lr_type[2]
+        bits.push_bool(true); // lr_unit_shift
+        bits.push_bool(false); // lr_unit_extra_shift
+        bits.push_bool(true); // lr_uv_shift
+        bits.push_bool(true); // tx_mode_select
+        bits.push_bool(true); // reference_select
+        bits.push_bool(true); // skip_mode_present
+        bits.push_bool(true); // allow_warped_motion
+        bits.push_bool(false); // reduced_tx_set
+        // Last: rotzoom, Golden: translation, the rest identity.
+        bits.push_bool(true); // is_global
+        bits.push_bool(true); // is_rot_zoom
+        for value in [5, 2, 7, 1] {
+            bits.push_bool(false); // subexp_more_bits
+            bits.push_bits(value, 3);
+        }
+        for i in 1..REFS_PER_FRAME {
+            bits.push_bool(i == 3); // is_global
+            if i == 3 {
+                bits.push_bool(false); // is_rot_zoom
+                bits.push_bool(true); // is_translation
+                for value in [6, 3] {
+                    bits.push_bool(false); // subexp_more_bits
+                    bits.push_bits(value, 3);
+                }
+            }
+        }
+        bits.push_bool(true); // apply_grain
+        bits.push_bits(0xBEEF, 16); // grain_seed
+        bits.push_bool(false); // update_grain
+        bits.push_bits(2, 3); // film_grain_params_ref_idx
+
+        let header = assert_frame_header_round_trips(&mut parser, bits);
+        assert_eq!(header.tile_info.tile_cols, 2);
+        assert_eq!(header.tile_info.tile_rows, 2);
+        assert!(header.skip_mode_allowed);
+        assert_eq!(header.frame_size.render_size.width, 500);
+        assert_eq!(
+            header.coded_film_grain_params,
+            FilmGrainHeader::CopyRefFrame {
+                ref_idx: 2,
+                grain_seed: 0xBEEF,
+            }
+        );
+        assert_eq!(
+            header.global_motion_params.gm_type[RefType::Last as usize],
+            ROTZOOM
+        );
+    }
+
+    #[test]
+    fn write_bits_round_trips_inter_frame_with_size_from_reference() {
+        let mut parser = make_parser::<false>();
+        let mut seq = feature_rich_sequence_header();
+        seq.enable_superres = true;
+        seq.film_grain_params_present = false;
+        parser.sequence_header = Some(seq);
+
+        let mut bits = BitBuilder::default();
+        bits.push_bool(false); // show_existing_frame
+        bits.push_bits(1, 2); // frame_type = Inter
+        bits.push_bool(false); // show_frame
+        bits.push_bool(true); // showable_frame
+        bits.push_bool(false); // error_resilient_mode
+        bits.push_bool(true); // disable_cdf_update
+        bits.push_bool(true); // allow_screen_content_tools
+        bits.push_bool(true); // force_integer_mv
+        bits.push_bool(true); // frame_size_override_flag
+        bits.push_bits(9, 7); // order_hint
+        bits.push_bits(7, 3); // primary_ref_frame = PRIMARY_REF_NONE
+        bits.push_bits(0b1000_0001, 8); // refresh_frame_flags
+        bits.push_bool(true); // frame_refs_short_signaling
+        bits.push_bits(1, 3); // last_frame_idx
+        bits.push_bits(4, 3); // gold_frame_idx
+        bits.push_bool(false); // found_ref[0]
+        bits.push_bool(true); // found_ref[1]
+        bits.push_bool(true); // use_superres
+        bits.push_bits(3, 3); // coded_denom
+        // force_integer_mv: no allow_high_precision_mv
+        bits.push_bool(true); // is_filter_switchable
+        bits.push_bool(false); // is_motion_mode_switchable
+        bits.push_bool(false); // use_ref_frame_mvs
+        bits.push_bool(true); // uniform_tile_spacing_flag
+        bits.push_bool(true); // increment_tile_cols_log2
+        bits.push_bool(false); // increment_tile_cols_log2
+        bits.push_bool(false); // increment_tile_rows_log2
+        bits.push_bits(1, 1); // context_update_tile_id
+        bits.push_bits(3, 2); // tile_size_bytes_minus_1
+        bits.push_bits(0, 8); // base_q_idx
+        bits.push_bool(false); // delta_q_y_dc
+        bits.push_bool(false); // diff_uv_delta
+        bits.push_bool(false); // delta_q_u_dc
+        bits.push_bool(false); // delta_q_u_ac
+        bits.push_bool(false); // using_qmatrix
+        bits.push_bool(false); // segmentation_enabled
+        // Lossless, so no loop filter, CDEF or tx mode, but superres allows restoration.
+        bits.push_bits(3, 2); // lr_type[0]
+        bits.push_bits(0, 2); // lr_type[1]
+        bits.push_bits(0, 2); // lr_type[2]
+        bits.push_bool(false); // lr_unit_shift
+        bits.push_bool(false); // reference_select
+        bits.push_bool(false); // allow_warped_motion
+        bits.push_bool(true); // reduced_tx_set
+        for _ in 0..REFS_PER_FRAME {
+            bits.push_bool(false); // is_global
+        }
+
+        let header = assert_frame_header_round_trips(&mut parser, bits);
+        assert_eq!(header.frame_size.found_ref, Some(1));
+        assert!(header.frame_size.use_superres);
+        assert_eq!(header.tile_info.tile_cols_log2, 1);
+    }
+
+    #[test]
+    fn parse_inter_frame_reads_use_ref_frame_mvs_with_integer_mv() {
+        let mut parser = make_parser::<false>();
+        parser.sequence_header = Some(feature_rich_sequence_header());
+        parser.ref_gm_params.fill(initialize_prev_gm_params());
+
+        let mut bits = BitBuilder::default();
+        bits.push_bool(false); // show_existing_frame
+        bits.push_bits(1, 2); // frame_type = Inter
+        bits.push_bool(true); // show_frame
+        bits.push_bool(false); // error_resilient_mode
+        bits.push_bool(false); // disable_cdf_update
+        bits.push_bool(true); // allow_screen_content_tools
+        bits.push_bool(true); // force_integer_mv
+        bits.push_bool(false); // frame_size_override_flag
+        bits.push_bits(5, 7); // order_hint
+        bits.push_bits(0, 3); // primary_ref_frame
+        bits.push_bits(0b0000_0010, 8); // refresh_frame_flags
+        bits.push_bool(false); // frame_refs_short_signaling
+        for _ in 0..REFS_PER_FRAME {
+            bits.push_bits(0, 3); // ref_frame_idx[i]
+        }
+        bits.push_bool(false); // render_and_frame_size_different
+        // force_integer_mv: no allow_high_precision_mv
+        bits.push_bool(false); // is_filter_switchable
+        bits.push_bits(1, 2); // interpolation_filter
+        bits.push_bool(true); // is_motion_mode_switchable
+        bits.push_bool(true); // use_ref_frame_mvs
+        bits.push_bool(true); // disable_frame_end_update_cdf
+        bits.push_bool(true); // uniform_tile_spacing_flag
+        bits.push_bool(true); // increment_tile_cols_log2
+        bits.push_bool(false); // increment_tile_cols_log2
+        bits.push_bool(false); // increment_tile_rows_log2
+        bits.push_bits(1, 1); // context_update_tile_id
+        bits.push_bits(1, 2); // tile_size_bytes_minus_1
+        bits.push_bits(90, 8); // base_q_idx
+        bits.push_bool(true); // delta_q_y_dc coded
+        bits.push_su(-2, 7);
+        bits.push_bool(false); // diff_uv_delta
+        bits.push_bool(false); // delta_q_u_dc
+        bits.push_bool(true); // delta_q_u_ac coded
+        bits.push_su(5, 7);
+        bits.push_bool(false); // using_qmatrix
+        bits.push_bool(false); // segmentation_enabled
+        bits.push_bool(true); // delta_q_present
+        bits.push_bits(2, 2); // delta_q_res
+        bits.push_bool(false); // delta_lf_present
+        bits.push_bits(7, 6); // loop_filter_level[0]
+        bits.push_bits(9, 6); // loop_filter_level[1]
+        bits.push_bits(3, 6); // loop_filter_level[2]
+        bits.push_bits(2, 6); // loop_filter_level[3]
+        bits.push_bits(4, 3); // loop_filter_sharpness
+        bits.push_bool(false); // loop_filter_delta_enabled
+        bits.push_bits(2, 2); // cdef_damping_minus_3
+        bits.push_bits(0, 2); // cdef_bits
+        bits.push_bits(6, 4); // cdef_y_pri_strength
+        bits.push_bits(1, 2); // cdef_y_sec_strength
+        bits.push_bits(2, 4); // cdef_uv_pri_strength
+        bits.push_bits(3, 2); // cdef_uv_sec_strength
+        bits.push_bits(2, 2); // lr_type[0]
+        bits.push_bits(0, 2); // lr_type[1]
+        bits.push_bits(0, 2); // lr_type[2]
+        bits.push_bool(false); // lr_unit_shift
+        bits.push_bool(true); // tx_mode_select
+        bits.push_bool(true); // reference_select
+        bits.push_bool(true); // allow_warped_motion
+        bits.push_bool(true); // reduced_tx_set
+        bits.push_bool(false); // is_global[LAST_FRAME]
+        bits.push_bool(true); // is_global[LAST2_FRAME]
+        bits.push_bool(false); // is_rot_zoom
+        bits.push_bool(true); // is_translation
+        for value in [4, 1] {
+            bits.push_bool(false); // subexp_more_bits
+            bits.push_bits(value, 3);
+        }
+        for _ in 2..REFS_PER_FRAME {
+            bits.push_bool(false); // is_global
+        }
+        bits.push_bool(true); // apply_grain
+        bits.push_bits(0x1234, 16); // grain_seed
+        bits.push_bool(false); // update_grain
+        bits.push_bits(3, 3); // film_grain_params_ref_idx
+
+        let header = assert_frame_header_round_trips(&mut parser, bits);
+        assert!(header.force_integer_mv);
+        assert!(!header.allow_high_precision_mv);
+        assert_eq!(header.interpolation_filter, 1);
+        assert!(header.is_motion_mode_switchable);
+        assert!(header.use_ref_frame_mvs);
+        assert!(header.disable_frame_end_update_cdf);
+        assert_eq!(header.tile_info.tile_cols_log2, 1);
+        assert_eq!(header.tile_info.context_update_tile_id, 1);
+        assert_eq!(header.tile_info.tile_size_bytes_minus_1, 1);
+        assert_eq!(header.quantization_params.base_q_idx, 90);
+        assert_eq!(header.quantization_params.deltaq_y_dc, -2);
+        assert_eq!(header.quantization_params.deltaq_u_ac, 5);
+        assert!(!header.segmentation_params.segmentation_enabled);
+        assert!(header.delta_q_present);
+        assert_eq!(header.delta_q_res, 2);
+        assert!(!header.delta_lf_present);
+        assert_eq!(header.loop_filter_params.loop_filter_level, [7, 9, 3, 2]);
+        assert_eq!(header.loop_filter_params.loop_filter_sharpness, 4);
+        assert_eq!(header.cdef_params.cdef_damping_minus_3, 2);
+        assert_eq!(header.cdef_params.cdef_y_pri_strength[0], 6);
+        assert_eq!(header.cdef_params.cdef_uv_sec_strength[0], 3);
+        assert_eq!(header.lr_params.lr_type, [2, 0, 0]);
+        assert!(header.tx_mode_select);
+        assert!(header.reference_select);
+        assert!(!header.skip_mode_allowed);
+        assert!(header.allow_warped_motion);
+        assert!(header.reduced_tx_set);
+        assert_eq!(
+            header.global_motion_params.gm_type[RefType::Last2 as usize],
+            TRANSLATION
+        );
+        assert_eq!(
+            header.coded_film_grain_params,
+            FilmGrainHeader::CopyRefFrame {
+                ref_idx: 3,
+                grain_seed: 0x1234,
+            }
+        );
+    }
 }
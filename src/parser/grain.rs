@@ -5,16 +5,34 @@ use nom::{IResult, error::Error};
 use super::{
     frame::FrameType,
     trace::{TraceCtx, trace_bool, trace_take_u8, trace_take_u16},
-    util::BitInput,
+    util::{BitInput, BitWriter},
 };
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum FilmGrainHeader {
+    #[default]
     Disable,
-    CopyRefFrame,
+    /// `update_grain = 0`: all parameters except the seed are loaded from a reference slot.
+    CopyRefFrame {
+        /// `film_grain_params_ref_idx`, the reference slot to load parameters from.
+        ref_idx: u8,
+        /// The seed signalled in this frame, which replaces the loaded one.
+        grain_seed: u16,
+    },
     UpdateGrain(FilmGrainParams),
 }
 
+/// The film grain a decoder applies to one displayed frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameGrainHeader {
+    /// Presentation timestamp of the frame, in 1/10,000,000ths of a second.
+    pub timestamp: u64,
+    pub film_grain_params: FilmGrainHeader,
+    /// Layer of the frame, from its OBU extension. Both are 0 for non-scalable streams.
+    pub temporal_id: u8,
+    pub spatial_id: u8,
+}
+
 /// Specifies parameters for enabling decoder-side grain synthesis for
 /// a segment of video from `start_time` to `end_time`.
 #[derive(Debug, Clone)]
@@ -157,9 +175,15 @@ pub fn film_grain_params<'a>(
         (input, true)
     };
     if !update_grain {
-        let (input, _film_grain_params_ref_idx) =
+        let (input, film_grain_params_ref_idx) =
             trace_take_u8(input, ctx, 3, "film_grain_params_ref_idx")?;
-        return Ok((input, FilmGrainHeader::CopyRefFrame));
+        return Ok((
+            input,
+            FilmGrainHeader::CopyRefFrame {
+                ref_idx: film_grain_params_ref_idx,
+                grain_seed,
+            },
+        ));
     }
 
     let (mut input, num_y_points) = trace_take_u8(input, ctx, 4, "num_y_points")?;
@@ -294,6 +318,108 @@ pub fn film_grain_params<'a>(
     ))
 }
 
+/// Writes the `film_grain_params()` syntax for `film_grain`, the counterpart of
+/// [`film_grain_params`] when film grain is allowed for the frame.
+///
+/// [`FilmGrainHeader::CopyRefFrame`] is only valid for inter frames.
+pub fn write_film_grain_params(
+    writer: &mut BitWriter,
+    film_grain: &FilmGrainHeader,
+    frame_type: FrameType,
+    monochrome: bool,
+    subsampling: (u8, u8),
+) {
+    let params = match film_grain {
+        FilmGrainHeader::Disable => {
+            writer.write_bool(false);
+            return;
+        }
+        FilmGrainHeader::CopyRefFrame {
+            ref_idx,
+            grain_seed,
+        } => {
+            debug_assert_eq!(frame_type, FrameType::Inter);
+            writer.write_bool(true);
+            writer.write_bits(u64::from(*grain_seed), 16);
+            writer.write_bool(false);
+            writer.write_bits(u64::from(*ref_idx), 3);
+            return;
+        }
+        FilmGrainHeader::UpdateGrain(params) => params,
+    };
+
+    writer.write_bool(true);
+    writer.write_bits(u64::from(params.grain_seed), 16);
+    if frame_type == FrameType::Inter {
+        writer.write_bool(true);
+    }
+
+    let num_y_points = params.scaling_points_y.len();
+    writer.write_bits(num_y_points as u64, 4);
+    for point in &params.scaling_points_y {
+        writer.write_bits(u64::from(point[0]), 8);
+        writer.write_bits(u64::from(point[1]), 8);
+    }
+    if !monochrome {
+        writer.write_bool(params.chroma_scaling_from_luma);
+    }
+    let (num_cb_points, num_cr_points) = if monochrome
+        || params.chroma_scaling_from_luma
+        || (subsampling == (1, 1) && num_y_points == 0)
+    {
+        (0, 0)
+    } else {
+        for points in [&params.scaling_points_cb, &params.scaling_points_cr] {
+            writer.write_bits(points.len() as u64, 4);
+            for point in points {
+                writer.write_bits(u64::from(point[0]), 8);
+                writer.write_bits(u64::from(point[1]), 8);
+            }
+        }
+        (
+            params.scaling_points_cb.len(),
+            params.scaling_points_cr.len(),
+        )
+    };
+
+    writer.write_bits(u64::from(params.scaling_shift - 8), 2);
+    writer.write_bits(u64::from(params.ar_coeff_lag), 2);
+    let num_pos_luma = 2 * params.ar_coeff_lag as usize * (params.ar_coeff_lag as usize + 1);
+    let num_pos_chroma = if num_y_points > 0 {
+        write_ar_coeffs(writer, &params.ar_coeffs_y[..num_pos_luma]);
+        num_pos_luma + 1
+    } else {
+        num_pos_luma
+    };
+    if params.chroma_scaling_from_luma || num_cb_points > 0 {
+        write_ar_coeffs(writer, &params.ar_coeffs_cb[..num_pos_chroma]);
+    }
+    if params.chroma_scaling_from_luma || num_cr_points > 0 {
+        write_ar_coeffs(writer, &params.ar_coeffs_cr[..num_pos_chroma]);
+    }
+    writer.write_bits(u64::from(params.ar_coeff_shift - 6), 2);
+    writer.write_bits(u64::from(params.grain_scale_shift), 2);
+    if num_cb_points > 0 {
+        writer.write_bits(u64::from(params.cb_mult), 8);
+        writer.write_bits(u64::from(params.cb_luma_mult), 8);
+        writer.write_bits(u64::from(params.cb_offset), 9);
+    }
+    if num_cr_points > 0 {
+        writer.write_bits(u64::from(params.cr_mult), 8);
+        writer.write_bits(u64::from(params.cr_luma_mult), 8);
+        writer.write_bits(u64::from(params.cr_offset), 9);
+    }
+    writer.write_bool(params.overlap_flag);
+    writer.write_bool(params.clip_to_restricted_range);
+}
+
+/// Writes AR coefficients as `ar_coeffs_*_plus_128`.
+fn write_ar_coeffs(writer: &mut BitWriter, coeffs: &[i8]) {
+    for &coeff in coeffs {
+        writer.write_bits(u64::from((i16::from(coeff) + 128) as u8), 8);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::trace::TraceCtx;
@@ -424,7 +550,13 @@ mod tests {
         )
         .expect("expected parser to parse inter-frame copy-from-reference mode");
 
-        assert_eq!(parsed, FilmGrainHeader::CopyRefFrame);
+        assert_eq!(
+            parsed,
+            FilmGrainHeader::CopyRefFrame {
+                ref_idx: 0b101,
+                grain_seed: 0x1234,
+            }
+        );
         assert_remaining_position(remaining, &data, consumed_bits);
     }
 
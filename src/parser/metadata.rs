@@ -0,0 +1,792 @@
+use std::fmt::{self, Display};
+
+use log::debug;
+use nom::{
+    IResult,
+    bits::bits,
+    error::{Error, ErrorKind},
+};
+use num_enum::TryFromPrimitive;
+
+use super::{
+    BitstreamParser,
+    obu::ObuType,
+    trace::{TraceCtx, trace_bool, trace_leb128, trace_take_u8, trace_take_u16, trace_take_u32},
+    util::{BitInput, leb128_write},
+};
+
+/// `scalability_mode_idc` value that signals an explicit `scalability_structure()`.
+pub const SCALABILITY_SS: u8 = 14;
+
+/// ITU-T T.35 country code of the United States, used by HDR10+ and Dolby Vision.
+const T35_COUNTRY_CODE_US: u8 = 0xB5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u8)]
+pub enum MetadataType {
+    HdrCll = 1,
+    HdrMdcv = 2,
+    Scalability = 3,
+    ItutT35 = 4,
+    Timecode = 5,
+}
+
+/// The decoded payload of a metadata OBU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Metadata {
+    HdrCll(ContentLightLevel),
+    HdrMdcv(MasteringDisplay),
+    ItutT35(ItutT35),
+    Scalability(Scalability),
+    Timecode(Timecode),
+    /// Reserved and unregistered types, and payloads too short for their type.
+    /// The payload excludes the OBU's trailing bits.
+    Other {
+        metadata_type: u64,
+        payload: Vec<u8>,
+    },
+}
+
+impl Metadata {
+    /// The `metadata_type` this payload is signalled with.
+    #[must_use]
+    pub const fn metadata_type(&self) -> u64 {
+        match self {
+            Self::HdrCll(_) => MetadataType::HdrCll as u64,
+            Self::HdrMdcv(_) => MetadataType::HdrMdcv as u64,
+            Self::ItutT35(_) => MetadataType::ItutT35 as u64,
+            Self::Scalability(_) => MetadataType::Scalability as u64,
+            Self::Timecode(_) => MetadataType::Timecode as u64,
+            Self::Other { metadata_type, .. } => *metadata_type,
+        }
+    }
+
+    /// Serializes a complete metadata OBU, with `obu_has_size_field` set and no
+    /// extension header, so it applies to every layer.
+    ///
+    /// Returns `None` for scalability and timecode metadata, which are not
+    /// byte-aligned and cannot be written yet.
+    #[must_use]
+    pub fn to_obu(&self) -> Option<Vec<u8>> {
+        let mut payload = leb128_write(u32::try_from(self.metadata_type()).ok()?).to_vec();
+        match self {
+            Self::HdrCll(cll) => {
+                payload.extend_from_slice(&cll.max_cll.to_be_bytes());
+                payload.extend_from_slice(&cll.max_fall.to_be_bytes());
+            }
+            Self::HdrMdcv(mdcv) => {
+                for i in 0..3 {
+                    payload.extend_from_slice(&mdcv.primary_chromaticity_x[i].to_be_bytes());
+                    payload.extend_from_slice(&mdcv.primary_chromaticity_y[i].to_be_bytes());
+                }
+                payload.extend_from_slice(&mdcv.white_point_chromaticity_x.to_be_bytes());
+                payload.extend_from_slice(&mdcv.white_point_chromaticity_y.to_be_bytes());
+                payload.extend_from_slice(&mdcv.luminance_max.to_be_bytes());
+                payload.extend_from_slice(&mdcv.luminance_min.to_be_bytes());
+            }
+            Self::ItutT35(t35) => {
+                payload.push(t35.country_code);
+                payload.extend(t35.country_code_extension);
+                payload.extend_from_slice(&t35.payload);
+            }
+            Self::Other { payload: raw, .. } => payload.extend_from_slice(raw),
+            Self::Scalability(_) | Self::Timecode(_) => return None,
+        }
+        // trailing_bits()
+        payload.push(0x80);
+
+        // obu_type in bits 3-6, then obu_has_size_field
+        let mut obu = vec![((ObuType::Metadata as u8) << 3) | 0b10];
+        obu.extend_from_slice(&leb128_write(u32::try_from(payload.len()).ok()?));
+        obu.extend_from_slice(&payload);
+        Some(obu)
+    }
+}
+
+/// A metadata OBU with the timestamp of the temporal unit that carried it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedMetadata {
+    /// In 1/10,000,000ths of a second, like grain headers.
+    pub timestamp: u64,
+    pub metadata: Metadata,
+}
+
+/// `metadata_hdr_cll()`, both values in cd/m².
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentLightLevel {
+    pub max_cll: u16,
+    pub max_fall: u16,
+}
+
+/// `metadata_hdr_mdcv()`, in the fixed-point units of the bitstream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MasteringDisplay {
+    /// Red, green and blue primaries, in 0.16 fixed point.
+    pub primary_chromaticity_x: [u16; 3],
+    pub primary_chromaticity_y: [u16; 3],
+    pub white_point_chromaticity_x: u16,
+    pub white_point_chromaticity_y: u16,
+    /// 24.8 fixed point.
+    pub luminance_max: u32,
+    /// 18.14 fixed point.
+    pub luminance_min: u32,
+}
+
+impl MasteringDisplay {
+    /// The peak luminance of the mastering display in cd/m².
+    #[must_use]
+    pub fn max_luminance(&self) -> f64 {
+        f64::from(self.luminance_max) / f64::from(1 << 8)
+    }
+
+    /// The minimum luminance of the mastering display in cd/m².
+    #[must_use]
+    pub fn min_luminance(&self) -> f64 {
+        f64::from(self.luminance_min) / f64::from(1 << 14)
+    }
+}
+
+/// `metadata_itut_t35()`. The payload is kept as-is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItutT35 {
+    pub country_code: u8,
+    /// Only present when `country_code` is `0xFF`.
+    pub country_code_extension: Option<u8>,
+    pub payload: Vec<u8>,
+}
+
+/// Well-known ITU-T T.35 payloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItutT35Kind {
+    /// SMPTE ST 2094-40 dynamic metadata.
+    Hdr10Plus,
+    DolbyVisionRpu,
+    Other,
+}
+
+impl ItutT35 {
+    /// Identifies the payload by its terminal provider codes.
+    #[must_use]
+    pub fn kind(&self) -> ItutT35Kind {
+        if self.country_code != T35_COUNTRY_CODE_US {
+            return ItutT35Kind::Other;
+        }
+        match self.payload.as_slice() {
+            // Samsung, with `application_identifier` 4.
+            [0x00, 0x3C, 0x00, 0x01, 0x04, ..] => ItutT35Kind::Hdr10Plus,
+            // Dolby, with provider oriented code 0x800.
+            [0x00, 0x3B, 0x00, 0x00, 0x08, 0x00, ..] => ItutT35Kind::DolbyVisionRpu,
+            _ => ItutT35Kind::Other,
+        }
+    }
+}
+
+/// `metadata_scalability()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scalability {
+    pub scalability_mode_idc: u8,
+    /// Only present when `scalability_mode_idc` is [`SCALABILITY_SS`].
+    pub structure: Option<ScalabilityStructure>,
+}
+
+/// `scalability_structure()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScalabilityStructure {
+    pub spatial_layers_cnt_minus_1: u8,
+    /// Maximum `(width, height)` of each spatial layer, if signalled.
+    pub spatial_layer_dimensions: Vec<(u16, u16)>,
+    /// `spatial_layer_ref_id` of each spatial layer, if signalled.
+    pub spatial_layer_ref_ids: Vec<u8>,
+    pub temporal_group: Vec<TemporalGroupEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemporalGroupEntry {
+    pub temporal_id: u8,
+    pub temporal_switching_up_point: bool,
+    pub spatial_switching_up_point: bool,
+    pub ref_pic_diffs: Vec<u8>,
+}
+
+/// `metadata_timecode()`. Fields that are not signalled are `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timecode {
+    pub counting_type: u8,
+    pub full_timestamp: bool,
+    pub discontinuity: bool,
+    pub cnt_dropped: bool,
+    pub n_frames: u16,
+    pub seconds: Option<u8>,
+    pub minutes: Option<u8>,
+    pub hours: Option<u8>,
+    pub time_offset: Option<u32>,
+}
+
+impl Display for Timecode {
+    /// Formats as `HH:MM:SS:FF`, with missing fields as zero.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}:{:02}:{:02}",
+            self.hours.unwrap_or_default(),
+            self.minutes.unwrap_or_default(),
+            self.seconds.unwrap_or_default(),
+            self.n_frames
+        )
+    }
+}
+
+impl<const WRITE: bool> BitstreamParser<WRITE> {
+    /// Parses a metadata OBU payload of `size` bytes.
+    ///
+    /// The payload is never modified: in write mode it is copied to `packet_out`
+    /// unchanged, so HDR metadata survives re-graining. Dropping stripped types is
+    /// left to the caller, which also wrote the OBU header.
+    pub fn parse_metadata_obu<'a>(
+        &mut self,
+        input: &'a [u8],
+        size: usize,
+        obu_bit_offset: usize,
+    ) -> IResult<&'a [u8], Metadata, Error<&'a [u8]>> {
+        if size > input.len() {
+            return Err(nom::Err::Error(Error::new(input, ErrorKind::Eof)));
+        }
+        let obu = &input[..size];
+        let (payload, metadata_type) = trace_leb128(obu, obu_bit_offset, "metadata_type")?;
+        let payload_bit_offset = obu_bit_offset + metadata_type.bytes_read * 8;
+        let metadata = metadata_payload(payload, metadata_type.value, payload_bit_offset)
+            .map_or_else(
+                |_| {
+                    debug!(
+                        "Keeping metadata of type {} as raw bytes",
+                        metadata_type.value
+                    );
+                    Metadata::Other {
+                        metadata_type: metadata_type.value,
+                        payload: strip_trailing_bits(payload).to_vec(),
+                    }
+                },
+                |(_, metadata)| metadata,
+            );
+
+        if WRITE {
+            self.packet_out.extend_from_slice(obu);
+            debug!("Copying metadata obu of size {}", size);
+        }
+        Ok((&input[size..], metadata))
+    }
+}
+
+/// Decodes the payload of a registered metadata type.
+///
+/// Returns an error for unregistered types, so the caller keeps their raw bytes.
+fn metadata_payload(
+    input: &[u8],
+    metadata_type: u64,
+    bit_offset: usize,
+) -> IResult<&[u8], Metadata, Error<&[u8]>> {
+    let metadata_type = u8::try_from(metadata_type)
+        .ok()
+        .and_then(|metadata_type| MetadataType::try_from(metadata_type).ok())
+        .ok_or_else(|| nom::Err::Error(Error::new(input, ErrorKind::Switch)))?;
+    match metadata_type {
+        MetadataType::HdrCll => bits(|input| {
+            let ctx = TraceCtx::new(input, bit_offset);
+            let (input, cll) = hdr_cll(input, ctx)?;
+            Ok((input, Metadata::HdrCll(cll)))
+        })(input),
+        MetadataType::HdrMdcv => bits(|input| {
+            let ctx = TraceCtx::new(input, bit_offset);
+            let (input, mdcv) = hdr_mdcv(input, ctx)?;
+            Ok((input, Metadata::HdrMdcv(mdcv)))
+        })(input),
+        MetadataType::Scalability => bits(|input| {
+            let ctx = TraceCtx::new(input, bit_offset);
+            let (input, scalability) = scalability(input, ctx)?;
+            Ok((input, Metadata::Scalability(scalability)))
+        })(input),
+        MetadataType::ItutT35 => {
+            let (payload, (country_code, country_code_extension)) = bits(|input| {
+                let ctx = TraceCtx::new(input, bit_offset);
+                let (input, country_code) = trace_take_u8(input, ctx, 8, "itu_t_t35_country_code")?;
+                let (input, country_code_extension) = if country_code == 0xFF {
+                    let (input, extension) =
+                        trace_take_u8(input, ctx, 8, "itu_t_t35_country_code_extension_byte")?;
+                    (input, Some(extension))
+                } else {
+                    (input, None)
+                };
+                Ok((input, (country_code, country_code_extension)))
+            })(input)?;
+            Ok((
+                &[],
+                Metadata::ItutT35(ItutT35 {
+                    country_code,
+                    country_code_extension,
+                    payload: strip_trailing_bits(payload).to_vec(),
+                }),
+            ))
+        }
+        MetadataType::Timecode => bits(|input| {
+            let ctx = TraceCtx::new(input, bit_offset);
+            let (input, timecode) = timecode(input, ctx)?;
+            Ok((input, Metadata::Timecode(timecode)))
+        })(input),
+    }
+}
+
+fn hdr_cll<'a>(
+    input: BitInput<'a>,
+    ctx: TraceCtx,
+) -> IResult<BitInput<'a>, ContentLightLevel, Error<BitInput<'a>>> {
+    let (input, max_cll) = trace_take_u16(input, ctx, 16, "max_cll")?;
+    let (input, max_fall) = trace_take_u16(input, ctx, 16, "max_fall")?;
+    Ok((input, ContentLightLevel { max_cll, max_fall }))
+}
+
+fn hdr_mdcv<'a>(
+    input: BitInput<'a>,
+    ctx: TraceCtx,
+) -> IResult<BitInput<'a>, MasteringDisplay, Error<BitInput<'a>>> {
+    let mut primary_chromaticity_x = [0; 3];
+    let mut primary_chromaticity_y = [0; 3];
+    let mut input = input;
+    for i in 0..3 {
+        let (inner_input, x) =
+            trace_take_u16(input, ctx, 16, &format!("primary_chromaticity_x[{i}]"))?;
+        let (inner_input, y) = trace_take_u16(
+            inner_input,
+            ctx,
+            16,
+            &format!("primary_chromaticity_y[{i}]"),
+        )?;
+        primary_chromaticity_x[i] = x;
+        primary_chromaticity_y[i] = y;
+        input = inner_input;
+    }
+    let (input, white_point_chromaticity_x) =
+        trace_take_u16(input, ctx, 16, "white_point_chromaticity_x")?;
+    let (input, white_point_chromaticity_y) =
+        trace_take_u16(input, ctx, 16, "white_point_chromaticity_y")?;
+    let (input, luminance_max) = trace_take_u32(input, ctx, 32, "luminance_max")?;
+    let (input, luminance_min) = trace_take_u32(input, ctx, 32, "luminance_min")?;
+    Ok((
+        input,
+        MasteringDisplay {
+            primary_chromaticity_x,
+            primary_chromaticity_y,
+            white_point_chromaticity_x,
+            white_point_chromaticity_y,
+            luminance_max,
+            luminance_min,
+        },
+    ))
+}
+
+fn scalability<'a>(
+    input: BitInput<'a>,
+    ctx: TraceCtx,
+) -> IResult<BitInput<'a>, Scalability, Error<BitInput<'a>>> {
+    let (input, scalability_mode_idc) = trace_take_u8(input, ctx, 8, "scalability_mode_idc")?;
+    let (input, structure) = if scalability_mode_idc == SCALABILITY_SS {
+        let (input, structure) = scalability_structure(input, ctx)?;
+        (input, Some(structure))
+    } else {
+        (input, None)
+    };
+    Ok((
+        input,
+        Scalability {
+            scalability_mode_idc,
+            structure,
+        },
+    ))
+}
+
+fn scalability_structure<'a>(
+    input: BitInput<'a>,
+    ctx: TraceCtx,
+) -> IResult<BitInput<'a>, ScalabilityStructure, Error<BitInput<'a>>> {
+    let (input, spatial_layers_cnt_minus_1) =
+        trace_take_u8(input, ctx, 2, "spatial_layers_cnt_minus_1")?;
+    let (input, dimensions_present) =
+        trace_bool(input, ctx, "spatial_layer_dimensions_present_flag")?;
+    let (input, description_present) =
+        trace_bool(input, ctx, "spatial_layer_description_present_flag")?;
+    let (input, temporal_group_present) =
+        trace_bool(input, ctx, "temporal_group_description_present_flag")?;
+    let (mut input, _reserved) =
+        trace_take_u8(input, ctx, 3, "scalability_structure_reserved_3bits")?;
+
+    let mut spatial_layer_dimensions = Vec::new();
+    if dimensions_present {
+        for i in 0..=spatial_layers_cnt_minus_1 {
+            let (inner_input, width) =
+                trace_take_u16(input, ctx, 16, &format!("spatial_layer_max_width[{i}]"))?;
+            let (inner_input, height) = trace_take_u16(
+                inner_input,
+                ctx,
+                16,
+                &format!("spatial_layer_max_height[{i}]"),
+            )?;
+            spatial_layer_dimensions.push((width, height));
+            input = inner_input;
+        }
+    }
+
+    let mut spatial_layer_ref_ids = Vec::new();
+    if description_present {
+        for i in 0..=spatial_layers_cnt_minus_1 {
+            let (inner_input, ref_id) =
+                trace_take_u8(input, ctx, 8, &format!("spatial_layer_ref_id[{i}]"))?;
+            spatial_layer_ref_ids.push(ref_id);
+            input = inner_input;
+        }
+    }
+
+    let mut temporal_group = Vec::new();
+    if temporal_group_present {
+        let (inner_input, temporal_group_size) =
+            trace_take_u8(input, ctx, 8, "temporal_group_size")?;
+        input = inner_input;
+        for i in 0..temporal_group_size {
+            let (inner_input, temporal_id) =
+                trace_take_u8(input, ctx, 3, &format!("temporal_group_temporal_id[{i}]"))?;
+            let (inner_input, temporal_switching_up_point) = trace_bool(
+                inner_input,
+                ctx,
+                &format!("temporal_group_temporal_switching_up_point_flag[{i}]"),
+            )?;
+            let (inner_input, spatial_switching_up_point) = trace_bool(
+                inner_input,
+                ctx,
+                &format!("temporal_group_spatial_switching_up_point_flag[{i}]"),
+            )?;
+            let (mut inner_input, ref_cnt) =
+                trace_take_u8(inner_input, ctx, 3, &format!("temporal_group_ref_cnt[{i}]"))?;
+            let mut ref_pic_diffs = Vec::with_capacity(usize::from(ref_cnt));
+            for j in 0..ref_cnt {
+                let (ref_input, diff) = trace_take_u8(
+                    inner_input,
+                    ctx,
+                    8,
+                    &format!("temporal_group_ref_pic_diff[{i}][{j}]"),
+                )?;
+                ref_pic_diffs.push(diff);
+                inner_input = ref_input;
+            }
+            temporal_group.push(TemporalGroupEntry {
+                temporal_id,
+                temporal_switching_up_point,
+                spatial_switching_up_point,
+                ref_pic_diffs,
+            });
+            input = inner_input;
+        }
+    }
+
+    Ok((
+        input,
+        ScalabilityStructure {
+            spatial_layers_cnt_minus_1,
+            spatial_layer_dimensions,
+            spatial_layer_ref_ids,
+            temporal_group,
+        },
+    ))
+}
+
+fn timecode<'a>(
+    input: BitInput<'a>,
+    ctx: TraceCtx,
+) -> IResult<BitInput<'a>, Timecode, Error<BitInput<'a>>> {
+    let (input, counting_type) = trace_take_u8(input, ctx, 5, "counting_type")?;
+    let (input, full_timestamp) = trace_bool(input, ctx, "full_timestamp_flag")?;
+    let (input, discontinuity) = trace_bool(input, ctx, "discontinuity_flag")?;
+    let (input, cnt_dropped) = trace_bool(input, ctx, "cnt_dropped_flag")?;
+    let (input, n_frames) = trace_take_u16(input, ctx, 9, "n_frames")?;
+    let (input, seconds, minutes, hours) = if full_timestamp {
+        let (input, seconds) = trace_take_u8(input, ctx, 6, "seconds_value")?;
+        let (input, minutes) = trace_take_u8(input, ctx, 6, "minutes_value")?;
+        let (input, hours) = trace_take_u8(input, ctx, 5, "hours_value")?;
+        (input, Some(seconds), Some(minutes), Some(hours))
+    } else {
+        // Each field is only present if the larger units before it are.
+        let (input, seconds_flag) = trace_bool(input, ctx, "seconds_flag")?;
+        if seconds_flag {
+            let (input, seconds) = trace_take_u8(input, ctx, 6, "seconds_value")?;
+            let (input, minutes_flag) = trace_bool(input, ctx, "minutes_flag")?;
+            if minutes_flag {
+                let (input, minutes) = trace_take_u8(input, ctx, 6, "minutes_value")?;
+                let (input, hours_flag) = trace_bool(input, ctx, "hours_flag")?;
+                if hours_flag {
+                    let (input, hours) = trace_take_u8(input, ctx, 5, "hours_value")?;
+                    (input, Some(seconds), Some(minutes), Some(hours))
+                } else {
+                    (input, Some(seconds), Some(minutes), None)
+                }
+            } else {
+                (input, Some(seconds), None, None)
+            }
+        } else {
+            (input, None, None, None)
+        }
+    };
+    let (input, time_offset_length) = trace_take_u8(input, ctx, 5, "time_offset_length")?;
+    let (input, time_offset) = if time_offset_length > 0 {
+        let (input, time_offset) = trace_take_u32(
+            input,
+            ctx,
+            usize::from(time_offset_length),
+            "time_offset_value",
+        )?;
+        (input, Some(time_offset))
+    } else {
+        (input, None)
+    };
+    Ok((
+        input,
+        Timecode {
+            counting_type,
+            full_timestamp,
+            discontinuity,
+            cnt_dropped,
+            n_frames,
+            seconds,
+            minutes,
+            hours,
+            time_offset,
+        },
+    ))
+}
+
+/// Drops `trailing_bits()` from a byte-aligned payload: the zero bytes at the end,
+/// and the `0x80` byte holding the trailing one bit.
+fn strip_trailing_bits(payload: &[u8]) -> &[u8] {
+    let end = payload
+        .iter()
+        .rposition(|&byte| byte != 0)
+        .map_or(0, |last| last + usize::from(payload[last] != 0x80));
+    &payload[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_parser<const WRITE: bool>() -> BitstreamParser<WRITE> {
+        BitstreamParser::without_container(None)
+    }
+
+    #[test]
+    fn parses_content_light_level() {
+        // metadata_type 1, max_cll 1000, max_fall 400, trailing bits
+        let obu = [0x01, 0x03, 0xE8, 0x01, 0x90, 0x80];
+        let (remaining, metadata) = make_parser::<false>()
+            .parse_metadata_obu(&obu, obu.len(), 0)
+            .expect("CLL should parse");
+        assert!(remaining.is_empty());
+        assert_eq!(
+            metadata,
+            Metadata::HdrCll(ContentLightLevel {
+                max_cll: 1000,
+                max_fall: 400,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_mastering_display_luminance() {
+        let mut obu = vec![0x02];
+        for value in [
+            34000u16, 16000, 13250, 34500, 7500, 3000, 15635, 16450, // primaries + white
+        ] {
+            obu.extend_from_slice(&value.to_be_bytes());
+        }
+        obu.extend_from_slice(&(1000u32 << 8).to_be_bytes());
+        obu.extend_from_slice(&82u32.to_be_bytes());
+        obu.push(0x80);
+
+        let (_, metadata) = make_parser::<false>()
+            .parse_metadata_obu(&obu, obu.len(), 0)
+            .expect("MDCV should parse");
+        let Metadata::HdrMdcv(mdcv) = metadata else {
+            panic!("expected MDCV, got {metadata:?}");
+        };
+        assert_eq!(mdcv.primary_chromaticity_x, [34000, 13250, 7500]);
+        assert_eq!(mdcv.white_point_chromaticity_y, 16450);
+        assert!((mdcv.max_luminance() - 1000.0).abs() < f64::EPSILON);
+        assert!((mdcv.min_luminance() - 0.005).abs() < 1e-4);
+    }
+
+    #[test]
+    fn recognizes_hdr10_plus_t35_payload() {
+        let obu = [0x04, 0xB5, 0x00, 0x3C, 0x00, 0x01, 0x04, 0x01, 0x40, 0x80];
+        let (_, metadata) = make_parser::<false>()
+            .parse_metadata_obu(&obu, obu.len(), 0)
+            .expect("T.35 should parse");
+        let Metadata::ItutT35(t35) = metadata else {
+            panic!("expected T.35, got {metadata:?}");
+        };
+        assert_eq!(t35.country_code, 0xB5);
+        assert_eq!(t35.country_code_extension, None);
+        assert_eq!(t35.payload, [0x00, 0x3C, 0x00, 0x01, 0x04, 0x01, 0x40]);
+        assert_eq!(t35.kind(), ItutT35Kind::Hdr10Plus);
+    }
+
+    #[test]
+    fn recognizes_dolby_vision_t35_payload() {
+        let t35 = ItutT35 {
+            country_code: 0xB5,
+            country_code_extension: None,
+            payload: vec![0x00, 0x3B, 0x00, 0x00, 0x08, 0x00, 0x37],
+        };
+        assert_eq!(t35.kind(), ItutT35Kind::DolbyVisionRpu);
+    }
+
+    #[test]
+    fn parses_scalability_structure() {
+        // scalability_mode_idc = SCALABILITY_SS
+        // spatial_layers_cnt_minus_1 = 1, dimensions present, no description,
+        // temporal group present, reserved
+        let mut obu = vec![0x03, SCALABILITY_SS, 0b0110_1000];
+        for value in [640u16, 360, 1280, 720] {
+            obu.extend_from_slice(&value.to_be_bytes());
+        }
+        // temporal_group_size = 1: temporal_id 0, both switching flags, 1 reference
+        // with ref_pic_diff 1, then trailing bits
+        obu.extend_from_slice(&[0x01, 0b0001_1001, 0x01, 0x80]);
+
+        let (_, metadata) = make_parser::<false>()
+            .parse_metadata_obu(&obu, obu.len(), 0)
+            .expect("scalability structure should parse");
+        let Metadata::Scalability(scalability) = metadata else {
+            panic!("expected scalability, got {metadata:?}");
+        };
+        let structure = scalability.structure.expect("structure should be present");
+        assert_eq!(structure.spatial_layers_cnt_minus_1, 1);
+        assert_eq!(
+            structure.spatial_layer_dimensions,
+            [(640, 360), (1280, 720)]
+        );
+        assert!(structure.spatial_layer_ref_ids.is_empty());
+        assert_eq!(
+            structure.temporal_group,
+            [TemporalGroupEntry {
+                temporal_id: 0,
+                temporal_switching_up_point: true,
+                spatial_switching_up_point: true,
+                ref_pic_diffs: vec![1],
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_full_timecode() {
+        let bits = [
+            "00000",     // counting_type
+            "1",         // full_timestamp_flag
+            "0",         // discontinuity_flag
+            "0",         // cnt_dropped_flag
+            "000001100", // n_frames = 12
+            "000011",    // seconds_value = 3
+            "000010",    // minutes_value = 2
+            "00001",     // hours_value = 1
+            "00000",     // time_offset_length
+            "1",         // trailing_one_bit
+        ]
+        .concat();
+        let mut obu = vec![0x05];
+        obu.extend(bits.as_bytes().chunks(8).map(|chunk| {
+            let chunk = format!("{:0<8}", std::str::from_utf8(chunk).unwrap());
+            u8::from_str_radix(&chunk, 2).unwrap()
+        }));
+
+        let (_, metadata) = make_parser::<false>()
+            .parse_metadata_obu(&obu, obu.len(), 0)
+            .expect("timecode should parse");
+        let Metadata::Timecode(timecode) = metadata else {
+            panic!("expected timecode, got {metadata:?}");
+        };
+        assert!(timecode.full_timestamp);
+        assert_eq!(timecode.time_offset, None);
+        assert_eq!(timecode.to_string(), "01:02:03:12");
+    }
+
+    #[test]
+    fn keeps_unregistered_metadata_as_raw_bytes() {
+        let obu = [0x06, 0xAA, 0xBB, 0x80];
+        let (_, metadata) = make_parser::<false>()
+            .parse_metadata_obu(&obu, obu.len(), 0)
+            .expect("unregistered metadata should parse");
+        assert_eq!(
+            metadata,
+            Metadata::Other {
+                metadata_type: 6,
+                payload: vec![0xAA, 0xBB],
+            }
+        );
+    }
+
+    #[test]
+    fn write_mode_copies_metadata_unchanged() {
+        let obu = [0x01, 0x03, 0xE8, 0x01, 0x90, 0x80, 0xFF];
+        let mut parser = make_parser::<true>();
+        let (remaining, _) = parser
+            .parse_metadata_obu(&obu, 6, 0)
+            .expect("CLL should parse");
+        assert_eq!(remaining, [0xFF]);
+        assert_eq!(parser.take_packet_out(), obu[..6]);
+    }
+
+    #[test]
+    fn serialized_metadata_parses_back() {
+        let mastering_display = Metadata::HdrMdcv(MasteringDisplay {
+            primary_chromaticity_x: [46399, 11141, 9830],
+            primary_chromaticity_y: [19136, 52232, 3014],
+            white_point_chromaticity_x: 20493,
+            white_point_chromaticity_y: 21561,
+            luminance_max: 1000 << 8,
+            luminance_min: 82,
+        });
+        let content_light_level = Metadata::HdrCll(ContentLightLevel {
+            max_cll: 1000,
+            max_fall: 400,
+        });
+
+        for metadata in [mastering_display, content_light_level] {
+            let obu = metadata.to_obu().expect("HDR metadata can be written");
+            assert_eq!(obu[0], 0x2A);
+            let size = usize::from(obu[1]);
+            assert_eq!(obu.len(), size + 2);
+            let (_, parsed) = make_parser::<false>()
+                .parse_metadata_obu(&obu[2..], size, 16)
+                .expect("serialized metadata should parse");
+            assert_eq!(parsed, metadata);
+        }
+    }
+
+    #[test]
+    fn timecode_cannot_be_serialized() {
+        let timecode = Metadata::Timecode(Timecode {
+            counting_type: 0,
+            full_timestamp: false,
+            discontinuity: false,
+            cnt_dropped: false,
+            n_frames: 0,
+            seconds: None,
+            minutes: None,
+            hours: None,
+            time_offset: None,
+        });
+        assert_eq!(timecode.metadata_type(), 5);
+        assert!(timecode.to_obu().is_none());
+    }
+
+    #[test]
+    fn strip_trailing_bits_keeps_payload_ending_in_the_trailing_bit() {
+        assert_eq!(strip_trailing_bits(&[0x12, 0x80, 0x00]), [0x12]);
+        assert_eq!(strip_trailing_bits(&[0x12, 0x31]), [0x12, 0x31]);
+        assert_eq!(strip_trailing_bits(&[0x00]), [] as [u8; 0]);
+    }
+}
@@ -2,7 +2,7 @@ use log::{debug, trace};
 use nom::{
     IResult, Parser,
     bits::{bits, complete as bit_parsers},
-    error::{Error, context},
+    error::{Error, ErrorKind, context},
 };
 use num_enum::TryFromPrimitive;
 
@@ -11,6 +11,7 @@ use crate::misc::to_binary_string;
 use super::{
     BitstreamParser,
     frame::FrameHeader,
+    metadata::Metadata,
     sequence::SequenceHeader,
     trace::{
         TraceCtx, trace_bool, trace_field, trace_leb128, trace_section, trace_take_u8,
@@ -35,7 +36,8 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
     ///
     /// # Returns
     /// - Remaining unconsumed input.
-    /// - `Some(Obu)` for parsed sequence/frame header payloads that are surfaced to callers.
+    /// - `Some(Obu)` for parsed sequence header, frame header and metadata payloads that are
+    ///   surfaced to callers.
     /// - `None` for OBUs that are intentionally skipped or passed through.
     ///
     /// # Errors
@@ -128,6 +130,10 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                     pre_len - input.len()
                 );
                 if obu_header.has_size_field {
+                    // Skip any padding first: it was already written along with the
+                    // rest of the payload, so it counts as taken.
+                    let adjustment = obu_size - (pre_len - input.len());
+                    input = &input[adjustment..];
                     if WRITE {
                         let bytes_written = self.packet_out.len() - packet_start_len;
                         let bytes_taken = pre_input.len() - input.len();
@@ -140,8 +146,6 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                             );
                         }
                     }
-                    let adjustment = obu_size - (pre_len - input.len());
-                    input = &input[adjustment..];
                 }
 
                 Ok((input, Some(Obu::SequenceHeader(header))))
@@ -215,9 +219,40 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 Ok((input, header.map(Obu::FrameHeader)))
             }
             ObuType::TileGroup => {
-                // I'm adding an assert here explicitly because I'm not sure if the spec
-                // actually requires this. I think it does. But it's 681 pages.
-                unreachable!("This should only be called from within a frame OBU.");
+                trace_section("Tile Group");
+                debug!("Parsing standalone tile group");
+                // A standalone tile group belongs to the most recent `OBU_FRAME_HEADER`,
+                // which determines the tile layout used to read `tg_start`/`tg_end`.
+                let Some(tile_info) = self.frame_tile_info.clone() else {
+                    return Err(nom::Err::Error(Error::new(input, ErrorKind::Verify)));
+                };
+                // Writing handled within this function. The payload is passed through
+                // unchanged, so the OBU size never needs adjusting.
+                let (input, ()) = context("Failed parsing tile group obu", |input| {
+                    self.parse_tile_group_obu(input, obu_size, &tile_info, obu_bit_offset)
+                })
+                .parse(input)?;
+                if !WRITE && let Some(record) = self.frame_records.last_mut() {
+                    record.obu_size += obu_size;
+                }
+
+                Ok((input, None))
+            }
+            ObuType::Metadata => {
+                trace_section("Metadata");
+                debug!("Parsing metadata");
+                // Writing handled within this function. The payload is passed through
+                // unchanged, so the OBU size never needs adjusting.
+                let (input, metadata) = context("Failed parsing metadata obu", |input| {
+                    self.parse_metadata_obu(input, obu_size, obu_bit_offset)
+                })
+                .parse(input)?;
+                if WRITE && self.stripped_metadata.contains(&metadata.metadata_type()) {
+                    debug!("Dropping metadata obu of type {}", metadata.metadata_type());
+                    self.packet_out.truncate(packet_start_len);
+                }
+
+                Ok((input, Some(Obu::Metadata(metadata))))
             }
             ObuType::TemporalDelimiter => {
                 trace_section("Temporal Delimiter");
@@ -269,9 +304,11 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Obu {
     SequenceHeader(SequenceHeader),
     FrameHeader(FrameHeader),
+    Metadata(Metadata),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -498,12 +535,16 @@ mod tests {
 
     use super::super::{
         BitstreamParser,
+        frame::TileInfo,
+        grain::FilmGrainHeader,
+        metadata::{ContentLightLevel, TimedMetadata},
         sequence::{
             ColorConfig, ColorPrimaries, ColorRange, MatrixCoefficients, SequenceHeader,
             TransferCharacteristics,
         },
         util::leb128_write,
     };
+    use crate::frame_records::FrameRecord;
     use arrayvec::ArrayVec;
 
     fn make_parser<const WRITE: bool>(
@@ -513,21 +554,11 @@ mod tests {
         packet_out: Vec<u8>,
     ) -> BitstreamParser<WRITE> {
         BitstreamParser {
-            reader: None,
-            writer: None,
             packet_out,
-            incoming_grain_header: None,
-            parsed: false,
             size,
             seen_frame_header,
             sequence_header,
-            previous_frame_header: None,
-            ref_frame_idx: Default::default(),
-            ref_order_hint: Default::default(),
-            big_ref_order_hint: Default::default(),
-            big_ref_valid: Default::default(),
-            big_order_hints: Default::default(),
-            grain_headers: Vec::new(),
+            ..BitstreamParser::without_container(None)
         }
     }
 
@@ -556,6 +587,8 @@ mod tests {
 
     fn sequence_header_with_idc(idc: u16) -> SequenceHeader {
         SequenceHeader {
+            seq_profile: 0,
+            still_picture: false,
             reduced_still_picture_header: false,
             frame_id_numbers_present: false,
             additional_frame_id_len_minus_1: 0,
@@ -573,8 +606,20 @@ mod tests {
             decoder_model_present_for_op: ArrayVec::new(),
             operating_points_cnt_minus_1: 0,
             operating_point_idc: ArrayVec::new(),
+            seq_level_idx: ArrayVec::new(),
+            seq_tier: ArrayVec::new(),
+            operating_parameters_info: ArrayVec::new(),
+            initial_display_delay_present: false,
+            initial_display_delay_minus_1: ArrayVec::new(),
+            operating_point: 0,
             cur_operating_point_idc: idc,
             timing_info: None,
+            enable_filter_intra: false,
+            enable_intra_edge_filter: false,
+            enable_interintra_compound: false,
+            enable_masked_compound: false,
+            enable_dual_filter: false,
+            enable_jnt_comp: false,
             enable_ref_frame_mvs: false,
             enable_warped_motion: false,
             enable_superres: false,
@@ -582,6 +627,8 @@ mod tests {
             enable_restoration: false,
             use_128x128_superblock: false,
             color_config: ColorConfig {
+                bit_depth: 8,
+                color_description_present: false,
                 color_primaries: ColorPrimaries::Unspecified,
                 transfer_characteristics: TransferCharacteristics::Unspecified,
                 matrix_coefficients: MatrixCoefficients::Unspecified,
@@ -589,6 +636,7 @@ mod tests {
                 num_planes: 1,
                 separate_uv_delta_q: false,
                 subsampling: (0, 0),
+                chroma_sample_position: 0,
             },
         }
     }
@@ -655,6 +703,7 @@ mod tests {
         b.push_bool(false); // separate_uv_delta_q
         // film_grain_params_present
         b.push_bool(false);
+        b.push_bool(true); // trailing_bits
         b.into_bytes()
     }
 
@@ -834,13 +883,116 @@ mod tests {
     }
 
     #[test]
-    fn parse_obu_metadata_type_returns_none() {
-        let obu = build_obu_bytes(ObuType::Metadata, None, true, &[0x01, 0x02]);
+    fn parse_obu_metadata_type_returns_metadata() {
+        // HDR CLL: max_cll 1000, max_fall 400
+        let obu = build_obu_bytes(
+            ObuType::Metadata,
+            None,
+            true,
+            &[0x01, 0x03, 0xE8, 0x01, 0x90, 0x80],
+        );
         let mut parser = make_parser::<false>(0, false, None, Vec::new());
 
         let (_, result) = parser.parse_obu(&obu, 0).expect("should parse Metadata");
 
-        assert!(result.is_none());
+        assert!(matches!(
+            result,
+            Some(Obu::Metadata(Metadata::HdrCll(ContentLightLevel {
+                max_cll: 1000,
+                max_fall: 400,
+            })))
+        ));
+    }
+
+    #[test]
+    fn parse_packet_collects_metadata_with_timestamp() {
+        let obu = build_obu_bytes(ObuType::Metadata, None, true, &[0x06, 0xAA, 0x80]);
+        let mut parser = make_parser::<true>(0, false, None, Vec::new());
+
+        let output = parser
+            .rewrite_packet(&obu, 417_083)
+            .expect("should rewrite Metadata");
+
+        assert_eq!(output, obu, "metadata must survive rewriting unchanged");
+        assert_eq!(
+            parser.metadata(),
+            [TimedMetadata {
+                timestamp: 417_083,
+                metadata: Metadata::Other {
+                    metadata_type: 6,
+                    payload: vec![0xAA],
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn rewrite_packet_drops_stripped_metadata_types() {
+        let cll = build_obu_bytes(
+            ObuType::Metadata,
+            None,
+            true,
+            &[0x01, 0x03, 0xE8, 0x01, 0x90, 0x80],
+        );
+        let other = build_obu_bytes(ObuType::Metadata, None, true, &[0x06, 0xAA, 0x80]);
+        let mut parser = make_parser::<true>(0, false, None, Vec::new()).with_stripped_metadata(1);
+
+        let output = parser
+            .rewrite_packet(&[cll, other.clone()].concat(), 0)
+            .expect("should rewrite Metadata");
+
+        assert_eq!(output, other);
+        assert_eq!(
+            parser.metadata().len(),
+            2,
+            "stripped metadata is still reported"
+        );
+    }
+
+    #[test]
+    fn rewrite_packet_inserts_metadata_after_sequence_header() {
+        let sequence_header = build_obu_bytes(
+            ObuType::SequenceHeader,
+            None,
+            true,
+            &build_reduced_sequence_header_bytes(),
+        );
+        let content_light_level = Metadata::HdrCll(ContentLightLevel {
+            max_cll: 1000,
+            max_fall: 400,
+        });
+        let mut parser = make_parser::<true>(0, false, None, Vec::new())
+            .with_original_grain()
+            .with_inserted_metadata(&content_light_level)
+            .expect("CLL can be written");
+
+        let output = parser
+            .rewrite_packet(&sequence_header, 0)
+            .expect("should rewrite sequence header");
+
+        assert_eq!(
+            output,
+            [sequence_header, content_light_level.to_obu().unwrap()].concat()
+        );
+    }
+
+    #[test]
+    fn parse_obu_sequence_header_write_keeps_original_grain_flag() {
+        let mut padded_payload = build_reduced_sequence_header_bytes();
+        padded_payload.extend_from_slice(&[0x00; 4]);
+        let obu = build_obu_bytes(ObuType::SequenceHeader, None, true, &padded_payload);
+        let mut parser = make_parser::<true>(0, false, None, Vec::new()).with_original_grain();
+        parser.incoming_grain_header = Some(Vec::new());
+
+        let (_, result) = parser
+            .parse_obu(&obu, 0)
+            .expect("should parse seq header OBU");
+
+        assert_eq!(parser.packet_out, obu);
+        assert!(matches!(
+            result,
+            Some(Obu::SequenceHeader(header)) if !header.new_film_grain_state
+        ));
     }
 
     #[test]
@@ -1165,15 +1317,164 @@ mod tests {
         assert!(!parser.packet_out.is_empty());
     }
 
-    // ===== Group 8: parse_obu — TileGroup panic + error conditions =====
+    #[test]
+    fn parse_obu_sequence_header_write_sets_grain_flag_before_padding() {
+        let mut padded_payload = build_reduced_sequence_header_bytes();
+        padded_payload.extend_from_slice(&[0x00; 4]);
+        let obu = build_obu_bytes(ObuType::SequenceHeader, None, true, &padded_payload);
+        let mut parser = make_parser::<true>(0, false, None, Vec::new());
+        parser.incoming_grain_header = Some(Vec::new());
+
+        parser
+            .parse_obu(&obu, 0)
+            .expect("should parse seq header OBU");
+
+        // film_grain_params_present is bit 33 of the payload, after the 2 byte OBU header,
+        // followed by the trailing one bit.
+        assert_eq!(parser.packet_out[2 + 4], 0x60);
+        assert!(parser.packet_out[2 + 5..].iter().all(|&byte| byte == 0));
+        let mut read_parser = make_parser::<false>(0, false, None, Vec::new());
+        let (_, result) = read_parser
+            .parse_obu(&parser.packet_out, 0)
+            .expect("should parse rewritten seq header OBU");
+        assert!(matches!(
+            result,
+            Some(Obu::SequenceHeader(header)) if header.film_grain_params_present
+        ));
+    }
+
+    // ===== Group 7b: new coded video sequences =====
 
     #[test]
-    #[should_panic(expected = "This should only be called from within a frame OBU")]
-    fn parse_obu_tile_group_panics() {
+    fn repeated_sequence_header_keeps_reference_state() {
+        let obu = build_obu_bytes(
+            ObuType::SequenceHeader,
+            None,
+            true,
+            &build_reduced_sequence_header_bytes(),
+        );
+        let mut parser = make_parser::<false>(0, false, None, Vec::new());
+        parser.parse_packet(&obu, 0).expect("should parse");
+        parser.ref_order_hint[0] = 5;
+        parser.big_ref_valid[0] = true;
+
+        parser.parse_packet(&obu, 0).expect("should parse");
+
+        assert_eq!(parser.ref_order_hint[0], 5);
+        assert!(parser.big_ref_valid[0]);
+    }
+
+    #[test]
+    fn changed_sequence_header_resets_reference_state() {
+        let first = build_reduced_sequence_header_bytes();
+        let mut second = first.clone();
+        second[3] |= 0x80; // enable_cdef
+        let mut parser = make_parser::<false>(0, false, None, Vec::new());
+        parser
+            .parse_packet(
+                &build_obu_bytes(ObuType::SequenceHeader, None, true, &first),
+                0,
+            )
+            .expect("should parse");
+        parser.ref_order_hint[0] = 5;
+        parser.big_ref_valid[0] = true;
+        parser.ref_film_grain[0] = FilmGrainHeader::CopyRefFrame {
+            ref_idx: 0,
+            grain_seed: 1,
+        };
+
+        parser
+            .parse_packet(
+                &build_obu_bytes(ObuType::SequenceHeader, None, true, &second),
+                0,
+            )
+            .expect("should parse");
+
+        assert_eq!(parser.ref_order_hint[0], 0);
+        assert!(!parser.big_ref_valid[0]);
+        assert_eq!(parser.ref_film_grain[0], FilmGrainHeader::Disable);
+        assert!(parser.sequence_header.as_ref().unwrap().enable_cdef);
+    }
+
+    // ===== Group 8: parse_obu — Standalone TileGroup + error conditions =====
+
+    fn single_tile_info() -> TileInfo {
+        TileInfo {
+            tile_cols: 1,
+            tile_rows: 1,
+            tile_cols_log2: 0,
+            tile_rows_log2: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_obu_tile_group_without_frame_header_errors() {
         let obu = build_obu_bytes(ObuType::TileGroup, None, true, &[0x00]);
         let mut parser = make_parser::<false>(0, false, None, Vec::new());
 
-        let _ = parser.parse_obu(&obu, 0);
+        assert!(parser.parse_obu(&obu, 0).is_err());
+    }
+
+    #[test]
+    fn parse_obu_tile_group_after_frame_header_clears_seen_frame_header() {
+        let trailing = [0xBE, 0xEF];
+        let mut obu = build_obu_bytes(ObuType::TileGroup, None, true, &[0x12, 0x34, 0x56]);
+        obu.extend_from_slice(&trailing);
+        let mut parser = make_parser::<false>(0, true, None, Vec::new());
+        parser.frame_tile_info = Some(single_tile_info());
+
+        let (remaining, result) = parser.parse_obu(&obu, 0).expect("should parse tile group");
+
+        assert!(result.is_none());
+        assert_eq!(remaining, &trailing);
+        assert!(!parser.seen_frame_header);
+        assert!(parser.packet_out.is_empty());
+    }
+
+    #[test]
+    fn parse_obu_tile_group_counts_towards_frame_record_size() {
+        let obu = build_obu_bytes(ObuType::TileGroup, None, true, &[0x12, 0x34, 0x56]);
+        let mut parser = make_parser::<false>(0, true, None, Vec::new());
+        parser.frame_tile_info = Some(single_tile_info());
+        parser
+            .frame_records
+            .push(FrameRecord::new(0, 0, 10, &FrameHeader::default()));
+
+        parser.parse_obu(&obu, 0).expect("should parse tile group");
+
+        assert_eq!(parser.frame_records()[0].obu_size, 13);
+    }
+
+    #[test]
+    fn parse_obu_tile_group_not_last_preserves_seen_frame_header() {
+        // Bits: tile_start_and_end_present=1, tg_start=00, tg_end=01.
+        let obu = build_obu_bytes(ObuType::TileGroup, None, true, &[0b1000_1000, 0xAA]);
+        let mut parser = make_parser::<false>(0, true, None, Vec::new());
+        parser.frame_tile_info = Some(TileInfo {
+            tile_cols: 2,
+            tile_rows: 2,
+            tile_cols_log2: 1,
+            tile_rows_log2: 1,
+            ..Default::default()
+        });
+
+        let (remaining, result) = parser.parse_obu(&obu, 0).expect("should parse tile group");
+
+        assert!(result.is_none());
+        assert!(remaining.is_empty());
+        assert!(parser.seen_frame_header);
+    }
+
+    #[test]
+    fn parse_obu_tile_group_write_passes_obu_through() {
+        let obu = build_obu_bytes(ObuType::TileGroup, None, true, &[0x12, 0x34, 0x56]);
+        let mut parser = make_parser::<true>(0, true, None, Vec::new());
+        parser.frame_tile_info = Some(single_tile_info());
+
+        let _ = parser.parse_obu(&obu, 0).expect("should parse tile group");
+
+        assert_eq!(parser.packet_out, obu);
     }
 
     #[test]
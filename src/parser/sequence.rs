@@ -1,8 +1,12 @@
 use arrayvec::ArrayVec;
-use bit::BitIndex;
-use log::{debug, trace};
-use nom::{IResult, bits::bits, error::Error};
+use log::{debug, error, trace};
+use nom::{
+    IResult,
+    bits::bits,
+    error::{Error, ErrorKind},
+};
 use num_enum::TryFromPrimitive;
+use num_rational::Rational32;
 
 use crate::misc::to_binary_string;
 
@@ -12,14 +16,16 @@ use super::{
         TraceCtx, trace_bool, trace_field, trace_take_u8, trace_take_u16, trace_take_u32,
         trace_take_u64, trace_take_usize,
     },
-    util::{BitInput, uvlc},
+    util::{BitInput, BitWriter, uvlc},
 };
 
 pub const SELECT_SCREEN_CONTENT_TOOLS: u8 = 2;
 pub const SELECT_INTEGER_MV: u8 = 2;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SequenceHeader {
+    pub seq_profile: u8,
+    pub still_picture: bool,
     pub reduced_still_picture_header: bool,
     pub frame_id_numbers_present: bool,
     pub additional_frame_id_len_minus_1: usize,
@@ -37,8 +43,23 @@ pub struct SequenceHeader {
     pub decoder_model_present_for_op: ArrayVec<bool, { 1 << 5u8 }>,
     pub operating_points_cnt_minus_1: usize,
     pub operating_point_idc: ArrayVec<u16, { 1 << 5u8 }>,
+    pub seq_level_idx: ArrayVec<u8, { 1 << 5u8 }>,
+    pub seq_tier: ArrayVec<bool, { 1 << 5u8 }>,
+    /// Present for operating points with `decoder_model_present_for_this_op` set.
+    pub operating_parameters_info: ArrayVec<Option<OperatingParametersInfo>, { 1 << 5u8 }>,
+    pub initial_display_delay_present: bool,
+    /// Present for operating points with `initial_display_delay_present_for_this_op` set.
+    pub initial_display_delay_minus_1: ArrayVec<Option<u8>, { 1 << 5u8 }>,
+    /// Index of the operating point selected for decoding.
+    pub operating_point: usize,
     pub cur_operating_point_idc: u16,
     pub timing_info: Option<TimingInfo>,
+    pub enable_filter_intra: bool,
+    pub enable_intra_edge_filter: bool,
+    pub enable_interintra_compound: bool,
+    pub enable_masked_compound: bool,
+    pub enable_dual_filter: bool,
+    pub enable_jnt_comp: bool,
     pub enable_ref_frame_mvs: bool,
     pub enable_warped_motion: bool,
     pub enable_superres: bool,
@@ -57,22 +78,235 @@ impl SequenceHeader {
     pub const fn enable_order_hint(&self) -> bool {
         self.order_hint_bits > 0
     }
+
+    /// Lists every operating point signalled by this sequence header, in bitstream order.
+    pub fn operating_points(&self) -> impl Iterator<Item = OperatingPoint> + '_ {
+        self.operating_point_idc
+            .iter()
+            .zip(&self.seq_level_idx)
+            .zip(&self.seq_tier)
+            .map(|((&idc, &seq_level_idx), &seq_tier)| OperatingPoint {
+                idc,
+                seq_level_idx,
+                seq_tier,
+            })
+    }
+
+    /// Writes this header as `sequence_header_obu()` syntax, without the
+    /// `trailing_bits()` that follow it.
+    pub fn write_bits(&self, writer: &mut BitWriter) {
+        writer.write_bits(u64::from(self.seq_profile), 3);
+        writer.write_bool(self.still_picture);
+        writer.write_bool(self.reduced_still_picture_header);
+        if self.reduced_still_picture_header {
+            writer.write_bits(u64::from(self.seq_level_idx[0]), 5);
+        } else {
+            writer.write_bool(self.timing_info.is_some());
+            if let Some(timing_info) = self.timing_info {
+                timing_info.write_bits(writer);
+                writer.write_bool(self.decoder_model_info.is_some());
+                if let Some(decoder_model_info) = self.decoder_model_info {
+                    decoder_model_info.write_bits(writer);
+                }
+            }
+            writer.write_bool(self.initial_display_delay_present);
+            writer.write_bits(self.operating_points_cnt_minus_1 as u64, 5);
+            for i in 0..=self.operating_points_cnt_minus_1 {
+                writer.write_bits(u64::from(self.operating_point_idc[i]), 12);
+                writer.write_bits(u64::from(self.seq_level_idx[i]), 5);
+                if self.seq_level_idx[i] > 7 {
+                    writer.write_bool(self.seq_tier[i]);
+                }
+                if let Some(decoder_model_info) = self.decoder_model_info {
+                    let info = self.operating_parameters_info[i];
+                    writer.write_bool(info.is_some());
+                    if let Some(info) = info {
+                        info.write_bits(
+                            writer,
+                            decoder_model_info.buffer_delay_length_minus_1 as usize + 1,
+                        );
+                    }
+                }
+                if self.initial_display_delay_present {
+                    let delay = self.initial_display_delay_minus_1[i];
+                    writer.write_bool(delay.is_some());
+                    if let Some(delay) = delay {
+                        writer.write_bits(u64::from(delay), 4);
+                    }
+                }
+            }
+        }
+
+        writer.write_bits(self.frame_width_bits_minus_1 as u64, 4);
+        writer.write_bits(self.frame_height_bits_minus_1 as u64, 4);
+        writer.write_bits(
+            u64::from(self.max_frame_width_minus_1),
+            self.frame_width_bits_minus_1 + 1,
+        );
+        writer.write_bits(
+            u64::from(self.max_frame_height_minus_1),
+            self.frame_height_bits_minus_1 + 1,
+        );
+        if !self.reduced_still_picture_header {
+            writer.write_bool(self.frame_id_numbers_present);
+        }
+        if self.frame_id_numbers_present {
+            writer.write_bits(self.delta_frame_id_len_minus_2 as u64, 4);
+            writer.write_bits(self.additional_frame_id_len_minus_1 as u64, 3);
+        }
+        writer.write_bool(self.use_128x128_superblock);
+        writer.write_bool(self.enable_filter_intra);
+        writer.write_bool(self.enable_intra_edge_filter);
+        if !self.reduced_still_picture_header {
+            writer.write_bool(self.enable_interintra_compound);
+            writer.write_bool(self.enable_masked_compound);
+            writer.write_bool(self.enable_warped_motion);
+            writer.write_bool(self.enable_dual_filter);
+            writer.write_bool(self.enable_order_hint());
+            if self.enable_order_hint() {
+                writer.write_bool(self.enable_jnt_comp);
+                writer.write_bool(self.enable_ref_frame_mvs);
+            }
+            let seq_choose_screen_content_tools =
+                self.force_screen_content_tools == SELECT_SCREEN_CONTENT_TOOLS;
+            writer.write_bool(seq_choose_screen_content_tools);
+            if !seq_choose_screen_content_tools {
+                writer.write_bits(u64::from(self.force_screen_content_tools), 1);
+            }
+            if self.force_screen_content_tools > 0 {
+                let seq_choose_integer_mv = self.force_integer_mv == SELECT_INTEGER_MV;
+                writer.write_bool(seq_choose_integer_mv);
+                if !seq_choose_integer_mv {
+                    writer.write_bits(u64::from(self.force_integer_mv), 1);
+                }
+            }
+            if self.enable_order_hint() {
+                writer.write_bits(self.order_hint_bits as u64 - 1, 3);
+            }
+        }
+        writer.write_bool(self.enable_superres);
+        writer.write_bool(self.enable_cdef);
+        writer.write_bool(self.enable_restoration);
+        self.color_config.write_bits(writer, self.seq_profile);
+        writer.write_bool(self.film_grain_params_present);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperatingPoint {
+    /// Bits 0-7 select temporal layers and bits 8-11 spatial layers.
+    /// Zero means every layer.
+    pub idc: u16,
+    pub seq_level_idx: u8,
+    /// `true` for the High tier.
+    pub seq_tier: bool,
+}
+
+impl OperatingPoint {
+    /// The level as `(major, minor)`, e.g. `(5, 1)` for level 5.1.
+    ///
+    /// Returns `None` for `seq_level_idx` 31, which places no level restrictions.
+    #[must_use]
+    pub const fn level(&self) -> Option<(u8, u8)> {
+        if self.seq_level_idx == 31 {
+            return None;
+        }
+        Some((2 + (self.seq_level_idx >> 2), self.seq_level_idx & 3))
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TimingInfo {
+    pub num_units_in_display_tick: u32,
+    pub time_scale: u32,
     pub equal_picture_interval: bool,
+    /// Only signalled when `equal_picture_interval` is set.
+    pub num_ticks_per_picture: Option<u64>,
+}
+
+impl TimingInfo {
+    /// Timing info for a constant `frame_rate`, with one display tick per picture.
+    ///
+    /// Returns `None` unless both parts of `frame_rate` are positive.
+    #[must_use]
+    pub fn from_frame_rate(frame_rate: Rational32) -> Option<Self> {
+        Some(Self {
+            num_units_in_display_tick: u32::try_from(*frame_rate.denom())
+                .ok()
+                .filter(|&denom| denom > 0)?,
+            time_scale: u32::try_from(*frame_rate.numer())
+                .ok()
+                .filter(|&numer| numer > 0)?,
+            equal_picture_interval: true,
+            num_ticks_per_picture: Some(1),
+        })
+    }
+
+    /// The frame rate signalled by the sequence header.
+    ///
+    /// Without `equal_picture_interval`, each picture is assumed to last one display tick.
+    /// Returns `None` if either field of the tick duration is zero.
+    #[must_use]
+    pub fn frame_rate(&self) -> Option<Rational32> {
+        let ticks_per_picture = self.num_ticks_per_picture.unwrap_or(1);
+        let den = u64::from(self.num_units_in_display_tick) * ticks_per_picture;
+        if den == 0 || self.time_scale == 0 {
+            return None;
+        }
+        Some(Rational32::new(
+            i32::try_from(self.time_scale).ok()?,
+            i32::try_from(den).ok()?,
+        ))
+    }
+
+    fn write_bits(&self, writer: &mut BitWriter) {
+        writer.write_bits(u64::from(self.num_units_in_display_tick), 32);
+        writer.write_bits(u64::from(self.time_scale), 32);
+        writer.write_bool(self.equal_picture_interval);
+        if self.equal_picture_interval {
+            let num_ticks_per_picture_minus_1 = self.num_ticks_per_picture.unwrap_or(1) - 1;
+            writer.write_uvlc(u32::try_from(num_ticks_per_picture_minus_1).unwrap_or(u32::MAX));
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DecoderModelInfo {
     pub buffer_delay_length_minus_1: u8,
+    pub num_units_in_decoding_tick: u32,
     pub buffer_removal_time_length_minus_1: u8,
     pub frame_presentation_time_length_minus_1: u8,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl DecoderModelInfo {
+    fn write_bits(self, writer: &mut BitWriter) {
+        writer.write_bits(u64::from(self.buffer_delay_length_minus_1), 5);
+        writer.write_bits(u64::from(self.num_units_in_decoding_tick), 32);
+        writer.write_bits(u64::from(self.buffer_removal_time_length_minus_1), 5);
+        writer.write_bits(u64::from(self.frame_presentation_time_length_minus_1), 5);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperatingParametersInfo {
+    pub decoder_buffer_delay: u64,
+    pub encoder_buffer_delay: u64,
+    pub low_delay_mode_flag: bool,
+}
+
+impl OperatingParametersInfo {
+    fn write_bits(&self, writer: &mut BitWriter, buffer_delay_length: usize) {
+        writer.write_bits(self.decoder_buffer_delay, buffer_delay_length);
+        writer.write_bits(self.encoder_buffer_delay, buffer_delay_length);
+        writer.write_bool(self.low_delay_mode_flag);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ColorConfig {
+    pub bit_depth: u8,
+    /// Without a color description, the three fields below are `Unspecified`.
+    pub color_description_present: bool,
     pub color_primaries: ColorPrimaries,
     pub transfer_characteristics: TransferCharacteristics,
     pub matrix_coefficients: MatrixCoefficients,
@@ -80,6 +314,157 @@ pub struct ColorConfig {
     pub num_planes: u8,
     pub separate_uv_delta_q: bool,
     pub subsampling: (u8, u8),
+    /// Only coded for 4:2:0 video; zero otherwise.
+    pub chroma_sample_position: u8,
+}
+
+impl ColorConfig {
+    /// Whether this is the identity-matrix sRGB combination, for which `color_config()`
+    /// implies full range and 4:4:4 instead of coding them.
+    #[must_use]
+    pub fn is_srgb(&self) -> bool {
+        is_srgb(
+            self.color_primaries,
+            self.transfer_characteristics,
+            self.matrix_coefficients,
+        )
+    }
+
+    fn write_bits(&self, writer: &mut BitWriter, seq_profile: u8) {
+        let high_bitdepth = self.bit_depth > 8;
+        writer.write_bool(high_bitdepth);
+        if seq_profile == 2 && high_bitdepth {
+            writer.write_bool(self.bit_depth == 12);
+        }
+        let monochrome = self.num_planes == 1;
+        if seq_profile != 1 {
+            writer.write_bool(monochrome);
+        }
+        writer.write_bool(self.color_description_present);
+        if self.color_description_present {
+            writer.write_bits(self.color_primaries as u64, 8);
+            writer.write_bits(self.transfer_characteristics as u64, 8);
+            writer.write_bits(self.matrix_coefficients as u64, 8);
+        }
+        if monochrome {
+            writer.write_bits(self.color_range as u64, 1);
+            return;
+        }
+        if !self.is_srgb() {
+            writer.write_bits(self.color_range as u64, 1);
+            let (ss_x, ss_y) = self.subsampling;
+            if seq_profile == 2 && self.bit_depth == 12 {
+                writer.write_bits(u64::from(ss_x), 1);
+                if ss_x > 0 {
+                    writer.write_bits(u64::from(ss_y), 1);
+                }
+            }
+            if ss_x > 0 && ss_y > 0 {
+                writer.write_bits(u64::from(self.chroma_sample_position), 2);
+            }
+        }
+        writer.write_bool(self.separate_uv_delta_q);
+    }
+}
+
+fn is_srgb(
+    color_primaries: ColorPrimaries,
+    transfer_characteristics: TransferCharacteristics,
+    matrix_coefficients: MatrixCoefficients,
+) -> bool {
+    color_primaries == ColorPrimaries::Bt709
+        && transfer_characteristics == TransferCharacteristics::Srgb
+        && matrix_coefficients == MatrixCoefficients::Identity
+}
+
+/// New values for the color description in `color_config()`.
+/// Fields left as `None` keep their value from the stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColorDescription {
+    pub color_primaries: Option<ColorPrimaries>,
+    pub transfer_characteristics: Option<TransferCharacteristics>,
+    pub matrix_coefficients: Option<MatrixCoefficients>,
+    pub color_range: Option<ColorRange>,
+}
+
+#[cfg(feature = "ffmpeg")]
+impl From<ColorPrimaries> for ffmpeg::ffi::AVColorPrimaries {
+    fn from(value: ColorPrimaries) -> Self {
+        match value {
+            ColorPrimaries::Bt709 => Self::BT709,
+            ColorPrimaries::Unspecified => Self::UNSPECIFIED,
+            ColorPrimaries::Bt470m => Self::BT470M,
+            ColorPrimaries::Bt470bg => Self::BT470BG,
+            ColorPrimaries::Bt601 => Self::SMPTE170M,
+            ColorPrimaries::Smpte240 => Self::SMPTE240M,
+            ColorPrimaries::Film => Self::FILM,
+            ColorPrimaries::Bt2020 => Self::BT2020,
+            ColorPrimaries::Xyz => Self::SMPTE428,
+            ColorPrimaries::Smpte431 => Self::SMPTE431,
+            ColorPrimaries::Smpte432 => Self::SMPTE432,
+            ColorPrimaries::Ebu3213 => Self::EBU3213,
+        }
+    }
+}
+
+#[cfg(feature = "ffmpeg")]
+impl From<TransferCharacteristics> for ffmpeg::ffi::AVColorTransferCharacteristic {
+    fn from(value: TransferCharacteristics) -> Self {
+        match value {
+            TransferCharacteristics::Reserved0 => Self::RESERVED0,
+            TransferCharacteristics::Bt709 => Self::BT709,
+            TransferCharacteristics::Unspecified => Self::UNSPECIFIED,
+            TransferCharacteristics::Reserved3 => Self::RESERVED,
+            TransferCharacteristics::Bt470m => Self::GAMMA22,
+            TransferCharacteristics::Bt470bg => Self::GAMMA28,
+            TransferCharacteristics::Bt601 => Self::SMPTE170M,
+            TransferCharacteristics::Smpte240 => Self::SMPTE240M,
+            TransferCharacteristics::Linear => Self::LINEAR,
+            TransferCharacteristics::Log100 => Self::LOG,
+            TransferCharacteristics::Log100Sqrt10 => Self::LOG_SQRT,
+            TransferCharacteristics::Iec61966 => Self::IEC61966_2_4,
+            TransferCharacteristics::Bt1361 => Self::BT1361_ECG,
+            TransferCharacteristics::Srgb => Self::IEC61966_2_1,
+            TransferCharacteristics::Bt2020_10Bit => Self::BT2020_10,
+            TransferCharacteristics::Bt2020_12Bit => Self::BT2020_12,
+            TransferCharacteristics::Smpte2084 => Self::SMPTE2084,
+            TransferCharacteristics::Smpte428 => Self::SMPTE428,
+            TransferCharacteristics::Hlg => Self::ARIB_STD_B67,
+        }
+    }
+}
+
+#[cfg(feature = "ffmpeg")]
+impl From<MatrixCoefficients> for ffmpeg::ffi::AVColorSpace {
+    fn from(value: MatrixCoefficients) -> Self {
+        match value {
+            MatrixCoefficients::Identity => Self::RGB,
+            MatrixCoefficients::Bt709 => Self::BT709,
+            MatrixCoefficients::Unspecified => Self::UNSPECIFIED,
+            MatrixCoefficients::Reserved3 => Self::RESERVED,
+            MatrixCoefficients::Fcc => Self::FCC,
+            MatrixCoefficients::Bt470bg => Self::BT470BG,
+            MatrixCoefficients::Bt601 => Self::SMPTE170M,
+            MatrixCoefficients::Smpte240 => Self::SMPTE240M,
+            MatrixCoefficients::SmpteYCgCo => Self::YCGCO,
+            MatrixCoefficients::Bt2020Ncl => Self::BT2020_NCL,
+            MatrixCoefficients::Bt2020Cl => Self::BT2020_CL,
+            MatrixCoefficients::Smpte2085 => Self::SMPTE2085,
+            MatrixCoefficients::ChromaticityNcl => Self::CHROMA_DERIVED_NCL,
+            MatrixCoefficients::ChromaticityCl => Self::CHROMA_DERIVED_CL,
+            MatrixCoefficients::ICtCp => Self::ICTCP,
+        }
+    }
+}
+
+#[cfg(feature = "ffmpeg")]
+impl From<ColorRange> for ffmpeg::ffi::AVColorRange {
+    fn from(value: ColorRange) -> Self {
+        match value {
+            ColorRange::Limited => Self::MPEG,
+            ColorRange::Full => Self::JPEG,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
@@ -157,52 +542,63 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
     /// CONTRACT: `input` must begin at the first bit of the sequence header
     /// payload (after OBU framing has been handled by the caller).
     ///
-    /// In write mode (`WRITE = true`), this parser mirrors the consumed OBU
-    /// bytes and rewrites only the `film_grain_params_present` bit so it
-    /// matches whether incoming grain data is being applied.
+    /// In write mode (`WRITE = true`), this parser serializes the parsed header
+    /// back out with the `film_grain_params_present` bit set to match whether
+    /// incoming grain data is being applied, and with the timing info and color
+    /// description if they were given to the parser.
     pub fn parse_sequence_header<'a>(
         &mut self,
         input: &'a [u8],
         obu_bit_offset: usize,
     ) -> IResult<&'a [u8], SequenceHeader, Error<&'a [u8]>> {
-        let mut obu_out = if WRITE {
+        let obu_out = if WRITE {
             input[..self.size].to_owned()
         } else {
             Vec::new()
         };
+        let input_len = input.len();
         bits(move |input| {
             let ctx = TraceCtx::new(input, obu_bit_offset);
+            // Bit positions within the payload, for rewriting fields.
+            let payload_pos = |input: BitInput| (input_len - input.0.len()) * 8 + input.1;
             let (input, seq_profile) = trace_take_u8(input, ctx, 3, "seq_profile")?;
-            let (input, _still_picture) = trace_bool(input, ctx, "still_picture")?;
+            let (input, still_picture) = trace_bool(input, ctx, "still_picture")?;
             let (input, reduced_still_picture_header) =
                 trace_bool(input, ctx, "reduced_still_picture_header")?;
+            let mut decoder_model_present_for_op = ArrayVec::new();
+            let mut operating_point_idc = ArrayVec::new();
+            let mut seq_level_idx = ArrayVec::new();
+            let mut seq_tier = ArrayVec::new();
+            let mut operating_parameters = ArrayVec::new();
+            let mut initial_display_delay_minus_1 = ArrayVec::new();
             let (
                 input,
                 decoder_model_info,
                 operating_points_cnt_minus_1,
-                decoder_model_present_for_op,
-                operating_point_idc,
                 timing_info,
+                initial_display_delay_present,
             ) = if reduced_still_picture_header {
-                let (input, _seq_level_idx) = trace_take_u8(input, ctx, 5, "seq_level_idx[0]")?;
+                let (input, level) = trace_take_u8(input, ctx, 5, "seq_level_idx[0]")?;
                 // AV1 spec: reduced_still_picture_header implies a single
                 // operating point with idc=0 and no decoder model.
-                let mut op_idc = ArrayVec::new();
-                op_idc.push(0);
-                let mut dm_present = ArrayVec::new();
-                dm_present.push(false);
-                (input, None, 0, dm_present, op_idc, None)
+                operating_point_idc.push(0);
+                seq_level_idx.push(level);
+                seq_tier.push(false);
+                decoder_model_present_for_op.push(false);
+                operating_parameters.push(None);
+                initial_display_delay_minus_1.push(None);
+                (input, None, 0, None, false)
             } else {
                 let (input, timing_info_present_flag) =
                     trace_bool(input, ctx, "timing_info_present_flag")?;
                 let (input, decoder_model_info, timing_info) = if timing_info_present_flag {
                     let (input, timing_info) = timing_info(input, ctx)?;
                     let (input, flag) = trace_bool(input, ctx, "decoder_model_info_present_flag")?;
-                    let (input, decoder_model, timing_info) = if flag {
+                    let (input, decoder_model) = if flag {
                         let (input, decoder_model) = decoder_model_info(input, ctx)?;
-                        (input, Some(decoder_model), timing_info)
+                        (input, Some(decoder_model))
                     } else {
-                        (input, None, timing_info)
+                        (input, None)
                     };
                     (input, decoder_model, Some(timing_info))
                 } else {
@@ -211,8 +607,6 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                 let (input, initial_display_delay_present_flag) =
                     trace_bool(input, ctx, "initial_display_delay_present_flag")?;
 
-                let mut decoder_model_present_for_op = ArrayVec::new();
-                let mut operating_point_idc = ArrayVec::new();
                 let (mut input, operating_points_cnt_minus_1) =
                     trace_take_usize(input, ctx, 5, "operating_points_cnt_minus_1")?;
                 for i in 0..=operating_points_cnt_minus_1 {
@@ -220,14 +614,16 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                     let (inner_input, cur_operating_point_idc) =
                         trace_take_u16(inner_input, ctx, 12, &format!("operating_point_idc[{i}]"))?;
                     operating_point_idc.push(cur_operating_point_idc);
-                    let (inner_input, seq_level_idx) =
+                    let (inner_input, cur_seq_level_idx) =
                         trace_take_u8(inner_input, ctx, 5, &format!("seq_level_idx[{i}]"))?;
-                    let (inner_input, _seq_tier) = if seq_level_idx > 7 {
+                    seq_level_idx.push(cur_seq_level_idx);
+                    let (inner_input, cur_seq_tier) = if cur_seq_level_idx > 7 {
                         trace_bool(inner_input, ctx, &format!("seq_tier[{i}]"))?
                     } else {
                         (inner_input, false)
                     };
-                    let (inner_input, cur_decoder_model_present_for_op) =
+                    seq_tier.push(cur_seq_tier);
+                    let (inner_input, cur_operating_parameters) =
                         if let Some(decoder_model_info) = decoder_model_info {
                             let (inner_input, flag) = trace_bool(
                                 inner_input,
@@ -235,23 +631,21 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                                 &format!("decoder_model_present_for_this_op[{i}]"),
                             )?;
                             if flag {
-                                (
-                                    operating_parameters_info(
-                                        inner_input,
-                                        ctx,
-                                        decoder_model_info.buffer_delay_length_minus_1 as usize + 1,
-                                    )?
-                                    .0,
-                                    flag,
-                                )
+                                let (inner_input, info) = operating_parameters_info(
+                                    inner_input,
+                                    ctx,
+                                    decoder_model_info.buffer_delay_length_minus_1 as usize + 1,
+                                )?;
+                                (inner_input, Some(info))
                             } else {
-                                (inner_input, flag)
+                                (inner_input, None)
                             }
                         } else {
-                            (inner_input, false)
+                            (inner_input, None)
                         };
-                    decoder_model_present_for_op.push(cur_decoder_model_present_for_op);
-                    let (inner_input, _initial_display_delay_present_for_op) =
+                    decoder_model_present_for_op.push(cur_operating_parameters.is_some());
+                    operating_parameters.push(cur_operating_parameters);
+                    let (inner_input, cur_initial_display_delay_minus_1) =
                         if initial_display_delay_present_flag {
                             let (inner_input, flag) = trace_bool(
                                 inner_input,
@@ -259,32 +653,33 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                                 &format!("initial_display_delay_present_for_this_op[{i}]"),
                             )?;
                             if flag {
-                                let (inner_input, _initial_display_delay_minus_1) = trace_take_u8(
+                                let (inner_input, initial_display_delay_minus_1) = trace_take_u8(
                                     inner_input,
                                     ctx,
                                     4,
                                     &format!("initial_display_delay_minus_1[{i}]"),
                                 )?;
-                                (inner_input, flag)
+                                (inner_input, Some(initial_display_delay_minus_1))
                             } else {
-                                (inner_input, flag)
+                                (inner_input, None)
                             }
                         } else {
-                            (inner_input, false)
+                            (inner_input, None)
                         };
+                    initial_display_delay_minus_1.push(cur_initial_display_delay_minus_1);
                     input = inner_input;
                 }
                 (
                     input,
                     decoder_model_info,
                     operating_points_cnt_minus_1,
-                    decoder_model_present_for_op,
-                    operating_point_idc,
                     timing_info,
+                    initial_display_delay_present_flag,
                 )
             };
 
-            let operating_point = choose_operating_point();
+            let operating_point =
+                choose_operating_point(self.operating_point, operating_points_cnt_minus_1);
             let cur_operating_point_idc = operating_point_idc[operating_point];
             let (input, frame_width_bits_minus_1) =
                 trace_take_usize(input, ctx, 4, "frame_width_bits_minus_1")?;
@@ -322,40 +717,48 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
                     (input, 0, 0)
                 };
             let (input, use_128x128_superblock) = trace_bool(input, ctx, "use_128x128_superblock")?;
-            let (input, _enable_filter_intra) = trace_bool(input, ctx, "enable_filter_intra")?;
-            let (input, _enable_intra_edge_filter) =
+            let (input, enable_filter_intra) = trace_bool(input, ctx, "enable_filter_intra")?;
+            let (input, enable_intra_edge_filter) =
                 trace_bool(input, ctx, "enable_intra_edge_filter")?;
             let (
                 input,
+                enable_interintra_compound,
+                enable_masked_compound,
+                enable_warped_motion,
+                enable_dual_filter,
+                enable_jnt_comp,
+                enable_ref_frame_mvs,
                 force_screen_content_tools,
                 force_integer_mv,
                 order_hint_bits,
-                enable_ref_frame_mvs,
-                enable_warped_motion,
             ) = if reduced_still_picture_header {
                 (
                     input,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
                     SELECT_SCREEN_CONTENT_TOOLS,
                     SELECT_INTEGER_MV,
                     0,
-                    false,
-                    false,
                 )
             } else {
-                let (input, _enable_interintra_compound) =
+                let (input, enable_interintra_compound) =
                     trace_bool(input, ctx, "enable_interintra_compound")?;
-                let (input, _enable_masked_compound) =
+                let (input, enable_masked_compound) =
                     trace_bool(input, ctx, "enable_masked_compound")?;
                 let (input, enable_warped_motion) = trace_bool(input, ctx, "enable_warped_motion")?;
-                let (input, _enable_dual_filter) = trace_bool(input, ctx, "enable_dual_filter")?;
+                let (input, enable_dual_filter) = trace_bool(input, ctx, "enable_dual_filter")?;
                 let (input, enable_order_hint) = trace_bool(input, ctx, "enable_order_hint")?;
-                let (input, enable_ref_frame_mvs) = if enable_order_hint {
-                    let (input, _enable_jnt_comp) = trace_bool(input, ctx, "enable_jnt_comp")?;
+                let (input, enable_jnt_comp, enable_ref_frame_mvs) = if enable_order_hint {
+                    let (input, enable_jnt_comp) = trace_bool(input, ctx, "enable_jnt_comp")?;
                     let (input, enable_ref_frame_mvs) =
                         trace_bool(input, ctx, "enable_ref_frame_mvs")?;
-                    (input, enable_ref_frame_mvs)
+                    (input, enable_jnt_comp, enable_ref_frame_mvs)
                 } else {
-                    (input, false)
+                    (input, false, false)
                 };
                 let (input, seq_choose_screen_content_tools) =
                     trace_bool(input, ctx, "seq_choose_screen_content_tools")?;
@@ -387,11 +790,15 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
 
                 (
                     input,
+                    enable_interintra_compound,
+                    enable_masked_compound,
+                    enable_warped_motion,
+                    enable_dual_filter,
+                    enable_jnt_comp,
+                    enable_ref_frame_mvs,
                     seq_force_screen_content_tools,
                     seq_force_integer_mv,
                     order_hint_bits,
-                    enable_ref_frame_mvs,
-                    enable_warped_motion,
                 )
             };
 
@@ -399,78 +806,122 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
             let (input, enable_cdef) = trace_bool(input, ctx, "enable_cdef")?;
             let (input, enable_restoration) = trace_bool(input, ctx, "enable_restoration")?;
             let (input, color_config) = color_config(input, ctx, seq_profile)?;
-            let fgp_bit_offset = input.1;
             let (input, film_grain_params_present) =
                 trace_bool(input, ctx, "film_grain_params_present")?;
 
+            let header = SequenceHeader {
+                seq_profile,
+                still_picture,
+                reduced_still_picture_header,
+                frame_id_numbers_present,
+                additional_frame_id_len_minus_1,
+                delta_frame_id_len_minus_2,
+                film_grain_params_present,
+                new_film_grain_state: if self.keep_grain {
+                    film_grain_params_present
+                } else {
+                    self.writes_grain()
+                },
+                force_screen_content_tools,
+                force_integer_mv,
+                order_hint_bits,
+                frame_width_bits_minus_1,
+                frame_height_bits_minus_1,
+                max_frame_width_minus_1,
+                max_frame_height_minus_1,
+                decoder_model_info,
+                decoder_model_present_for_op,
+                operating_points_cnt_minus_1,
+                operating_point_idc,
+                seq_level_idx,
+                seq_tier,
+                operating_parameters_info: operating_parameters,
+                initial_display_delay_present,
+                initial_display_delay_minus_1,
+                operating_point,
+                cur_operating_point_idc,
+                timing_info,
+                enable_filter_intra,
+                enable_intra_edge_filter,
+                enable_interintra_compound,
+                enable_masked_compound,
+                enable_dual_filter,
+                enable_jnt_comp,
+                enable_ref_frame_mvs,
+                enable_warped_motion,
+                enable_superres,
+                enable_cdef,
+                enable_restoration,
+                use_128x128_superblock,
+                color_config,
+            };
+
             if WRITE {
-                // Toggle the film grain params present flag
+                let mut new_header = header.clone();
+                if let Some(new_timing_info) = self.timing_info {
+                    if reduced_still_picture_header {
+                        error!("A reduced still picture header cannot signal timing_info");
+                        return Err(nom::Err::Failure(Error::new(input, ErrorKind::Verify)));
+                    }
+                    let Some(timing_info) = adjusted_timing_info(
+                        header.timing_info.as_ref(),
+                        header.decoder_model_info.is_some(),
+                        new_timing_info,
+                    ) else {
+                        return Err(nom::Err::Failure(Error::new(input, ErrorKind::Verify)));
+                    };
+                    new_header.timing_info = Some(timing_info);
+                }
+                if let Some(description) = self.color_description {
+                    let Some(color_config) =
+                        described_color_config(&header.color_config, description)
+                    else {
+                        return Err(nom::Err::Failure(Error::new(input, ErrorKind::Verify)));
+                    };
+                    new_header.color_config = color_config;
+                }
+                // Set the film grain params present flag
                 // based on whether we are adding or removing film grain.
-                // We use the bit offset captured BEFORE parsing the flag so
-                // it points at the flag itself rather than one past it.
-                obu_out
-                    .last_mut()
-                    .unwrap()
-                    .set_bit(7 - fgp_bit_offset, self.incoming_grain_header.is_some());
-                self.packet_out.extend_from_slice(&obu_out);
+                if !self.keep_grain {
+                    new_header.film_grain_params_present = self.writes_grain();
+                }
+
+                let mut writer = BitWriter::new();
+                new_header.write_bits(&mut writer);
+                writer.trailing_bits();
+                let mut new_obu = writer.into_bytes();
+                // The OBU may be padded after its trailing bits; keep the padding.
+                let padding_start = (payload_pos(input) + 1).div_ceil(8).min(obu_out.len());
+                new_obu.extend_from_slice(&obu_out[padding_start..]);
+                self.packet_out.extend_from_slice(&new_obu);
                 debug!(
                     "Writing updated sequence header of size {} to packet_out, total packet size \
                      at {}",
-                    obu_out.len(),
+                    new_obu.len(),
                     self.packet_out.len()
                 );
-                trace!("Packet contents: {}", to_binary_string(&obu_out));
+                trace!("Packet contents: {}", to_binary_string(&new_obu));
             }
 
-            Ok((
-                input,
-                SequenceHeader {
-                    reduced_still_picture_header,
-                    frame_id_numbers_present,
-                    additional_frame_id_len_minus_1,
-                    delta_frame_id_len_minus_2,
-                    film_grain_params_present,
-                    new_film_grain_state: self.incoming_grain_header.is_some(),
-                    force_screen_content_tools,
-                    force_integer_mv,
-                    order_hint_bits,
-                    frame_width_bits_minus_1,
-                    frame_height_bits_minus_1,
-                    max_frame_width_minus_1,
-                    max_frame_height_minus_1,
-                    decoder_model_info,
-                    decoder_model_present_for_op,
-                    operating_points_cnt_minus_1,
-                    operating_point_idc,
-                    cur_operating_point_idc,
-                    timing_info,
-                    enable_ref_frame_mvs,
-                    enable_warped_motion,
-                    enable_superres,
-                    enable_cdef,
-                    enable_restoration,
-                    use_128x128_superblock,
-                    color_config,
-                },
-            ))
+            Ok((input, header))
         })(input)
     }
 }
 
-/// Parses sequence-level `timing_info` and keeps the fields needed later.
+/// Parses sequence-level `timing_info`.
 ///
-/// RATIONALE: frame-header parsing only needs `equal_picture_interval` to know
-/// whether `temporal_point_info` is present; other timing fields are consumed
-/// only to keep bit parsing aligned.
+/// RATIONALE: frame-header parsing needs `equal_picture_interval` to know
+/// whether `temporal_point_info` is present, and elementary streams without a
+/// container take their frame rate from the remaining fields.
 fn timing_info<'a>(
     input: BitInput<'a>,
     ctx: TraceCtx,
 ) -> IResult<BitInput<'a>, TimingInfo, Error<BitInput<'a>>> {
-    let (input, _num_units_in_display_tick) =
+    let (input, num_units_in_display_tick) =
         trace_take_u32(input, ctx, 32, "num_units_in_display_tick")?;
-    let (input, _time_scale) = trace_take_u32(input, ctx, 32, "time_scale")?;
+    let (input, time_scale) = trace_take_u32(input, ctx, 32, "time_scale")?;
     let (input, equal_picture_interval) = trace_bool(input, ctx, "equal_picture_interval")?;
-    let input = if equal_picture_interval {
+    let (input, num_ticks_per_picture) = if equal_picture_interval {
         let pos = ctx.pos(input);
         let (input, value) = uvlc(input)?;
         let bits_consumed = ctx.pos(input) - pos;
@@ -480,18 +931,67 @@ fn timing_info<'a>(
             bits_consumed,
             u64::from(value),
         );
-        input
+        (input, Some(u64::from(value) + 1))
     } else {
-        input
+        (input, None)
     };
     Ok((
         input,
         TimingInfo {
+            num_units_in_display_tick,
+            time_scale,
             equal_picture_interval,
+            num_ticks_per_picture,
         },
     ))
 }
 
+/// Works out the `timing_info()` that replaces `old` with `timing_info`.
+///
+/// With a decoder model, frame headers code `temporal_point_info` only when
+/// `equal_picture_interval` is unset, so that flag keeps its coded value and the
+/// picture duration is moved between `num_units_in_display_tick` and
+/// `num_ticks_per_picture` to match. Returns `None` if that does not fit.
+fn adjusted_timing_info(
+    old: Option<&TimingInfo>,
+    decoder_model_info_present: bool,
+    mut timing_info: TimingInfo,
+) -> Option<TimingInfo> {
+    if let Some(old) = old.filter(|_| decoder_model_info_present)
+        && old.equal_picture_interval != timing_info.equal_picture_interval
+    {
+        if old.equal_picture_interval {
+            timing_info.num_ticks_per_picture = Some(1);
+        } else {
+            let ticks_per_picture = timing_info.num_ticks_per_picture.unwrap_or(1);
+            let Ok(num_units_in_display_tick) =
+                u32::try_from(u64::from(timing_info.num_units_in_display_tick) * ticks_per_picture)
+            else {
+                error!("The picture duration does not fit in num_units_in_display_tick");
+                return None;
+            };
+            timing_info.num_units_in_display_tick = num_units_in_display_tick;
+            timing_info.num_ticks_per_picture = None;
+        }
+        timing_info.equal_picture_interval = old.equal_picture_interval;
+    }
+    if timing_info.num_units_in_display_tick == 0 || timing_info.time_scale == 0 {
+        error!("num_units_in_display_tick and time_scale must be greater than 0");
+        return None;
+    }
+    if timing_info.equal_picture_interval {
+        let num_ticks_per_picture = timing_info.num_ticks_per_picture.unwrap_or(1);
+        if !(1..=u64::from(u32::MAX)).contains(&num_ticks_per_picture) {
+            error!("num_ticks_per_picture must be between 1 and 2^32 - 1");
+            return None;
+        }
+        timing_info.num_ticks_per_picture = Some(num_ticks_per_picture);
+    } else {
+        timing_info.num_ticks_per_picture = None;
+    }
+    Some(timing_info)
+}
+
 /// Parses sequence-level decoder model timing widths.
 ///
 /// The returned lengths are reused when parsing per-operating-point and
@@ -502,7 +1002,7 @@ fn decoder_model_info<'a>(
 ) -> IResult<BitInput<'a>, DecoderModelInfo, Error<BitInput<'a>>> {
     let (input, buffer_delay_length_minus_1) =
         trace_take_u8(input, ctx, 5, "buffer_delay_length_minus_1")?;
-    let (input, _num_units_in_decoding_tick) =
+    let (input, num_units_in_decoding_tick) =
         trace_take_u32(input, ctx, 32, "num_units_in_decoding_tick")?;
     let (input, buffer_removal_time_length_minus_1) =
         trace_take_u8(input, ctx, 5, "buffer_removal_time_length_minus_1")?;
@@ -512,6 +1012,7 @@ fn decoder_model_info<'a>(
         input,
         DecoderModelInfo {
             buffer_delay_length_minus_1,
+            num_units_in_decoding_tick,
             buffer_removal_time_length_minus_1,
             frame_presentation_time_length_minus_1,
         },
@@ -519,20 +1020,24 @@ fn decoder_model_info<'a>(
 }
 
 /// Parses per-operating-point decoder buffering parameters.
-///
-/// CONTRACT: this helper only advances the bitstream; the parsed values are
-/// sequence-level timing side data that are not required by grain workflows.
 fn operating_parameters_info<'a>(
     input: BitInput<'a>,
     ctx: TraceCtx,
     buffer_delay_length: usize,
-) -> IResult<BitInput<'a>, (), Error<BitInput<'a>>> {
-    let (input, _decoder_buffer_delay) =
+) -> IResult<BitInput<'a>, OperatingParametersInfo, Error<BitInput<'a>>> {
+    let (input, decoder_buffer_delay) =
         trace_take_u64(input, ctx, buffer_delay_length, "decoder_buffer_delay")?;
-    let (input, _encoder_buffer_delay) =
+    let (input, encoder_buffer_delay) =
         trace_take_u64(input, ctx, buffer_delay_length, "encoder_buffer_delay")?;
-    let (input, _low_delay_mode_flag) = trace_bool(input, ctx, "low_delay_mode_flag")?;
-    Ok((input, ()))
+    let (input, low_delay_mode_flag) = trace_bool(input, ctx, "low_delay_mode_flag")?;
+    Ok((
+        input,
+        OperatingParametersInfo {
+            decoder_buffer_delay,
+            encoder_buffer_delay,
+            low_delay_mode_flag,
+        },
+    ))
 }
 
 #[allow(clippy::too_many_lines)]
@@ -588,11 +1093,13 @@ fn color_config<'a>(
                 ),
             )
         };
-    let (input, color_range, subsampling) = if monochrome {
+    let (input, color_range, subsampling, chroma_sample_position) = if monochrome {
         let (input, color_range) = trace_take_u8(input, ctx, 1, "color_range")?;
         return Ok((
             input,
             ColorConfig {
+                bit_depth,
+                color_description_present: color_description_present_flag,
                 color_primaries,
                 transfer_characteristics,
                 matrix_coefficients,
@@ -600,13 +1107,15 @@ fn color_config<'a>(
                 num_planes,
                 separate_uv_delta_q: false,
                 subsampling: (1, 1),
+                chroma_sample_position: 0,
             },
         ));
-    } else if color_primaries == ColorPrimaries::Bt709
-        && transfer_characteristics == TransferCharacteristics::Srgb
-        && matrix_coefficients == MatrixCoefficients::Identity
-    {
-        (input, ColorRange::Full, (0, 0))
+    } else if is_srgb(
+        color_primaries,
+        transfer_characteristics,
+        matrix_coefficients,
+    ) {
+        (input, ColorRange::Full, (0, 0), 0)
     } else {
         let (input, color_range) = trace_take_u8(input, ctx, 1, "color_range")?;
         let (input, ss_x, ss_y) = if seq_profile == 0 {
@@ -624,23 +1133,24 @@ fn color_config<'a>(
         } else {
             (input, 1, 0)
         };
-        let input = if ss_x > 0 && ss_y > 0 {
-            let (input, _chroma_sample_position) =
-                trace_take_u8(input, ctx, 2, "chroma_sample_position")?;
-            input
+        let (input, chroma_sample_position) = if ss_x > 0 && ss_y > 0 {
+            trace_take_u8(input, ctx, 2, "chroma_sample_position")?
         } else {
-            input
+            (input, 0)
         };
         (
             input,
             ColorRange::try_from(color_range).unwrap(),
             (ss_x, ss_y),
+            chroma_sample_position,
         )
     };
     let (input, separate_uv_delta_q) = trace_bool(input, ctx, "separate_uv_delta_q")?;
     Ok((
         input,
         ColorConfig {
+            bit_depth,
+            color_description_present: color_description_present_flag,
             color_primaries,
             transfer_characteristics,
             matrix_coefficients,
@@ -648,26 +1158,79 @@ fn color_config<'a>(
             num_planes,
             separate_uv_delta_q,
             subsampling,
+            chroma_sample_position,
         },
     ))
 }
 
+/// Works out the `color_config()` that applies `description` to `config`.
+///
+/// Returns `None` if the new values would change which fields `color_config()` codes,
+/// as going to or from sRGB does, or break the requirement that identity matrix
+/// coefficients are only used for 4:4:4.
+fn described_color_config(
+    config: &ColorConfig,
+    description: ColorDescription,
+) -> Option<ColorConfig> {
+    let mut config = *config;
+    config.color_description_present = true;
+    let monochrome = config.num_planes == 1;
+    let was_srgb = config.is_srgb();
+    if let Some(color_primaries) = description.color_primaries {
+        config.color_primaries = color_primaries;
+    }
+    if let Some(transfer_characteristics) = description.transfer_characteristics {
+        config.transfer_characteristics = transfer_characteristics;
+    }
+    if let Some(matrix_coefficients) = description.matrix_coefficients {
+        config.matrix_coefficients = matrix_coefficients;
+    }
+    let srgb = config.is_srgb();
+    if !monochrome && srgb != was_srgb {
+        error!(
+            "Cannot switch the color description to or from sRGB, which changes the coded \
+             color range and subsampling"
+        );
+        return None;
+    }
+    if config.matrix_coefficients == MatrixCoefficients::Identity && config.subsampling != (0, 0) {
+        error!("Identity matrix coefficients require 4:4:4 video");
+        return None;
+    }
+
+    if let Some(color_range) = description.color_range {
+        if monochrome || !srgb {
+            config.color_range = color_range;
+        } else if color_range != ColorRange::Full {
+            error!("sRGB is always full range");
+            return None;
+        }
+    }
+    Some(config)
+}
+
 #[must_use]
 /// Selects the active sequence operating point for downstream parsing.
 ///
-/// COMPAT: the current CLI does not expose operating-point selection, so we
-/// conservatively parse against operating point 0.
-const fn choose_operating_point() -> usize {
-    0
+/// COMPAT: a requested operating point that this sequence header does not
+/// signal falls back to operating point 0, the spec's default choice.
+const fn choose_operating_point(requested: usize, operating_points_cnt_minus_1: usize) -> usize {
+    if requested > operating_points_cnt_minus_1 {
+        0
+    } else {
+        requested
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        super::trace::TraceCtx, super::util::BitInput, BitstreamParser, ColorPrimaries, ColorRange,
-        MatrixCoefficients, SELECT_INTEGER_MV, SELECT_SCREEN_CONTENT_TOOLS,
-        TransferCharacteristics, color_config, decoder_model_info, operating_parameters_info,
-        timing_info,
+        super::trace::TraceCtx,
+        super::util::{BitInput, BitWriter},
+        BitstreamParser, ColorDescription, ColorPrimaries, ColorRange, MatrixCoefficients,
+        OperatingParametersInfo, OperatingPoint, Rational32, SELECT_INTEGER_MV,
+        SELECT_SCREEN_CONTENT_TOOLS, SequenceHeader, TimingInfo, TransferCharacteristics,
+        color_config, decoder_model_info, operating_parameters_info, timing_info,
     };
     use crate::GrainTableSegment;
 
@@ -731,6 +1294,9 @@ mod tests {
             timing_info(input, test_ctx(input)).expect("expected timing_info without uvlc payload");
 
         assert!(!parsed.equal_picture_interval);
+        assert_eq!(parsed.num_units_in_display_tick, 0x1122_3344);
+        assert_eq!(parsed.time_scale, 0x5566_7788);
+        assert!(parsed.num_ticks_per_picture.is_none());
         assert_remaining_position(remaining, &data, consumed_bits);
     }
 
@@ -749,6 +1315,8 @@ mod tests {
             timing_info(input, test_ctx(input)).expect("expected timing_info with uvlc payload");
 
         assert!(parsed.equal_picture_interval);
+        // uvlc value is (1 << 3) - 1 + 0b101 = 12.
+        assert_eq!(parsed.num_ticks_per_picture, Some(13));
         assert_remaining_position(remaining, &data, consumed_bits);
     }
 
@@ -768,6 +1336,30 @@ mod tests {
         assert!(timing_info(input, test_ctx(input)).is_err());
     }
 
+    #[test]
+    fn timing_info_frame_rate_accounts_for_ticks_per_picture() {
+        let timing_info = TimingInfo {
+            num_units_in_display_tick: 1001,
+            time_scale: 48000,
+            equal_picture_interval: true,
+            num_ticks_per_picture: Some(2),
+        };
+
+        assert_eq!(timing_info.frame_rate(), Some(Rational32::new(24000, 1001)));
+    }
+
+    #[test]
+    fn timing_info_frame_rate_is_none_for_zero_tick() {
+        let timing_info = TimingInfo {
+            num_units_in_display_tick: 0,
+            time_scale: 24,
+            equal_picture_interval: false,
+            num_ticks_per_picture: None,
+        };
+
+        assert_eq!(timing_info.frame_rate(), None);
+    }
+
     #[test]
     fn decoder_model_info_parses_all_fields() {
         let mut bits = BitBuilder::default();
@@ -803,9 +1395,13 @@ mod tests {
 
         let (data, consumed_bits) = with_trailer(bits);
         let input: BitInput = (&data, 0);
-        let (remaining, ()) = operating_parameters_info(input, test_ctx(input), 5)
+        let (remaining, info) = operating_parameters_info(input, test_ctx(input), 5)
             .expect("expected operating_parameters_info to parse");
 
+        assert_eq!(info.decoder_buffer_delay, 0b1_0101);
+        assert_eq!(info.encoder_buffer_delay, 0b0_0110);
+        assert!(info.low_delay_mode_flag);
+
         assert_remaining_position(remaining, &data, consumed_bits);
     }
 
@@ -816,9 +1412,12 @@ mod tests {
 
         let (data, consumed_bits) = with_trailer(bits);
         let input: BitInput = (&data, 0);
-        let (remaining, ()) = operating_parameters_info(input, test_ctx(input), 0)
+        let (remaining, info) = operating_parameters_info(input, test_ctx(input), 0)
             .expect("expected operating_parameters_info to parse with zero-width delays");
 
+        assert_eq!(info.decoder_buffer_delay, 0);
+        assert!(!info.low_delay_mode_flag);
+
         assert_remaining_position(remaining, &data, consumed_bits);
     }
 
@@ -1037,21 +1636,8 @@ mod tests {
         incoming_grain_header: Option<Vec<GrainTableSegment>>,
     ) -> BitstreamParser<WRITE> {
         BitstreamParser {
-            reader: None,
-            writer: None,
-            packet_out: Vec::new(),
-            incoming_grain_header,
-            parsed: false,
             size,
-            seen_frame_header: false,
-            sequence_header: None,
-            previous_frame_header: None,
-            ref_frame_idx: Default::default(),
-            ref_order_hint: Default::default(),
-            big_ref_order_hint: Default::default(),
-            big_ref_valid: Default::default(),
-            big_order_hints: Default::default(),
-            grain_headers: Vec::new(),
+            ..BitstreamParser::without_container(incoming_grain_header)
         }
     }
 
@@ -1284,6 +1870,94 @@ mod tests {
         assert_eq!(seq.decoder_model_present_for_op.as_slice(), &[true, false]);
     }
 
+    /// An L1T2 sequence header: operating point 0 decodes both temporal
+    /// layers, operating point 1 only the base layer.
+    fn two_operating_points_header() -> Vec<u8> {
+        let mut bits = BitBuilder::default();
+        bits.push_bits(0, 3); // seq_profile = 0
+        bits.push_bool(false); // still_picture
+        bits.push_bool(false); // reduced
+        bits.push_bool(false); // timing_info_present
+        bits.push_bool(false); // initial_display_delay_present
+        bits.push_bits(1, 5); // operating_points_cnt_minus_1 = 1
+        bits.push_bits(0x103, 12); // operating_point_idc[0]
+        bits.push_bits(13, 5); // seq_level_idx[0] = 13 (level 5.1)
+        bits.push_bool(true); // seq_tier[0]
+        bits.push_bits(0x101, 12); // operating_point_idc[1]
+        bits.push_bits(4, 5); // seq_level_idx[1] = 4 (level 3.0, no tier)
+        bits.push_bits(0, 4); // frame_width_bits_minus_1 = 0
+        bits.push_bits(0, 4); // frame_height_bits_minus_1 = 0
+        bits.push_bits(0, 1); // max_frame_width_minus_1
+        bits.push_bits(0, 1); // max_frame_height_minus_1
+        bits.push_bool(false); // frame_id_numbers_present
+        push_minimal_non_reduced_suffix(&mut bits, false);
+        bits.into_bytes()
+    }
+
+    #[test]
+    fn operating_points_report_idc_level_and_tier() {
+        let data = two_operating_points_header();
+        let mut parser = make_parser::<false>(0, None);
+        let (_, seq) = parser
+            .parse_sequence_header(&data, 0)
+            .expect("two operating points should parse");
+
+        let ops: Vec<_> = seq.operating_points().collect();
+        assert_eq!(
+            ops,
+            [
+                OperatingPoint {
+                    idc: 0x103,
+                    seq_level_idx: 13,
+                    seq_tier: true,
+                },
+                OperatingPoint {
+                    idc: 0x101,
+                    seq_level_idx: 4,
+                    seq_tier: false,
+                },
+            ]
+        );
+        assert_eq!(ops[0].level(), Some((5, 1)));
+        assert_eq!(ops[1].level(), Some((3, 0)));
+        assert_eq!(seq.operating_point, 0);
+        assert_eq!(seq.cur_operating_point_idc, 0x103);
+    }
+
+    #[test]
+    fn selected_operating_point_sets_current_idc() {
+        let data = two_operating_points_header();
+        let mut parser = make_parser::<false>(0, None).with_operating_point(1);
+        let (_, seq) = parser
+            .parse_sequence_header(&data, 0)
+            .expect("two operating points should parse");
+
+        assert_eq!(seq.operating_point, 1);
+        assert_eq!(seq.cur_operating_point_idc, 0x101);
+    }
+
+    #[test]
+    fn unsignalled_operating_point_falls_back_to_zero() {
+        let data = two_operating_points_header();
+        let mut parser = make_parser::<false>(0, None).with_operating_point(5);
+        let (_, seq) = parser
+            .parse_sequence_header(&data, 0)
+            .expect("two operating points should parse");
+
+        assert_eq!(seq.operating_point, 0);
+        assert_eq!(seq.cur_operating_point_idc, 0x103);
+    }
+
+    #[test]
+    fn unrestricted_level_has_no_level_number() {
+        let op = OperatingPoint {
+            idc: 0,
+            seq_level_idx: 31,
+            seq_tier: false,
+        };
+        assert_eq!(op.level(), None);
+    }
+
     #[test]
     fn frame_id_numbers_present_parses_lengths() {
         let mut bits = BitBuilder::default();
@@ -1533,4 +2207,350 @@ mod tests {
         // Film grain bit at byte 7, bit 3 should be cleared.
         assert_eq!(parser.packet_out[7] & (1 << 3), 0);
     }
+
+    fn bt709_description() -> ColorDescription {
+        ColorDescription {
+            color_primaries: Some(ColorPrimaries::Bt709),
+            transfer_characteristics: Some(TransferCharacteristics::Bt709),
+            matrix_coefficients: Some(MatrixCoefficients::Bt709),
+            color_range: Some(ColorRange::Full),
+        }
+    }
+
+    #[test]
+    fn write_mode_inserts_missing_color_description() {
+        let (data, size) = build_write_test_bitstream(true);
+        let mut parser =
+            make_parser::<true>(size, Some(Vec::new())).with_color_description(bt709_description());
+        parser
+            .parse_sequence_header(&data, 0)
+            .expect("write mode with a color description should parse");
+        let out = parser.take_packet_out();
+
+        // 24 bits of color description, and the trailing one bit.
+        assert_eq!(out.len(), size + 3);
+        // The header ends at bit 85, so the trailing one bit is followed by two zeros.
+        assert_eq!(out.last().map(|byte| byte.trailing_zeros()), Some(2));
+        let (_, seq) = make_parser::<false>(out.len(), None)
+            .parse_sequence_header(&out, 0)
+            .expect("rewritten header should parse");
+        assert!(seq.color_config.color_description_present);
+        assert_eq!(seq.color_config.color_primaries, ColorPrimaries::Bt709);
+        assert_eq!(
+            seq.color_config.transfer_characteristics,
+            TransferCharacteristics::Bt709
+        );
+        assert_eq!(
+            seq.color_config.matrix_coefficients,
+            MatrixCoefficients::Bt709
+        );
+        assert_eq!(seq.color_config.color_range, ColorRange::Full);
+        assert!(seq.film_grain_params_present);
+    }
+
+    #[test]
+    fn write_mode_replaces_color_description_in_place() {
+        let (data, size) = build_write_test_bitstream(false);
+        let mut parser =
+            make_parser::<true>(size, None).with_color_description(bt709_description());
+        parser.parse_sequence_header(&data, 0).unwrap();
+        let tagged = parser.take_packet_out();
+
+        let mut parser =
+            make_parser::<true>(tagged.len(), None).with_color_description(ColorDescription {
+                color_primaries: Some(ColorPrimaries::Bt2020),
+                ..ColorDescription::default()
+            });
+        parser.parse_sequence_header(&tagged, 0).unwrap();
+        let retagged = parser.take_packet_out();
+
+        assert_eq!(retagged.len(), tagged.len());
+        let (_, seq) = make_parser::<false>(retagged.len(), None)
+            .parse_sequence_header(&retagged, 0)
+            .expect("rewritten header should parse");
+        assert_eq!(seq.color_config.color_primaries, ColorPrimaries::Bt2020);
+        assert_eq!(
+            seq.color_config.transfer_characteristics,
+            TransferCharacteristics::Bt709
+        );
+        assert_eq!(seq.color_config.color_range, ColorRange::Full);
+    }
+
+    #[test]
+    fn write_mode_refuses_switching_to_srgb() {
+        let (data, size) = build_write_test_bitstream(false);
+        let mut parser = make_parser::<true>(size, None).with_color_description(ColorDescription {
+            color_primaries: Some(ColorPrimaries::Bt709),
+            transfer_characteristics: Some(TransferCharacteristics::Srgb),
+            matrix_coefficients: Some(MatrixCoefficients::Identity),
+            color_range: None,
+        });
+
+        assert!(parser.parse_sequence_header(&data, 0).is_err());
+    }
+
+    #[test]
+    fn timing_info_from_frame_rate_rejects_non_positive_rates() {
+        assert!(TimingInfo::from_frame_rate(Rational32::new_raw(0, 1)).is_none());
+        assert!(TimingInfo::from_frame_rate(Rational32::new_raw(-25, 1)).is_none());
+    }
+
+    #[test]
+    fn write_mode_inserts_missing_timing_info() {
+        let (data, size) = build_write_test_bitstream(true);
+        let frame_rate = Rational32::new(24000, 1001);
+        let mut parser = make_parser::<true>(size, Some(Vec::new()))
+            .with_timing_info(TimingInfo::from_frame_rate(frame_rate).unwrap());
+        parser
+            .parse_sequence_header(&data, 0)
+            .expect("write mode with timing info should parse");
+        let out = parser.take_packet_out();
+
+        // timing_info() and decoder_model_info_present_flag add 67 bits, and the header
+        // gains trailing bits.
+        assert_eq!(out.len(), size + 9);
+        let (_, seq) = make_parser::<false>(out.len(), None)
+            .parse_sequence_header(&out, 0)
+            .expect("rewritten header should parse");
+        let timing_info = seq.timing_info.expect("timing_info should be Some");
+        assert!(timing_info.equal_picture_interval);
+        assert_eq!(timing_info.frame_rate(), Some(frame_rate));
+        assert!(seq.decoder_model_info.is_none());
+        assert!(seq.film_grain_params_present);
+    }
+
+    #[test]
+    fn write_mode_keeps_equal_picture_interval_with_decoder_model() {
+        let mut bits = BitBuilder::default();
+        bits.push_bits(0, 3); // seq_profile = 0
+        bits.push_bool(false); // still_picture
+        bits.push_bool(false); // reduced
+        bits.push_bool(true); // timing_info_present_flag
+        bits.push_bits(1, 32); // num_units_in_display_tick
+        bits.push_bits(25, 32); // time_scale
+        bits.push_bool(false); // equal_picture_interval
+        bits.push_bool(true); // decoder_model_info_present_flag
+        bits.push_bits(0, 5); // buffer_delay_length_minus_1 = 0
+        bits.push_bits(1, 32); // num_units_in_decoding_tick
+        bits.push_bits(0, 5); // buffer_removal_time_length_minus_1 = 0
+        bits.push_bits(0, 5); // frame_presentation_time_length_minus_1 = 0
+        bits.push_bool(false); // initial_display_delay_present_flag
+        bits.push_bits(0, 5); // operating_points_cnt_minus_1 = 0
+        bits.push_bits(0, 12); // operating_point_idc[0]
+        bits.push_bits(4, 5); // seq_level_idx = 4
+        bits.push_bool(false); // decoder_model_present_for_op[0]
+        bits.push_bits(0, 4); // frame_width_bits_minus_1 = 0
+        bits.push_bits(0, 4); // frame_height_bits_minus_1 = 0
+        bits.push_bits(0, 1); // max_frame_width_minus_1
+        bits.push_bits(0, 1); // max_frame_height_minus_1
+        bits.push_bool(false); // frame_id_numbers_present
+        push_minimal_non_reduced_suffix(&mut bits, false);
+        let data = bits.into_bytes();
+        let frame_rate = Rational32::new(30000, 1001);
+        let mut parser = make_parser::<true>(data.len(), None).with_timing_info(TimingInfo {
+            num_units_in_display_tick: 1001,
+            time_scale: 60000,
+            equal_picture_interval: true,
+            num_ticks_per_picture: Some(2),
+        });
+        parser
+            .parse_sequence_header(&data, 0)
+            .expect("write mode with timing info should parse");
+        let out = parser.take_packet_out();
+
+        assert_eq!(out.len(), data.len());
+        let (_, seq) = make_parser::<false>(out.len(), None)
+            .parse_sequence_header(&out, 0)
+            .expect("rewritten header should parse");
+        let timing_info = seq.timing_info.expect("timing_info should be Some");
+        assert!(!timing_info.equal_picture_interval);
+        assert_eq!(timing_info.num_units_in_display_tick, 2002);
+        assert_eq!(timing_info.frame_rate(), Some(frame_rate));
+        assert!(seq.decoder_model_info.is_some());
+    }
+
+    #[test]
+    fn write_mode_refuses_timing_info_in_reduced_still_picture_header() {
+        let mut bits = BitBuilder::default();
+        bits.push_bits(0, 3); // seq_profile = 0
+        bits.push_bool(true); // still_picture
+        bits.push_bool(true); // reduced_still_picture_header
+        bits.push_bits(0, 5); // seq_level_idx[0]
+        bits.push_bits(0, 4); // frame_width_bits_minus_1 = 0
+        bits.push_bits(0, 4); // frame_height_bits_minus_1 = 0
+        bits.push_bits(0, 1); // max_frame_width_minus_1
+        bits.push_bits(0, 1); // max_frame_height_minus_1
+        bits.push_bool(false); // use_128x128_superblock
+        bits.push_bool(false); // enable_filter_intra
+        bits.push_bool(false); // enable_intra_edge_filter
+        bits.push_bool(false); // enable_superres
+        bits.push_bool(false); // enable_cdef
+        bits.push_bool(false); // enable_restoration
+        push_color_config_profile0_8bit(&mut bits);
+        bits.push_bool(false); // film_grain_params_present
+        let data = bits.into_bytes();
+        let mut parser = make_parser::<true>(data.len(), None)
+            .with_timing_info(TimingInfo::from_frame_rate(Rational32::new(25, 1)).unwrap());
+
+        assert!(parser.parse_sequence_header(&data, 0).is_err());
+    }
+
+    #[test]
+    fn write_mode_keeps_padding_after_trailing_bits() {
+        let (mut data, _) = build_write_test_bitstream(true);
+        // The header ends at bit 61, so its trailing bits fill the last byte.
+        data[7] |= 0b100;
+        data.push(0x00);
+        let mut parser = make_parser::<true>(data.len(), Some(Vec::new()))
+            .with_color_description(bt709_description());
+        parser.parse_sequence_header(&data, 0).unwrap();
+        let out = parser.take_packet_out();
+
+        assert_eq!(out.len(), data.len() + 3);
+        assert_eq!(out.last(), Some(&0x00));
+        assert_ne!(out[out.len() - 2], 0x00);
+    }
+
+    /// Parses `data` and serializes the result again with `SequenceHeader::write_bits`.
+    fn rewrite_sequence_header(data: &[u8]) -> (SequenceHeader, Vec<u8>) {
+        let (_, seq) = make_parser::<false>(data.len(), None)
+            .parse_sequence_header(data, 0)
+            .expect("sequence header should parse");
+        let mut writer = BitWriter::new();
+        seq.write_bits(&mut writer);
+        writer.trailing_bits();
+        (seq, writer.into_bytes())
+    }
+
+    fn push_trailing_bits(bits: &mut BitBuilder) {
+        bits.push_bool(true);
+        while !bits.len().is_multiple_of(8) {
+            bits.push_bool(false);
+        }
+    }
+
+    #[test]
+    fn write_bits_round_trips_decoder_model_and_display_delays() {
+        let mut bits = BitBuilder::default();
+        bits.push_bits(0, 3); // seq_profile = 0
+        bits.push_bool(false); // still_picture
+        bits.push_bool(false); // reduced
+        bits.push_bool(true); // timing_info_present_flag
+        bits.push_bits(1001, 32); // num_units_in_display_tick
+        bits.push_bits(60000, 32); // time_scale
+        bits.push_bool(true); // equal_picture_interval
+        bits.push_bits(0b011, 3); // num_ticks_per_picture_minus_1 = 2
+        bits.push_bool(true); // decoder_model_info_present_flag
+        bits.push_bits(9, 5); // buffer_delay_length_minus_1 = 9
+        bits.push_bits(90, 32); // num_units_in_decoding_tick
+        bits.push_bits(3, 5); // buffer_removal_time_length_minus_1
+        bits.push_bits(4, 5); // frame_presentation_time_length_minus_1
+        bits.push_bool(true); // initial_display_delay_present_flag
+        bits.push_bits(1, 5); // operating_points_cnt_minus_1 = 1
+        bits.push_bits(0x103, 12); // operating_point_idc[0]
+        bits.push_bits(9, 5); // seq_level_idx[0] = 9
+        bits.push_bool(true); // seq_tier[0]
+        bits.push_bool(true); // decoder_model_present_for_this_op[0]
+        bits.push_bits(700, 10); // decoder_buffer_delay
+        bits.push_bits(300, 10); // encoder_buffer_delay
+        bits.push_bool(true); // low_delay_mode_flag
+        bits.push_bool(true); // initial_display_delay_present_for_this_op[0]
+        bits.push_bits(9, 4); // initial_display_delay_minus_1[0]
+        bits.push_bits(0x101, 12); // operating_point_idc[1]
+        bits.push_bits(5, 5); // seq_level_idx[1] = 5
+        bits.push_bool(false); // decoder_model_present_for_this_op[1]
+        bits.push_bool(false); // initial_display_delay_present_for_this_op[1]
+        bits.push_bits(10, 4); // frame_width_bits_minus_1
+        bits.push_bits(10, 4); // frame_height_bits_minus_1
+        bits.push_bits(1919, 11); // max_frame_width_minus_1
+        bits.push_bits(1079, 11); // max_frame_height_minus_1
+        bits.push_bool(true); // frame_id_numbers_present_flag
+        bits.push_bits(5, 4); // delta_frame_id_length_minus_2
+        bits.push_bits(2, 3); // additional_frame_id_length_minus_1
+        bits.push_bool(true); // use_128x128_superblock
+        bits.push_bool(true); // enable_filter_intra
+        bits.push_bool(false); // enable_intra_edge_filter
+        bits.push_bool(true); // enable_interintra_compound
+        bits.push_bool(false); // enable_masked_compound
+        bits.push_bool(true); // enable_warped_motion
+        bits.push_bool(true); // enable_dual_filter
+        bits.push_bool(true); // enable_order_hint
+        bits.push_bool(false); // enable_jnt_comp
+        bits.push_bool(true); // enable_ref_frame_mvs
+        bits.push_bool(false); // seq_choose_screen_content_tools
+        bits.push_bits(1, 1); // seq_force_screen_content_tools = 1
+        bits.push_bool(false); // seq_choose_integer_mv
+        bits.push_bits(0, 1); // seq_force_integer_mv = 0
+        bits.push_bits(6, 3); // order_hint_bits_minus_1
+        bits.push_bool(true); // enable_superres
+        bits.push_bool(true); // enable_cdef
+        bits.push_bool(false); // enable_restoration
+        push_color_config_profile0_8bit(&mut bits);
+        bits.push_bool(true); // film_grain_params_present
+        push_trailing_bits(&mut bits);
+        let data = bits.into_bytes();
+
+        let (seq, out) = rewrite_sequence_header(&data);
+
+        assert_eq!(out, data);
+        assert_eq!(seq.seq_tier.as_slice(), &[true, false]);
+        assert_eq!(
+            seq.initial_display_delay_minus_1.as_slice(),
+            &[Some(9), None]
+        );
+        assert_eq!(
+            seq.operating_parameters_info[0],
+            Some(OperatingParametersInfo {
+                decoder_buffer_delay: 700,
+                encoder_buffer_delay: 300,
+                low_delay_mode_flag: true,
+            })
+        );
+        assert_eq!(
+            seq.decoder_model_info.unwrap().num_units_in_decoding_tick,
+            90
+        );
+        assert_eq!(seq.force_integer_mv, 0);
+    }
+
+    #[test]
+    fn write_bits_round_trips_profile_2_twelve_bit_color_config() {
+        let mut bits = BitBuilder::default();
+        bits.push_bits(2, 3); // seq_profile = 2
+        bits.push_bool(true); // still_picture
+        bits.push_bool(true); // reduced_still_picture_header
+        bits.push_bits(12, 5); // seq_level_idx[0]
+        bits.push_bits(3, 4); // frame_width_bits_minus_1
+        bits.push_bits(3, 4); // frame_height_bits_minus_1
+        bits.push_bits(15, 4); // max_frame_width_minus_1
+        bits.push_bits(7, 4); // max_frame_height_minus_1
+        bits.push_bool(false); // use_128x128_superblock
+        bits.push_bool(true); // enable_filter_intra
+        bits.push_bool(true); // enable_intra_edge_filter
+        bits.push_bool(false); // enable_superres
+        bits.push_bool(true); // enable_cdef
+        bits.push_bool(true); // enable_restoration
+        bits.push_bool(true); // high_bitdepth
+        bits.push_bool(true); // twelve_bit
+        bits.push_bool(false); // mono_chrome
+        bits.push_bool(true); // color_description_present_flag
+        bits.push_bits(9, 8); // color_primaries = BT.2020
+        bits.push_bits(16, 8); // transfer_characteristics = PQ
+        bits.push_bits(9, 8); // matrix_coefficients = BT.2020 NCL
+        bits.push_bool(true); // color_range
+        bits.push_bits(1, 1); // subsampling_x
+        bits.push_bits(1, 1); // subsampling_y
+        bits.push_bits(2, 2); // chroma_sample_position
+        bits.push_bool(true); // separate_uv_delta_q
+        bits.push_bool(false); // film_grain_params_present
+        push_trailing_bits(&mut bits);
+        let data = bits.into_bytes();
+
+        let (seq, out) = rewrite_sequence_header(&data);
+
+        assert_eq!(out, data);
+        assert_eq!(seq.color_config.bit_depth, 12);
+        assert_eq!(seq.color_config.subsampling, (1, 1));
+        assert_eq!(seq.color_config.chroma_sample_position, 2);
+    }
 }
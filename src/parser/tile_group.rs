@@ -32,7 +32,7 @@ impl<const WRITE: bool> BitstreamParser<WRITE> {
         &mut self,
         input: &'a [u8],
         size: usize,
-        tile_info: TileInfo,
+        tile_info: &TileInfo,
         obu_bit_offset: usize,
     ) -> IResult<&'a [u8], (), Error<&'a [u8]>> {
         // Tile group header--we only need to parse this part
@@ -78,21 +78,9 @@ mod tests {
         packet_out: Vec<u8>,
     ) -> BitstreamParser<WRITE> {
         BitstreamParser {
-            reader: None,
-            writer: None,
             packet_out,
-            incoming_grain_header: None,
-            parsed: false,
-            size: 0,
             seen_frame_header,
-            sequence_header: None,
-            previous_frame_header: None,
-            ref_frame_idx: Default::default(),
-            ref_order_hint: Default::default(),
-            big_ref_order_hint: Default::default(),
-            big_ref_valid: Default::default(),
-            big_order_hints: Default::default(),
-            grain_headers: Vec::new(),
+            ..BitstreamParser::without_container(None)
         }
     }
 
@@ -107,6 +95,7 @@ mod tests {
             tile_rows,
             tile_cols_log2,
             tile_rows_log2,
+            ..Default::default()
         }
     }
 
@@ -117,7 +106,7 @@ mod tests {
         let size = 0;
 
         let (remaining, ()) = parser
-            .parse_tile_group_obu(&input, size, tile_info(1, 1, 0, 0), 0)
+            .parse_tile_group_obu(&input, size, &tile_info(1, 1, 0, 0), 0)
             .expect("single-tile tile-group OBU should parse without reading header bits");
 
         assert_eq!(remaining, &input[size..]);
@@ -132,7 +121,7 @@ mod tests {
         let size = 1;
 
         let (remaining, ()) = parser
-            .parse_tile_group_obu(&input, size, tile_info(2, 2, 1, 1), 0)
+            .parse_tile_group_obu(&input, size, &tile_info(2, 2, 1, 1), 0)
             .expect("multi-tile tile-group with tile_start_and_end_present = false should parse");
 
         assert_eq!(remaining, &input[size..]);
@@ -149,7 +138,7 @@ mod tests {
         let size = 2;
 
         let (remaining, ()) = parser
-            .parse_tile_group_obu(&input, size, tile_info(2, 2, 1, 1), 0)
+            .parse_tile_group_obu(&input, size, &tile_info(2, 2, 1, 1), 0)
             .expect("multi-tile tile-group with explicit tg_start/tg_end should parse");
 
         assert_eq!(remaining, &input[size..]);
@@ -165,7 +154,7 @@ mod tests {
         let size = 1;
 
         let (remaining, ()) = parser
-            .parse_tile_group_obu(&input, size, tile_info(2, 2, 1, 1), 0)
+            .parse_tile_group_obu(&input, size, &tile_info(2, 2, 1, 1), 0)
             .expect("multi-tile tile-group should clear seen_frame_header when tg_end is last");
 
         assert_eq!(remaining, &input[size..]);
@@ -180,7 +169,7 @@ mod tests {
         let input = [0b0100_0000u8, 0xAA];
         let size = 1;
 
-        let result = parser.parse_tile_group_obu(&input, size, tile_info(2, 2, 1, 1), 0);
+        let result = parser.parse_tile_group_obu(&input, size, &tile_info(2, 2, 1, 1), 0);
         assert!(
             result.is_err(),
             "non-zero alignment padding bits should cause a parse error"
@@ -194,7 +183,7 @@ mod tests {
         let size = 2;
 
         let (remaining, ()) = parser
-            .parse_tile_group_obu(&input, size, tile_info(1, 1, 0, 0), 0)
+            .parse_tile_group_obu(&input, size, &tile_info(1, 1, 0, 0), 0)
             .expect("write-mode tile-group parse should succeed");
 
         assert_eq!(remaining, &input[size..]);
@@ -1,6 +1,7 @@
 use std::fmt::Debug;
 
 use arrayvec::ArrayVec;
+use bitvec::{order::Msb0, vec::BitVec, view::BitView};
 use nom::{IResult, Parser, bits::complete as bit_parsers, bytes::complete::take, error::Error};
 use num_traits::PrimInt;
 
@@ -220,6 +221,7 @@ pub fn su(input: BitInput, n: usize) -> IResult<BitInput, i64, Error<BitInput>>
 ///
 /// # Notes
 /// This helper is intended for non-zero values only.
+#[must_use]
 pub fn floor_log2<T: PrimInt>(mut x: T) -> T {
     let zero = T::from(0u8).unwrap();
     let one = T::from(1u8).unwrap();
@@ -231,14 +233,114 @@ pub fn floor_log2<T: PrimInt>(mut x: T) -> T {
     s - one
 }
 
+/// Writes AV1 syntax elements most significant bit first, mirroring the readers in
+/// this module.
+#[derive(Debug, Clone, Default)]
+pub struct BitWriter {
+    bits: BitVec<u8, Msb0>,
+}
+
+impl BitWriter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of bits written so far.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// Writes a single bit, the counterpart of [`take_bool_bit`].
+    pub fn write_bool(&mut self, bit: bool) {
+        self.bits.push(bit);
+    }
+
+    /// Writes AV1 `f(n)`: the low `n` bits of `value`.
+    ///
+    /// # Panics
+    /// Panics if `n > 64`.
+    pub fn write_bits(&mut self, value: u64, n: usize) {
+        assert!(n <= 64, "f(n) cannot write more than 64 bits");
+        self.bits.extend(&value.view_bits::<Msb0>()[64 - n..]);
+    }
+
+    /// Writes AV1 `su(n)`: `value` as an `n`-bit two's complement integer,
+    /// the counterpart of [`su`].
+    pub fn write_su(&mut self, value: i64, n: usize) {
+        self.write_bits(value as u64, n);
+    }
+
+    /// Writes AV1 `ns(n)`: `value` in the non-symmetric coding of `0..n`,
+    /// the counterpart of [`ns`].
+    ///
+    /// # Panics
+    /// In debug builds, may panic if `n == 0` due to overflow in [`floor_log2`].
+    pub fn write_ns(&mut self, value: u64, n: usize) {
+        let w = floor_log2(n) + 1;
+        let m = ((1 << w) - n) as u64;
+        if value < m {
+            self.write_bits(value, w - 1);
+        } else {
+            self.write_bits(value + m, w);
+        }
+    }
+
+    /// Writes AV1 `uvlc()`, the counterpart of [`uvlc`].
+    ///
+    /// `u32::MAX` is written as 32 leading zeros, which the reader saturates to that value.
+    pub fn write_uvlc(&mut self, value: u32) {
+        let value = u64::from(value) + 1;
+        let leading_zeros = floor_log2(value) as usize;
+        self.bits.resize(self.bits.len() + leading_zeros, false);
+        self.write_bool(true);
+        if leading_zeros < 32 {
+            self.write_bits(value, leading_zeros);
+        }
+    }
+
+    /// Writes AV1 `leb128()`, the counterpart of [`leb128`].
+    pub fn write_leb128(&mut self, value: u32) {
+        for byte in leb128_write(value) {
+            self.write_bits(u64::from(byte), 8);
+        }
+    }
+
+    /// Writes AV1 `trailing_bits()`: a `1` bit followed by zeros up to the next byte
+    /// boundary.
+    pub fn trailing_bits(&mut self) {
+        self.write_bool(true);
+        self.byte_alignment();
+    }
+
+    /// Writes AV1 `byte_alignment()`: zeros up to the next byte boundary.
+    pub fn byte_alignment(&mut self) {
+        let len = self.bits.len().next_multiple_of(8);
+        self.bits.resize(len, false);
+    }
+
+    /// Returns the written bytes, with any partial last byte padded with zeros.
+    #[must_use]
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        self.bits.set_uninitialized(false);
+        self.bits.into_vec()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use nom::Err;
     use quickcheck_macros::quickcheck;
 
     use super::{
-        byte_alignment, leb128, leb128_write, ns, su, take_bool_bit, take_zero_bit, take_zero_bits,
-        uvlc,
+        BitWriter, byte_alignment, leb128, leb128_write, ns, su, take_bool_bit, take_zero_bit,
+        take_zero_bits, uvlc,
     };
 
     #[test]
@@ -549,4 +651,68 @@ mod tests {
         let result = leb128(&encoded).unwrap();
         u64::from(val) == result.1.value && result.0.is_empty()
     }
+
+    #[test]
+    fn bit_writer_writes_msb_first_and_pads_with_zeros() {
+        let mut writer = BitWriter::new();
+        writer.write_bool(true);
+        writer.write_bits(0b0110, 4);
+        assert_eq!(writer.len(), 5);
+        writer.byte_alignment();
+        writer.write_bits(0xABC, 12);
+        writer.trailing_bits();
+        assert_eq!(writer.into_bytes(), [0b1011_0000, 0xAB, 0b1100_1000]);
+    }
+
+    #[test]
+    fn bit_writer_uvlc_writes_32_leading_zeros_for_u32_max() {
+        let mut writer = BitWriter::new();
+        writer.write_uvlc(u32::MAX);
+        assert_eq!(writer.len(), 33);
+        let data = writer.into_bytes();
+        assert_eq!(uvlc((&data, 0)).unwrap().1, u32::MAX);
+    }
+
+    #[quickcheck]
+    pub fn validate_bit_writer_uvlc(val: u32, offset: u8) -> bool {
+        let offset = usize::from(offset % 8);
+        let mut writer = BitWriter::new();
+        writer.write_bits(0, offset);
+        writer.write_uvlc(val);
+        let len = writer.len();
+        let data = writer.into_bytes();
+        let (rest, decoded) = uvlc((&data, offset)).unwrap();
+        decoded == val && (data.len() - rest.0.len()) * 8 + rest.1 == len
+    }
+
+    #[quickcheck]
+    pub fn validate_bit_writer_su(val: i64, n: u8) -> bool {
+        let n = usize::from(n % 32) + 1;
+        let val = val >> (64 - n);
+        let mut writer = BitWriter::new();
+        writer.write_su(val, n);
+        let data = writer.into_bytes();
+        su((&data, 0), n).unwrap().1 == val
+    }
+
+    #[quickcheck]
+    pub fn validate_bit_writer_ns(val: u16, n: u16) -> bool {
+        let n = usize::from(n).max(1);
+        let val = u64::from(val) % n as u64;
+        let mut writer = BitWriter::new();
+        writer.write_ns(val, n);
+        let len = writer.len();
+        let data = writer.into_bytes();
+        let (rest, decoded) = ns((&data, 0), n).unwrap();
+        decoded == val && (data.len() - rest.0.len()) * 8 + rest.1 == len
+    }
+
+    #[quickcheck]
+    pub fn validate_bit_writer_leb128(val: u32) -> bool {
+        let mut writer = BitWriter::new();
+        writer.write_leb128(val);
+        let data = writer.into_bytes();
+        let result = leb128(&data).unwrap();
+        u64::from(val) == result.1.value && result.0.is_empty()
+    }
 }
@@ -1,6 +1,11 @@
-use std::{num::NonZeroUsize, path::Path};
+use std::{
+    fmt::{self, Display},
+    num::NonZeroUsize,
+    path::Path,
+    str::FromStr,
+};
 
-use anyhow::{Result, bail};
+use anyhow::{Result, anyhow, bail};
 use av1_grain::v_frame::{
     chroma::ChromaSubsampling,
     frame::{Frame, FrameBuilder},
@@ -8,17 +13,75 @@ use av1_grain::v_frame::{
 };
 use ffmpeg::{
     Stream,
-    codec::{decoder, packet},
+    codec::{self, decoder, packet},
     format::{self, context::Input},
     frame, media,
 };
 use num_rational::Rational32;
 
+/// Which video streams of a file to work on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamSelection {
+    /// The stream FFmpeg considers the best video stream.
+    #[default]
+    Best,
+    /// The stream with this index in the container.
+    Index(usize),
+    /// Every AV1 video stream.
+    AllAv1,
+}
+
+impl FromStr for StreamSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "best" => Ok(Self::Best),
+            "all" => Ok(Self::AllAv1),
+            index => index.parse().map(Self::Index).map_err(|_| {
+                format!("expected a stream index, \"best\" or \"all\", found \"{index}\"")
+            }),
+        }
+    }
+}
+
+/// A selected stream is not AV1, so its packets cannot be parsed as OBUs.
+///
+/// Callers that process many files can downcast to this to skip them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotAv1Error {
+    /// Container index of the stream.
+    pub stream_index: usize,
+    /// FFmpeg's name for the stream's codec, e.g. `h264`.
+    pub codec: &'static str,
+    /// The AV1 video streams of the file, which could be selected instead.
+    pub av1_streams: Vec<usize>,
+}
+
+impl Display for NotAv1Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "stream {} is {}, not AV1", self.stream_index, self.codec)?;
+        if !self.av1_streams.is_empty() {
+            let streams: Vec<String> = self.av1_streams.iter().map(ToString::to_string).collect();
+            write!(
+                f,
+                " (AV1 streams: {}; pick one with --stream)",
+                streams.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NotAv1Error {}
+
+/// Demuxes the selected video streams of a file, and decodes the first of them into frames.
 pub struct BitstreamReader {
     input_ctx: Input,
     decoder: decoder::Video,
     video_details: VideoDetails,
     stream_index: usize,
+    stream_indices: Vec<usize>,
     frameno: usize,
     end_of_stream: bool,
     eof_sent: bool,
@@ -34,15 +97,28 @@ pub struct VideoDetails {
 }
 
 impl BitstreamReader {
+    /// Opens the best video stream of a file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened, has no video stream, or its
+    /// pixel format is not supported.
     pub fn open<P: AsRef<Path>>(input: P) -> Result<Self> {
+        Self::open_streams(input, StreamSelection::Best)
+    }
+
+    /// Opens the video streams of a file picked by `selection`.
+    /// Frames are decoded from the first of them.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened, `selection` matches no video
+    /// stream, or the pixel format of the first stream is not supported.
+    pub fn open_streams<P: AsRef<Path>>(input: P, selection: StreamSelection) -> Result<Self> {
         ffmpeg::init()?;
 
         let input_ctx = format::input(input.as_ref())?;
-        let stream = input_ctx
-            .streams()
-            .best(media::Type::Video)
-            .ok_or(ffmpeg::Error::StreamNotFound)?;
-        let stream_index = stream.index();
+        let stream_indices = select_streams(&input_ctx, selection)?;
+        let stream_index = stream_indices[0];
+        let stream = input_ctx.stream(stream_index).unwrap();
 
         let context = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
         let mut decoder = context.decoder().video()?;
@@ -84,36 +160,68 @@ impl BitstreamReader {
             }
         };
 
-        let mut frame_rate = stream.avg_frame_rate();
-        if frame_rate.denominator() == 0 {
-            frame_rate = stream.rate();
-        }
-
         Ok(Self {
             video_details: VideoDetails {
                 width: decoder.width() as usize,
                 height: decoder.height() as usize,
                 bit_depth,
                 chroma_sampling,
-                frame_rate: Rational32::new(frame_rate.numerator(), frame_rate.denominator()),
+                frame_rate: stream_frame_rate(&stream),
             },
             input_ctx,
             decoder,
             stream_index,
+            stream_indices,
             frameno: 0,
             end_of_stream: false,
             eof_sent: false,
         })
     }
 
+    /// The first selected video stream, which frames are decoded from.
+    ///
+    /// # Errors
+    /// Returns an error if the stream no longer exists.
     pub fn get_video_stream(&self) -> Result<Stream<'_>> {
         Ok(self
             .input_ctx
-            .streams()
-            .best(media::Type::Video)
+            .stream(self.stream_index)
             .ok_or(ffmpeg::Error::StreamNotFound)?)
     }
 
+    /// The container indices of every selected video stream, in ascending order.
+    #[must_use]
+    pub fn stream_indices(&self) -> &[usize] {
+        &self.stream_indices
+    }
+
+    /// Checks that every selected stream is AV1, before any of it is parsed.
+    ///
+    /// # Errors
+    /// Returns a [`NotAv1Error`] for the first selected stream that is not AV1.
+    pub fn ensure_av1(&self) -> Result<()> {
+        for &index in &self.stream_indices {
+            let codec_id = self.input_ctx.stream(index).unwrap().parameters().id();
+            if codec_id != codec::Id::AV1 {
+                return Err(NotAv1Error {
+                    stream_index: index,
+                    codec: codec_id.name(),
+                    av1_streams: av1_streams(&self.input_ctx),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// The frame rate of the stream at `stream_index`, if there is such a stream.
+    #[must_use]
+    pub fn frame_rate(&self, stream_index: usize) -> Option<Rational32> {
+        self.input_ctx
+            .stream(stream_index)
+            .map(|stream| stream_frame_rate(&stream))
+    }
+
     pub fn input(&mut self) -> &mut Input {
         &mut self.input_ctx
     }
@@ -169,6 +277,54 @@ impl BitstreamReader {
     }
 }
 
+fn select_streams(input_ctx: &Input, selection: StreamSelection) -> Result<Vec<usize>> {
+    let is_video = |stream: &Stream| stream.parameters().medium() == media::Type::Video;
+    match selection {
+        StreamSelection::Best => Ok(vec![
+            input_ctx
+                .streams()
+                .best(media::Type::Video)
+                .ok_or(ffmpeg::Error::StreamNotFound)?
+                .index(),
+        ]),
+        StreamSelection::Index(index) => {
+            let stream = input_ctx
+                .stream(index)
+                .ok_or_else(|| anyhow!("there is no stream {index}"))?;
+            if !is_video(&stream) {
+                bail!("stream {index} is not a video stream");
+            }
+            Ok(vec![index])
+        }
+        StreamSelection::AllAv1 => {
+            let indices = av1_streams(input_ctx);
+            if indices.is_empty() {
+                bail!("there are no AV1 video streams");
+            }
+            Ok(indices)
+        }
+    }
+}
+
+fn av1_streams(input_ctx: &Input) -> Vec<usize> {
+    input_ctx
+        .streams()
+        .filter(|stream| {
+            stream.parameters().medium() == media::Type::Video
+                && stream.parameters().id() == codec::Id::AV1
+        })
+        .map(|stream| stream.index())
+        .collect()
+}
+
+pub(crate) fn stream_frame_rate(stream: &Stream) -> Rational32 {
+    let mut frame_rate = stream.avg_frame_rate();
+    if frame_rate.denominator() == 0 {
+        frame_rate = stream.rate();
+    }
+    Rational32::new(frame_rate.numerator(), frame_rate.denominator())
+}
+
 fn decode_frame<T: Pixel>(details: &VideoDetails, decoded: &frame::Video) -> Result<Frame<T>> {
     let width = details.width;
     let height = details.height;
@@ -1,8 +1,9 @@
 #![cfg(feature = "dav1d_tests")]
 #![recursion_limit = "1024"]
 
-use std::{path::PathBuf, process::Command};
+use std::{fs, path::PathBuf, process::Command};
 
+use grav1synth::{BitstreamParser, ivf::IvfReader};
 use interpolate_name::interpolate_test;
 
 #[interpolate_test(bd8_cdfupdate_04, "8-bit/cdfupdate/av1-1-b8-04-cdfupdate.ivf")]
@@ -1545,6 +1546,787 @@ fn inspect(path: &str) {
 #[interpolate_test(12b_data_00000790, "12-bit/data/00000790.ivf")]
 #[interpolate_test(12b_data_00000791, "12-bit/data/00000791.ivf")]
 #[interpolate_test(12b_features_lossless, "12-bit/features/lossless.ivf")]
+fn round_trip(path: &str) {
+    let data_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("dav1d-test-data");
+    let file_path = data_path.join(PathBuf::from(path));
+    assert!(
+        file_path.exists(),
+        "Could not find test file; maybe you need to run `git submodule update --init`?"
+    );
+
+    // Every sequence and frame header is serialized again from its parsed fields, so
+    // keeping the grain must reproduce the input byte for byte.
+    let input = fs::read(&file_path).unwrap();
+    let mut parser = BitstreamParser::<true>::without_container(None).with_original_grain();
+    let output = parser
+        .rewrite_ivf(IvfReader::new(input.as_slice()).unwrap(), Vec::new())
+        .unwrap();
+    assert!(
+        output == input,
+        "Round trip changed the bitstream of {path}"
+    );
+}
+
+#[interpolate_test(bd8_cdfupdate_04, "8-bit/cdfupdate/av1-1-b8-04-cdfupdate.ivf")]
+#[interpolate_test(bd8_data_00000000, "8-bit/data/00000000.ivf")]
+#[interpolate_test(bd8_data_00000001, "8-bit/data/00000001.ivf")]
+#[interpolate_test(bd8_data_00000002, "8-bit/data/00000002.ivf")]
+#[interpolate_test(bd8_data_00000003, "8-bit/data/00000003.ivf")]
+#[interpolate_test(bd8_data_00000004, "8-bit/data/00000004.ivf")]
+#[interpolate_test(bd8_data_00000009, "8-bit/data/00000009.ivf")]
+#[interpolate_test(bd8_data_00000011, "8-bit/data/00000011.ivf")]
+#[interpolate_test(bd8_data_00000013, "8-bit/data/00000013.ivf")]
+#[interpolate_test(bd8_data_00000015, "8-bit/data/00000015.ivf")]
+#[interpolate_test(bd8_data_00000017, "8-bit/data/00000017.ivf")]
+#[interpolate_test(bd8_data_00000019, "8-bit/data/00000019.ivf")]
+#[interpolate_test(bd8_data_00000021, "8-bit/data/00000021.ivf")]
+#[interpolate_test(bd8_data_00000023, "8-bit/data/00000023.ivf")]
+#[interpolate_test(bd8_data_00000025, "8-bit/data/00000025.ivf")]
+#[interpolate_test(bd8_data_00000027, "8-bit/data/00000027.ivf")]
+#[interpolate_test(bd8_data_00000029, "8-bit/data/00000029.ivf")]
+#[interpolate_test(bd8_data_00000031, "8-bit/data/00000031.ivf")]
+#[interpolate_test(bd8_data_00000033, "8-bit/data/00000033.ivf")]
+#[interpolate_test(bd8_data_00000035, "8-bit/data/00000035.ivf")]
+#[interpolate_test(bd8_data_00000037, "8-bit/data/00000037.ivf")]
+#[interpolate_test(bd8_data_00000039, "8-bit/data/00000039.ivf")]
+#[interpolate_test(bd8_data_00000041, "8-bit/data/00000041.ivf")]
+#[interpolate_test(bd8_data_00000043, "8-bit/data/00000043.ivf")]
+#[interpolate_test(bd8_data_00000045, "8-bit/data/00000045.ivf")]
+#[interpolate_test(bd8_data_00000047, "8-bit/data/00000047.ivf")]
+#[interpolate_test(bd8_data_00000049, "8-bit/data/00000049.ivf")]
+#[interpolate_test(bd8_data_00000051, "8-bit/data/00000051.ivf")]
+#[interpolate_test(bd8_data_00000053, "8-bit/data/00000053.ivf")]
+#[interpolate_test(bd8_data_00000055, "8-bit/data/00000055.ivf")]
+#[interpolate_test(bd8_data_00000057, "8-bit/data/00000057.ivf")]
+#[interpolate_test(bd8_data_00000059, "8-bit/data/00000059.ivf")]
+#[interpolate_test(bd8_data_00000061, "8-bit/data/00000061.ivf")]
+#[interpolate_test(bd8_data_00000063, "8-bit/data/00000063.ivf")]
+#[interpolate_test(bd8_data_00000065, "8-bit/data/00000065.ivf")]
+#[interpolate_test(bd8_data_00000067, "8-bit/data/00000067.ivf")]
+#[interpolate_test(bd8_data_00000069, "8-bit/data/00000069.ivf")]
+#[interpolate_test(bd8_data_00000071, "8-bit/data/00000071.ivf")]
+#[interpolate_test(bd8_data_00000073, "8-bit/data/00000073.ivf")]
+#[interpolate_test(bd8_data_00000075, "8-bit/data/00000075.ivf")]
+#[interpolate_test(bd8_data_00000077, "8-bit/data/00000077.ivf")]
+#[interpolate_test(bd8_data_00000079, "8-bit/data/00000079.ivf")]
+#[interpolate_test(bd8_data_00000085, "8-bit/data/00000085.ivf")]
+#[interpolate_test(bd8_data_00000087, "8-bit/data/00000087.ivf")]
+#[interpolate_test(bd8_data_00000089, "8-bit/data/00000089.ivf")]
+#[interpolate_test(bd8_data_00000091, "8-bit/data/00000091.ivf")]
+#[interpolate_test(bd8_data_00000093, "8-bit/data/00000093.ivf")]
+#[interpolate_test(bd8_data_00000095, "8-bit/data/00000095.ivf")]
+#[interpolate_test(bd8_data_00000097, "8-bit/data/00000097.ivf")]
+#[interpolate_test(bd8_data_00000099, "8-bit/data/00000099.ivf")]
+#[interpolate_test(bd8_data_00000101, "8-bit/data/00000101.ivf")]
+#[interpolate_test(bd8_data_00000103, "8-bit/data/00000103.ivf")]
+#[interpolate_test(bd8_data_00000105, "8-bit/data/00000105.ivf")]
+#[interpolate_test(bd8_data_00000107, "8-bit/data/00000107.ivf")]
+#[interpolate_test(bd8_data_00000109, "8-bit/data/00000109.ivf")]
+#[interpolate_test(bd8_data_00000111, "8-bit/data/00000111.ivf")]
+#[interpolate_test(bd8_data_00000113, "8-bit/data/00000113.ivf")]
+#[interpolate_test(bd8_data_00000115, "8-bit/data/00000115.ivf")]
+#[interpolate_test(bd8_data_00000117, "8-bit/data/00000117.ivf")]
+#[interpolate_test(bd8_data_00000119, "8-bit/data/00000119.ivf")]
+#[interpolate_test(bd8_data_00000121, "8-bit/data/00000121.ivf")]
+#[interpolate_test(bd8_data_00000123, "8-bit/data/00000123.ivf")]
+#[interpolate_test(bd8_data_00000125, "8-bit/data/00000125.ivf")]
+#[interpolate_test(bd8_data_00000133, "8-bit/data/00000133.ivf")]
+#[interpolate_test(bd8_data_00000135, "8-bit/data/00000135.ivf")]
+#[interpolate_test(bd8_data_00000137, "8-bit/data/00000137.ivf")]
+#[interpolate_test(bd8_data_00000139, "8-bit/data/00000139.ivf")]
+#[interpolate_test(bd8_data_00000141, "8-bit/data/00000141.ivf")]
+#[interpolate_test(bd8_data_00000149, "8-bit/data/00000149.ivf")]
+#[interpolate_test(bd8_data_00000153, "8-bit/data/00000153.ivf")]
+#[interpolate_test(bd8_data_00000157, "8-bit/data/00000157.ivf")]
+#[interpolate_test(bd8_data_00000165, "8-bit/data/00000165.ivf")]
+#[interpolate_test(bd8_data_00000169, "8-bit/data/00000169.ivf")]
+#[interpolate_test(bd8_data_00000173, "8-bit/data/00000173.ivf")]
+#[interpolate_test(bd8_data_00000181, "8-bit/data/00000181.ivf")]
+#[interpolate_test(bd8_data_00000183, "8-bit/data/00000183.ivf")]
+#[interpolate_test(bd8_data_00000185, "8-bit/data/00000185.ivf")]
+#[interpolate_test(bd8_data_00000187, "8-bit/data/00000187.ivf")]
+#[interpolate_test(bd8_data_00000189, "8-bit/data/00000189.ivf")]
+#[interpolate_test(bd8_data_00000197, "8-bit/data/00000197.ivf")]
+#[interpolate_test(bd8_data_00000199, "8-bit/data/00000199.ivf")]
+#[interpolate_test(bd8_data_00000201, "8-bit/data/00000201.ivf")]
+#[interpolate_test(bd8_data_00000203, "8-bit/data/00000203.ivf")]
+#[interpolate_test(bd8_data_00000205, "8-bit/data/00000205.ivf")]
+#[interpolate_test(bd8_data_00000213, "8-bit/data/00000213.ivf")]
+#[interpolate_test(bd8_data_00000217, "8-bit/data/00000217.ivf")]
+#[interpolate_test(bd8_data_00000221, "8-bit/data/00000221.ivf")]
+#[interpolate_test(bd8_data_00000229, "8-bit/data/00000229.ivf")]
+#[interpolate_test(bd8_data_00000233, "8-bit/data/00000233.ivf")]
+#[interpolate_test(bd8_data_00000237, "8-bit/data/00000237.ivf")]
+#[interpolate_test(bd8_data_00000245, "8-bit/data/00000245.ivf")]
+#[interpolate_test(bd8_data_00000247, "8-bit/data/00000247.ivf")]
+#[interpolate_test(bd8_data_00000249, "8-bit/data/00000249.ivf")]
+#[interpolate_test(bd8_data_00000251, "8-bit/data/00000251.ivf")]
+#[interpolate_test(bd8_data_00000253, "8-bit/data/00000253.ivf")]
+#[interpolate_test(bd8_data_00000261, "8-bit/data/00000261.ivf")]
+#[interpolate_test(bd8_data_00000263, "8-bit/data/00000263.ivf")]
+#[interpolate_test(bd8_data_00000265, "8-bit/data/00000265.ivf")]
+#[interpolate_test(bd8_data_00000267, "8-bit/data/00000267.ivf")]
+#[interpolate_test(bd8_data_00000269, "8-bit/data/00000269.ivf")]
+#[interpolate_test(bd8_data_00000277, "8-bit/data/00000277.ivf")]
+#[interpolate_test(bd8_data_00000281, "8-bit/data/00000281.ivf")]
+#[interpolate_test(bd8_data_00000285, "8-bit/data/00000285.ivf")]
+#[interpolate_test(bd8_data_00000293, "8-bit/data/00000293.ivf")]
+#[interpolate_test(bd8_data_00000297, "8-bit/data/00000297.ivf")]
+#[interpolate_test(bd8_data_00000301, "8-bit/data/00000301.ivf")]
+#[interpolate_test(bd8_data_00000309, "8-bit/data/00000309.ivf")]
+#[interpolate_test(bd8_data_00000311, "8-bit/data/00000311.ivf")]
+#[interpolate_test(bd8_data_00000313, "8-bit/data/00000313.ivf")]
+#[interpolate_test(bd8_data_00000315, "8-bit/data/00000315.ivf")]
+#[interpolate_test(bd8_data_00000317, "8-bit/data/00000317.ivf")]
+#[interpolate_test(bd8_data_00000325, "8-bit/data/00000325.ivf")]
+#[interpolate_test(bd8_data_00000327, "8-bit/data/00000327.ivf")]
+#[interpolate_test(bd8_data_00000329, "8-bit/data/00000329.ivf")]
+#[interpolate_test(bd8_data_00000331, "8-bit/data/00000331.ivf")]
+#[interpolate_test(bd8_data_00000333, "8-bit/data/00000333.ivf")]
+#[interpolate_test(bd8_data_00000341, "8-bit/data/00000341.ivf")]
+#[interpolate_test(bd8_data_00000345, "8-bit/data/00000345.ivf")]
+#[interpolate_test(bd8_data_00000349, "8-bit/data/00000349.ivf")]
+#[interpolate_test(bd8_data_00000357, "8-bit/data/00000357.ivf")]
+#[interpolate_test(bd8_data_00000361, "8-bit/data/00000361.ivf")]
+#[interpolate_test(bd8_data_00000365, "8-bit/data/00000365.ivf")]
+#[interpolate_test(bd8_data_00000501, "8-bit/data/00000501.ivf")]
+#[interpolate_test(bd8_data_00000502, "8-bit/data/00000502.ivf")]
+#[interpolate_test(bd8_data_00000504, "8-bit/data/00000504.ivf")]
+#[interpolate_test(bd8_data_00000505, "8-bit/data/00000505.ivf")]
+#[interpolate_test(bd8_data_00000507, "8-bit/data/00000507.ivf")]
+#[interpolate_test(bd8_data_00000508, "8-bit/data/00000508.ivf")]
+#[interpolate_test(bd8_data_00000511, "8-bit/data/00000511.ivf")]
+#[interpolate_test(bd8_data_00000512, "8-bit/data/00000512.ivf")]
+#[interpolate_test(bd8_data_00000523, "8-bit/data/00000523.ivf")]
+#[interpolate_test(bd8_data_00000527, "8-bit/data/00000527.ivf")]
+#[interpolate_test(bd8_data_00000528, "8-bit/data/00000528.ivf")]
+#[interpolate_test(bd8_data_00000529, "8-bit/data/00000529.ivf")]
+#[interpolate_test(bd8_data_00000530, "8-bit/data/00000530.ivf")]
+#[interpolate_test(bd8_data_00000531, "8-bit/data/00000531.ivf")]
+#[interpolate_test(bd8_data_00000532, "8-bit/data/00000532.ivf")]
+#[interpolate_test(bd8_data_00000533, "8-bit/data/00000533.ivf")]
+#[interpolate_test(bd8_data_00000534, "8-bit/data/00000534.ivf")]
+#[interpolate_test(bd8_data_00000535, "8-bit/data/00000535.ivf")]
+#[interpolate_test(bd8_data_00000536, "8-bit/data/00000536.ivf")]
+#[interpolate_test(bd8_data_00000537, "8-bit/data/00000537.ivf")]
+#[interpolate_test(bd8_data_00000539, "8-bit/data/00000539.ivf")]
+#[interpolate_test(bd8_data_00000540, "8-bit/data/00000540.ivf")]
+#[interpolate_test(bd8_data_00000542, "8-bit/data/00000542.ivf")]
+#[interpolate_test(bd8_data_00000543, "8-bit/data/00000543.ivf")]
+#[interpolate_test(bd8_data_00000544, "8-bit/data/00000544.ivf")]
+#[interpolate_test(bd8_data_00000545, "8-bit/data/00000545.ivf")]
+#[interpolate_test(bd8_data_00000546, "8-bit/data/00000546.ivf")]
+#[interpolate_test(bd8_data_00000547, "8-bit/data/00000547.ivf")]
+#[interpolate_test(bd8_data_00000548, "8-bit/data/00000548.ivf")]
+#[interpolate_test(bd8_data_00000549, "8-bit/data/00000549.ivf")]
+#[interpolate_test(bd8_data_00000550, "8-bit/data/00000550.ivf")]
+#[interpolate_test(bd8_data_00000551, "8-bit/data/00000551.ivf")]
+#[interpolate_test(bd8_data_00000556, "8-bit/data/00000556.ivf")]
+#[interpolate_test(bd8_data_00000557, "8-bit/data/00000557.ivf")]
+#[interpolate_test(bd8_data_00000558, "8-bit/data/00000558.ivf")]
+#[interpolate_test(bd8_data_00000559, "8-bit/data/00000559.ivf")]
+#[interpolate_test(bd8_data_00000564, "8-bit/data/00000564.ivf")]
+#[interpolate_test(bd8_data_00000565, "8-bit/data/00000565.ivf")]
+#[interpolate_test(bd8_data_00000566, "8-bit/data/00000566.ivf")]
+#[interpolate_test(bd8_data_00000567, "8-bit/data/00000567.ivf")]
+#[interpolate_test(bd8_data_00000572, "8-bit/data/00000572.ivf")]
+#[interpolate_test(bd8_data_00000573, "8-bit/data/00000573.ivf")]
+#[interpolate_test(bd8_data_00000574, "8-bit/data/00000574.ivf")]
+#[interpolate_test(bd8_data_00000575, "8-bit/data/00000575.ivf")]
+#[interpolate_test(bd8_data_00000576, "8-bit/data/00000576.ivf")]
+#[interpolate_test(bd8_data_00000577, "8-bit/data/00000577.ivf")]
+#[interpolate_test(bd8_data_00000578, "8-bit/data/00000578.ivf")]
+#[interpolate_test(bd8_data_00000579, "8-bit/data/00000579.ivf")]
+#[interpolate_test(bd8_data_00000580, "8-bit/data/00000580.ivf")]
+#[interpolate_test(bd8_data_00000581, "8-bit/data/00000581.ivf")]
+#[interpolate_test(bd8_data_00000584, "8-bit/data/00000584.ivf")]
+#[interpolate_test(bd8_data_00000585, "8-bit/data/00000585.ivf")]
+#[interpolate_test(bd8_data_00000588, "8-bit/data/00000588.ivf")]
+#[interpolate_test(bd8_data_00000589, "8-bit/data/00000589.ivf")]
+#[interpolate_test(bd8_data_00000592, "8-bit/data/00000592.ivf")]
+#[interpolate_test(bd8_data_00000593, "8-bit/data/00000593.ivf")]
+#[interpolate_test(bd8_data_00000594, "8-bit/data/00000594.ivf")]
+#[interpolate_test(bd8_data_00000595, "8-bit/data/00000595.ivf")]
+#[interpolate_test(bd8_data_00000596, "8-bit/data/00000596.ivf")]
+#[interpolate_test(bd8_data_00000597, "8-bit/data/00000597.ivf")]
+#[interpolate_test(bd8_data_00000598, "8-bit/data/00000598.ivf")]
+#[interpolate_test(bd8_data_00000599, "8-bit/data/00000599.ivf")]
+#[interpolate_test(bd8_data_00000600, "8-bit/data/00000600.ivf")]
+#[interpolate_test(bd8_data_00000601, "8-bit/data/00000601.ivf")]
+#[interpolate_test(bd8_data_00000602, "8-bit/data/00000602.ivf")]
+#[interpolate_test(bd8_data_00000603, "8-bit/data/00000603.ivf")]
+#[interpolate_test(bd8_data_00000604, "8-bit/data/00000604.ivf")]
+#[interpolate_test(bd8_data_00000605, "8-bit/data/00000605.ivf")]
+#[interpolate_test(bd8_data_00000608, "8-bit/data/00000608.ivf")]
+#[interpolate_test(bd8_data_00000609, "8-bit/data/00000609.ivf")]
+#[interpolate_test(bd8_data_00000611, "8-bit/data/00000611.ivf")]
+#[interpolate_test(bd8_data_00000612, "8-bit/data/00000612.ivf")]
+#[interpolate_test(bd8_data_00000613, "8-bit/data/00000613.ivf")]
+#[interpolate_test(bd8_data_00000614, "8-bit/data/00000614.ivf")]
+#[interpolate_test(bd8_data_00000615, "8-bit/data/00000615.ivf")]
+#[interpolate_test(bd8_data_00000616, "8-bit/data/00000616.ivf")]
+#[interpolate_test(bd8_data_00000617, "8-bit/data/00000617.ivf")]
+#[interpolate_test(bd8_data_00000618, "8-bit/data/00000618.ivf")]
+#[interpolate_test(bd8_data_00000619, "8-bit/data/00000619.ivf")]
+#[interpolate_test(bd8_data_00000621, "8-bit/data/00000621.ivf")]
+#[interpolate_test(bd8_data_00000622, "8-bit/data/00000622.ivf")]
+#[interpolate_test(bd8_data_00000623, "8-bit/data/00000623.ivf")]
+#[interpolate_test(bd8_data_00000624, "8-bit/data/00000624.ivf")]
+#[interpolate_test(bd8_data_00000625, "8-bit/data/00000625.ivf")]
+#[interpolate_test(bd8_data_00000626, "8-bit/data/00000626.ivf")]
+#[interpolate_test(bd8_data_00000627, "8-bit/data/00000627.ivf")]
+#[interpolate_test(bd8_data_00000632, "8-bit/data/00000632.ivf")]
+#[interpolate_test(bd8_data_00000633, "8-bit/data/00000633.ivf")]
+#[interpolate_test(bd8_data_00000634, "8-bit/data/00000634.ivf")]
+#[interpolate_test(bd8_data_00000635, "8-bit/data/00000635.ivf")]
+#[interpolate_test(bd8_data_00000640, "8-bit/data/00000640.ivf")]
+#[interpolate_test(bd8_data_00000641, "8-bit/data/00000641.ivf")]
+#[interpolate_test(bd8_data_00000642, "8-bit/data/00000642.ivf")]
+#[interpolate_test(bd8_data_00000643, "8-bit/data/00000643.ivf")]
+#[interpolate_test(bd8_data_00000644, "8-bit/data/00000644.ivf")]
+#[interpolate_test(bd8_data_00000645, "8-bit/data/00000645.ivf")]
+#[interpolate_test(bd8_data_00000646, "8-bit/data/00000646.ivf")]
+#[interpolate_test(bd8_data_00000647, "8-bit/data/00000647.ivf")]
+#[interpolate_test(bd8_data_00000648, "8-bit/data/00000648.ivf")]
+#[interpolate_test(bd8_data_00000649, "8-bit/data/00000649.ivf")]
+#[interpolate_test(bd8_data_00000650, "8-bit/data/00000650.ivf")]
+#[interpolate_test(bd8_data_00000651, "8-bit/data/00000651.ivf")]
+#[interpolate_test(bd8_data_00000656, "8-bit/data/00000656.ivf")]
+#[interpolate_test(bd8_data_00000657, "8-bit/data/00000657.ivf")]
+#[interpolate_test(bd8_data_00000658, "8-bit/data/00000658.ivf")]
+#[interpolate_test(bd8_data_00000659, "8-bit/data/00000659.ivf")]
+#[interpolate_test(bd8_data_00000660, "8-bit/data/00000660.ivf")]
+#[interpolate_test(bd8_data_00000661, "8-bit/data/00000661.ivf")]
+#[interpolate_test(bd8_data_00000662, "8-bit/data/00000662.ivf")]
+#[interpolate_test(bd8_data_00000663, "8-bit/data/00000663.ivf")]
+#[interpolate_test(bd8_data_00000664, "8-bit/data/00000664.ivf")]
+#[interpolate_test(bd8_data_00000665, "8-bit/data/00000665.ivf")]
+#[interpolate_test(bd8_data_00000666, "8-bit/data/00000666.ivf")]
+#[interpolate_test(bd8_data_00000667, "8-bit/data/00000667.ivf")]
+#[interpolate_test(bd8_data_00000668, "8-bit/data/00000668.ivf")]
+#[interpolate_test(bd8_data_00000669, "8-bit/data/00000669.ivf")]
+#[interpolate_test(bd8_data_00000670, "8-bit/data/00000670.ivf")]
+#[interpolate_test(bd8_data_00000701, "8-bit/data/00000701.ivf")]
+#[interpolate_test(bd8_data_00000702, "8-bit/data/00000702.ivf")]
+#[interpolate_test(bd8_data_00000703, "8-bit/data/00000703.ivf")]
+#[interpolate_test(bd8_data_00000704, "8-bit/data/00000704.ivf")]
+#[interpolate_test(bd8_data_00000705, "8-bit/data/00000705.ivf")]
+#[interpolate_test(bd8_data_00000706, "8-bit/data/00000706.ivf")]
+#[interpolate_test(bd8_data_00000707, "8-bit/data/00000707.ivf")]
+#[interpolate_test(bd8_data_00000708, "8-bit/data/00000708.ivf")]
+#[interpolate_test(bd8_data_00000709, "8-bit/data/00000709.ivf")]
+#[interpolate_test(bd8_data_00000710, "8-bit/data/00000710.ivf")]
+#[interpolate_test(bd8_data_00000711, "8-bit/data/00000711.ivf")]
+#[interpolate_test(bd8_data_00000712, "8-bit/data/00000712.ivf")]
+#[interpolate_test(bd8_data_00000713, "8-bit/data/00000713.ivf")]
+#[interpolate_test(bd8_data_00000714, "8-bit/data/00000714.ivf")]
+#[interpolate_test(bd8_data_00000715, "8-bit/data/00000715.ivf")]
+#[interpolate_test(bd8_data_00000746, "8-bit/data/00000746.ivf")]
+#[interpolate_test(bd8_data_00000747, "8-bit/data/00000747.ivf")]
+#[interpolate_test(bd8_data_00000748, "8-bit/data/00000748.ivf")]
+#[interpolate_test(bd8_data_00000749, "8-bit/data/00000749.ivf")]
+#[interpolate_test(bd8_data_00000750, "8-bit/data/00000750.ivf")]
+#[interpolate_test(bd8_data_00000751, "8-bit/data/00000751.ivf")]
+#[interpolate_test(bd8_data_00000752, "8-bit/data/00000752.ivf")]
+#[interpolate_test(bd8_data_00000753, "8-bit/data/00000753.ivf")]
+#[interpolate_test(bd8_data_00000754, "8-bit/data/00000754.ivf")]
+#[interpolate_test(bd8_data_00000755, "8-bit/data/00000755.ivf")]
+#[interpolate_test(bd8_data_00000756, "8-bit/data/00000756.ivf")]
+#[interpolate_test(bd8_data_00000757, "8-bit/data/00000757.ivf")]
+#[interpolate_test(bd8_data_00000758, "8-bit/data/00000758.ivf")]
+#[interpolate_test(bd8_data_00000759, "8-bit/data/00000759.ivf")]
+#[interpolate_test(bd8_data_00000760, "8-bit/data/00000760.ivf")]
+#[interpolate_test(bd8_data_00000792, "8-bit/data/00000792.ivf")]
+#[interpolate_test(bd8_data_00000793, "8-bit/data/00000793.ivf")]
+#[interpolate_test(bd8_data_00000794, "8-bit/data/00000794.ivf")]
+#[interpolate_test(bd8_data_00000795, "8-bit/data/00000795.ivf")]
+#[interpolate_test(bd8_data_00000796, "8-bit/data/00000796.ivf")]
+#[interpolate_test(bd8_data_00000797, "8-bit/data/00000797.ivf")]
+#[interpolate_test(bd8_data_00000798, "8-bit/data/00000798.ivf")]
+#[interpolate_test(bd8_data_00000799, "8-bit/data/00000799.ivf")]
+#[interpolate_test(bd8_data_00000800, "8-bit/data/00000800.ivf")]
+#[interpolate_test(bd8_data_00000801, "8-bit/data/00000801.ivf")]
+#[interpolate_test(bd8_data_00000802, "8-bit/data/00000802.ivf")]
+#[interpolate_test(bd8_data_00000803, "8-bit/data/00000803.ivf")]
+#[interpolate_test(bd8_data_00000804, "8-bit/data/00000804.ivf")]
+#[interpolate_test(bd8_data_00000805, "8-bit/data/00000805.ivf")]
+#[interpolate_test(bd8_data_00000806, "8-bit/data/00000806.ivf")]
+#[interpolate_test(bd8_data_00000807, "8-bit/data/00000807.ivf")]
+#[interpolate_test(bd8_data_00000808, "8-bit/data/00000808.ivf")]
+#[interpolate_test(bd8_data_00000809, "8-bit/data/00000809.ivf")]
+#[interpolate_test(bd8_data_00000810, "8-bit/data/00000810.ivf")]
+#[interpolate_test(bd8_data_00000811, "8-bit/data/00000811.ivf")]
+#[interpolate_test(bd8_data_00000812, "8-bit/data/00000812.ivf")]
+#[interpolate_test(bd8_data_00000813, "8-bit/data/00000813.ivf")]
+#[interpolate_test(bd8_data_00000814, "8-bit/data/00000814.ivf")]
+#[interpolate_test(bd8_data_00000815, "8-bit/data/00000815.ivf")]
+#[interpolate_test(bd8_data_00000816, "8-bit/data/00000816.ivf")]
+#[interpolate_test(bd8_data_00000817, "8-bit/data/00000817.ivf")]
+#[interpolate_test(bd8_data_00000818, "8-bit/data/00000818.ivf")]
+#[interpolate_test(bd8_data_00000819, "8-bit/data/00000819.ivf")]
+#[interpolate_test(bd8_data_00000838, "8-bit/data/00000838.ivf")]
+#[interpolate_test(bd8_data_00000839, "8-bit/data/00000839.ivf")]
+#[interpolate_test(bd8_data_00000840, "8-bit/data/00000840.ivf")]
+#[interpolate_test(bd8_data_00000841, "8-bit/data/00000841.ivf")]
+#[interpolate_test(bd8_data_00000842, "8-bit/data/00000842.ivf")]
+#[interpolate_test(bd8_data_00000843, "8-bit/data/00000843.ivf")]
+#[interpolate_test(bd8_data_00000844, "8-bit/data/00000844.ivf")]
+#[interpolate_test(bd8_data_00000845, "8-bit/data/00000845.ivf")]
+#[interpolate_test(bd8_data_00000846, "8-bit/data/00000846.ivf")]
+#[interpolate_test(bd8_data_00000847, "8-bit/data/00000847.ivf")]
+#[interpolate_test(bd8_data_00000848, "8-bit/data/00000848.ivf")]
+#[interpolate_test(bd8_data_00000849, "8-bit/data/00000849.ivf")]
+#[interpolate_test(bd8_data_00000850, "8-bit/data/00000850.ivf")]
+#[interpolate_test(bd8_data_00000851, "8-bit/data/00000851.ivf")]
+#[interpolate_test(bd8_data_00000852, "8-bit/data/00000852.ivf")]
+#[interpolate_test(bd8_data_00000853, "8-bit/data/00000853.ivf")]
+#[interpolate_test(bd8_data_00000854, "8-bit/data/00000854.ivf")]
+#[interpolate_test(bd8_data_00000855, "8-bit/data/00000855.ivf")]
+#[interpolate_test(bd8_data_00000856, "8-bit/data/00000856.ivf")]
+#[interpolate_test(bd8_data_00000857, "8-bit/data/00000857.ivf")]
+#[interpolate_test(bd8_data_00000858, "8-bit/data/00000858.ivf")]
+#[interpolate_test(bd8_data_00000859, "8-bit/data/00000859.ivf")]
+#[interpolate_test(bd8_data_00000860, "8-bit/data/00000860.ivf")]
+#[interpolate_test(bd8_data_00000861, "8-bit/data/00000861.ivf")]
+#[interpolate_test(bd8_data_00000862, "8-bit/data/00000862.ivf")]
+#[interpolate_test(bd8_data_00000863, "8-bit/data/00000863.ivf")]
+#[interpolate_test(bd8_data_00001018, "8-bit/data/00001018.ivf")]
+#[interpolate_test(bd8_data_00001019, "8-bit/data/00001019.ivf")]
+#[interpolate_test(bd8_data_00001099, "8-bit/data/00001099.ivf")]
+#[interpolate_test(bd8_data_00001100, "8-bit/data/00001100.ivf")]
+#[interpolate_test(bd8_data_00001101, "8-bit/data/00001101.ivf")]
+#[interpolate_test(bd8_data_00001102, "8-bit/data/00001102.ivf")]
+#[interpolate_test(bd8_data_00001105, "8-bit/data/00001105.ivf")]
+#[interpolate_test(bd8_data_00001106, "8-bit/data/00001106.ivf")]
+#[interpolate_test(bd8_data_00001107, "8-bit/data/00001107.ivf")]
+#[interpolate_test(bd8_data_00001108, "8-bit/data/00001108.ivf")]
+#[interpolate_test(bd8_data_00001109, "8-bit/data/00001109.ivf")]
+#[interpolate_test(bd8_data_00001114, "8-bit/data/00001114.ivf")]
+#[interpolate_test(bd8_data_00001115, "8-bit/data/00001115.ivf")]
+#[interpolate_test(bd8_data_00001116, "8-bit/data/00001116.ivf")]
+#[interpolate_test(bd8_data_00001117, "8-bit/data/00001117.ivf")]
+#[interpolate_test(bd8_data_00001122, "8-bit/data/00001122.ivf")]
+#[interpolate_test(bd8_data_00001123, "8-bit/data/00001123.ivf")]
+#[interpolate_test(bd8_data_00001124, "8-bit/data/00001124.ivf")]
+#[interpolate_test(bd8_data_00001125, "8-bit/data/00001125.ivf")]
+#[interpolate_test(bd8_data_00001130, "8-bit/data/00001130.ivf")]
+#[interpolate_test(bd8_data_00001131, "8-bit/data/00001131.ivf")]
+#[interpolate_test(bd8_data_00001132, "8-bit/data/00001132.ivf")]
+#[interpolate_test(bd8_data_00001133, "8-bit/data/00001133.ivf")]
+#[interpolate_test(bd8_data_00001135, "8-bit/data/00001135.ivf")]
+#[interpolate_test(bd8_data_00001137, "8-bit/data/00001137.ivf")]
+#[interpolate_test(bd8_data_00001138, "8-bit/data/00001138.ivf")]
+#[interpolate_test(bd8_data_00001139, "8-bit/data/00001139.ivf")]
+#[interpolate_test(bd8_data_00001140, "8-bit/data/00001140.ivf")]
+#[interpolate_test(bd8_data_00001141, "8-bit/data/00001141.ivf")]
+#[interpolate_test(bd8_data_00001142, "8-bit/data/00001142.ivf")]
+#[interpolate_test(bd8_data_00001143, "8-bit/data/00001143.ivf")]
+#[interpolate_test(bd8_data_00001144, "8-bit/data/00001144.ivf")]
+#[interpolate_test(bd8_data_00001145, "8-bit/data/00001145.ivf")]
+#[interpolate_test(bd8_data_00001146, "8-bit/data/00001146.ivf")]
+#[interpolate_test(bd8_data_00001147, "8-bit/data/00001147.ivf")]
+#[interpolate_test(bd8_data_00001148, "8-bit/data/00001148.ivf")]
+#[interpolate_test(bd8_features_ccvb_film_grain, "8-bit/features/ccvb_film_grain.ivf")]
+#[interpolate_test(
+    bd8_features_frames_refs_short_signaling,
+    "8-bit/features/frames_refs_short_signaling.ivf"
+)]
+#[interpolate_test(bd8_features_itut_t35, "8-bit/features/itut_t35.ivf")]
+#[interpolate_test(bd8_features_long_leb, "8-bit/features/long_leb.ivf")]
+#[interpolate_test(
+    bd8_features_non_uniform_tiling,
+    "8-bit/features/non_uniform_tiling.ivf"
+)]
+#[interpolate_test(
+    bd8_features_redundant_frame_header,
+    "8-bit/features/redundant_frame_header.ivf"
+)]
+#[interpolate_test(bd8_features_rgb, "8-bit/features/rgb.ivf")]
+#[interpolate_test(
+    bd8_features_seq_hdr_op_param_info,
+    "8-bit/features/seq_hdr_op_param_info.ivf"
+)]
+#[interpolate_test(bd8_film_grain_23, "8-bit/film_grain/av1-1-b8-23-film_grain-50.ivf")]
+#[interpolate_test(bd8_intra_02, "8-bit/intra/av1-1-b8-02-allintra.ivf")]
+#[interpolate_test(bd8_issues_1023_synth, "8-bit/issues/1023_synth.ivf")]
+#[interpolate_test(bd8_issues_182_x, "8-bit/issues/182_x.ivf")]
+#[interpolate_test(bd8_issues_295_adst_precision, "8-bit/issues/295_adst_precision.ivf")]
+#[interpolate_test(bd8_issues_309_odd_width, "8-bit/issues/309_odd_width.ivf")]
+#[interpolate_test(
+    bd8_issues_319_order_hint_bits_1,
+    "8-bit/issues/319_order_hint_bits_1.ivf"
+)]
+#[interpolate_test(bd8_issues_320_tennis, "8-bit/issues/320_tennis.ivf")]
+#[interpolate_test(bd8_issues_321_tennis, "8-bit/issues/321_tennis.ivf")]
+#[interpolate_test(bd8_issues_322_tennis, "8-bit/issues/322_tennis.ivf")]
+#[interpolate_test(bd8_issues_323_tennis, "8-bit/issues/323_tennis.ivf")]
+#[interpolate_test(bd8_issues_324_tennis, "8-bit/issues/324_tennis.ivf")]
+#[interpolate_test(bd8_issues_325_tennis, "8-bit/issues/325_tennis.ivf")]
+#[interpolate_test(
+    bd8_issues_326_order_hint_bits_1,
+    "8-bit/issues/326_order_hint_bits_1.ivf"
+)]
+#[interpolate_test(bd8_issues_327_tennis_1, "8-bit/issues/327_tennis_1.ivf")]
+#[interpolate_test(bd8_issues_327_tennis_2, "8-bit/issues/327_tennis_2.ivf")]
+#[interpolate_test(bd8_issues_48_delayed, "8-bit/issues/48_delayed.ivf")]
+#[interpolate_test(bd8_issues_86_decode_model, "8-bit/issues/86_decode_model.ivf")]
+#[interpolate_test(bd8_mfmv_06, "8-bit/mfmv/av1-1-b8-06-mfmv.ivf")]
+#[interpolate_test(bd8_mv_05, "8-bit/mv/av1-1-b8-05-mv.ivf")]
+#[interpolate_test(bd8_quantizer_00, "8-bit/quantizer/av1-1-b8-00-quantizer-00.ivf")]
+#[interpolate_test(bd8_quantizer_01, "8-bit/quantizer/av1-1-b8-00-quantizer-01.ivf")]
+#[interpolate_test(bd8_quantizer_02, "8-bit/quantizer/av1-1-b8-00-quantizer-02.ivf")]
+#[interpolate_test(bd8_quantizer_03, "8-bit/quantizer/av1-1-b8-00-quantizer-03.ivf")]
+#[interpolate_test(bd8_quantizer_04, "8-bit/quantizer/av1-1-b8-00-quantizer-04.ivf")]
+#[interpolate_test(bd8_quantizer_05, "8-bit/quantizer/av1-1-b8-00-quantizer-05.ivf")]
+#[interpolate_test(bd8_quantizer_06, "8-bit/quantizer/av1-1-b8-00-quantizer-06.ivf")]
+#[interpolate_test(bd8_quantizer_07, "8-bit/quantizer/av1-1-b8-00-quantizer-07.ivf")]
+#[interpolate_test(bd8_quantizer_08, "8-bit/quantizer/av1-1-b8-00-quantizer-08.ivf")]
+#[interpolate_test(bd8_quantizer_09, "8-bit/quantizer/av1-1-b8-00-quantizer-09.ivf")]
+#[interpolate_test(bd8_quantizer_10, "8-bit/quantizer/av1-1-b8-00-quantizer-10.ivf")]
+#[interpolate_test(bd8_quantizer_11, "8-bit/quantizer/av1-1-b8-00-quantizer-11.ivf")]
+#[interpolate_test(bd8_quantizer_12, "8-bit/quantizer/av1-1-b8-00-quantizer-12.ivf")]
+#[interpolate_test(bd8_quantizer_13, "8-bit/quantizer/av1-1-b8-00-quantizer-13.ivf")]
+#[interpolate_test(bd8_quantizer_14, "8-bit/quantizer/av1-1-b8-00-quantizer-14.ivf")]
+#[interpolate_test(bd8_quantizer_15, "8-bit/quantizer/av1-1-b8-00-quantizer-15.ivf")]
+#[interpolate_test(bd8_quantizer_16, "8-bit/quantizer/av1-1-b8-00-quantizer-16.ivf")]
+#[interpolate_test(bd8_quantizer_17, "8-bit/quantizer/av1-1-b8-00-quantizer-17.ivf")]
+#[interpolate_test(bd8_quantizer_18, "8-bit/quantizer/av1-1-b8-00-quantizer-18.ivf")]
+#[interpolate_test(bd8_quantizer_19, "8-bit/quantizer/av1-1-b8-00-quantizer-19.ivf")]
+#[interpolate_test(bd8_quantizer_20, "8-bit/quantizer/av1-1-b8-00-quantizer-20.ivf")]
+#[interpolate_test(bd8_quantizer_21, "8-bit/quantizer/av1-1-b8-00-quantizer-21.ivf")]
+#[interpolate_test(bd8_quantizer_22, "8-bit/quantizer/av1-1-b8-00-quantizer-22.ivf")]
+#[interpolate_test(bd8_quantizer_23, "8-bit/quantizer/av1-1-b8-00-quantizer-23.ivf")]
+#[interpolate_test(bd8_quantizer_24, "8-bit/quantizer/av1-1-b8-00-quantizer-24.ivf")]
+#[interpolate_test(bd8_quantizer_25, "8-bit/quantizer/av1-1-b8-00-quantizer-25.ivf")]
+#[interpolate_test(bd8_quantizer_26, "8-bit/quantizer/av1-1-b8-00-quantizer-26.ivf")]
+#[interpolate_test(bd8_quantizer_27, "8-bit/quantizer/av1-1-b8-00-quantizer-27.ivf")]
+#[interpolate_test(bd8_quantizer_28, "8-bit/quantizer/av1-1-b8-00-quantizer-28.ivf")]
+#[interpolate_test(bd8_quantizer_29, "8-bit/quantizer/av1-1-b8-00-quantizer-29.ivf")]
+#[interpolate_test(bd8_quantizer_30, "8-bit/quantizer/av1-1-b8-00-quantizer-30.ivf")]
+#[interpolate_test(bd8_quantizer_31, "8-bit/quantizer/av1-1-b8-00-quantizer-31.ivf")]
+#[interpolate_test(bd8_quantizer_32, "8-bit/quantizer/av1-1-b8-00-quantizer-32.ivf")]
+#[interpolate_test(bd8_quantizer_33, "8-bit/quantizer/av1-1-b8-00-quantizer-33.ivf")]
+#[interpolate_test(bd8_quantizer_34, "8-bit/quantizer/av1-1-b8-00-quantizer-34.ivf")]
+#[interpolate_test(bd8_quantizer_35, "8-bit/quantizer/av1-1-b8-00-quantizer-35.ivf")]
+#[interpolate_test(bd8_quantizer_36, "8-bit/quantizer/av1-1-b8-00-quantizer-36.ivf")]
+#[interpolate_test(bd8_quantizer_37, "8-bit/quantizer/av1-1-b8-00-quantizer-37.ivf")]
+#[interpolate_test(bd8_quantizer_38, "8-bit/quantizer/av1-1-b8-00-quantizer-38.ivf")]
+#[interpolate_test(bd8_quantizer_39, "8-bit/quantizer/av1-1-b8-00-quantizer-39.ivf")]
+#[interpolate_test(bd8_quantizer_40, "8-bit/quantizer/av1-1-b8-00-quantizer-40.ivf")]
+#[interpolate_test(bd8_quantizer_41, "8-bit/quantizer/av1-1-b8-00-quantizer-41.ivf")]
+#[interpolate_test(bd8_quantizer_42, "8-bit/quantizer/av1-1-b8-00-quantizer-42.ivf")]
+#[interpolate_test(bd8_quantizer_43, "8-bit/quantizer/av1-1-b8-00-quantizer-43.ivf")]
+#[interpolate_test(bd8_quantizer_44, "8-bit/quantizer/av1-1-b8-00-quantizer-44.ivf")]
+#[interpolate_test(bd8_quantizer_45, "8-bit/quantizer/av1-1-b8-00-quantizer-45.ivf")]
+#[interpolate_test(bd8_quantizer_46, "8-bit/quantizer/av1-1-b8-00-quantizer-46.ivf")]
+#[interpolate_test(bd8_quantizer_47, "8-bit/quantizer/av1-1-b8-00-quantizer-47.ivf")]
+#[interpolate_test(bd8_quantizer_48, "8-bit/quantizer/av1-1-b8-00-quantizer-48.ivf")]
+#[interpolate_test(bd8_quantizer_49, "8-bit/quantizer/av1-1-b8-00-quantizer-49.ivf")]
+#[interpolate_test(bd8_quantizer_50, "8-bit/quantizer/av1-1-b8-00-quantizer-50.ivf")]
+#[interpolate_test(bd8_quantizer_51, "8-bit/quantizer/av1-1-b8-00-quantizer-51.ivf")]
+#[interpolate_test(bd8_quantizer_52, "8-bit/quantizer/av1-1-b8-00-quantizer-52.ivf")]
+#[interpolate_test(bd8_quantizer_53, "8-bit/quantizer/av1-1-b8-00-quantizer-53.ivf")]
+#[interpolate_test(bd8_quantizer_54, "8-bit/quantizer/av1-1-b8-00-quantizer-54.ivf")]
+#[interpolate_test(bd8_quantizer_55, "8-bit/quantizer/av1-1-b8-00-quantizer-55.ivf")]
+#[interpolate_test(bd8_quantizer_56, "8-bit/quantizer/av1-1-b8-00-quantizer-56.ivf")]
+#[interpolate_test(bd8_quantizer_57, "8-bit/quantizer/av1-1-b8-00-quantizer-57.ivf")]
+#[interpolate_test(bd8_quantizer_58, "8-bit/quantizer/av1-1-b8-00-quantizer-58.ivf")]
+#[interpolate_test(bd8_quantizer_59, "8-bit/quantizer/av1-1-b8-00-quantizer-59.ivf")]
+#[interpolate_test(bd8_quantizer_60, "8-bit/quantizer/av1-1-b8-00-quantizer-60.ivf")]
+#[interpolate_test(bd8_quantizer_61, "8-bit/quantizer/av1-1-b8-00-quantizer-61.ivf")]
+#[interpolate_test(bd8_quantizer_62, "8-bit/quantizer/av1-1-b8-00-quantizer-62.ivf")]
+#[interpolate_test(bd8_quantizer_63, "8-bit/quantizer/av1-1-b8-00-quantizer-63.ivf")]
+#[interpolate_test(bd8_resize_03_sizedown, "8-bit/resize/av1-1-b8-03-sizedown.ivf")]
+#[interpolate_test(bd8_resize_03_sizeup, "8-bit/resize/av1-1-b8-03-sizeup.ivf")]
+#[interpolate_test(
+    bd8_sframe_autostitch_10s,
+    "8-bit/sframe/autostitch-480p-240p-160p-10s.ivf"
+)]
+#[interpolate_test(bd8_sframe_autostitch, "8-bit/sframe/autostitch-480p-240p-160p.ivf")]
+#[interpolate_test(bd8_size_16x16, "8-bit/size/av1-1-b8-01-size-16x16.ivf")]
+#[interpolate_test(bd8_size_16x18, "8-bit/size/av1-1-b8-01-size-16x18.ivf")]
+#[interpolate_test(bd8_size_16x32, "8-bit/size/av1-1-b8-01-size-16x32.ivf")]
+#[interpolate_test(bd8_size_16x34, "8-bit/size/av1-1-b8-01-size-16x34.ivf")]
+#[interpolate_test(bd8_size_16x64, "8-bit/size/av1-1-b8-01-size-16x64.ivf")]
+#[interpolate_test(bd8_size_16x66, "8-bit/size/av1-1-b8-01-size-16x66.ivf")]
+#[interpolate_test(bd8_size_18x16, "8-bit/size/av1-1-b8-01-size-18x16.ivf")]
+#[interpolate_test(bd8_size_18x18, "8-bit/size/av1-1-b8-01-size-18x18.ivf")]
+#[interpolate_test(bd8_size_18x32, "8-bit/size/av1-1-b8-01-size-18x32.ivf")]
+#[interpolate_test(bd8_size_18x34, "8-bit/size/av1-1-b8-01-size-18x34.ivf")]
+#[interpolate_test(bd8_size_18x64, "8-bit/size/av1-1-b8-01-size-18x64.ivf")]
+#[interpolate_test(bd8_size_18x66, "8-bit/size/av1-1-b8-01-size-18x66.ivf")]
+#[interpolate_test(bd8_size_196x196, "8-bit/size/av1-1-b8-01-size-196x196.ivf")]
+#[interpolate_test(bd8_size_196x198, "8-bit/size/av1-1-b8-01-size-196x198.ivf")]
+#[interpolate_test(bd8_size_196x200, "8-bit/size/av1-1-b8-01-size-196x200.ivf")]
+#[interpolate_test(bd8_size_196x202, "8-bit/size/av1-1-b8-01-size-196x202.ivf")]
+#[interpolate_test(bd8_size_196x208, "8-bit/size/av1-1-b8-01-size-196x208.ivf")]
+#[interpolate_test(bd8_size_196x210, "8-bit/size/av1-1-b8-01-size-196x210.ivf")]
+#[interpolate_test(bd8_size_196x224, "8-bit/size/av1-1-b8-01-size-196x224.ivf")]
+#[interpolate_test(bd8_size_196x226, "8-bit/size/av1-1-b8-01-size-196x226.ivf")]
+#[interpolate_test(bd8_size_198x196, "8-bit/size/av1-1-b8-01-size-198x196.ivf")]
+#[interpolate_test(bd8_size_198x198, "8-bit/size/av1-1-b8-01-size-198x198.ivf")]
+#[interpolate_test(bd8_size_198x200, "8-bit/size/av1-1-b8-01-size-198x200.ivf")]
+#[interpolate_test(bd8_size_198x202, "8-bit/size/av1-1-b8-01-size-198x202.ivf")]
+#[interpolate_test(bd8_size_198x208, "8-bit/size/av1-1-b8-01-size-198x208.ivf")]
+#[interpolate_test(bd8_size_198x210, "8-bit/size/av1-1-b8-01-size-198x210.ivf")]
+#[interpolate_test(bd8_size_198x224, "8-bit/size/av1-1-b8-01-size-198x224.ivf")]
+#[interpolate_test(bd8_size_198x226, "8-bit/size/av1-1-b8-01-size-198x226.ivf")]
+#[interpolate_test(bd8_size_200x196, "8-bit/size/av1-1-b8-01-size-200x196.ivf")]
+#[interpolate_test(bd8_size_200x198, "8-bit/size/av1-1-b8-01-size-200x198.ivf")]
+#[interpolate_test(bd8_size_200x200, "8-bit/size/av1-1-b8-01-size-200x200.ivf")]
+#[interpolate_test(bd8_size_200x202, "8-bit/size/av1-1-b8-01-size-200x202.ivf")]
+#[interpolate_test(bd8_size_200x208, "8-bit/size/av1-1-b8-01-size-200x208.ivf")]
+#[interpolate_test(bd8_size_200x210, "8-bit/size/av1-1-b8-01-size-200x210.ivf")]
+#[interpolate_test(bd8_size_200x224, "8-bit/size/av1-1-b8-01-size-200x224.ivf")]
+#[interpolate_test(bd8_size_200x226, "8-bit/size/av1-1-b8-01-size-200x226.ivf")]
+#[interpolate_test(bd8_size_202x196, "8-bit/size/av1-1-b8-01-size-202x196.ivf")]
+#[interpolate_test(bd8_size_202x198, "8-bit/size/av1-1-b8-01-size-202x198.ivf")]
+#[interpolate_test(bd8_size_202x200, "8-bit/size/av1-1-b8-01-size-202x200.ivf")]
+#[interpolate_test(bd8_size_202x202, "8-bit/size/av1-1-b8-01-size-202x202.ivf")]
+#[interpolate_test(bd8_size_202x208, "8-bit/size/av1-1-b8-01-size-202x208.ivf")]
+#[interpolate_test(bd8_size_202x210, "8-bit/size/av1-1-b8-01-size-202x210.ivf")]
+#[interpolate_test(bd8_size_202x224, "8-bit/size/av1-1-b8-01-size-202x224.ivf")]
+#[interpolate_test(bd8_size_202x226, "8-bit/size/av1-1-b8-01-size-202x226.ivf")]
+#[interpolate_test(bd8_size_208x196, "8-bit/size/av1-1-b8-01-size-208x196.ivf")]
+#[interpolate_test(bd8_size_208x198, "8-bit/size/av1-1-b8-01-size-208x198.ivf")]
+#[interpolate_test(bd8_size_208x200, "8-bit/size/av1-1-b8-01-size-208x200.ivf")]
+#[interpolate_test(bd8_size_208x202, "8-bit/size/av1-1-b8-01-size-208x202.ivf")]
+#[interpolate_test(bd8_size_208x208, "8-bit/size/av1-1-b8-01-size-208x208.ivf")]
+#[interpolate_test(bd8_size_208x210, "8-bit/size/av1-1-b8-01-size-208x210.ivf")]
+#[interpolate_test(bd8_size_208x224, "8-bit/size/av1-1-b8-01-size-208x224.ivf")]
+#[interpolate_test(bd8_size_208x226, "8-bit/size/av1-1-b8-01-size-208x226.ivf")]
+#[interpolate_test(bd8_size_210x196, "8-bit/size/av1-1-b8-01-size-210x196.ivf")]
+#[interpolate_test(bd8_size_210x198, "8-bit/size/av1-1-b8-01-size-210x198.ivf")]
+#[interpolate_test(bd8_size_210x200, "8-bit/size/av1-1-b8-01-size-210x200.ivf")]
+#[interpolate_test(bd8_size_210x202, "8-bit/size/av1-1-b8-01-size-210x202.ivf")]
+#[interpolate_test(bd8_size_210x208, "8-bit/size/av1-1-b8-01-size-210x208.ivf")]
+#[interpolate_test(bd8_size_210x210, "8-bit/size/av1-1-b8-01-size-210x210.ivf")]
+#[interpolate_test(bd8_size_210x224, "8-bit/size/av1-1-b8-01-size-210x224.ivf")]
+#[interpolate_test(bd8_size_210x226, "8-bit/size/av1-1-b8-01-size-210x226.ivf")]
+#[interpolate_test(bd8_size_224x196, "8-bit/size/av1-1-b8-01-size-224x196.ivf")]
+#[interpolate_test(bd8_size_224x198, "8-bit/size/av1-1-b8-01-size-224x198.ivf")]
+#[interpolate_test(bd8_size_224x200, "8-bit/size/av1-1-b8-01-size-224x200.ivf")]
+#[interpolate_test(bd8_size_224x202, "8-bit/size/av1-1-b8-01-size-224x202.ivf")]
+#[interpolate_test(bd8_size_224x208, "8-bit/size/av1-1-b8-01-size-224x208.ivf")]
+#[interpolate_test(bd8_size_224x210, "8-bit/size/av1-1-b8-01-size-224x210.ivf")]
+#[interpolate_test(bd8_size_224x224, "8-bit/size/av1-1-b8-01-size-224x224.ivf")]
+#[interpolate_test(bd8_size_224x226, "8-bit/size/av1-1-b8-01-size-224x226.ivf")]
+#[interpolate_test(bd8_size_226x196, "8-bit/size/av1-1-b8-01-size-226x196.ivf")]
+#[interpolate_test(bd8_size_226x198, "8-bit/size/av1-1-b8-01-size-226x198.ivf")]
+#[interpolate_test(bd8_size_226x200, "8-bit/size/av1-1-b8-01-size-226x200.ivf")]
+#[interpolate_test(bd8_size_226x202, "8-bit/size/av1-1-b8-01-size-226x202.ivf")]
+#[interpolate_test(bd8_size_226x208, "8-bit/size/av1-1-b8-01-size-226x208.ivf")]
+#[interpolate_test(bd8_size_226x210, "8-bit/size/av1-1-b8-01-size-226x210.ivf")]
+#[interpolate_test(bd8_size_226x224, "8-bit/size/av1-1-b8-01-size-226x224.ivf")]
+#[interpolate_test(bd8_size_226x226, "8-bit/size/av1-1-b8-01-size-226x226.ivf")]
+#[interpolate_test(bd8_size_32x16, "8-bit/size/av1-1-b8-01-size-32x16.ivf")]
+#[interpolate_test(bd8_size_32x18, "8-bit/size/av1-1-b8-01-size-32x18.ivf")]
+#[interpolate_test(bd8_size_32x32, "8-bit/size/av1-1-b8-01-size-32x32.ivf")]
+#[interpolate_test(bd8_size_32x34, "8-bit/size/av1-1-b8-01-size-32x34.ivf")]
+#[interpolate_test(bd8_size_32x64, "8-bit/size/av1-1-b8-01-size-32x64.ivf")]
+#[interpolate_test(bd8_size_32x66, "8-bit/size/av1-1-b8-01-size-32x66.ivf")]
+#[interpolate_test(bd8_size_34x16, "8-bit/size/av1-1-b8-01-size-34x16.ivf")]
+#[interpolate_test(bd8_size_34x18, "8-bit/size/av1-1-b8-01-size-34x18.ivf")]
+#[interpolate_test(bd8_size_34x32, "8-bit/size/av1-1-b8-01-size-34x32.ivf")]
+#[interpolate_test(bd8_size_34x34, "8-bit/size/av1-1-b8-01-size-34x34.ivf")]
+#[interpolate_test(bd8_size_34x64, "8-bit/size/av1-1-b8-01-size-34x64.ivf")]
+#[interpolate_test(bd8_size_34x66, "8-bit/size/av1-1-b8-01-size-34x66.ivf")]
+#[interpolate_test(bd8_size_64x16, "8-bit/size/av1-1-b8-01-size-64x16.ivf")]
+#[interpolate_test(bd8_size_64x18, "8-bit/size/av1-1-b8-01-size-64x18.ivf")]
+#[interpolate_test(bd8_size_64x32, "8-bit/size/av1-1-b8-01-size-64x32.ivf")]
+#[interpolate_test(bd8_size_64x34, "8-bit/size/av1-1-b8-01-size-64x34.ivf")]
+#[interpolate_test(bd8_size_64x64, "8-bit/size/av1-1-b8-01-size-64x64.ivf")]
+#[interpolate_test(bd8_size_64x66, "8-bit/size/av1-1-b8-01-size-64x66.ivf")]
+#[interpolate_test(bd8_size_66x16, "8-bit/size/av1-1-b8-01-size-66x16.ivf")]
+#[interpolate_test(bd8_size_66x18, "8-bit/size/av1-1-b8-01-size-66x18.ivf")]
+#[interpolate_test(bd8_size_66x32, "8-bit/size/av1-1-b8-01-size-66x32.ivf")]
+#[interpolate_test(bd8_size_66x34, "8-bit/size/av1-1-b8-01-size-66x34.ivf")]
+#[interpolate_test(bd8_size_66x64, "8-bit/size/av1-1-b8-01-size-66x64.ivf")]
+#[interpolate_test(bd8_size_66x66, "8-bit/size/av1-1-b8-01-size-66x66.ivf")]
+#[interpolate_test(10b_data_00000671, "10-bit/data/00000671.ivf")]
+#[interpolate_test(10b_data_00000672, "10-bit/data/00000672.ivf")]
+#[interpolate_test(10b_data_00000673, "10-bit/data/00000673.ivf")]
+#[interpolate_test(10b_data_00000674, "10-bit/data/00000674.ivf")]
+#[interpolate_test(10b_data_00000675, "10-bit/data/00000675.ivf")]
+#[interpolate_test(10b_data_00000676, "10-bit/data/00000676.ivf")]
+#[interpolate_test(10b_data_00000677, "10-bit/data/00000677.ivf")]
+#[interpolate_test(10b_data_00000678, "10-bit/data/00000678.ivf")]
+#[interpolate_test(10b_data_00000679, "10-bit/data/00000679.ivf")]
+#[interpolate_test(10b_data_00000680, "10-bit/data/00000680.ivf")]
+#[interpolate_test(10b_data_00000681, "10-bit/data/00000681.ivf")]
+#[interpolate_test(10b_data_00000682, "10-bit/data/00000682.ivf")]
+#[interpolate_test(10b_data_00000683, "10-bit/data/00000683.ivf")]
+#[interpolate_test(10b_data_00000684, "10-bit/data/00000684.ivf")]
+#[interpolate_test(10b_data_00000685, "10-bit/data/00000685.ivf")]
+#[interpolate_test(10b_data_00000716, "10-bit/data/00000716.ivf")]
+#[interpolate_test(10b_data_00000717, "10-bit/data/00000717.ivf")]
+#[interpolate_test(10b_data_00000718, "10-bit/data/00000718.ivf")]
+#[interpolate_test(10b_data_00000719, "10-bit/data/00000719.ivf")]
+#[interpolate_test(10b_data_00000720, "10-bit/data/00000720.ivf")]
+#[interpolate_test(10b_data_00000721, "10-bit/data/00000721.ivf")]
+#[interpolate_test(10b_data_00000722, "10-bit/data/00000722.ivf")]
+#[interpolate_test(10b_data_00000723, "10-bit/data/00000723.ivf")]
+#[interpolate_test(10b_data_00000724, "10-bit/data/00000724.ivf")]
+#[interpolate_test(10b_data_00000725, "10-bit/data/00000725.ivf")]
+#[interpolate_test(10b_data_00000726, "10-bit/data/00000726.ivf")]
+#[interpolate_test(10b_data_00000727, "10-bit/data/00000727.ivf")]
+#[interpolate_test(10b_data_00000728, "10-bit/data/00000728.ivf")]
+#[interpolate_test(10b_data_00000729, "10-bit/data/00000729.ivf")]
+#[interpolate_test(10b_data_00000730, "10-bit/data/00000730.ivf")]
+#[interpolate_test(10b_data_00000761, "10-bit/data/00000761.ivf")]
+#[interpolate_test(10b_data_00000762, "10-bit/data/00000762.ivf")]
+#[interpolate_test(10b_data_00000763, "10-bit/data/00000763.ivf")]
+#[interpolate_test(10b_data_00000764, "10-bit/data/00000764.ivf")]
+#[interpolate_test(10b_data_00000765, "10-bit/data/00000765.ivf")]
+#[interpolate_test(10b_data_00000766, "10-bit/data/00000766.ivf")]
+#[interpolate_test(10b_data_00000767, "10-bit/data/00000767.ivf")]
+#[interpolate_test(10b_data_00000768, "10-bit/data/00000768.ivf")]
+#[interpolate_test(10b_data_00000769, "10-bit/data/00000769.ivf")]
+#[interpolate_test(10b_data_00000770, "10-bit/data/00000770.ivf")]
+#[interpolate_test(10b_data_00000771, "10-bit/data/00000771.ivf")]
+#[interpolate_test(10b_data_00000772, "10-bit/data/00000772.ivf")]
+#[interpolate_test(10b_data_00000773, "10-bit/data/00000773.ivf")]
+#[interpolate_test(10b_data_00000774, "10-bit/data/00000774.ivf")]
+#[interpolate_test(10b_data_00000775, "10-bit/data/00000775.ivf")]
+#[interpolate_test(10b_data_00000820, "10-bit/data/00000820.ivf")]
+#[interpolate_test(10b_data_00000821, "10-bit/data/00000821.ivf")]
+#[interpolate_test(10b_data_00000822, "10-bit/data/00000822.ivf")]
+#[interpolate_test(10b_data_00000823, "10-bit/data/00000823.ivf")]
+#[interpolate_test(10b_data_00000824, "10-bit/data/00000824.ivf")]
+#[interpolate_test(10b_data_00000825, "10-bit/data/00000825.ivf")]
+#[interpolate_test(10b_data_00000826, "10-bit/data/00000826.ivf")]
+#[interpolate_test(10b_data_00000827, "10-bit/data/00000827.ivf")]
+#[interpolate_test(10b_data_00000828, "10-bit/data/00000828.ivf")]
+#[interpolate_test(10b_data_00000829, "10-bit/data/00000829.ivf")]
+#[interpolate_test(10b_data_00000830, "10-bit/data/00000830.ivf")]
+#[interpolate_test(10b_data_00000831, "10-bit/data/00000831.ivf")]
+#[interpolate_test(10b_data_00000832, "10-bit/data/00000832.ivf")]
+#[interpolate_test(10b_data_00000833, "10-bit/data/00000833.ivf")]
+#[interpolate_test(10b_data_00000834, "10-bit/data/00000834.ivf")]
+#[interpolate_test(10b_data_00000835, "10-bit/data/00000835.ivf")]
+#[interpolate_test(10b_data_00000836, "10-bit/data/00000836.ivf")]
+#[interpolate_test(10b_data_00000837, "10-bit/data/00000837.ivf")]
+#[interpolate_test(10b_data_00000937, "10-bit/data/00000937.ivf")]
+#[interpolate_test(10b_data_00000938, "10-bit/data/00000938.ivf")]
+#[interpolate_test(10b_data_00000939, "10-bit/data/00000939.ivf")]
+#[interpolate_test(10b_data_00000940, "10-bit/data/00000940.ivf")]
+#[interpolate_test(10b_data_00000941, "10-bit/data/00000941.ivf")]
+#[interpolate_test(10b_data_00000942, "10-bit/data/00000942.ivf")]
+#[interpolate_test(10b_data_00000943, "10-bit/data/00000943.ivf")]
+#[interpolate_test(10b_data_00000944, "10-bit/data/00000944.ivf")]
+#[interpolate_test(10b_film_grain_23, "10-bit/film_grain/av1-1-b10-23-film_grain-50.ivf")]
+#[interpolate_test(10b_issues_318_tx_4x4, "10-bit/issues/318_tx_4x4.ivf")]
+#[interpolate_test(10b_quantizer_00, "10-bit/quantizer/av1-1-b10-00-quantizer-00.ivf")]
+#[interpolate_test(10b_quantizer_01, "10-bit/quantizer/av1-1-b10-00-quantizer-01.ivf")]
+#[interpolate_test(10b_quantizer_02, "10-bit/quantizer/av1-1-b10-00-quantizer-02.ivf")]
+#[interpolate_test(10b_quantizer_03, "10-bit/quantizer/av1-1-b10-00-quantizer-03.ivf")]
+#[interpolate_test(10b_quantizer_04, "10-bit/quantizer/av1-1-b10-00-quantizer-04.ivf")]
+#[interpolate_test(10b_quantizer_05, "10-bit/quantizer/av1-1-b10-00-quantizer-05.ivf")]
+#[interpolate_test(10b_quantizer_06, "10-bit/quantizer/av1-1-b10-00-quantizer-06.ivf")]
+#[interpolate_test(10b_quantizer_07, "10-bit/quantizer/av1-1-b10-00-quantizer-07.ivf")]
+#[interpolate_test(10b_quantizer_08, "10-bit/quantizer/av1-1-b10-00-quantizer-08.ivf")]
+#[interpolate_test(10b_quantizer_09, "10-bit/quantizer/av1-1-b10-00-quantizer-09.ivf")]
+#[interpolate_test(10b_quantizer_10, "10-bit/quantizer/av1-1-b10-00-quantizer-10.ivf")]
+#[interpolate_test(10b_quantizer_11, "10-bit/quantizer/av1-1-b10-00-quantizer-11.ivf")]
+#[interpolate_test(10b_quantizer_12, "10-bit/quantizer/av1-1-b10-00-quantizer-12.ivf")]
+#[interpolate_test(10b_quantizer_13, "10-bit/quantizer/av1-1-b10-00-quantizer-13.ivf")]
+#[interpolate_test(10b_quantizer_14, "10-bit/quantizer/av1-1-b10-00-quantizer-14.ivf")]
+#[interpolate_test(10b_quantizer_15, "10-bit/quantizer/av1-1-b10-00-quantizer-15.ivf")]
+#[interpolate_test(10b_quantizer_16, "10-bit/quantizer/av1-1-b10-00-quantizer-16.ivf")]
+#[interpolate_test(10b_quantizer_17, "10-bit/quantizer/av1-1-b10-00-quantizer-17.ivf")]
+#[interpolate_test(10b_quantizer_18, "10-bit/quantizer/av1-1-b10-00-quantizer-18.ivf")]
+#[interpolate_test(10b_quantizer_19, "10-bit/quantizer/av1-1-b10-00-quantizer-19.ivf")]
+#[interpolate_test(10b_quantizer_20, "10-bit/quantizer/av1-1-b10-00-quantizer-20.ivf")]
+#[interpolate_test(10b_quantizer_21, "10-bit/quantizer/av1-1-b10-00-quantizer-21.ivf")]
+#[interpolate_test(10b_quantizer_22, "10-bit/quantizer/av1-1-b10-00-quantizer-22.ivf")]
+#[interpolate_test(10b_quantizer_23, "10-bit/quantizer/av1-1-b10-00-quantizer-23.ivf")]
+#[interpolate_test(10b_quantizer_24, "10-bit/quantizer/av1-1-b10-00-quantizer-24.ivf")]
+#[interpolate_test(10b_quantizer_25, "10-bit/quantizer/av1-1-b10-00-quantizer-25.ivf")]
+#[interpolate_test(10b_quantizer_26, "10-bit/quantizer/av1-1-b10-00-quantizer-26.ivf")]
+#[interpolate_test(10b_quantizer_27, "10-bit/quantizer/av1-1-b10-00-quantizer-27.ivf")]
+#[interpolate_test(10b_quantizer_28, "10-bit/quantizer/av1-1-b10-00-quantizer-28.ivf")]
+#[interpolate_test(10b_quantizer_29, "10-bit/quantizer/av1-1-b10-00-quantizer-29.ivf")]
+#[interpolate_test(10b_quantizer_30, "10-bit/quantizer/av1-1-b10-00-quantizer-30.ivf")]
+#[interpolate_test(10b_quantizer_31, "10-bit/quantizer/av1-1-b10-00-quantizer-31.ivf")]
+#[interpolate_test(10b_quantizer_32, "10-bit/quantizer/av1-1-b10-00-quantizer-32.ivf")]
+#[interpolate_test(10b_quantizer_33, "10-bit/quantizer/av1-1-b10-00-quantizer-33.ivf")]
+#[interpolate_test(10b_quantizer_34, "10-bit/quantizer/av1-1-b10-00-quantizer-34.ivf")]
+#[interpolate_test(10b_quantizer_35, "10-bit/quantizer/av1-1-b10-00-quantizer-35.ivf")]
+#[interpolate_test(10b_quantizer_36, "10-bit/quantizer/av1-1-b10-00-quantizer-36.ivf")]
+#[interpolate_test(10b_quantizer_37, "10-bit/quantizer/av1-1-b10-00-quantizer-37.ivf")]
+#[interpolate_test(10b_quantizer_38, "10-bit/quantizer/av1-1-b10-00-quantizer-38.ivf")]
+#[interpolate_test(10b_quantizer_39, "10-bit/quantizer/av1-1-b10-00-quantizer-39.ivf")]
+#[interpolate_test(10b_quantizer_40, "10-bit/quantizer/av1-1-b10-00-quantizer-40.ivf")]
+#[interpolate_test(10b_quantizer_41, "10-bit/quantizer/av1-1-b10-00-quantizer-41.ivf")]
+#[interpolate_test(10b_quantizer_42, "10-bit/quantizer/av1-1-b10-00-quantizer-42.ivf")]
+#[interpolate_test(10b_quantizer_43, "10-bit/quantizer/av1-1-b10-00-quantizer-43.ivf")]
+#[interpolate_test(10b_quantizer_44, "10-bit/quantizer/av1-1-b10-00-quantizer-44.ivf")]
+#[interpolate_test(10b_quantizer_45, "10-bit/quantizer/av1-1-b10-00-quantizer-45.ivf")]
+#[interpolate_test(10b_quantizer_46, "10-bit/quantizer/av1-1-b10-00-quantizer-46.ivf")]
+#[interpolate_test(10b_quantizer_47, "10-bit/quantizer/av1-1-b10-00-quantizer-47.ivf")]
+#[interpolate_test(10b_quantizer_48, "10-bit/quantizer/av1-1-b10-00-quantizer-48.ivf")]
+#[interpolate_test(10b_quantizer_49, "10-bit/quantizer/av1-1-b10-00-quantizer-49.ivf")]
+#[interpolate_test(10b_quantizer_50, "10-bit/quantizer/av1-1-b10-00-quantizer-50.ivf")]
+#[interpolate_test(10b_quantizer_51, "10-bit/quantizer/av1-1-b10-00-quantizer-51.ivf")]
+#[interpolate_test(10b_quantizer_52, "10-bit/quantizer/av1-1-b10-00-quantizer-52.ivf")]
+#[interpolate_test(10b_quantizer_53, "10-bit/quantizer/av1-1-b10-00-quantizer-53.ivf")]
+#[interpolate_test(10b_quantizer_54, "10-bit/quantizer/av1-1-b10-00-quantizer-54.ivf")]
+#[interpolate_test(10b_quantizer_55, "10-bit/quantizer/av1-1-b10-00-quantizer-55.ivf")]
+#[interpolate_test(10b_quantizer_56, "10-bit/quantizer/av1-1-b10-00-quantizer-56.ivf")]
+#[interpolate_test(10b_quantizer_57, "10-bit/quantizer/av1-1-b10-00-quantizer-57.ivf")]
+#[interpolate_test(10b_quantizer_58, "10-bit/quantizer/av1-1-b10-00-quantizer-58.ivf")]
+#[interpolate_test(10b_quantizer_59, "10-bit/quantizer/av1-1-b10-00-quantizer-59.ivf")]
+#[interpolate_test(10b_quantizer_60, "10-bit/quantizer/av1-1-b10-00-quantizer-60.ivf")]
+#[interpolate_test(10b_quantizer_61, "10-bit/quantizer/av1-1-b10-00-quantizer-61.ivf")]
+#[interpolate_test(10b_quantizer_62, "10-bit/quantizer/av1-1-b10-00-quantizer-62.ivf")]
+#[interpolate_test(10b_quantizer_63, "10-bit/quantizer/av1-1-b10-00-quantizer-63.ivf")]
+#[interpolate_test(12b_data_00000686, "12-bit/data/00000686.ivf")]
+#[interpolate_test(12b_data_00000687, "12-bit/data/00000687.ivf")]
+#[interpolate_test(12b_data_00000688, "12-bit/data/00000688.ivf")]
+#[interpolate_test(12b_data_00000689, "12-bit/data/00000689.ivf")]
+#[interpolate_test(12b_data_00000690, "12-bit/data/00000690.ivf")]
+#[interpolate_test(12b_data_00000691, "12-bit/data/00000691.ivf")]
+#[interpolate_test(12b_data_00000692, "12-bit/data/00000692.ivf")]
+#[interpolate_test(12b_data_00000693, "12-bit/data/00000693.ivf")]
+#[interpolate_test(12b_data_00000694, "12-bit/data/00000694.ivf")]
+#[interpolate_test(12b_data_00000695, "12-bit/data/00000695.ivf")]
+#[interpolate_test(12b_data_00000696, "12-bit/data/00000696.ivf")]
+#[interpolate_test(12b_data_00000697, "12-bit/data/00000697.ivf")]
+#[interpolate_test(12b_data_00000698, "12-bit/data/00000698.ivf")]
+#[interpolate_test(12b_data_00000699, "12-bit/data/00000699.ivf")]
+#[interpolate_test(12b_data_00000700, "12-bit/data/00000700.ivf")]
+#[interpolate_test(12b_data_00000731, "12-bit/data/00000731.ivf")]
+#[interpolate_test(12b_data_00000732, "12-bit/data/00000732.ivf")]
+#[interpolate_test(12b_data_00000733, "12-bit/data/00000733.ivf")]
+#[interpolate_test(12b_data_00000734, "12-bit/data/00000734.ivf")]
+#[interpolate_test(12b_data_00000735, "12-bit/data/00000735.ivf")]
+#[interpolate_test(12b_data_00000736, "12-bit/data/00000736.ivf")]
+#[interpolate_test(12b_data_00000737, "12-bit/data/00000737.ivf")]
+#[interpolate_test(12b_data_00000738, "12-bit/data/00000738.ivf")]
+#[interpolate_test(12b_data_00000739, "12-bit/data/00000739.ivf")]
+#[interpolate_test(12b_data_00000740, "12-bit/data/00000740.ivf")]
+#[interpolate_test(12b_data_00000741, "12-bit/data/00000741.ivf")]
+#[interpolate_test(12b_data_00000742, "12-bit/data/00000742.ivf")]
+#[interpolate_test(12b_data_00000743, "12-bit/data/00000743.ivf")]
+#[interpolate_test(12b_data_00000744, "12-bit/data/00000744.ivf")]
+#[interpolate_test(12b_data_00000745, "12-bit/data/00000745.ivf")]
+#[interpolate_test(12b_data_00000776, "12-bit/data/00000776.ivf")]
+#[interpolate_test(12b_data_00000777, "12-bit/data/00000777.ivf")]
+#[interpolate_test(12b_data_00000778, "12-bit/data/00000778.ivf")]
+#[interpolate_test(12b_data_00000779, "12-bit/data/00000779.ivf")]
+#[interpolate_test(12b_data_00000780, "12-bit/data/00000780.ivf")]
+#[interpolate_test(12b_data_00000781, "12-bit/data/00000781.ivf")]
+#[interpolate_test(12b_data_00000782, "12-bit/data/00000782.ivf")]
+#[interpolate_test(12b_data_00000783, "12-bit/data/00000783.ivf")]
+#[interpolate_test(12b_data_00000784, "12-bit/data/00000784.ivf")]
+#[interpolate_test(12b_data_00000785, "12-bit/data/00000785.ivf")]
+#[interpolate_test(12b_data_00000786, "12-bit/data/00000786.ivf")]
+#[interpolate_test(12b_data_00000787, "12-bit/data/00000787.ivf")]
+#[interpolate_test(12b_data_00000788, "12-bit/data/00000788.ivf")]
+#[interpolate_test(12b_data_00000789, "12-bit/data/00000789.ivf")]
+#[interpolate_test(12b_data_00000790, "12-bit/data/00000790.ivf")]
+#[interpolate_test(12b_data_00000791, "12-bit/data/00000791.ivf")]
+#[interpolate_test(12b_features_lossless, "12-bit/features/lossless.ivf")]
 fn apply(path: &str) {
     let grain_file = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("tests")